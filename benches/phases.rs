@@ -0,0 +1,99 @@
+//! Time each phase of a run (parsing the input, inferring its type and
+//! emitting declarations) on generated documents of varying width and
+//! depth.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use json::JsonValue;
+use json_analyzer::{DataType, Decls, Options, Value};
+
+/// Generate an object with `width` members, each an object of a
+/// different shape, so that every one of them is declared separately.
+fn wide(width: usize) -> JsonValue {
+    let mut doc = JsonValue::new_object();
+    for i in 0..width {
+        let mut member = json::object! {
+            "id": i,
+            "name": format!("member {}", i),
+            "score": 1.5,
+            "active": i % 2 == 0,
+            "tags": ["a", "b"],
+            "mixed": [1, "one", null],
+        };
+        member[format!("extra_{}", i).as_str()] = i.into();
+        doc[format!("member_{}", i).as_str()] = member;
+    }
+    doc
+}
+
+/// Generate objects nested `depth` deep, with a few scalar members at
+/// each level.
+fn deep(depth: usize) -> JsonValue {
+    let mut doc = json::object! {"leaf": true};
+    for i in 0..depth {
+        doc = json::object! {
+            "level": i,
+            "label": format!("level {}", i),
+            "child": doc,
+        };
+    }
+    doc
+}
+
+/// The fixtures to run each phase on, by name.
+fn fixtures() -> Vec<(String, JsonValue)> {
+    let mut fixtures = Vec::new();
+    for width in [100, 1000, 5000] {
+        fixtures.push((format!("wide/{}", width), wide(width)));
+    }
+    for depth in [16, 64, 200] {
+        fixtures.push((format!("deep/{}", depth), deep(depth)));
+    }
+    fixtures
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(20);
+    for (name, doc) in fixtures() {
+        let text = doc.dump();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &text, |b, text| {
+            b.iter(|| Value::from_json(&json::parse(text).unwrap()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn infer(c: &mut Criterion) {
+    let mut group = c.benchmark_group("infer");
+    group.sample_size(20);
+    for (name, doc) in fixtures() {
+        let value = Value::from_json(&doc).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &value, |b, value| {
+            b.iter(|| DataType::from_value(value).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn emit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("emit");
+    group.sample_size(20);
+    let options = Options {
+        explicit_default: true,
+        ..Options::default()
+    };
+    for (name, doc) in fixtures() {
+        let typ = DataType::from_json_value(&doc).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &typ, |b, typ| {
+            b.iter(|| {
+                let mut decls = Decls::new(options.clone());
+                typ.clone().declare(&mut decls);
+                decls.output()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, infer, emit);
+criterion_main!(benches);
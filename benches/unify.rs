@@ -0,0 +1,91 @@
+//! Compare type inference over a large, repetitive dataset as it's
+//! done, through the interner, against folding an owned `DataType` tree
+//! per record, and time folding the types of many similar records
+//! together.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use json::JsonValue;
+use json_analyzer::{DataType, Interner, Value};
+
+/// Generate an array of `n` records that share most of their shape,
+/// with a few optional and mixed-type members.
+fn records(n: usize) -> JsonValue {
+    JsonValue::Array(
+        (0..n)
+            .map(|i| {
+                let mut record = json::object! {
+                    "id": i,
+                    "name": format!("record {}", i),
+                    "score": if i % 7 == 0 { JsonValue::from(i as f64 + 0.5) } else { JsonValue::from(i) },
+                    "tags": ["a", "b", "c"],
+                    "location": {
+                        "lat": 1.5,
+                        "lon": 2.5,
+                        "address": {"street": "Main", "number": i}
+                    }
+                };
+                if i % 3 == 0 {
+                    record["parent"] = i.saturating_sub(1).into();
+                }
+                if i % 11 == 0 {
+                    record["note"] = JsonValue::Null;
+                }
+                record
+            })
+            .collect(),
+    )
+}
+
+fn inference(c: &mut Criterion) {
+    let data = records(100_000);
+    let values = data
+        .members()
+        .map(|record| Value::from_json(record).unwrap())
+        .collect::<Vec<_>>();
+    let data = Value::from_json(&data).unwrap();
+
+    let mut group = c.benchmark_group("infer 100k records");
+    group.sample_size(10);
+    // How types were inferred before the interner: an owned tree for
+    // every record, folded into the type of the ones before it.
+    group.bench_function("tree", |b| {
+        b.iter(|| {
+            values
+                .iter()
+                .map(|record| DataType::from_value(record).unwrap())
+                .fold(DataType::unknown(), DataType::unify)
+        })
+    });
+    group.bench_function("interned", |b| {
+        b.iter(|| DataType::from_value(&data).unwrap())
+    });
+    group.bench_function("interned, reusing the arena", |b| {
+        let mut interner = Interner::new();
+        b.iter(|| {
+            let id = interner.infer(&data).unwrap();
+            interner.to_data_type(id)
+        })
+    });
+    group.finish();
+}
+
+fn folding(c: &mut Criterion) {
+    let types = records(10_000)
+        .members()
+        .map(|record| DataType::from_json_value(record).unwrap())
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("fold 10k record types");
+    group.sample_size(10);
+    group.bench_function("unify", |b| {
+        b.iter_batched(
+            || types.clone(),
+            |types| types.into_iter().reduce(DataType::unify),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, inference, folding);
+criterion_main!(benches);
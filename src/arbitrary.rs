@@ -0,0 +1,189 @@
+//! Generators for arbitrary JSON values and data types, and property
+//! tests over the unification algebra built on them. Only compiled
+//! with the `proptest` feature, since the properties take a while to
+//! run.
+
+use std::collections::BTreeSet;
+
+use json::JsonValue;
+use proptest::prelude::*;
+
+use crate::{DataType, Decls, Interner, Options, Temporal, Value};
+
+/// Generate arbitrary JSON values. Object keys are drawn from a small
+/// pool so that unrelated objects share some of their members, and
+/// include numeric keys so that some objects are detected as maps.
+/// Some strings are dates and times.
+pub fn json_value() -> impl Strategy<Value = JsonValue> {
+    let leaf = prop_oneof![
+        Just(JsonValue::Null),
+        any::<bool>().prop_map(JsonValue::Boolean),
+        any::<i32>().prop_map(|n| JsonValue::Number(n.into())),
+        (-1e6..1e6f64).prop_map(|n| JsonValue::Number(n.into())),
+        "[a-z]{0,8}".prop_map(JsonValue::String),
+        prop::sample::select(
+            &[
+                "2023-11-05",
+                "14:30:00",
+                "2023-11-05T14:30:00",
+                "2023-11-05T14:30:00Z",
+            ][..]
+        )
+        .prop_map(|s| JsonValue::String(s.to_string())),
+    ];
+
+    leaf.prop_recursive(4, 32, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(JsonValue::Array),
+            prop::collection::btree_map("[a-e1-2]", inner, 0..6).prop_map(|members| {
+                let mut obj = JsonValue::new_object();
+                for (key, value) in members {
+                    obj[key] = value;
+                }
+                obj
+            }),
+        ]
+    })
+}
+
+/// Generate arbitrary data types, including the unknown type, integer
+/// enums, dates and times, and fixed-length arrays.
+pub fn data_type() -> impl Strategy<Value = DataType> {
+    let detailed = Options {
+        int_enum_threshold: Some(3),
+        detect_dates: true,
+        matrix: true,
+        ..Options::default()
+    };
+    prop_oneof![
+        1 => Just(DataType::Variant(BTreeSet::new())),
+        7 => json_value().prop_map(|v| DataType::from_json_value(&v).unwrap()),
+        2 => json_value().prop_map(move |v| {
+            DataType::from_value_with(&Value::from_json(&v).unwrap(), &detailed).unwrap()
+        }),
+    ]
+}
+
+/// Whether `v` is a value that `typ` can represent.
+fn covers(typ: &DataType, v: &JsonValue) -> bool {
+    match (typ, v) {
+        (DataType::Variant(options), v) => options.iter().any(|t| covers(t, v)),
+        (DataType::Null, JsonValue::Null) => true,
+        (DataType::String, v) => v.is_string(),
+        (DataType::Temporal(kind), v) => v.as_str().and_then(Temporal::detect) == Some(*kind),
+        (DataType::Int, JsonValue::Number(n)) => crate::Number::from(*n).as_i64().is_some(),
+        (DataType::Float, JsonValue::Number(_)) => true,
+        (DataType::IntEnum(codes), JsonValue::Number(n)) => crate::Number::from(*n)
+            .as_i64()
+            .is_some_and(|n| codes.contains(&n)),
+        (DataType::Bool, JsonValue::Boolean(_)) => true,
+        (DataType::Object(members), JsonValue::Object(obj)) => {
+            obj.iter()
+                .all(|(key, value)| members.get(key).is_some_and(|t| covers(t, value)))
+                && members
+                    .iter()
+                    .filter(|(key, _)| obj.get(key).is_none())
+                    .all(|(_, t)| covers(t, &JsonValue::Null))
+        }
+        (DataType::Array(elem), JsonValue::Array(elems)) => elems.iter().all(|v| covers(elem, v)),
+        (DataType::FixedArray(elem, len), JsonValue::Array(elems)) => {
+            elems.len() == *len && elems.iter().all(|v| covers(elem, v))
+        }
+        (DataType::Map(values), JsonValue::Object(obj)) => {
+            obj.iter().all(|(_, v)| covers(values, v))
+        }
+        _ => false,
+    }
+}
+
+/// Whether `s` is a valid (non-raw) Rust identifier.
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+proptest! {
+    #[test]
+    fn unify_commutative(a in data_type(), b in data_type()) {
+        prop_assert_eq!(a.clone().unify(b.clone()), b.unify(a));
+    }
+
+    #[test]
+    fn unify_associative(a in data_type(), b in data_type(), c in data_type()) {
+        prop_assert_eq!(
+            a.clone().unify(b.clone()).unify(c.clone()),
+            a.unify(b.unify(c))
+        );
+    }
+
+    #[test]
+    fn unify_idempotent(a in data_type()) {
+        prop_assert_eq!(a.clone().unify(a.clone()), a);
+    }
+
+    #[test]
+    fn inferred_type_covers_value(v in json_value()) {
+        prop_assert!(covers(&DataType::from_json_value(&v).unwrap(), &v));
+
+        let options = Options {
+            int_enum_threshold: Some(3),
+            detect_dates: true,
+            matrix: true,
+            ..Options::default()
+        };
+        let typ = DataType::from_value_with(&Value::from_json(&v).unwrap(), &options).unwrap();
+        prop_assert!(covers(&typ, &v));
+    }
+
+    #[test]
+    fn unified_type_covers_both(a in json_value(), b in json_value()) {
+        let typ = DataType::from_json_value(&a).unwrap().unify(DataType::from_json_value(&b).unwrap());
+        prop_assert!(covers(&typ, &a));
+        prop_assert!(covers(&typ, &b));
+    }
+
+    #[test]
+    fn example_round_trips(v in json_value()) {
+        let typ = DataType::from_json_value(&v).unwrap();
+        let example = typ.example();
+        prop_assert!(covers(&typ, &example));
+        prop_assert_eq!(
+            typ.clone().unify(DataType::from_json_value(&example).unwrap()),
+            typ
+        );
+    }
+
+    #[test]
+    fn interned_unify_matches_tree(a in json_value(), b in json_value()) {
+        let mut interner = Interner::new();
+        let a = interner.infer(&Value::from_json(&a).unwrap()).unwrap();
+        let b = interner.infer(&Value::from_json(&b).unwrap()).unwrap();
+        let unified = interner.unify(a, b);
+        prop_assert_eq!(
+            interner.to_data_type(unified),
+            interner.to_data_type(a).unify(interner.to_data_type(b))
+        );
+    }
+
+    #[test]
+    fn declared_names_unique(typ in data_type()) {
+        let mut decls = Decls::default();
+        typ.declare(&mut decls);
+
+        let mut names = BTreeSet::new();
+        let output = decls.output();
+        for decl in output.split("\n\n").filter(|decl| !decl.is_empty()) {
+            let name = decl
+                .lines()
+                .skip_while(|line| line.starts_with("#["))
+                .flat_map(str::split_whitespace)
+                .nth(1)
+                .expect("declarations have a name");
+            prop_assert!(is_identifier(name), "invalid identifier {:?}", name);
+            prop_assert!(names.insert(name.to_string()), "duplicate name {:?}", name);
+        }
+    }
+}
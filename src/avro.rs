@@ -0,0 +1,682 @@
+//! Reading of Avro object container files: the records in them, and the
+//! type the schema they're written with gives the records.
+
+use std::{collections::BTreeMap, convert::TryFrom, io::Read};
+
+use flate2::read::DeflateDecoder;
+use json::JsonValue;
+
+use crate::{
+    format::{date_value, time_value, timestamp_value},
+    DataType, DepthExceeded, Error, Format, Number, Value, MAX_DEPTH,
+};
+
+/// The bytes container files start with.
+const MAGIC: &[u8] = b"Obj\x01";
+
+/// The length of the marker written after the header and each block.
+const SYNC_LEN: usize = 16;
+
+/// An Avro schema, as much of it as decoding needs.
+#[derive(Debug, Clone, PartialEq)]
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    /// An `int` counting days since the Unix epoch.
+    Date,
+    /// An `int` or `long` counting time since midnight, in units of
+    /// which there are this many a second.
+    Time(i64),
+    /// A `long` counting time since the Unix epoch, in units of which
+    /// there are `per_second` a second, in UTC unless it's `local`.
+    Timestamp {
+        per_second: i64,
+        local: bool,
+    },
+    /// An unscaled two's complement integer, in `bytes`, or in a
+    /// `fixed` of `size` bytes, and how many of its digits come after
+    /// the decimal point.
+    Decimal {
+        size: Option<usize>,
+        scale: usize,
+    },
+    Fixed(usize),
+    Enum(Vec<String>),
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    Record(Vec<(String, Schema)>),
+    /// The named type with this full name.
+    Named(String),
+}
+
+/// The named types defined in a schema, by their full names.
+type Names = BTreeMap<String, Schema>;
+
+/// What the header of a container file says.
+struct Header {
+    schema: Schema,
+    names: Names,
+    codec: Codec,
+    sync: Vec<u8>,
+}
+
+/// A way the blocks of a container file can be compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Null,
+    Deflate,
+    Snappy,
+    Zstd,
+}
+
+/// Decode the records, one after another, in the container file
+/// `input`.
+pub(crate) fn parse_avro(input: &[u8]) -> Result<Vec<Value>, Error> {
+    let (header, mut rest) = header(input)?;
+    let mut records = Vec::new();
+    while !rest.is_empty() {
+        let start = input.len() - rest.len();
+        let decode_error = |message: String| Error::Decode {
+            format: Format::Avro,
+            path: None,
+            offset: start,
+            message,
+        };
+        let mut block = Decoder::new(rest, &header.names);
+        let count = block.len().map_err(decode_error)?;
+        let data = block.bytes().map_err(decode_error)?;
+        if block.take(SYNC_LEN).map_err(decode_error)? != header.sync {
+            return Err(decode_error("the block's sync marker is wrong".to_string()));
+        }
+        rest = block.input;
+
+        let data = decompress(header.codec, data).map_err(decode_error)?;
+        let mut decoder = Decoder::new(&data, &header.names);
+        for _ in 0..count {
+            let record = decoder.value(&header.schema, 0).map_err(decode_error)?;
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// The type the schema of the container file `input` gives its records.
+/// It says which fields there are and which of them can be null, but
+/// not what the records show better: the types of strings, which may
+/// be dates, of integers, which may be codes, and of maps, which may
+/// look like records.
+pub(crate) fn declared_type(input: &[u8]) -> Result<DataType, Error> {
+    let (header, _) = header(input)?;
+    Ok(schema_type(&header.schema, &header.names, &mut Vec::new()))
+}
+
+/// Read the header of the container file `input`, returning it and the
+/// blocks after it.
+fn header(input: &[u8]) -> Result<(Header, &[u8]), Error> {
+    static NO_NAMES: Names = BTreeMap::new();
+    let mut decoder = Decoder::new(input, &NO_NAMES);
+    let read = |decoder: &mut Decoder| {
+        if decoder.take(MAGIC.len())? != MAGIC {
+            return Err("not an Avro object container file".to_string());
+        }
+        let mut metadata = BTreeMap::new();
+        loop {
+            let len = decoder.block_len()?;
+            if len == 0 {
+                break;
+            }
+            for _ in 0..len {
+                let key = decoder.string()?;
+                metadata.insert(key, decoder.bytes()?);
+            }
+        }
+        let schema = metadata
+            .get("avro.schema")
+            .ok_or("the file has no schema")?;
+        let schema = std::str::from_utf8(schema).map_err(|_| "the schema isn't UTF-8")?;
+        let schema = json::parse(schema).map_err(|e| format!("invalid schema: {}", e))?;
+        let mut names = Names::new();
+        let schema = parse_schema(&schema, "", &mut names)?;
+        let codec = match metadata.get("avro.codec").map(|codec| &codec[..]) {
+            None | Some(b"null") => Codec::Null,
+            Some(b"deflate") => Codec::Deflate,
+            Some(b"snappy") => Codec::Snappy,
+            Some(b"zstandard") => Codec::Zstd,
+            Some(codec) => {
+                return Err(format!(
+                    "blocks compressed with {:?} can't be read",
+                    String::from_utf8_lossy(codec)
+                ))
+            }
+        };
+        let sync = decoder.take(SYNC_LEN)?.to_vec();
+        Ok(Header {
+            schema,
+            names,
+            codec,
+            sync,
+        })
+    };
+    match read(&mut decoder) {
+        Ok(header) => Ok((header, decoder.input)),
+        Err(message) => Err(Error::Decode {
+            format: Format::Avro,
+            path: None,
+            offset: input.len() - decoder.input.len(),
+            message,
+        }),
+    }
+}
+
+/// Decompress a block compressed with `codec`.
+fn decompress(codec: Codec, data: &[u8]) -> Result<Vec<u8>, String> {
+    match codec {
+        Codec::Null => Ok(data.to_vec()),
+        Codec::Deflate => {
+            let mut output = Vec::new();
+            DeflateDecoder::new(data)
+                .read_to_end(&mut output)
+                .map_err(|e| e.to_string())?;
+            Ok(output)
+        }
+        // Snappy blocks end with a checksum of what they decompress to.
+        Codec::Snappy => snap::raw::Decoder::new()
+            .decompress_vec(&data[..data.len().saturating_sub(4)])
+            .map_err(|e| e.to_string()),
+        Codec::Zstd => zstd::decode_all(data).map_err(|e| e.to_string()),
+    }
+}
+
+/// Parse the JSON form of a schema, which is in `namespace`, defining
+/// the named types in it in `names`.
+fn parse_schema(json: &JsonValue, namespace: &str, names: &mut Names) -> Result<Schema, String> {
+    if let Some(name) = json.as_str() {
+        return Ok(match name {
+            "null" => Schema::Null,
+            "boolean" => Schema::Boolean,
+            "int" => Schema::Int,
+            "long" => Schema::Long,
+            "float" => Schema::Float,
+            "double" => Schema::Double,
+            "bytes" => Schema::Bytes,
+            "string" => Schema::String,
+            name => {
+                let full_name = full_name(name, namespace);
+                match (names.contains_key(&full_name), names.contains_key(name)) {
+                    (true, _) => Schema::Named(full_name),
+                    (false, true) => Schema::Named(name.to_string()),
+                    (false, false) => return Err(format!("the schema has no type {:?}", name)),
+                }
+            }
+        });
+    }
+    if let JsonValue::Array(options) = json {
+        return Ok(Schema::Union(
+            options
+                .iter()
+                .map(|option| parse_schema(option, namespace, names))
+                .collect::<Result<_, _>>()?,
+        ));
+    }
+    if !json.is_object() {
+        return Err(format!("{} isn't a schema", json.dump()));
+    }
+
+    // Named types are defined before what's in them is parsed, so that
+    // records can hold themselves.
+    let define = |names: &mut Names| {
+        let name = json["name"]
+            .as_str()
+            .ok_or_else(|| format!("{} has no name", json.dump()))?;
+        let namespace = json["namespace"].as_str().unwrap_or(namespace);
+        let full_name = full_name(name, namespace);
+        names.insert(full_name.clone(), Schema::Null);
+        Ok::<_, String>(full_name)
+    };
+    let logical = json["logicalType"].as_str();
+    let schema = match json["type"].as_str() {
+        Some("record" | "error") => {
+            let full_name = define(names)?;
+            let namespace = full_name
+                .rsplit_once('.')
+                .map_or("", |(namespace, _)| namespace);
+            let fields = json["fields"]
+                .members()
+                .map(|field| {
+                    let name = field["name"]
+                        .as_str()
+                        .ok_or_else(|| format!("{} has no name", field.dump()))?;
+                    Ok((
+                        name.to_string(),
+                        parse_schema(&field["type"], namespace, names)?,
+                    ))
+                })
+                .collect::<Result<_, String>>()?;
+            names.insert(full_name.clone(), Schema::Record(fields));
+            return Ok(Schema::Named(full_name));
+        }
+        Some("enum") => {
+            let full_name = define(names)?;
+            let symbols = json["symbols"]
+                .members()
+                .map(|symbol| symbol.as_str().unwrap_or_default().to_string())
+                .collect();
+            names.insert(full_name.clone(), Schema::Enum(symbols));
+            return Ok(Schema::Named(full_name));
+        }
+        Some("fixed") => {
+            let full_name = define(names)?;
+            let size = json["size"]
+                .as_usize()
+                .ok_or_else(|| format!("{} has no size", json.dump()))?;
+            let fixed = match logical {
+                Some("decimal") => Schema::Decimal {
+                    size: Some(size),
+                    scale: json["scale"].as_usize().unwrap_or(0),
+                },
+                _ => Schema::Fixed(size),
+            };
+            names.insert(full_name.clone(), fixed);
+            return Ok(Schema::Named(full_name));
+        }
+        Some("array") => Schema::Array(Box::new(parse_schema(&json["items"], namespace, names)?)),
+        Some("map") => Schema::Map(Box::new(parse_schema(&json["values"], namespace, names)?)),
+        _ => parse_schema(&json["type"], namespace, names)?,
+    };
+
+    // Logical types that aren't known, or aren't on the type they go
+    // with, are read as the type they're on.
+    Ok(match (schema, logical) {
+        (Schema::Int, Some("date")) => Schema::Date,
+        (Schema::Int, Some("time-millis")) => Schema::Time(1_000),
+        (Schema::Long, Some("time-micros")) => Schema::Time(1_000_000),
+        (Schema::Long, Some(logical)) if logical.contains("timestamp-") => {
+            let per_second = match logical.rsplit('-').next() {
+                Some("millis") => 1_000,
+                Some("micros") => 1_000_000,
+                Some("nanos") => 1_000_000_000,
+                _ => return Ok(Schema::Long),
+            };
+            Schema::Timestamp {
+                per_second,
+                local: logical.starts_with("local-"),
+            }
+        }
+        (Schema::Bytes, Some("decimal")) => Schema::Decimal {
+            size: None,
+            scale: json["scale"].as_usize().unwrap_or(0),
+        },
+        (schema, _) => schema,
+    })
+}
+
+/// The full name of the named type `name` defined in `namespace`.
+fn full_name(name: &str, namespace: &str) -> String {
+    match name.contains('.') || namespace.is_empty() {
+        true => name.to_string(),
+        false => format!("{}.{}", namespace, name),
+    }
+}
+
+/// The type a schema gives values, as `declared_type` describes. Named
+/// types already being expanded, in `expanding`, are unknown, so that
+/// records that hold themselves end.
+fn schema_type<'a>(schema: &'a Schema, names: &'a Names, expanding: &mut Vec<&'a str>) -> DataType {
+    match schema {
+        Schema::Null => DataType::Null,
+        Schema::Boolean => DataType::Bool,
+        Schema::Float | Schema::Double => DataType::Float,
+        Schema::Bytes | Schema::Fixed(_) => DataType::Bytes,
+        Schema::Int
+        | Schema::Long
+        | Schema::String
+        | Schema::Date
+        | Schema::Time(_)
+        | Schema::Timestamp { .. }
+        | Schema::Decimal { .. }
+        | Schema::Enum(_)
+        | Schema::Map(_) => DataType::unknown(),
+        Schema::Array(items) => DataType::Array(Box::new(schema_type(items, names, expanding))),
+        Schema::Union(options) => options.iter().fold(DataType::unknown(), |typ, option| {
+            typ.unify(schema_type(option, names, expanding))
+        }),
+        Schema::Record(fields) => DataType::Object(
+            fields
+                .iter()
+                .map(|(name, schema)| (name.clone(), schema_type(schema, names, expanding)))
+                .collect(),
+        ),
+        Schema::Named(name) if expanding.contains(&name.as_str()) => DataType::unknown(),
+        Schema::Named(name) => {
+            expanding.push(name);
+            let typ = schema_type(&names[name], names, expanding);
+            expanding.pop();
+            typ
+        }
+    }
+}
+
+/// A reader of values in Avro's binary encoding.
+struct Decoder<'a> {
+    /// What's left to read.
+    input: &'a [u8],
+    names: &'a Names,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(input: &'a [u8], names: &'a Names) -> Self {
+        Decoder { input, names }
+    }
+
+    /// The next `len` bytes.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.input.len() {
+            return Err("unexpected end of input".to_string());
+        }
+        let (taken, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(taken)
+    }
+
+    /// A zigzag-encoded variable-length integer.
+    fn long(&mut self) -> Result<i64, String> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((n >> 1) as i64 ^ -((n & 1) as i64));
+            }
+        }
+        Err("an integer is too long".to_string())
+    }
+
+    /// A length, which can't be negative.
+    fn len(&mut self) -> Result<usize, String> {
+        usize::try_from(self.long()?).map_err(|_| "a length is negative".to_string())
+    }
+
+    /// A string of bytes, after its length.
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    /// A string of characters, after its length.
+    fn string(&mut self) -> Result<String, String> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "a string isn't UTF-8".to_string())
+    }
+
+    /// How many items there are in the next block of an array or map,
+    /// or 0 after the last block.
+    fn block_len(&mut self) -> Result<usize, String> {
+        let count = self.long()?;
+        if count < 0 {
+            // Blocks with negative counts say how many bytes they are.
+            self.long()?;
+        }
+        let count = usize::try_from(count.unsigned_abs()).map_err(|e| e.to_string())?;
+        // Every item but nulls takes at least a byte, so a count past
+        // the end of the input is wrong, and would take long to read.
+        if count > self.input.len() {
+            return Err(format!(
+                "a block of {} items doesn't fit in the input",
+                count
+            ));
+        }
+        Ok(count)
+    }
+
+    /// A value of the type `schema`, which is nested `depth` levels
+    /// into its record.
+    fn value(&mut self, schema: &Schema, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err(DepthExceeded.to_string());
+        }
+
+        let float = |n: f64| {
+            Number::from_f64(n)
+                .map(Value::Number)
+                .ok_or_else(|| format!("{} isn't a number JSON can represent", n))
+        };
+        Ok(match schema {
+            Schema::Null => Value::Null,
+            Schema::Boolean => Value::Bool(self.take(1)?[0] != 0),
+            Schema::Int | Schema::Long => Value::Number(self.long()?.into()),
+            Schema::Float => {
+                let bytes = <[u8; 4]>::try_from(self.take(4)?).expect("took 4 bytes");
+                float(f32::from_le_bytes(bytes).into())?
+            }
+            Schema::Double => {
+                let bytes = <[u8; 8]>::try_from(self.take(8)?).expect("took 8 bytes");
+                float(f64::from_le_bytes(bytes))?
+            }
+            Schema::Bytes => Value::Bytes(self.bytes()?.to_vec()),
+            Schema::Fixed(size) => Value::Bytes(self.take(*size)?.to_vec()),
+            Schema::String => Value::String(self.string()?),
+            Schema::Date => date_value(self.long()?),
+            Schema::Time(per_second) => time_value(self.long()?, *per_second),
+            Schema::Timestamp { per_second, local } => {
+                timestamp_value(self.long()?, *per_second, *local)
+            }
+            Schema::Decimal { size, scale } => {
+                let bytes = match size {
+                    Some(size) => self.take(*size)?,
+                    None => self.bytes()?,
+                };
+                Value::Number(decimal(bytes, *scale)?)
+            }
+            Schema::Enum(symbols) => {
+                let idx = self.long()?;
+                let symbol = usize::try_from(idx).ok().and_then(|idx| symbols.get(idx));
+                Value::String(
+                    symbol
+                        .ok_or_else(|| format!("enum symbol {} doesn't exist", idx))?
+                        .clone(),
+                )
+            }
+            Schema::Array(items) => {
+                let mut elems = Vec::new();
+                loop {
+                    let len = self.block_len()?;
+                    if len == 0 {
+                        break;
+                    }
+                    for _ in 0..len {
+                        elems.push(self.value(items, depth + 1)?);
+                    }
+                }
+                Value::Array(elems)
+            }
+            Schema::Map(values) => {
+                let mut members = Vec::new();
+                loop {
+                    let len = self.block_len()?;
+                    if len == 0 {
+                        break;
+                    }
+                    for _ in 0..len {
+                        let key = self.string()?;
+                        members.push((key, self.value(values, depth + 1)?));
+                    }
+                }
+                Value::Object(members)
+            }
+            Schema::Union(options) => {
+                let idx = self.long()?;
+                let option = usize::try_from(idx).ok().and_then(|idx| options.get(idx));
+                let option = option.ok_or_else(|| format!("union branch {} doesn't exist", idx))?;
+                self.value(option, depth)?
+            }
+            Schema::Record(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, schema)| Ok((name.clone(), self.value(schema, depth + 1)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            Schema::Named(name) => {
+                let names = self.names;
+                self.value(&names[name], depth)?
+            }
+        })
+    }
+}
+
+/// The number in a decimal's bytes, an unscaled two's complement
+/// integer with `scale` digits after the decimal point.
+fn decimal(bytes: &[u8], scale: usize) -> Result<Number, String> {
+    if bytes.len() > 16 {
+        return Err("a decimal is too big to read".to_string());
+    }
+    let negative = bytes.first().is_some_and(|byte| byte & 0x80 != 0);
+    let mut unscaled = [if negative { 0xff } else { 0 }; 16];
+    unscaled[16 - bytes.len()..].copy_from_slice(bytes);
+    let unscaled = i128::from_be_bytes(unscaled);
+
+    let digits = format!("{:0>width$}", unscaled.unsigned_abs(), width = scale + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - scale);
+    let sign = if negative { "-" } else { "" };
+    let text = match fraction.is_empty() {
+        true => format!("{}{}", sign, whole),
+        false => format!("{}{}.{}", sign, whole, fraction),
+    };
+    text.parse()
+        .map_err(|_| format!("decimal {} isn't a number", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode `n` as a zigzag variable-length integer.
+    fn long(n: i64) -> Vec<u8> {
+        let mut n = ((n << 1) ^ (n >> 63)) as u64;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+            match n {
+                0 => break bytes.push(byte),
+                _ => bytes.push(byte | 0x80),
+            }
+        }
+        bytes
+    }
+
+    /// Encode `s` as a string, after its length.
+    fn string(s: &[u8]) -> Vec<u8> {
+        [long(s.len() as i64), s.to_vec()].concat()
+    }
+
+    #[test]
+    fn container_files() {
+        let schema = br#"{"type": "record", "name": "User", "namespace": "example", "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "name", "type": ["null", "string"]},
+            {"name": "born", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "price", "type": {"type": "bytes", "logicalType": "decimal", "precision": 5, "scale": 2}},
+            {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B"]}},
+            {"name": "tags", "type": {"type": "map", "values": "double"}},
+            {"name": "next", "type": ["null", "User"]}
+        ]}"#;
+        let sync = [7u8; SYNC_LEN];
+        let mut records = Vec::new();
+        records.extend(long(1));
+        records.extend(long(1));
+        records.extend(string(b"a"));
+        records.extend(long(19_675));
+        records.extend(string(&[0x00, 0x96]));
+        records.extend(long(1));
+        records.extend(long(1));
+        records.extend(string(b"x"));
+        records.extend(1.5f64.to_le_bytes());
+        records.extend(long(0));
+        records.extend(long(1));
+        // The record held in `next`.
+        records.extend(long(2));
+        records.extend(long(0));
+        records.extend(long(0));
+        records.extend(string(&[0xfb]));
+        records.extend(long(0));
+        records.extend(long(0));
+        records.extend(long(0));
+
+        let mut input = MAGIC.to_vec();
+        input.extend(long(2));
+        input.extend(string(b"avro.schema"));
+        input.extend(string(schema));
+        input.extend(string(b"avro.codec"));
+        input.extend(string(b"deflate"));
+        input.extend(long(0));
+        input.extend(sync);
+        let mut compressed = flate2::write::DeflateEncoder::new(Vec::new(), Default::default());
+        std::io::Write::write_all(&mut compressed, &records).unwrap();
+        input.extend(long(1));
+        input.extend(string(&compressed.finish().unwrap()));
+        input.extend(sync);
+
+        let user = |id: u64, name: Value, born: &str, price: &str, kind: &str, tags, next| {
+            Value::Object(vec![
+                ("id".to_string(), Value::Number(id.into())),
+                ("name".to_string(), name),
+                ("born".to_string(), Value::String(born.to_string())),
+                ("price".to_string(), Value::Number(price.parse().unwrap())),
+                ("kind".to_string(), Value::String(kind.to_string())),
+                ("tags".to_string(), Value::Object(tags)),
+                ("next".to_string(), next),
+            ])
+        };
+        assert_eq!(
+            parse_avro(&input).unwrap(),
+            vec![user(
+                1,
+                Value::String("a".to_string()),
+                "2023-11-14",
+                "1.50",
+                "B",
+                vec![("x".to_string(), Value::Number("1.5".parse().unwrap()))],
+                user(
+                    2,
+                    Value::Null,
+                    "1970-01-01",
+                    "-0.05",
+                    "A",
+                    vec![],
+                    Value::Null
+                ),
+            )]
+        );
+
+        let fields = match declared_type(&input).unwrap() {
+            DataType::Object(fields) => fields,
+            typ => panic!("{:?}", typ),
+        };
+        assert_eq!(fields["name"], DataType::Null);
+        assert_eq!(fields["id"], DataType::unknown());
+        // The record holds itself in `next`, which the records say
+        // more about than the schema.
+        assert_eq!(fields["next"], DataType::Null);
+
+        let mut damaged = input.clone();
+        *damaged.last_mut().unwrap() = 0;
+        assert!(matches!(
+            parse_avro(&damaged),
+            Err(Error::Decode {
+                format: Format::Avro,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_avro(b"Obj\x02"),
+            Err(Error::Decode { offset: 4, .. })
+        ));
+    }
+}
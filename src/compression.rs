@@ -0,0 +1,118 @@
+//! The compression formats inputs can be read through.
+
+use std::{
+    fmt,
+    io::{self, Read},
+    path::Path,
+};
+
+use flate2::read::MultiGzDecoder;
+
+/// A compression format inputs can be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip, with any number of members one after another.
+    Gzip,
+
+    /// Zstandard, with any number of frames one after another.
+    Zstd,
+}
+
+impl Compression {
+    /// Every compression format.
+    pub const ALL: &'static [Compression] = &[Compression::Gzip, Compression::Zstd];
+
+    /// The extension files compressed in this format have after the
+    /// extension of the format they're in.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// The bytes compressed input in this format starts with.
+    fn magic(self) -> &'static [u8] {
+        match self {
+            Compression::Gzip => b"\x1f\x8b",
+            Compression::Zstd => b"\x28\xb5\x2f\xfd",
+        }
+    }
+
+    /// The compression of the file at `path`, going by its extension,
+    /// if it's one of a compression format's.
+    pub fn of_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Compression::ALL
+            .iter()
+            .copied()
+            .find(|compression| compression.extension() == extension)
+    }
+
+    /// The compression of `input`, going by the bytes it starts with.
+    pub fn of_input(input: &[u8]) -> Option<Self> {
+        Compression::ALL
+            .iter()
+            .copied()
+            .find(|compression| input.starts_with(compression.magic()))
+    }
+
+    /// Decompress `input`.
+    pub fn decompress(self, input: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.decoder(input)?.read_to_end(&mut output)?;
+        Ok(output)
+    }
+
+    /// A reader of what `reader` decompresses to, decompressing it as
+    /// it's read.
+    pub fn decoder<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        })
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "Zstandard",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression as Level};
+
+    use super::*;
+
+    #[test]
+    fn decompression() {
+        let mut gzip = GzEncoder::new(Vec::new(), Level::default());
+        gzip.write_all(b"[1, 2]").unwrap();
+        let gzip = gzip.finish().unwrap();
+        let zstd = zstd::encode_all(&b"[1, 2]"[..], 0).unwrap();
+
+        assert_eq!(Compression::of_input(&gzip), Some(Compression::Gzip));
+        assert_eq!(Compression::of_input(&zstd), Some(Compression::Zstd));
+        assert_eq!(Compression::of_input(b"[1, 2]"), None);
+        assert_eq!(Compression::Gzip.decompress(&gzip).unwrap(), b"[1, 2]");
+        assert_eq!(Compression::Zstd.decompress(&zstd).unwrap(), b"[1, 2]");
+        assert!(Compression::Gzip.decompress(b"[1, 2]").is_err());
+
+        assert_eq!(
+            Compression::of_path(Path::new("dump.json.GZ")),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::of_path(Path::new("dump.zst")),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::of_path(Path::new("dump.json")), None);
+    }
+}
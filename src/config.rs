@@ -0,0 +1,158 @@
+//! Loading options from a configuration file, so that they don't have
+//! to be given on the command line every time.
+//!
+//! The file is TOML, with a key for each field of `Options`:
+//!
+//! ```toml
+//! lang = "rust"
+//! explicit_default = true
+//! emit_main = "anyhow"
+//! ignore = ["$.users[].debug"]
+//!
+//! [names]
+//! "$.users[]" = "User"
+//! ```
+//!
+//! Settings on the command line take precedence over the file, and the
+//! file over the built-in defaults.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{Options, MAX_INT_ENUM_CODES};
+
+/// Name of the configuration file looked for in the working directory
+/// when none is given.
+pub const CONFIG_FILE: &str = "json-analyzer.toml";
+
+/// Error returned when a configuration file can't be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read.
+    Io(PathBuf, io::Error),
+
+    /// The file isn't a valid configuration file.
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => {
+                write!(f, "failed to read configuration file {:?}: {}", path, e)
+            }
+            ConfigError::Invalid(path, why) => {
+                write!(f, "invalid configuration file {:?}: {}", path, why)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Options {
+    /// Load the options set in the configuration file at `path`. Those
+    /// it doesn't set have their defaults.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_owned(), e))?;
+        Self::from_toml(&text).map_err(|why| ConfigError::Invalid(path.to_owned(), why))
+    }
+
+    /// Parse the options set in the text of a configuration file.
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let options: Options = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        if let Some(fraction) = options.min_presence {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(format!(
+                    "min_presence must be a number from 0 to 1, not {}",
+                    fraction
+                ));
+            }
+        }
+        if options.sample_limit == Some(0) {
+            return Err("sample_limit must be a positive integer, not 0".to_string());
+        }
+        if let Some(threshold) = options.int_enum_threshold {
+            if !(1..=MAX_INT_ENUM_CODES).contains(&threshold) {
+                return Err(format!(
+                    "int_enum_threshold must be an integer from 1 to {}, not {}",
+                    MAX_INT_ENUM_CODES, threshold
+                ));
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{config_snippet, Format, Lang, MainErrors, MapType, PathsFormat, WarningsFormat};
+
+    #[test]
+    fn from_toml() {
+        let options = Options::from_toml(
+            "lang = \"proto\"\n\
+             map_type = \"btree\"\n\
+             explicit_default = true\n\
+             emit_main = \"anyhow\"\n\
+             annotate_paths = \"dotted\"\n\
+             derives = [\"Clone\"]\n\
+             int_enum_threshold = 4\n\
+             format = \"yaml\"\n\
+             warnings_format = \"json\"\n\
+             \n\
+             [code_names.\"$.status\"]\n\
+             0 = \"Active\"\n",
+        )
+        .unwrap();
+        assert_eq!(options.lang, Lang::Proto);
+        assert_eq!(options.map_type, MapType::BTree);
+        assert!(options.explicit_default);
+        assert!(!options.emit_helpers);
+        assert_eq!(options.emit_main, Some(MainErrors::Anyhow));
+        assert_eq!(options.annotate_paths, Some(PathsFormat::Dotted));
+        assert_eq!(options.derives, ["Clone"]);
+        assert_eq!(options.int_enum_threshold, Some(4));
+        assert_eq!(options.code_name("$.status", 0), "Active");
+        assert_eq!(options.format, Some(Format::Yaml));
+        assert_eq!(options.warnings_format, WarningsFormat::Json);
+
+        for bad in [
+            "lang = \"cobol\"",
+            "explicit_defaults = true",
+            "min_presence = 2.0",
+            "int_enum_threshold = 65",
+            "warnings_format = \"xml\"",
+        ] {
+            assert!(Options::from_toml(bad).is_err(), "{:?} was accepted", bad);
+        }
+    }
+
+    #[test]
+    fn snippets_load() {
+        let mut options = Options::default();
+        options.ignore.insert("$.users[].debug".to_string());
+        options
+            .names
+            .insert("$.users[]".to_string(), "User \"1\"".to_string());
+        options
+            .types
+            .insert("$.users[].id".to_string(), "u64".to_string());
+
+        let loaded = Options::from_toml(&config_snippet(&options)).unwrap();
+        assert_eq!(loaded.ignore, options.ignore);
+        assert_eq!(loaded.names, options.names);
+        assert_eq!(loaded.types, options.types);
+    }
+}
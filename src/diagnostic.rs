@@ -0,0 +1,298 @@
+//! Writing errors out for people to read at a terminal: with the text
+//! around a parse error and a caret under it, and a suggestion for
+//! fixing common mistakes.
+
+use std::{
+    error::Error as StdError,
+    fmt::Write,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use owo_colors::Style;
+
+use crate::{error::offset, Error, Format, Warnings};
+
+/// Whether diagnostics written to a stream should be in color: only if
+/// it's a terminal and `NO_COLOR` isn't set to anything.
+pub fn use_color(is_terminal: bool) -> bool {
+    is_terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// Describe `error` and what caused it, one cause per line. Causes
+/// whose text the error already includes are left out. Parse errors in
+/// files show the line they were found on, if it can still be read,
+/// and a suggestion for fixing them if the mistake is a common one.
+pub fn render(error: &(dyn StdError + 'static), color: bool) -> String {
+    let paint = |style: Style, text: &str| match color {
+        true => style.style(text).to_string(),
+        false => text.to_string(),
+    };
+
+    let message = error.to_string();
+    let mut s = format!(
+        "{} {}\n",
+        paint(Style::new().red().bold(), "error:"),
+        paint(Style::new().bold(), &message)
+    );
+    let mut shown = message;
+    let mut cause = error.source();
+    while let Some(e) = cause {
+        let message = e.to_string();
+        if !shown.contains(&message) {
+            writeln!(s, "  caused by: {}", message).expect("writing to a String can't fail");
+            shown = message;
+        }
+        cause = e.source();
+    }
+
+    let parse_error = chain(error).find_map(|e| match e.downcast_ref::<Error>() {
+        Some(Error::Parse {
+            format,
+            path: Some(path),
+            line,
+            column,
+            ..
+        }) => Some((*format, path, *line, *column)),
+        _ => None,
+    });
+    if let Some((format, path, line, column)) = parse_error {
+        if let Some((context, source_line)) = read_line(path, line) {
+            let blue = Style::new().blue().bold();
+            let number = line.to_string();
+            let gutter = " ".repeat(number.len());
+            let (excerpt, caret) = excerpt(&source_line, column);
+            writeln!(
+                s,
+                "{}{} {}:{}:{}\n{} {}\n{} {} {}\n{} {} {}{}",
+                gutter,
+                paint(blue, "-->"),
+                path.display(),
+                line,
+                column,
+                gutter,
+                paint(blue, "|"),
+                paint(blue, &number),
+                paint(blue, "|"),
+                excerpt,
+                gutter,
+                paint(blue, "|"),
+                " ".repeat(caret),
+                paint(Style::new().red().bold(), "^"),
+            )
+            .expect("writing to a String can't fail");
+            // The suggestions are for mistakes in JSON.
+            let help = match format {
+                Format::Json => {
+                    let text = format!("{}{}", context, source_line);
+                    suggestion(&text, context.matches('\n').count() + 1, column)
+                }
+                _ => None,
+            };
+            if let Some(help) = help {
+                writeln!(s, "{} {} {}", gutter, paint(blue, "="), help)
+                    .expect("writing to a String can't fail");
+            }
+        }
+    }
+    s
+}
+
+/// Describe the distinct warnings in `warnings`, one per line, as
+/// `Warnings::to_text` does.
+pub fn render_warnings(warnings: &Warnings, color: bool) -> String {
+    let text = warnings.to_text();
+    if !color {
+        return text;
+    }
+    let prefix = Style::new().yellow().bold().style("warning:").to_string();
+    text.lines()
+        .map(|line| match line.strip_prefix("warning:") {
+            Some(rest) => format!("{}{}\n", prefix, rest),
+            None => format!("{}\n", line),
+        })
+        .collect()
+}
+
+/// How many characters of a line are shown around the place a parse
+/// error was found in it.
+const EXCERPT_WIDTH: usize = 80;
+
+/// Read `path` up to line `line`, counting from 1, returning the line
+/// with its line break, and the lines before it back to the last one
+/// that isn't blank, which are all that suggestions look back at. The
+/// file is read a line at a time, so huge inputs aren't read again
+/// whole to show one line of them.
+fn read_line(path: &Path, line: usize) -> Option<(String, String)> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut context = String::new();
+    let mut current = String::new();
+    for _ in 0..line {
+        if !current.trim().is_empty() {
+            context.clear();
+        }
+        context.push_str(&current);
+        current.clear();
+        if reader.read_line(&mut current).ok()? == 0 {
+            break;
+        }
+    }
+    Some((context, current))
+}
+
+/// The part of `line` shown around `column`, counting characters from
+/// 1, with `…` where it was cut, along with how many characters into it
+/// `column` is.
+fn excerpt(line: &str, column: usize) -> (String, usize) {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let chars = line.chars().collect::<Vec<_>>();
+    let at = column.saturating_sub(1).min(chars.len());
+    let start = at.saturating_sub(EXCERPT_WIDTH / 2);
+    let end = chars.len().min(start + EXCERPT_WIDTH);
+    let start = end.saturating_sub(EXCERPT_WIDTH).min(start);
+
+    let mut s = String::new();
+    let mut caret = at - start;
+    if start > 0 {
+        s.push('…');
+        caret += 1;
+    }
+    // Tabs would throw the caret off, so show them as spaces.
+    s.extend(chars[start..end].iter().map(|&c| match c {
+        '\t' => ' ',
+        c => c,
+    }));
+    if end < chars.len() {
+        s.push('…');
+    }
+    (s, caret)
+}
+
+/// A suggestion for fixing the mistake that made `text` fail to parse
+/// at `line` and `column`, if it's a common one.
+pub fn suggestion(text: &str, line: usize, column: usize) -> Option<&'static str> {
+    let offset = offset(text, line, column)?;
+    let (before, after) = text.split_at(offset);
+    let found = after.chars().next();
+    let previous = before.trim_end().chars().last();
+
+    if found.is_none() {
+        return Some("help: the document ends early; check for an unclosed bracket or string");
+    }
+    if matches!(found, Some('}' | ']')) && previous == Some(',') {
+        return Some(
+            "help: JSON doesn't allow a comma after the last element; remove it, or read JSON5 \
+             with --lenient",
+        );
+    }
+    if found == Some('\'') {
+        return Some(
+            "help: JSON strings are written in double quotes, not single quotes; read JSON5 \
+             with --lenient to allow them",
+        );
+    }
+    if found == Some('/') || found == Some('#') {
+        return Some(
+            "help: JSON doesn't allow comments; remove them, or read JSON with comments with \
+             --lenient",
+        );
+    }
+    if after.starts_with("NaN") || after.starts_with("Infinity") || after.starts_with("-Infinity") {
+        return Some("help: JSON has no NaN or infinite numbers; write them as null or strings");
+    }
+    if found.is_some_and(|c| c.is_alphabetic() || c == '_') && matches!(previous, Some('{' | ',')) {
+        return Some(
+            "help: object keys have to be strings in double quotes; read JSON5 with --lenient \
+             to allow bare keys",
+        );
+    }
+    None
+}
+
+/// `error` followed by the errors that caused it.
+fn chain<'a>(
+    error: &'a (dyn StdError + 'static),
+) -> impl Iterator<Item = &'a (dyn StdError + 'static)> {
+    std::iter::successors(Some(error), |&e| e.source())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use crate::Value;
+
+    /// The suggestion for the error found parsing `text`.
+    fn suggest(text: &str) -> Option<&'static str> {
+        match Value::parse_json(text) {
+            Err(Error::Parse { line, column, .. }) => suggestion(text, line, column),
+            other => panic!("{:?} parsed as {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn suggestions() {
+        for (text, suggested) in [
+            ("{\"a\": 1,\n}", "comma after the last element"),
+            ("[1, 2, ]", "comma after the last element"),
+            ("{'a': 1}", "double quotes"),
+            ("{\"a\": 1 // one\n}", "comments"),
+            ("[NaN]", "NaN"),
+            ("{a: 1}", "keys"),
+            ("{\"a\": [1, 2", "ends early"),
+        ] {
+            let suggestion = suggest(text).unwrap_or_else(|| panic!("{:?}", text));
+            assert!(suggestion.contains(suggested), "{:?}: {}", text, suggestion);
+        }
+        assert_eq!(suggest("[1 2]"), None);
+    }
+
+    #[test]
+    fn rendering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.json");
+        fs::write(&path, "{\n\t\"a\": 1,\n}\n").unwrap();
+        let error = Value::read_json(&path).unwrap_err();
+
+        let plain = render(&error, false);
+        assert_eq!(
+            plain,
+            format!(
+                "error: invalid JSON in {:?} at line 3, column 1: unexpected character '}}'\n \
+                 --> {}:3:1\n  |\n3 | }}\n  | ^\n  = help: JSON doesn't allow a comma after the \
+                 last element; remove it, or read JSON5 with --lenient\n",
+                path,
+                path.display()
+            )
+        );
+        let colored = render(&error, true);
+        assert!(colored.contains("\u{1b}["));
+        assert_ne!(colored, plain);
+
+        // Long lines are cut down to the part around the error, and
+        // the caret counts characters, not bytes.
+        let long = dir.path().join("long.json");
+        fs::write(&long, format!("[{}\"é\", 1 2]", "1, ".repeat(50_000))).unwrap();
+        let error = Value::read_json(&long).unwrap_err();
+        let rendered = render(&error, false);
+        let lines = rendered.lines().collect::<Vec<_>>();
+        let excerpt = lines[3].split_once("| ").unwrap().1;
+        assert!(excerpt.starts_with('…') && excerpt.ends_with("\"é\", 1 2]"));
+        assert_eq!(excerpt.chars().count(), EXCERPT_WIDTH + 1);
+        let caret = lines[4].split_once("| ").unwrap().1;
+        assert_eq!(excerpt.chars().nth(caret.len() - 1), Some('2'));
+
+        let missing = Value::read_json(&dir.path().join("missing.json")).unwrap_err();
+        assert_eq!(render(&missing, false).lines().count(), 1);
+
+        let mut warnings = Warnings::default();
+        warnings.push("$.a", crate::WarningKind::EmptyArray, "");
+        assert_eq!(render_warnings(&warnings, false), warnings.to_text());
+        assert!(render_warnings(&warnings, true)
+            .ends_with(" $.a: arrays were always empty, so their element type is unknown\n"));
+    }
+}
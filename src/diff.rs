@@ -0,0 +1,279 @@
+//! Comparing the types inferred from two samples of the same data, such
+//! as yesterday's and today's responses from an API, to see how its
+//! shape changed.
+
+use std::fmt;
+
+use crate::DataType;
+
+/// The ways the type at a path can change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+    /// An object member that wasn't there before, and whether it can be
+    /// null.
+    FieldAdded {
+        /// Whether the member can be null.
+        optional: bool,
+    },
+
+    /// An object member that isn't there any more.
+    FieldRemoved,
+
+    /// A value of a different kind than before, or of the same kind
+    /// with a different range, like an integer that became a float.
+    TypeChanged {
+        /// What the value was before.
+        from: String,
+        /// What the value is now.
+        to: String,
+    },
+
+    /// A kind of value that a value can be now and couldn't before,
+    /// like null for a value that became nullable.
+    ArmAdded(String),
+
+    /// A kind of value that a value could be before and can't now.
+    ArmRemoved(String),
+}
+
+/// A change to the type at one path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// The path of the value that changed, such as `$.users[].id`.
+    pub path: String,
+
+    /// How it changed.
+    pub kind: DiffKind,
+
+    /// Whether declarations made from the old type can fail to read
+    /// data of the new one. New members don't break them, since
+    /// unknown members are ignored, and neither do members that were
+    /// optional going away; but new kinds of values do.
+    pub breaking: bool,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            DiffKind::FieldAdded { optional: true } => write!(f, "optional member added"),
+            DiffKind::FieldAdded { optional: false } => write!(f, "member added"),
+            DiffKind::FieldRemoved => write!(f, "member removed"),
+            DiffKind::TypeChanged { from, to } => write!(f, "changed from {} to {}", from, to),
+            DiffKind::ArmAdded(kind) => write!(f, "can now be {}", kind),
+            DiffKind::ArmRemoved(kind) => write!(f, "can no longer be {}", kind),
+        }
+    }
+}
+
+/// The differences between two types, in the order of the paths they
+/// were found at.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// The differences found.
+    pub differences: Vec<Difference>,
+}
+
+impl SchemaDiff {
+    /// Whether the types are the same.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Whether any of the differences is breaking.
+    pub fn is_breaking(&self) -> bool {
+        self.differences
+            .iter()
+            .any(|difference| difference.breaking)
+    }
+
+    /// Describe the differences, one per line, each marked as breaking
+    /// or not.
+    pub fn to_text(&self) -> String {
+        self.differences
+            .iter()
+            .map(|difference| match difference.breaking {
+                true => format!("breaking: {}\n", difference),
+                false => format!("non-breaking: {}\n", difference),
+            })
+            .collect()
+    }
+
+    /// Record a difference.
+    fn push(&mut self, path: &str, kind: DiffKind, breaking: bool) {
+        self.differences.push(Difference {
+            path: path.to_string(),
+            kind,
+            breaking,
+        });
+    }
+}
+
+/// Compare the type `old` with the type `new` of the same data.
+pub fn diff(old: &DataType, new: &DataType) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+    compare(old, new, "$", &mut diff);
+    diff
+}
+
+/// Record the differences between the types `old` and `new` of the
+/// values at `path`.
+fn compare(old: &DataType, new: &DataType, path: &str, diff: &mut SchemaDiff) {
+    if old == new {
+        return;
+    }
+    // Values nothing was known about are declared with a type that can
+    // hold anything.
+    if *old == DataType::unknown() || *new == DataType::unknown() {
+        let breaking = *old != DataType::unknown();
+        let (from, to) = (describe(old), describe(new));
+        diff.push(path, DiffKind::TypeChanged { from, to }, breaking);
+        return;
+    }
+
+    match (old, new) {
+        (DataType::Variant(_), _) | (_, DataType::Variant(_)) => {
+            let (old_options, new_options) = (old.options(), new.options());
+            for new in &new_options {
+                match old_options.iter().find(|old| old.same_kind(new)) {
+                    Some(old) => compare(old, new, path, diff),
+                    None => diff.push(path, DiffKind::ArmAdded(describe(new)), true),
+                }
+            }
+            for old in old_options {
+                if !new_options.iter().any(|new| new.same_kind(old)) {
+                    diff.push(path, DiffKind::ArmRemoved(describe(old)), false);
+                }
+            }
+        }
+        (DataType::Object(old), DataType::Object(new)) => {
+            for (key, new_type) in new {
+                let member_path = format!("{}.{}", path, key);
+                match old.get(key) {
+                    Some(old_type) => compare(old_type, new_type, &member_path, diff),
+                    None => {
+                        let optional = is_optional(new_type);
+                        diff.push(&member_path, DiffKind::FieldAdded { optional }, false);
+                    }
+                }
+            }
+            for (key, old_type) in old {
+                if !new.contains_key(key) {
+                    let member_path = format!("{}.{}", path, key);
+                    let breaking = !is_optional(old_type);
+                    diff.push(&member_path, DiffKind::FieldRemoved, breaking);
+                }
+            }
+        }
+        (
+            DataType::Array(old_elem) | DataType::FixedArray(old_elem, _),
+            DataType::Array(new_elem) | DataType::FixedArray(new_elem, _),
+        ) => {
+            // Fixed-length arrays only hold arrays of their length.
+            let len = |typ: &DataType| match typ {
+                DataType::FixedArray(_, len) => Some(*len),
+                _ => None,
+            };
+            if len(old) != len(new) {
+                let (from, to) = (describe(old), describe(new));
+                let breaking = len(old).is_some();
+                diff.push(path, DiffKind::TypeChanged { from, to }, breaking);
+            }
+            compare(old_elem, new_elem, &format!("{}[]", path), diff);
+        }
+        (DataType::Map(old), DataType::Map(new)) => compare(old, new, &format!("{}.*", path), diff),
+        (old, new) => {
+            let breaking = !holds(old, new);
+            let (from, to) = (describe(old), describe(new));
+            diff.push(path, DiffKind::TypeChanged { from, to }, breaking);
+        }
+    }
+}
+
+/// Whether the declaration of scalar type `old` can hold every value of
+/// scalar type `new`.
+fn holds(old: &DataType, new: &DataType) -> bool {
+    match (old, new) {
+        (DataType::Float, DataType::Int | DataType::IntEnum(_))
+        | (DataType::Int, DataType::IntEnum(_))
+        | (DataType::String, DataType::Temporal(_)) => true,
+        (DataType::IntEnum(old), DataType::IntEnum(new)) => new.is_subset(old),
+        _ => false,
+    }
+}
+
+/// Whether values of `typ` can be null.
+fn is_optional(typ: &DataType) -> bool {
+    typ.options().contains(&&DataType::Null)
+}
+
+/// A short description of `typ` that tells apart the types of the same
+/// kind that aren't interchangeable.
+fn describe(typ: &DataType) -> String {
+    match typ {
+        DataType::Variant(options) if options.is_empty() => "unknown".to_string(),
+        DataType::Variant(options) => options.iter().map(describe).collect::<Vec<_>>().join(" | "),
+        DataType::IntEnum(codes) => format!(
+            "code ({})",
+            codes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        DataType::FixedArray(_, len) => format!("array of length {}", len),
+        typ => typ.kind_name().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The differences between the types of `old` and `new`.
+    fn differences(old: json::JsonValue, new: json::JsonValue) -> Vec<String> {
+        let old = DataType::from_json_value(&old).unwrap();
+        let new = DataType::from_json_value(&new).unwrap();
+        diff(&old, &new)
+            .to_text()
+            .lines()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn differences_between_types() {
+        assert_eq!(
+            differences(
+                json::array![{"id": 1, "name": "a", "nick": null}, {"id": 2, "name": "b", "nick": "c"}],
+                json::array![
+                    {"id": 1.5, "name": "a", "email": "d", "phone": null},
+                    {"id": 2, "name": null, "email": "e", "phone": "f"},
+                ],
+            ),
+            [
+                "non-breaking: $[].email: member added",
+                "breaking: $[].id: changed from int to float",
+                "breaking: $[].name: can now be null",
+                "non-breaking: $[].phone: optional member added",
+                "non-breaking: $[].nick: member removed",
+            ]
+        );
+        assert_eq!(
+            differences(
+                json::object! {"a": [1, "x"], "b": 1.5, "c": {"x": 1}, "d": []},
+                json::object! {"a": [1], "b": 2, "c": {}, "d": [true]},
+            ),
+            [
+                "non-breaking: $.a[]: can no longer be string",
+                "non-breaking: $.b: changed from float to int",
+                "breaking: $.c.x: member removed",
+                "non-breaking: $.d[]: changed from unknown to bool",
+            ]
+        );
+
+        let typ = DataType::from_json_value(&json::object! {"a": [1, null]}).unwrap();
+        assert!(diff(&typ, &typ).is_empty());
+        assert!(diff(&typ, &DataType::from_json_value(&json::object! {}).unwrap()).is_breaking());
+    }
+}
@@ -0,0 +1,242 @@
+//! Noticing when a sequence of records changes shape partway through,
+//! like when an API starts sending a new field, while folding their
+//! types together.
+
+use std::fmt;
+
+use crate::DataType;
+
+/// A change in the shape of the records, found when one of them was
+/// folded into the type of the ones before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    /// The index of the first record with the new shape, counting from
+    /// 0.
+    pub index: u64,
+
+    /// The path of the value that changed, such as `$[].email`.
+    pub path: String,
+
+    /// What changed about it.
+    pub change: Change,
+}
+
+/// Ways the shape of the records can change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A member that no record before had.
+    Added,
+
+    /// Values of kinds the records before didn't have, described as
+    /// the kinds before and after, such as `int` and `null | int`.
+    Retyped {
+        /// The kinds of values before.
+        from: String,
+        /// The kinds of values from this record on.
+        to: String,
+    },
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.change {
+            Change::Added => write!(f, "{}: first seen at index {}", self.path, self.index),
+            Change::Retyped { from, to } => write!(
+                f,
+                "{}: changed from {} to {} at index {}",
+                self.path, from, to, self.index
+            ),
+        }
+    }
+}
+
+/// The changes in shape found while folding a sequence of records into
+/// one type.
+#[derive(Debug, Clone, Default)]
+pub struct DriftReport {
+    path: String,
+    drifts: Vec<Drift>,
+}
+
+impl DriftReport {
+    /// Start a report on records found at `path`: `$[]` for the
+    /// elements of a document that's an array, or `$` for a stream of
+    /// documents.
+    pub fn new(path: impl Into<String>) -> Self {
+        DriftReport {
+            path: path.into(),
+            drifts: Vec::new(),
+        }
+    }
+
+    /// Fold `other`, the type of the record at `index`, into `typ` as
+    /// `DataType::unify_into` does, and record how that changed `typ`.
+    /// Learning the type of something that was unknown until now, such
+    /// as the elements of arrays that were all empty before, doesn't
+    /// count as a change.
+    pub fn unify(&mut self, typ: &mut DataType, other: DataType, index: u64) {
+        let before = typ.clone();
+        typ.unify_into(other);
+        let path = self.path.clone();
+        self.compare(&before, typ, path, index);
+    }
+
+    /// The changes found so far, in the order they were found.
+    pub fn drifts(&self) -> &[Drift] {
+        &self.drifts
+    }
+
+    /// Whether no changes were found.
+    pub fn is_empty(&self) -> bool {
+        self.drifts.is_empty()
+    }
+
+    /// Describe the changes, one per line.
+    pub fn to_text(&self) -> String {
+        self.drifts
+            .iter()
+            .map(|drift| format!("drift: {}\n", drift))
+            .collect()
+    }
+
+    /// Describe the changes as a JSON array.
+    pub fn to_json(&self) -> String {
+        json::stringify(
+            self.drifts
+                .iter()
+                .map(|drift| {
+                    let mut object = json::object! {
+                        "index": drift.index,
+                        "path": drift.path.as_str(),
+                    };
+                    if let Change::Retyped { from, to } = &drift.change {
+                        object["from"] = from.as_str().into();
+                        object["to"] = to.as_str().into();
+                    }
+                    object
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Record the differences between `before` and `after`, the types
+    /// of the value at `path` before and after folding in record
+    /// `index`.
+    fn compare(&mut self, before: &DataType, after: &DataType, path: String, index: u64) {
+        if before == after || *before == DataType::unknown() {
+            return;
+        }
+        let (from, to) = (describe(before), describe(after));
+        if from != to {
+            self.drifts.push(Drift {
+                index,
+                path: path.clone(),
+                change: Change::Retyped { from, to },
+            });
+        }
+
+        // Look for changes inside the options that were there before
+        // too, such as an object that became nullable and also gained
+        // a member.
+        for after in after.options() {
+            let before = match before.options().into_iter().find(|b| b.same_kind(after)) {
+                Some(before) => before,
+                None => continue,
+            };
+            match (before, after) {
+                (DataType::Object(before), DataType::Object(after)) => {
+                    for (key, typ) in after {
+                        let member_path = format!("{}.{}", path, key);
+                        match before.get(key) {
+                            Some(old) => self.compare(old, typ, member_path, index),
+                            None => self.drifts.push(Drift {
+                                index,
+                                path: member_path,
+                                change: Change::Added,
+                            }),
+                        }
+                    }
+                }
+                (
+                    DataType::Array(before) | DataType::FixedArray(before, _),
+                    DataType::Array(after) | DataType::FixedArray(after, _),
+                ) => self.compare(before, after, format!("{}[]", path), index),
+                (DataType::Map(before), DataType::Map(after)) => {
+                    self.compare(before, after, format!("{}.*", path), index)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The kinds of values `typ` allows, such as `null | int`, without
+/// what's inside them.
+fn describe(typ: &DataType) -> String {
+    match typ {
+        DataType::Variant(options) if options.is_empty() => "unknown".to_string(),
+        typ => typ
+            .options()
+            .into_iter()
+            .map(DataType::kind_name)
+            .collect::<Vec<_>>()
+            .join(" | "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Value;
+
+    /// The changes found folding the elements of `json`, an array.
+    fn drifts(json: &str) -> Vec<String> {
+        let mut report = DriftReport::new("$[]");
+        let mut typ = DataType::unknown();
+        match Value::parse_json(json).unwrap() {
+            Some(Value::Array(records)) => {
+                for (index, record) in records.iter().enumerate() {
+                    let other = DataType::from_value(record).unwrap();
+                    report.unify(&mut typ, other, index as u64);
+                }
+            }
+            other => panic!("{:?} isn't an array", other),
+        }
+        assert_eq!(
+            DataType::Array(Box::new(typ)),
+            DataType::from_value(&Value::parse_json(json).unwrap().unwrap()).unwrap()
+        );
+        report.drifts().iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn drift() {
+        assert_eq!(
+            drifts(r#"[{"a": 1}, {"a": 2}, {"a": 3, "b": "x"}, {"a": 4.5, "b": "y"}]"#),
+            [
+                "$[].b: first seen at index 2",
+                "$[].a: changed from int to float at index 3",
+            ]
+        );
+        assert_eq!(
+            drifts(
+                r#"[{"a": {"x": 1}}, {"a": null}, {"a": {"x": 1, "y": []}}, {"a": {"y": [1]}}]"#
+            ),
+            [
+                "$[].a: changed from object to null | object at index 1",
+                "$[].a.y: first seen at index 2",
+                "$[].a.x: changed from int to null | int at index 3",
+            ]
+        );
+        assert_eq!(
+            drifts(r#"[{"ids": []}, {"ids": [1]}, {"ids": [2]}]"#),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            drifts(r#"[[1], [2, 3], ["a"]]"#),
+            ["$[][]: changed from int to string | int at index 2"]
+        );
+        assert!(drifts("[1, 2, 3]").is_empty());
+    }
+}
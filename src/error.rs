@@ -0,0 +1,383 @@
+//! The error returned by the library's inference and code generation
+//! functions, for callers that need to tell failures apart rather than
+//! just report them.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    str::{self, Utf8Error},
+};
+
+use thiserror::Error;
+
+use crate::{DepthExceeded, Format, Lang, SchemaStateError, StateError};
+
+/// Error returned when a document can't be read or analyzed, its
+/// declarations can't be written, or the state kept between runs can't
+/// be loaded or saved.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The text isn't valid in the format it was read as.
+    #[error("invalid {format}{} at line {line}, column {column}: {message}", in_file(.path))]
+    Parse {
+        /// The format the text was read as.
+        format: Format,
+        /// The file the text came from, if it came from one.
+        path: Option<PathBuf>,
+        /// The line the problem was found on, counting from 1.
+        line: usize,
+        /// The column the problem was found at, counting characters
+        /// from 1.
+        column: usize,
+        /// What the problem is.
+        message: String,
+    },
+
+    /// The input isn't valid in the binary format it was read as.
+    #[error("invalid {format}{} at byte {offset}: {message}", in_file(.path))]
+    Decode {
+        /// The format the input was read as.
+        format: Format,
+        /// The file the input came from, if it came from one.
+        path: Option<PathBuf>,
+        /// How far into the input the problem was found, in bytes.
+        offset: usize,
+        /// What the problem is.
+        message: String,
+    },
+
+    /// The document isn't a schema that can be read.
+    #[error("invalid schema{} at {pointer}: {message}", in_file(.path))]
+    Schema {
+        /// The file the schema came from, if it came from one.
+        path: Option<PathBuf>,
+        /// Where in the document the problem is, as a JSON pointer in
+        /// a URI fragment, like `#/properties/id`.
+        pointer: String,
+        /// What the problem is.
+        message: String,
+    },
+
+    /// A file couldn't be read or written.
+    #[error("failed to access {path:?}: {source}")]
+    Io {
+        /// The file in question.
+        path: PathBuf,
+        /// What went wrong.
+        #[source]
+        source: io::Error,
+    },
+
+    /// Something was asked of a language that it can't do.
+    #[error("{feature} isn't supported for {lang:?} declarations")]
+    Unsupported {
+        /// What was asked for.
+        feature: &'static str,
+        /// The language declarations were being emitted in.
+        lang: Lang,
+    },
+
+    /// The document is nested too deeply.
+    #[error(transparent)]
+    TooDeep(#[from] DepthExceeded),
+
+    /// A state file couldn't be loaded or saved.
+    #[error(transparent)]
+    State(#[from] StateError),
+
+    /// A schema state file couldn't be loaded or saved.
+    #[error(transparent)]
+    SchemaState(#[from] SchemaStateError),
+
+    /// The directory declarations were to be written to already has
+    /// files in it, and overwriting them wasn't allowed.
+    #[error("output directory {0:?} isn't empty")]
+    NotEmpty(PathBuf),
+}
+
+impl Error {
+    /// An error for a failure to access the file at `path`.
+    pub(crate) fn io(path: &Path) -> impl FnOnce(io::Error) -> Self {
+        let path = path.to_owned();
+        move |source| Error::Io { path, source }
+    }
+
+    /// Record that the input that failed to parse came from the file at
+    /// `path`, starting on line `first_line` of it if it's text.
+    pub fn in_file(self, path: &Path, first_line: usize) -> Self {
+        match self {
+            Error::Parse {
+                format,
+                path: None,
+                line,
+                column,
+                message,
+            } => Error::Parse {
+                format,
+                path: Some(path.to_owned()),
+                line: line + first_line - 1,
+                column,
+                message,
+            },
+            Error::Decode {
+                format,
+                path: None,
+                offset,
+                message,
+            } => Error::Decode {
+                format,
+                path: Some(path.to_owned()),
+                offset,
+                message,
+            },
+            Error::Schema {
+                path: None,
+                pointer,
+                message,
+            } => Error::Schema {
+                path: Some(path.to_owned()),
+                pointer,
+                message,
+            },
+            e => e,
+        }
+    }
+
+    /// Move the position of a parse error found in the part of `text`
+    /// from the byte offset `start` on to where it is in all of `text`.
+    pub(crate) fn after(self, text: &str, start: usize) -> Self {
+        match self {
+            Error::Parse {
+                format,
+                path,
+                line,
+                column,
+                message,
+            } => {
+                let (start_line, start_column) = line_column(text, start);
+                Error::Parse {
+                    format,
+                    path,
+                    line: start_line + line - 1,
+                    column: match line {
+                        1 => start_column + column - 1,
+                        _ => column,
+                    },
+                    message,
+                }
+            }
+            e => e,
+        }
+    }
+
+    /// An error for text in `format` that isn't UTF-8, found in
+    /// decoding `input`.
+    pub(crate) fn from_utf8(e: Utf8Error, format: Format, input: &[u8]) -> Self {
+        let valid = str::from_utf8(&input[..e.valid_up_to()]).expect("checked by the decoder");
+        let (line, column) = line_column(valid, valid.len());
+        Error::Parse {
+            format,
+            path: None,
+            line,
+            column,
+            message: "invalid UTF-8".to_string(),
+        }
+    }
+
+    /// Convert an error from the `json` crate's parser, which was
+    /// parsing `text`.
+    pub(crate) fn from_json(e: json::Error, text: &str) -> Self {
+        let (line, column, message) = match e {
+            json::Error::UnexpectedCharacter { ch, line, column } => {
+                // The parser leaves out the last line if it's empty
+                // when it counts lines, so characters at the start of
+                // a line are put at the end of the one before.
+                let at = offset(text, line, column).and_then(|at| text[at..].chars().next());
+                match at {
+                    Some(c @ ('\n' | '\r')) if c != ch => {
+                        (line + 1, 1, format!("unexpected character {:?}", ch))
+                    }
+                    _ => (line, column, format!("unexpected character {:?}", ch)),
+                }
+            }
+            json::Error::ExceededDepthLimit => return Error::TooDeep(DepthExceeded),
+            e => {
+                // The other errors all happen at the end of the text.
+                (end_line(text), end_column(text), e.to_string())
+            }
+        };
+        Error::Parse {
+            format: Format::Json,
+            path: None,
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// Convert an error from `serde_yaml`, which was parsing `text`.
+    pub(crate) fn from_yaml(e: serde_yaml::Error, text: &str) -> Self {
+        let message = e.to_string();
+        let (line, column, message) = match e.location() {
+            // The message ends with where the problem is, which is
+            // said separately.
+            Some(at) => {
+                let suffix = format!(" at line {} column {}", at.line(), at.column());
+                let message = match message.find(&suffix) {
+                    Some(idx) => message[..idx].to_string() + &message[idx + suffix.len()..],
+                    None => message,
+                };
+                (at.line(), at.column(), message)
+            }
+            None => (end_line(text), end_column(text), message),
+        };
+        Error::Parse {
+            format: Format::Yaml,
+            path: None,
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// Convert an error from the `csv` crate, which was parsing `text`
+    /// as `format`.
+    pub(crate) fn from_csv(e: csv::Error, format: Format, text: &str) -> Self {
+        let (position, message) = match e.kind() {
+            csv::ErrorKind::UnequalLengths {
+                pos,
+                expected_len,
+                len,
+            } => (
+                pos.as_ref(),
+                format!("row has {} cells, but the header has {}", len, expected_len),
+            ),
+            _ => (e.position(), e.to_string()),
+        };
+        let (line, column) = match position {
+            Some(position) => (position.line() as usize, 1),
+            None => (end_line(text), end_column(text)),
+        };
+        Error::Parse {
+            format,
+            path: None,
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// Convert an error from `roxmltree`.
+    pub(crate) fn from_xml(e: roxmltree::Error) -> Self {
+        let at = e.pos();
+        // The message says where the problem is, which is said
+        // separately.
+        let message = e.to_string().replace(&format!(" at {}", at), "");
+        Error::Parse {
+            format: Format::Xml,
+            path: None,
+            line: at.row as usize,
+            column: at.col as usize,
+            message,
+        }
+    }
+
+    /// Convert an error from the `toml` crate, which was parsing `text`.
+    pub(crate) fn from_toml(e: toml::de::Error, text: &str) -> Self {
+        let at = e.span().map_or(text.len(), |span| span.start);
+        let (line, column) = line_column(text, at);
+        Error::Parse {
+            format: Format::Toml,
+            path: None,
+            line,
+            column,
+            message: e.message().to_string(),
+        }
+    }
+
+    /// Convert an error from the `serde_json` crate, which was reading
+    /// a JSON document that started at `line` and `column`.
+    pub(crate) fn from_serde_json(e: serde_json::Error, line: usize, column: usize) -> Self {
+        let message = e.to_string();
+        let message = message
+            .strip_suffix(&format!(" at line {} column {}", e.line(), e.column()))
+            .unwrap_or(&message);
+        if message == DepthExceeded.to_string() {
+            return Error::TooDeep(DepthExceeded);
+        }
+        Error::Parse {
+            format: Format::Json,
+            path: None,
+            line: line + e.line().saturating_sub(1),
+            column: match e.line() {
+                0 | 1 => column + e.column().saturating_sub(1),
+                _ => e.column(),
+            },
+            message: message.to_string(),
+        }
+    }
+
+    /// Convert an error from the `json5` crate, which was parsing
+    /// `text`.
+    pub(crate) fn from_json5(e: json5::Error, text: &str) -> Self {
+        let json5::Error::Message { msg, location } = e;
+        let (line, column) = match location {
+            Some(location) => (location.line, location.column),
+            None => (end_line(text), end_column(text)),
+        };
+        // Syntax errors come with a picture of where they are, then
+        // what was expected there.
+        let message = match msg.rsplit_once("= ") {
+            Some((_, expected)) if msg.contains('\n') => expected.trim().to_string(),
+            _ => msg,
+        };
+        Error::Parse {
+            format: Format::Json5,
+            path: None,
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+/// The line and column of the byte offset `at` in `text`, both counting
+/// from 1 and the column counting characters.
+fn line_column(text: &str, at: usize) -> (usize, usize) {
+    let before = &text[..at];
+    (end_line(before), end_column(before))
+}
+
+/// The line the end of `text` is on, counting from 1.
+fn end_line(text: &str) -> usize {
+    text.split('\n').count()
+}
+
+/// The column the end of `text` is at, counting characters from 1.
+fn end_column(text: &str) -> usize {
+    text.rsplit('\n').next().map_or(0, |s| s.chars().count()) + 1
+}
+
+/// The byte offset of `line` and `column`, both counting from 1 and
+/// the column counting characters, in `text`.
+pub(crate) fn offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = match line {
+        0 => return None,
+        1 => 0,
+        _ => text.match_indices('\n').nth(line - 2)?.0 + 1,
+    };
+    let rest = &text[line_start..];
+    let in_line = match rest.char_indices().nth(column.checked_sub(1)?) {
+        Some((idx, _)) => idx,
+        None => rest.len(),
+    };
+    Some(line_start + in_line)
+}
+
+/// The text saying what file a parse error happened in, if any.
+fn in_file(path: &Option<PathBuf>) -> String {
+    match path {
+        Some(path) => format!(" in {:?}", path),
+        None => String::new(),
+    }
+}
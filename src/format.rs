@@ -0,0 +1,1109 @@
+//! The formats documents can be read in.
+
+use std::{convert::TryFrom, fmt, io::Cursor, path::Path, str, str::FromStr};
+
+use bson::{spec::BinarySubtype, Bson};
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat, TimeDelta};
+use parquet::{
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field,
+};
+use serde::Deserialize;
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::{avro, Compression, DataType, DepthExceeded, Error, Number, Value, MAX_DEPTH};
+
+/// Prefix of the keys of members for the attributes of XML elements.
+const XML_ATTRIBUTE_PREFIX: &str = "@";
+
+/// Key of the member for the text of XML elements that are objects.
+const XML_TEXT_KEY: &str = "#text";
+
+/// A format documents can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// JSON, with any number of documents one after another.
+    #[default]
+    Json,
+
+    /// JSON5, which also allows comments, trailing commas, unquoted
+    /// keys and the like, so also JSON with comments.
+    Json5,
+
+    /// YAML, with any number of documents in a stream.
+    Yaml,
+
+    /// TOML, whose documents are always tables.
+    Toml,
+
+    /// Comma-separated values, with a header row naming the columns.
+    Csv,
+
+    /// Tab-separated values, with a header row naming the columns.
+    Tsv,
+
+    /// XML, with a single root element.
+    Xml,
+
+    /// MessagePack, with any number of values one after another.
+    MessagePack,
+
+    /// CBOR, with any number of values one after another.
+    Cbor,
+
+    /// BSON, with any number of documents one after another, as
+    /// `mongodump` writes collections.
+    Bson,
+
+    /// Parquet, whose rows are read as a table's are.
+    Parquet,
+
+    /// Avro object container files, with a document for each record.
+    Avro,
+}
+
+impl Format {
+    /// Every format, in the order they're listed in.
+    pub const ALL: &'static [Format] = &[
+        Format::Json,
+        Format::Json5,
+        Format::Yaml,
+        Format::Toml,
+        Format::Csv,
+        Format::Tsv,
+        Format::Xml,
+        Format::MessagePack,
+        Format::Cbor,
+        Format::Bson,
+        Format::Parquet,
+        Format::Avro,
+    ];
+
+    /// The extensions of files in this format, the usual one first.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Format::Json => &["json"],
+            Format::Json5 => &["json5", "jsonc"],
+            Format::Yaml => &["yaml", "yml"],
+            Format::Toml => &["toml"],
+            Format::Csv => &["csv"],
+            Format::Tsv => &["tsv", "tab"],
+            Format::Xml => &["xml"],
+            Format::MessagePack => &["msgpack", "mpk"],
+            Format::Cbor => &["cbor"],
+            Format::Bson => &["bson"],
+            Format::Parquet => &["parquet"],
+            Format::Avro => &["avro"],
+        }
+    }
+
+    /// The format of the file at `path`, going by its extension, if
+    /// it's one of a format's. The extension of a compressed file's
+    /// compression, like `.gz`, is skipped.
+    pub fn of_path(path: &Path) -> Option<Self> {
+        let path = match Compression::of_path(path) {
+            Some(_) => Path::new(path.file_stem()?),
+            None => path,
+        };
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    /// Parse the documents in `text`. Text with nothing but whitespace,
+    /// or comments where the format has them, has no documents.
+    ///
+    /// The document in a table, like CSV, is an array with an object
+    /// for each row, keyed by the header row. What's in each cell is
+    /// guessed from its text: empty cells are null, and cells that are
+    /// numbers or booleans as JSON would write them, give or take the
+    /// case of `true` and `false`, are those.
+    ///
+    /// The document in XML is the value of its root element. Elements
+    /// with attributes or child elements are objects, with a member for
+    /// each attribute, its name prefixed with `@`, and one for each
+    /// name of child element, which is an array if there was more than
+    /// one of them. Any text besides is in a member named `#text`. The
+    /// values of other elements, and of attributes, are guessed from
+    /// their text as cells of tables are.
+    ///
+    /// Byte strings, which MessagePack and CBOR have, are read as
+    /// [`Value::Bytes`]. CBOR's tags are skipped, so a tagged value is
+    /// read as the value the tag is on, except that big numbers are
+    /// read as numbers if they fit in 64 bits.
+    ///
+    /// BSON's other types are read as they'd be written in JSON:
+    /// object ids as their hex strings, dates as RFC 3339 strings,
+    /// UUIDs as their usual strings, decimals as numbers, and other
+    /// binary data as bytes.
+    ///
+    /// The document in Parquet is an array with an object for each
+    /// row, as with tables. Dates and timestamps are read as RFC 3339
+    /// strings, decimals as numbers, and maps as objects, keyed by the
+    /// text of their keys.
+    ///
+    /// Avro is read the same way as Parquet, besides that enums are
+    /// read as their symbols.
+    pub fn parse(self, input: &[u8]) -> Result<Vec<Value>, Error> {
+        match self {
+            Format::MessagePack => parse_msgpack(input),
+            Format::Cbor => parse_cbor(input),
+            Format::Bson => parse_bson(input),
+            Format::Parquet if input.is_empty() => Ok(Vec::new()),
+            Format::Parquet => Ok(vec![parse_parquet(input)?]),
+            Format::Avro if input.is_empty() => Ok(Vec::new()),
+            Format::Avro => avro::parse_avro(input),
+            _ => {
+                let text = str::from_utf8(input).map_err(|e| Error::from_utf8(e, self, input))?;
+                self.parse_text(text)
+            }
+        }
+    }
+
+    /// The type the schema in `input` gives its documents, for formats
+    /// whose files have one, like Avro. It's no more specific than
+    /// the schema is sure of, so the documents' own types are unified
+    /// with it: it says which members there are and which of them can
+    /// be null, even when none of the documents show it.
+    pub fn declared_type(self, input: &[u8]) -> Result<Option<DataType>, Error> {
+        match self {
+            Format::Avro if !input.is_empty() => avro::declared_type(input).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse the documents in `text`, in a format that's text.
+    fn parse_text(self, text: &str) -> Result<Vec<Value>, Error> {
+        match self {
+            Format::Json => Value::parse_json_documents(text),
+            Format::Json5 if only_comments(text, "//") => Ok(Vec::new()),
+            Format::Json5 => Ok(vec![
+                json5::from_str(text).map_err(|e| Error::from_json5(e, text))?
+            ]),
+            // The YAML parser reads a stream without any documents as
+            // a single null one.
+            Format::Yaml if only_comments(text, "#") => Ok(Vec::new()),
+            Format::Yaml => serde_yaml::Deserializer::from_str(text)
+                .map(|document| Value::deserialize(document).map_err(|e| Error::from_yaml(e, text)))
+                .collect(),
+            Format::Toml if only_comments(text, "#") => Ok(Vec::new()),
+            Format::Toml => Ok(vec![
+                toml::from_str(text).map_err(|e| Error::from_toml(e, text))?
+            ]),
+            Format::Csv | Format::Tsv if text.trim().is_empty() => Ok(Vec::new()),
+            Format::Csv => Ok(vec![parse_table(text, b',', self)?]),
+            Format::Tsv => Ok(vec![parse_table(text, b'\t', self)?]),
+            Format::Xml if text.trim().is_empty() => Ok(Vec::new()),
+            Format::Xml => Ok(vec![parse_xml(text)?]),
+            Format::MessagePack | Format::Cbor | Format::Bson | Format::Parquet | Format::Avro => {
+                unreachable!("{} isn't text", self)
+            }
+        }
+    }
+}
+
+/// Parse a table with cells separated by `delimiter`, as the format
+/// `format`, into an array of its rows.
+fn parse_table(text: &str, delimiter: u8, format: Format) -> Result<Value, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(text.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| Error::from_csv(e, format, text))?
+        .clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| Error::from_csv(e, format, text))?;
+        let row = headers
+            .iter()
+            .zip(&record)
+            .map(|(header, cell)| (header.to_string(), sniff(cell)))
+            .collect();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+/// Decode the MessagePack values, one after another, in `input`.
+fn parse_msgpack(input: &[u8]) -> Result<Vec<Value>, Error> {
+    let mut deserializer = rmp_serde::Deserializer::new(Cursor::new(input));
+    let mut documents = Vec::new();
+    while deserializer.position() < input.len() as u64 {
+        let document = Value::deserialize(&mut deserializer).map_err(|e| Error::Decode {
+            format: Format::MessagePack,
+            path: None,
+            offset: deserializer.position() as usize,
+            message: e.to_string(),
+        })?;
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+/// Decode the CBOR values, one after another, in `input`.
+fn parse_cbor(input: &[u8]) -> Result<Vec<Value>, Error> {
+    let mut rest = input;
+    let mut documents = Vec::new();
+    while !rest.is_empty() {
+        let start = input.len() - rest.len();
+        let document = ciborium::de::from_reader(&mut rest).map_err(|e| {
+            let (offset, message) = match e {
+                // Reading from a slice only fails when it runs out.
+                ciborium::de::Error::Io(_) => (input.len(), "unexpected end of input".to_string()),
+                ciborium::de::Error::Syntax(offset) => {
+                    (start + offset, "invalid syntax".to_string())
+                }
+                ciborium::de::Error::Semantic(offset, message) => {
+                    (start + offset.unwrap_or(0), message)
+                }
+                ciborium::de::Error::RecursionLimitExceeded => {
+                    (start, "values are nested too deeply".to_string())
+                }
+            };
+            Error::Decode {
+                format: Format::Cbor,
+                path: None,
+                offset,
+                message,
+            }
+        })?;
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+/// Decode the BSON documents, one after another, in `input`.
+fn parse_bson(input: &[u8]) -> Result<Vec<Value>, Error> {
+    let mut rest = input;
+    let mut documents = Vec::new();
+    while !rest.is_empty() {
+        let start = input.len() - rest.len();
+        let decode_error = |message: String| Error::Decode {
+            format: Format::Bson,
+            path: None,
+            offset: start,
+            message,
+        };
+        let document =
+            bson::Document::from_reader(&mut rest).map_err(|e| decode_error(e.to_string()))?;
+        let document = bson_value(Bson::Document(document), 0).map_err(decode_error)?;
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+/// The value of a BSON value, which is nested `depth` levels into its
+/// document.
+fn bson_value(bson: Bson, depth: usize) -> Result<Value, String> {
+    if depth > MAX_DEPTH {
+        return Err(DepthExceeded.to_string());
+    }
+
+    Ok(match bson {
+        Bson::Null | Bson::Undefined | Bson::MinKey | Bson::MaxKey | Bson::DbPointer(_) => {
+            Value::Null
+        }
+        Bson::Boolean(b) => Value::Bool(b),
+        Bson::Int32(n) => Value::Number(i64::from(n).into()),
+        Bson::Int64(n) => Value::Number(n.into()),
+        Bson::Double(n) => Value::Number(
+            Number::from_f64(n)
+                .ok_or_else(|| format!("{} isn't a number JSON can represent", n))?,
+        ),
+        Bson::Decimal128(n) => {
+            let n = n.to_string();
+            match n.parse() {
+                Ok(n) => Value::Number(n),
+                // Decimals too precise to be numbers, or that are NaN or
+                // infinite, are kept as they're written.
+                Err(_) => Value::String(n),
+            }
+        }
+        Bson::String(s) | Bson::Symbol(s) | Bson::JavaScriptCode(s) => Value::String(s),
+        Bson::JavaScriptCodeWithScope(code) => Value::String(code.code),
+        Bson::RegularExpression(regex) => {
+            Value::String(format!("/{}/{}", regex.pattern, regex.options))
+        }
+        Bson::ObjectId(id) => Value::String(id.to_hex()),
+        Bson::DateTime(at) => match at.try_to_rfc3339_string() {
+            Ok(at) => Value::String(at),
+            // Dates past the year 9999 are kept as milliseconds since
+            // the epoch, as Extended JSON writes them.
+            Err(_) => Value::Number(at.timestamp_millis().into()),
+        },
+        Bson::Timestamp(ts) => Value::Object(vec![
+            ("t".to_string(), Value::Number(u64::from(ts.time).into())),
+            (
+                "i".to_string(),
+                Value::Number(u64::from(ts.increment).into()),
+            ),
+        ]),
+        Bson::Binary(binary) => match (binary.subtype, <[u8; 16]>::try_from(&binary.bytes[..])) {
+            (BinarySubtype::Uuid | BinarySubtype::UuidOld, Ok(bytes)) => {
+                Value::String(bson::Uuid::from_bytes(bytes).to_string())
+            }
+            _ => Value::Bytes(binary.bytes),
+        },
+        Bson::Array(elems) => Value::Array(
+            elems
+                .into_iter()
+                .map(|elem| bson_value(elem, depth + 1))
+                .collect::<Result<_, _>>()?,
+        ),
+        Bson::Document(document) => Value::Object(
+            document
+                .into_iter()
+                .map(|(key, value)| Ok((key, bson_value(value, depth + 1)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+/// Decode the rows of the Parquet file `input` into an array of them.
+fn parse_parquet(input: &[u8]) -> Result<Value, Error> {
+    let decode_error = |offset: usize| {
+        move |e: parquet::errors::ParquetError| Error::Decode {
+            format: Format::Parquet,
+            path: None,
+            offset,
+            message: e.to_string(),
+        }
+    };
+    let reader =
+        SerializedFileReader::new(Bytes::copy_from_slice(input)).map_err(decode_error(0))?;
+    let mut rows = Vec::new();
+    for idx in 0..reader.num_row_groups() {
+        let row_group = reader.get_row_group(idx).map_err(decode_error(0))?;
+        // Errors in a row group are put at the start of its first
+        // column chunk, since the reader doesn't say where they are.
+        let start = row_group
+            .metadata()
+            .columns()
+            .first()
+            .map_or(0, |column| column.byte_range().0 as usize);
+        for row in row_group.get_row_iter(None).map_err(decode_error(start))? {
+            let row = row.map_err(decode_error(start))?;
+            let members = row
+                .get_column_iter()
+                .map(|(name, field)| Ok((name.clone(), parquet_value(field, 1)?)))
+                .collect::<Result<_, String>>()
+                .map_err(|message| Error::Decode {
+                    format: Format::Parquet,
+                    path: None,
+                    offset: start,
+                    message,
+                })?;
+            rows.push(Value::Object(members));
+        }
+    }
+    Ok(Value::Array(rows))
+}
+
+/// The value of a field of a Parquet row, which is nested `depth`
+/// levels into the document.
+fn parquet_value(field: &Field, depth: usize) -> Result<Value, String> {
+    if depth > MAX_DEPTH {
+        return Err(DepthExceeded.to_string());
+    }
+
+    let float = |n: f64| {
+        Number::from_f64(n)
+            .map(Value::Number)
+            .ok_or_else(|| format!("{} isn't a number JSON can represent", n))
+    };
+    Ok(match field {
+        Field::Null => Value::Null,
+        Field::Bool(b) => Value::Bool(*b),
+        Field::Byte(n) => Value::Number(i64::from(*n).into()),
+        Field::Short(n) => Value::Number(i64::from(*n).into()),
+        Field::Int(n) => Value::Number(i64::from(*n).into()),
+        Field::Long(n) => Value::Number((*n).into()),
+        Field::UByte(n) => Value::Number(u64::from(*n).into()),
+        Field::UShort(n) => Value::Number(u64::from(*n).into()),
+        Field::UInt(n) => Value::Number(u64::from(*n).into()),
+        Field::ULong(n) => Value::Number((*n).into()),
+        Field::Float16(n) => float(f64::from(*n))?,
+        Field::Float(n) => float(f64::from(*n))?,
+        Field::Double(n) => float(*n)?,
+        // Decimals are read through their text, so they keep their
+        // scale.
+        Field::Decimal(_) => Value::Number(
+            field
+                .to_string()
+                .parse()
+                .map_err(|_| format!("decimal {} isn't a number", field))?,
+        ),
+        Field::Str(s) => Value::String(s.clone()),
+        Field::Bytes(bytes) => Value::Bytes(bytes.data().to_vec()),
+        Field::Date(days) => date_value((*days).into()),
+        Field::TimestampMillis(at) => timestamp_value(*at, 1_000, false),
+        Field::TimestampMicros(at) => timestamp_value(*at, 1_000_000, false),
+        Field::Group(row) => Value::Object(
+            row.get_column_iter()
+                .map(|(name, field)| Ok((name.clone(), parquet_value(field, depth + 1)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        Field::ListInternal(list) => Value::Array(
+            list.elements()
+                .iter()
+                .map(|elem| parquet_value(elem, depth + 1))
+                .collect::<Result<_, _>>()?,
+        ),
+        Field::MapInternal(map) => Value::Object(
+            map.entries()
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Field::Str(key) => key.clone(),
+                        key => key.to_string(),
+                    };
+                    Ok((key, parquet_value(value, depth + 1)?))
+                })
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+/// A date `days` days after the Unix epoch, as an RFC 3339 string, or
+/// as the number of days if it's out of range.
+pub(crate) fn date_value(days: i64) -> Value {
+    let date = NaiveDate::from_ymd_opt(1970, 1, 1)
+        .zip(TimeDelta::try_days(days))
+        .and_then(|(epoch, days)| epoch.checked_add_signed(days));
+    match date {
+        Some(date) => Value::String(date.to_string()),
+        None => Value::Number(days.into()),
+    }
+}
+
+/// Split `at` units of time, of which there are `per_second` a second,
+/// into seconds and nanoseconds.
+fn split_seconds(at: i64, per_second: i64) -> (i64, u32) {
+    let nanos = at.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    (at.div_euclid(per_second), nanos as u32)
+}
+
+/// A time of day `at` units after midnight, of which there are
+/// `per_second` a second, as an RFC 3339 string, or as the number if
+/// it's out of range.
+pub(crate) fn time_value(at: i64, per_second: i64) -> Value {
+    let (secs, nanos) = split_seconds(at, per_second);
+    let time = u32::try_from(secs)
+        .ok()
+        .and_then(|secs| NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos));
+    match time {
+        Some(time) => Value::String(time.format("%H:%M:%S%.f").to_string()),
+        None => Value::Number(at.into()),
+    }
+}
+
+/// A timestamp `at` units after the Unix epoch, of which there are
+/// `per_second` a second, as an RFC 3339 string in UTC, or without an
+/// offset if it's in `local` time; or as the number if it's out of
+/// range.
+pub(crate) fn timestamp_value(at: i64, per_second: i64, local: bool) -> Value {
+    let (secs, nanos) = split_seconds(at, per_second);
+    match DateTime::from_timestamp(secs, nanos) {
+        Some(at) if local => {
+            Value::String(at.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+        }
+        Some(at) => Value::String(at.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+        None => Value::Number(at.into()),
+    }
+}
+
+/// Parse an XML document into the value of its root element.
+fn parse_xml(text: &str) -> Result<Value, Error> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(text, options).map_err(Error::from_xml)?;
+    Ok(xml_element(document.root_element(), 0)?)
+}
+
+/// The value of an XML element, which is nested `depth` levels into the
+/// document.
+fn xml_element(node: Node, depth: usize) -> Result<Value, DepthExceeded> {
+    if depth > MAX_DEPTH {
+        return Err(DepthExceeded);
+    }
+
+    let text = node
+        .children()
+        .filter(Node::is_text)
+        .filter_map(|child| child.text())
+        .collect::<String>();
+    let text = text.trim();
+    let mut children: Vec<(&str, Vec<Value>)> = Vec::new();
+    for child in node.children().filter(Node::is_element) {
+        let name = child.tag_name().name();
+        let value = xml_element(child, depth + 1)?;
+        match children.iter_mut().find(|(seen, _)| *seen == name) {
+            Some((_, values)) => values.push(value),
+            None => children.push((name, vec![value])),
+        }
+    }
+    if children.is_empty() && node.attributes().len() == 0 {
+        return Ok(sniff(text));
+    }
+
+    let mut members = node
+        .attributes()
+        .map(|attr| {
+            let key = format!("{}{}", XML_ATTRIBUTE_PREFIX, attr.name());
+            (key, sniff(attr.value()))
+        })
+        .collect::<Vec<_>>();
+    for (name, mut values) in children {
+        let value = match values.len() {
+            1 => values.pop().expect("checked above"),
+            _ => Value::Array(values),
+        };
+        members.push((name.to_string(), value));
+    }
+    if !text.is_empty() {
+        members.push((XML_TEXT_KEY.to_string(), sniff(text)));
+    }
+    Ok(Value::Object(members))
+}
+
+/// The value written in a cell of a table. Numbers with leading zeros,
+/// like postal codes, are kept as strings, since the zeros would be
+/// lost otherwise.
+fn sniff(cell: &str) -> Value {
+    let digits = cell.strip_prefix('-').unwrap_or(cell);
+    let leading_zero =
+        digits.len() > 1 && digits.starts_with('0') && !digits[1..].starts_with(['.', 'e', 'E']);
+    match cell.parse() {
+        _ if cell.is_empty() => Value::Null,
+        _ if cell.eq_ignore_ascii_case("true") => Value::Bool(true),
+        _ if cell.eq_ignore_ascii_case("false") => Value::Bool(false),
+        Ok(n) if !leading_zero => Value::Number(n),
+        _ => Value::String(cell.to_string()),
+    }
+}
+
+/// Whether `text` has nothing but blank lines and comments starting
+/// with `comment` in it.
+fn only_comments(text: &str, comment: &str) -> bool {
+    text.lines().all(|line| {
+        let line = line.trim();
+        line.is_empty() || line.starts_with(comment)
+    })
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "JSON",
+            Format::Json5 => "JSON5",
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+            Format::Csv => "CSV",
+            Format::Tsv => "TSV",
+            Format::Xml => "XML",
+            Format::MessagePack => "MessagePack",
+            Format::Cbor => "CBOR",
+            Format::Bson => "BSON",
+            Format::Parquet => "Parquet",
+            Format::Avro => "Avro",
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "json5" => Ok(Format::Json5),
+            "yaml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "xml" => Ok(Format::Xml),
+            "msgpack" => Ok(Format::MessagePack),
+            "cbor" => Ok(Format::Cbor),
+            "bson" => Ok(Format::Bson),
+            "parquet" => Ok(Format::Parquet),
+            "avro" => Ok(Format::Avro),
+            _ => Err(format!("unknown input format {:?}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_of_paths() {
+        assert_eq!(Format::of_path(Path::new("a/b.json")), Some(Format::Json));
+        assert_eq!(Format::of_path(Path::new("b.YML")), Some(Format::Yaml));
+        assert_eq!(Format::of_path(Path::new("Cargo.toml")), Some(Format::Toml));
+        assert_eq!(Format::of_path(Path::new("b.txt")), None);
+        assert_eq!(Format::of_path(Path::new("yaml")), None);
+        assert_eq!(
+            Format::of_path(Path::new("dump.yaml.gz")),
+            Some(Format::Yaml)
+        );
+        assert_eq!(Format::of_path(Path::new("dump.gz")), None);
+    }
+
+    #[test]
+    fn json_documents() {
+        let documents = Format::Json
+            .parse(b"{\"a\": 1}{\"a\": [2]}\n[\"x\"] 3.50 \n")
+            .unwrap();
+        assert_eq!(
+            documents,
+            vec![
+                Value::Object(vec![("a".to_string(), Value::Number(1u64.into()))]),
+                Value::Object(vec![(
+                    "a".to_string(),
+                    Value::Array(vec![Value::Number(2u64.into())])
+                )]),
+                Value::Array(vec![Value::String("x".to_string())]),
+                Value::Number("3.50".parse().unwrap()),
+            ]
+        );
+        assert_eq!(Format::Json.parse(b" \n").unwrap(), vec![]);
+        assert!(matches!(
+            Format::Json.parse(b"[1] [2,]"),
+            Err(Error::Parse {
+                format: Format::Json,
+                line: 1,
+                column: 8,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Format::Json.parse(b"[1,] [2]"),
+            Err(Error::Parse {
+                line: 1,
+                column: 4,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn json5_documents() {
+        let documents = Format::Json5
+            .parse(b"// Settings\n{\n  name: 'a', /* id */ \"id\": 0x10,\n  tags: [1.5, +2,],\n}\n")
+            .unwrap();
+        assert_eq!(
+            documents,
+            vec![Value::Object(vec![
+                ("name".to_string(), Value::String("a".to_string())),
+                ("id".to_string(), Value::Number(16u64.into())),
+                (
+                    "tags".to_string(),
+                    Value::Array(vec![
+                        Value::Number("1.5".parse().unwrap()),
+                        Value::Number(2u64.into()),
+                    ])
+                ),
+            ])]
+        );
+        assert_eq!(Format::Json5.parse(b"// nothing\n").unwrap(), vec![]);
+        assert!(matches!(
+            Format::Json5.parse(b"{\n  a: ,\n}"),
+            Err(Error::Parse {
+                format: Format::Json5,
+                line: 2,
+                column: 6,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn yaml_documents() {
+        let documents = Format::Yaml
+            .parse(b"name: a\nid: 1\ntags: [x, 2.5]\n? 3\n: !Ref b\n---\n- ~\n- true\n")
+            .unwrap();
+        assert_eq!(
+            documents,
+            vec![
+                Value::Object(vec![
+                    ("name".to_string(), Value::String("a".to_string())),
+                    ("id".to_string(), Value::Number(1u64.into())),
+                    (
+                        "tags".to_string(),
+                        Value::Array(vec![
+                            Value::String("x".to_string()),
+                            Value::Number("2.5".parse().unwrap()),
+                        ])
+                    ),
+                    ("3".to_string(), Value::String("b".to_string())),
+                ]),
+                Value::Array(vec![Value::Null, Value::Bool(true)]),
+            ]
+        );
+        assert_eq!(Format::Yaml.parse(b"# nothing\n\n").unwrap(), vec![]);
+        assert_eq!(Format::Yaml.parse(b"---\n").unwrap(), vec![Value::Null]);
+        assert!(matches!(
+            Format::Yaml.parse(b"a: 1\nb: [\n"),
+            Err(Error::Parse {
+                format: Format::Yaml,
+                line: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn toml_documents() {
+        let documents = Format::Toml
+            .parse(
+                b"name = \"a\"\nat = 1979-05-27T07:32:00Z\n\n[[bin]]\npath = \"b\"\nratio = 0.5\n",
+            )
+            .unwrap();
+        assert_eq!(
+            documents,
+            vec![Value::Object(vec![
+                ("name".to_string(), Value::String("a".to_string())),
+                (
+                    "at".to_string(),
+                    Value::String("1979-05-27T07:32:00Z".to_string())
+                ),
+                (
+                    "bin".to_string(),
+                    Value::Array(vec![Value::Object(vec![
+                        ("path".to_string(), Value::String("b".to_string())),
+                        ("ratio".to_string(), Value::Number("0.5".parse().unwrap())),
+                    ])])
+                ),
+            ])]
+        );
+        assert_eq!(Format::Toml.parse(b"# nothing\n").unwrap(), vec![]);
+        assert!(matches!(
+            Format::Toml.parse(b"a = 1\nb = nan\n"),
+            Err(Error::Parse {
+                format: Format::Toml,
+                line: 2,
+                column: 5,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Format::Toml.parse(b"a = 1\nb = [\n"),
+            Err(Error::Parse {
+                format: Format::Toml,
+                line: 3,
+                column: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn tables() {
+        let documents = Format::Csv
+            .parse(b"id,zip,score,ok,note\n1,02134,-1.5,TRUE,\n2,90210,3,false,\"a, b\"\n")
+            .unwrap();
+        let row = |id: u64, zip: &str, score: &str, ok: bool, note: Value| {
+            Value::Object(vec![
+                ("id".to_string(), Value::Number(id.into())),
+                ("zip".to_string(), sniff(zip)),
+                ("score".to_string(), Value::Number(score.parse().unwrap())),
+                ("ok".to_string(), Value::Bool(ok)),
+                ("note".to_string(), note),
+            ])
+        };
+        assert_eq!(
+            documents,
+            vec![Value::Array(vec![
+                row(1, "02134", "-1.5", true, Value::Null),
+                row(2, "90210", "3", false, Value::String("a, b".to_string())),
+            ])]
+        );
+        assert_eq!(sniff("02134"), Value::String("02134".to_string()));
+        assert_eq!(sniff("0.5"), Value::Number("0.5".parse().unwrap()));
+        assert_eq!(sniff("1,000"), Value::String("1,000".to_string()));
+
+        assert_eq!(
+            Format::Tsv.parse(b"a\tb\nx\t\n").unwrap(),
+            vec![Value::Array(vec![Value::Object(vec![
+                ("a".to_string(), Value::String("x".to_string())),
+                ("b".to_string(), Value::Null),
+            ])])]
+        );
+        assert!(matches!(
+            Format::Csv.parse(b"a,b\n1,2\n3\n"),
+            Err(Error::Parse {
+                format: Format::Csv,
+                line: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn xml_documents() {
+        let documents = Format::Xml
+            .parse(
+                b"<?xml version=\"1.0\"?>\n<order id=\"7\">\n  <item sku=\"a\">2</item>\n  \
+                 <item sku=\"b\"/>\n  <note>Leave at <b>door</b></note>\n  <gift/>\n</order>\n",
+            )
+            .unwrap();
+        let item = |sku: &str| ("@sku".to_string(), Value::String(sku.to_string()));
+        assert_eq!(
+            documents,
+            vec![Value::Object(vec![
+                ("@id".to_string(), Value::Number(7u64.into())),
+                (
+                    "item".to_string(),
+                    Value::Array(vec![
+                        Value::Object(vec![
+                            item("a"),
+                            ("#text".to_string(), Value::Number(2u64.into())),
+                        ]),
+                        Value::Object(vec![item("b")]),
+                    ])
+                ),
+                (
+                    "note".to_string(),
+                    Value::Object(vec![
+                        ("b".to_string(), Value::String("door".to_string())),
+                        ("#text".to_string(), Value::String("Leave at".to_string())),
+                    ])
+                ),
+                ("gift".to_string(), Value::Null),
+            ])]
+        );
+        assert!(matches!(
+            Format::Xml.parse(b"<a>\n  <b></c>\n</a>"),
+            Err(Error::Parse {
+                format: Format::Xml,
+                line: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn msgpack_documents() {
+        // {"id": 1, "ok": true, "blob": <bin 2>, "x": 1.5}, then [-2, nil]
+        let input = b"\x84\xa2id\x01\xa2ok\xc3\xa4blob\xc4\x02\x07\x08\xa1x\xcb\x3f\xf8\0\0\0\0\0\0\x92\xfe\xc0";
+        assert_eq!(
+            Format::MessagePack.parse(input).unwrap(),
+            vec![
+                Value::Object(vec![
+                    ("id".to_string(), Value::Number(1u64.into())),
+                    ("ok".to_string(), Value::Bool(true)),
+                    ("blob".to_string(), Value::Bytes(vec![7, 8])),
+                    ("x".to_string(), Value::Number("1.5".parse().unwrap())),
+                ]),
+                Value::Array(vec![Value::Number((-2i64).into()), Value::Null]),
+            ]
+        );
+        assert_eq!(Format::MessagePack.parse(b"").unwrap(), vec![]);
+        assert!(matches!(
+            Format::MessagePack.parse(b"\x92\x01"),
+            Err(Error::Decode {
+                format: Format::MessagePack,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Format::Json.parse(b"[\"a\",\n \"\xff\"]"),
+            Err(Error::Parse {
+                line: 2,
+                column: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn cbor_documents() {
+        // {"id": 1, "blob": h'0708', "at": 1(1700000000), "big": 2(h'01')},
+        // then [-2, 1.5 as a half, null]
+        let input = b"\xa4\x62id\x01\x64blob\x42\x07\x08\x62at\xc1\x1a\x65\x53\xf1\x00\x63big\xc2\x41\x01\x83\x21\xf9\x3e\x00\xf6";
+        assert_eq!(
+            Format::Cbor.parse(input).unwrap(),
+            vec![
+                Value::Object(vec![
+                    ("id".to_string(), Value::Number(1u64.into())),
+                    ("blob".to_string(), Value::Bytes(vec![7, 8])),
+                    ("at".to_string(), Value::Number(1_700_000_000u64.into())),
+                    ("big".to_string(), Value::Number(1u64.into())),
+                ]),
+                Value::Array(vec![
+                    Value::Number((-2i64).into()),
+                    Value::Number("1.5".parse().unwrap()),
+                    Value::Null,
+                ]),
+            ]
+        );
+        assert_eq!(Format::Cbor.parse(b"").unwrap(), vec![]);
+        assert!(matches!(
+            Format::Cbor.parse(b"\x82\x01"),
+            Err(Error::Decode {
+                format: Format::Cbor,
+                offset: 2,
+                ..
+            })
+        ));
+        assert_eq!(
+            DataType::from_value(&Format::Cbor.parse(b"\x42\x07\x08").unwrap()[0]).unwrap(),
+            DataType::Bytes
+        );
+    }
+
+    #[test]
+    fn bson_documents() {
+        let first = bson::doc! {
+            "_id": bson::oid::ObjectId::parse_str("65a1b2c3d4e5f60718293a4b").unwrap(),
+            "at": bson::DateTime::from_millis(1_700_000_000_500),
+            "blob": bson::Binary { subtype: BinarySubtype::Generic, bytes: vec![7, 8] },
+            "uuid": bson::Binary {
+                subtype: BinarySubtype::Uuid,
+                bytes: (0..16).collect(),
+            },
+            "n": 5i32,
+            "price": "1.50".parse::<bson::Decimal128>().unwrap(),
+        };
+        let mut input = Vec::new();
+        first.to_writer(&mut input).unwrap();
+        bson::doc! {"tags": ["a"], "x": 1.5}
+            .to_writer(&mut input)
+            .unwrap();
+        assert_eq!(
+            Format::Bson.parse(&input).unwrap(),
+            vec![
+                Value::Object(vec![
+                    (
+                        "_id".to_string(),
+                        Value::String("65a1b2c3d4e5f60718293a4b".to_string())
+                    ),
+                    (
+                        "at".to_string(),
+                        Value::String("2023-11-14T22:13:20.5Z".to_string())
+                    ),
+                    ("blob".to_string(), Value::Bytes(vec![7, 8])),
+                    (
+                        "uuid".to_string(),
+                        Value::String("00010203-0405-0607-0809-0a0b0c0d0e0f".to_string())
+                    ),
+                    ("n".to_string(), Value::Number(5u64.into())),
+                    ("price".to_string(), Value::Number("1.50".parse().unwrap())),
+                ]),
+                Value::Object(vec![
+                    (
+                        "tags".to_string(),
+                        Value::Array(vec![Value::String("a".to_string())])
+                    ),
+                    ("x".to_string(), Value::Number("1.5".parse().unwrap())),
+                ]),
+            ]
+        );
+        assert!(matches!(
+            Format::Bson.parse(&input[..input.len() - 1]),
+            Err(Error::Decode {
+                format: Format::Bson,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn parquet_documents() {
+        use std::sync::Arc;
+
+        use parquet::{
+            data_type::{ByteArrayType, Int32Type, Int64Type},
+            file::writer::SerializedFileWriter,
+            schema::parser::parse_message_type,
+        };
+
+        let schema = parse_message_type(
+            "message rows {\n  required int64 id;\n  optional binary name (STRING);\n  \
+             required int32 day (DATE);\n  required int64 at (TIMESTAMP(MILLIS,true));\n  \
+             required int32 price (DECIMAL(5,2));\n}",
+        )
+        .unwrap();
+        let mut input = Vec::new();
+        let mut writer =
+            SerializedFileWriter::new(&mut input, Arc::new(schema), Default::default()).unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1, 2], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&["a".into()], Some(&[1, 0]), None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int32Type>()
+            .write_batch(&[19_675, 0], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1_700_000_000_500, 0], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int32Type>()
+            .write_batch(&[150, -5], None, None)
+            .unwrap();
+        column.close().unwrap();
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        let row = |id: u64, name: Value, day: &str, at: &str, price: &str| {
+            Value::Object(vec![
+                ("id".to_string(), Value::Number(id.into())),
+                ("name".to_string(), name),
+                ("day".to_string(), Value::String(day.to_string())),
+                ("at".to_string(), Value::String(at.to_string())),
+                ("price".to_string(), Value::Number(price.parse().unwrap())),
+            ])
+        };
+        assert_eq!(
+            Format::Parquet.parse(&input).unwrap(),
+            vec![Value::Array(vec![
+                row(
+                    1,
+                    Value::String("a".to_string()),
+                    "2023-11-14",
+                    "2023-11-14T22:13:20.500Z",
+                    "1.50"
+                ),
+                row(
+                    2,
+                    Value::Null,
+                    "1970-01-01",
+                    "1970-01-01T00:00:00Z",
+                    "-0.05"
+                ),
+            ])]
+        );
+        assert_eq!(Format::Parquet.parse(b"").unwrap(), vec![]);
+        assert!(matches!(
+            Format::Parquet.parse(&input[..input.len() - 1]),
+            Err(Error::Decode {
+                format: Format::Parquet,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+//! Reading of HAR captures, as browsers' developer tools save them, for
+//! the JSON responses to each endpoint.
+
+use std::{collections::BTreeMap, fmt};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+
+use crate::{Error, Value, WarningKind, Warnings};
+
+/// What's put in place of the segments of paths that look like ids.
+const ID_PLACEHOLDER: &str = "{id}";
+
+/// Segments of paths at least this long that have digits in them look
+/// like ids, such as UUIDs and hashes.
+const MIN_ID_LEN: usize = 16;
+
+/// The JSON responses to the requests to an endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    /// The method of the requests, in upper case, such as `GET`.
+    pub method: String,
+
+    /// The path the requests were to, with `{id}` in place of segments
+    /// that look like ids, such as `/users/{id}`.
+    pub pattern: String,
+
+    /// The bodies of the responses, in the order they were captured.
+    pub bodies: Vec<Value>,
+}
+
+impl Endpoint {
+    /// The name of the type of the responses, before it's put in the
+    /// case of type names, such as `get users id response`.
+    pub fn type_name(&self) -> String {
+        let segments = self
+            .pattern
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|segment| !segment.is_empty());
+        let mut name = self.method.to_lowercase();
+        for segment in segments {
+            name.push(' ');
+            name.push_str(segment);
+        }
+        name + " response"
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.pattern)
+    }
+}
+
+/// The successful JSON responses in HAR captures, by endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct Har {
+    endpoints: Vec<Endpoint>,
+    /// Where each endpoint is in `endpoints`, by method and pattern.
+    index: BTreeMap<(String, String), usize>,
+    warnings: Warnings,
+}
+
+impl Har {
+    /// Start reading captures, with no responses yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the responses in the HAR capture `input` with a status in
+    /// the 200s and a JSON media type. Responses whose bodies weren't
+    /// saved are skipped, and so are ones that aren't valid JSON, with
+    /// a warning.
+    pub fn read(&mut self, input: &[u8]) -> Result<(), Error> {
+        let capture: Capture =
+            serde_json::from_slice(input).map_err(|e| Error::from_serde_json(e, 1, 1))?;
+        for Entry { request, response } in capture.log.entries {
+            let Content {
+                mime_type,
+                text,
+                encoding,
+            } = response.content;
+            let text = match text {
+                Some(text) if (200..300).contains(&response.status) && is_json(&mime_type) => text,
+                _ => continue,
+            };
+
+            let key = (request.method.to_uppercase(), pattern(&request.url));
+            let idx = match self.index.get(&key) {
+                Some(&idx) => idx,
+                None => {
+                    self.endpoints.push(Endpoint {
+                        method: key.0.clone(),
+                        pattern: key.1.clone(),
+                        bodies: Vec::new(),
+                    });
+                    self.index.insert(key, self.endpoints.len() - 1);
+                    self.endpoints.len() - 1
+                }
+            };
+            let endpoint = &mut self.endpoints[idx];
+            match body(text, encoding.as_deref()) {
+                Ok(Some(body)) => endpoint.bodies.push(body),
+                Ok(None) => {}
+                Err(why) => {
+                    self.warnings
+                        .push(endpoint.to_string(), WarningKind::InvalidResponse, why)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The endpoints that had any responses, in the order they were
+    /// first requested, and the warnings raised reading them.
+    pub fn finish(self) -> (Vec<Endpoint>, Warnings) {
+        let endpoints = self
+            .endpoints
+            .into_iter()
+            .filter(|endpoint| !endpoint.bodies.is_empty())
+            .collect();
+        (endpoints, self.warnings)
+    }
+}
+
+/// A HAR capture, as much of it as is read.
+#[derive(Deserialize)]
+struct Capture {
+    log: Log,
+}
+
+/// The log of requests in a capture.
+#[derive(Deserialize)]
+struct Log {
+    entries: Vec<Entry>,
+}
+
+/// A request and the response to it.
+#[derive(Deserialize)]
+struct Entry {
+    request: Request,
+    response: Response,
+}
+
+/// A request, as much of it as is read.
+#[derive(Deserialize)]
+struct Request {
+    method: String,
+    url: String,
+}
+
+/// A response, as much of it as is read.
+#[derive(Deserialize)]
+struct Response {
+    status: i64,
+    content: Content,
+}
+
+/// The body of a response.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    #[serde(default)]
+    mime_type: String,
+    /// The body, if it was saved.
+    text: Option<String>,
+    /// How the body is encoded, if it is.
+    encoding: Option<String>,
+}
+
+/// Whether `mime_type` is JSON's, or one whose syntax is JSON, like
+/// `application/problem+json`.
+pub(crate) fn is_json(mime_type: &str) -> bool {
+    let essence = mime_type.split(';').next().unwrap_or("").trim();
+    let essence = essence.to_ascii_lowercase();
+    essence == "application/json" || essence == "text/json" || essence.ends_with("+json")
+}
+
+/// The path of `url`, without its query, and with `{id}` in place of
+/// segments that look like ids.
+fn pattern(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = rest.find('/').map_or("", |at| &rest[at..]);
+    let path = path.split(['?', '#']).next().unwrap_or("");
+    if path.is_empty() {
+        return "/".to_string();
+    }
+    path.split('/')
+        .map(|segment| match looks_like_id(segment) {
+            true => ID_PLACEHOLDER,
+            false => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether a segment of a path looks like an id: it's a number, or
+/// it's long and has digits in it, like a UUID or a hash.
+fn looks_like_id(segment: &str) -> bool {
+    let digits = segment.bytes().filter(u8::is_ascii_digit).count();
+    let word = |b: u8| b.is_ascii_alphanumeric() || b == b'-' || b == b'_';
+    match digits {
+        0 => false,
+        _ if digits == segment.len() => true,
+        _ => segment.len() >= MIN_ID_LEN && segment.bytes().all(word),
+    }
+}
+
+/// The document in the body of a response, decoding it if it's
+/// `encoding`-encoded first, or `None` if it's empty.
+fn body(text: String, encoding: Option<&str>) -> Result<Option<Value>, String> {
+    let text = match encoding {
+        Some("base64") => {
+            let bytes = STANDARD.decode(text).map_err(|e| e.to_string())?;
+            String::from_utf8(bytes).map_err(|e| e.to_string())?
+        }
+        _ => text,
+    };
+    Value::parse_json(&text).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints() {
+        let capture = br#"{"log": {"version": "1.2", "entries": [
+            {"request": {"method": "GET", "url": "https://api.example.com/users/12?full=1"},
+             "response": {"status": 200, "content": {"mimeType": "application/json; charset=utf-8", "text": "{\"id\": 12}"}}},
+            {"request": {"method": "get", "url": "https://api.example.com/users/0b8f3c1e-7a2d-4f6b-9c1a-2e5d7f9a1b3c"},
+             "response": {"status": 200, "content": {"mimeType": "application/json", "text": "eyJpZCI6ICJ4In0=", "encoding": "base64"}}},
+            {"request": {"method": "GET", "url": "https://api.example.com/users/12"},
+             "response": {"status": 404, "content": {"mimeType": "application/json", "text": "{}"}}},
+            {"request": {"method": "GET", "url": "https://example.com/app.js"},
+             "response": {"status": 200, "content": {"mimeType": "text/javascript", "text": "1"}}},
+            {"request": {"method": "POST", "url": "https://api.example.com/v2/orders"},
+             "response": {"status": 201, "content": {"mimeType": "application/vnd.api+json", "text": "[1,"}}},
+            {"request": {"method": "POST", "url": "https://api.example.com/v2/orders"},
+             "response": {"status": 201, "content": {"mimeType": "application/vnd.api+json", "text": "[2]"}}}
+        ]}}"#;
+        let mut har = Har::new();
+        har.read(capture).unwrap();
+        let (endpoints, warnings) = har.finish();
+
+        assert_eq!(
+            endpoints,
+            vec![
+                Endpoint {
+                    method: "GET".to_string(),
+                    pattern: "/users/{id}".to_string(),
+                    bodies: vec![
+                        Value::Object(vec![("id".to_string(), Value::Number(12u64.into()))]),
+                        Value::Object(vec![("id".to_string(), Value::String("x".to_string()))]),
+                    ],
+                },
+                Endpoint {
+                    method: "POST".to_string(),
+                    pattern: "/v2/orders".to_string(),
+                    bodies: vec![Value::Array(vec![Value::Number(2u64.into())])],
+                },
+            ]
+        );
+        assert_eq!(endpoints[0].type_name(), "get users id response");
+        assert_eq!(endpoints[1].to_string(), "POST /v2/orders");
+        let warnings = warnings.summary();
+        let warning = warnings.keys().next().unwrap();
+        assert_eq!(warning.path, "POST /v2/orders");
+        assert_eq!(warning.kind, WarningKind::InvalidResponse);
+
+        assert!(matches!(
+            Har::new().read(b"{\"log\": {}}"),
+            Err(Error::Parse { .. })
+        ));
+    }
+}
@@ -0,0 +1,742 @@
+//! An interned representation of data types, for inferring types over
+//! large, repetitive inputs.
+//!
+//! Every distinct type lives exactly once in an arena and is referred
+//! to by a [`TypeId`], and object keys are interned the same way. Since
+//! structurally identical types share an ID, unification can be
+//! memoized on pairs of IDs: once the element type of a big array has
+//! settled, folding in another element of the same shape is a single
+//! hash lookup rather than a rebuild of the whole tree.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    types::{looks_like_map, sample, MAX_MATRIX_LEN},
+    warnings::{WarningKind, Warnings},
+    DataType, DepthExceeded, Error, Options, Value, MAX_DEPTH,
+};
+
+/// A reference to a type stored in an [`Interner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TypeId(u32);
+
+/// A reference to an object key stored in an [`Interner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Symbol(u32);
+
+/// A single type in the arena. These mirror the variants of
+/// `DataType`, except that children are referenced by ID.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    /// A type with no children; never an object, array, map or
+    /// variant, and not a fixed-length array.
+    Leaf(DataType),
+
+    /// An object, with members sorted by key symbol.
+    Object(Vec<(Symbol, TypeId)>),
+
+    /// An array of elements of the given type.
+    Array(TypeId),
+
+    /// An array of numbers, or of such arrays, that always had the
+    /// given length.
+    FixedArray(TypeId, usize),
+
+    /// A map with values of the given type.
+    Map(TypeId),
+
+    /// One of several types, sorted by ID. Like `DataType::Variant`,
+    /// this never nests and never has exactly one option, and the
+    /// empty variant is the unknown type.
+    Variant(Vec<TypeId>),
+}
+
+/// An arena of interned types, inferred according to a fixed set of
+/// options. `DataType::from_value_with` infers through one.
+#[derive(Debug, Default)]
+pub struct Interner {
+    options: Options,
+    nodes: Vec<Node>,
+    ids: HashMap<Node, TypeId>,
+    symbols: Vec<String>,
+    symbol_ids: HashMap<String, Symbol>,
+    /// The result of unifying each pair of types, and the paths,
+    /// relative to them, where doing so merged integers with
+    /// non-integers.
+    unified: HashMap<(TypeId, TypeId), (TypeId, Vec<String>)>,
+    /// The paths integers and non-integers were merged at, which are
+    /// only warned about once each.
+    merged: HashSet<String>,
+    warnings: Warnings,
+}
+
+impl Interner {
+    /// Create an empty arena.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty arena for inference with the given options.
+    pub fn with_options(options: &Options) -> Self {
+        Interner {
+            options: options.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// The number of distinct types in the arena.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the arena contains no types.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The warnings raised while inferring types so far.
+    pub fn warnings(&self) -> &Warnings {
+        &self.warnings
+    }
+
+    /// Take the warnings raised while inferring types so far, leaving
+    /// none.
+    pub fn take_warnings(&mut self) -> Warnings {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Stop inferring, and return the owned type of the documents,
+    /// interned as `id`, with the warnings raised for them. Arrays
+    /// whose element type is still unknown are warned about here,
+    /// since until now a later document could have had elements for
+    /// them.
+    pub fn finish(mut self, id: TypeId) -> (DataType, Warnings) {
+        self.note_empty_arrays(id, &mut "$".to_string());
+        (self.to_data_type(id), self.warnings)
+    }
+
+    /// Infer the type of a document.
+    pub fn infer(&mut self, v: &Value) -> Result<TypeId, Error> {
+        Ok(self.infer_at(v, &mut "$".to_string(), 0)?)
+    }
+
+    /// Infer the type of an element of a document that's an array, for
+    /// documents that are read a piece at a time. The types of the
+    /// elements are unified with `unify_elements`, and the type of the
+    /// document is then `array_of` them.
+    pub fn infer_element(&mut self, v: &Value) -> Result<TypeId, Error> {
+        Ok(self.infer_at(v, &mut "$[]".to_string(), 1)?)
+    }
+
+    /// The type of a document that's an array of `len` elements, read a
+    /// piece at a time, `sampled` of which had their types unified into
+    /// `elem`.
+    pub fn array_of(&mut self, elem: TypeId, len: usize, sampled: usize) -> TypeId {
+        self.array_at(elem, len, sampled, "$")
+    }
+
+    /// The type of an array at `path` of `len` elements, `sampled` of
+    /// which had their types unified into `elem`.
+    fn array_at(&mut self, elem: TypeId, len: usize, sampled: usize, path: &str) -> TypeId {
+        if sampled < len {
+            let detail = match self.options.sample_random {
+                true => format!("{} random elements", sampled),
+                false => format!("first {} elements", sampled),
+            };
+            self.warnings.push(path, WarningKind::ArraySampled, detail);
+        }
+        match len {
+            1..=MAX_MATRIX_LEN if self.options.matrix && self.fits_matrix(elem) => {
+                self.node(Node::FixedArray(elem, len))
+            }
+            _ => self.node(Node::Array(elem)),
+        }
+    }
+
+    /// Infer the type of a value at `path`, nested `depth` levels into
+    /// the document. `path` is left as it was.
+    fn infer_at(
+        &mut self,
+        v: &Value,
+        path: &mut String,
+        depth: usize,
+    ) -> Result<TypeId, DepthExceeded> {
+        if depth > MAX_DEPTH {
+            return Err(DepthExceeded);
+        }
+
+        let len = path.len();
+        let id = match v {
+            Value::Object(obj) if looks_like_map(obj) => {
+                self.warnings
+                    .push(path.as_str(), WarningKind::MapDetected, "");
+                path.push_str(".*");
+                let mut values = self.unknown();
+                for (_, v) in obj {
+                    let id = self.infer_at(v, path, depth + 1)?;
+                    values = self.unify_at(values, id, path);
+                }
+                path.truncate(len);
+                self.node(Node::Map(values))
+            }
+            Value::Object(obj) => {
+                let mut members = Vec::with_capacity(obj.len());
+                for (key, value) in obj {
+                    path.push('.');
+                    path.push_str(key);
+                    members.push((self.symbol(key), self.infer_at(value, path, depth + 1)?));
+                    path.truncate(len);
+                }
+                members.sort_unstable();
+                self.node(Node::Object(members))
+            }
+            Value::Array(elems) => {
+                path.push_str("[]");
+                let mut elem = self.unknown();
+                let sampled = sample(elems, &self.options);
+                for v in &sampled {
+                    let id = self.infer_at(v, path, depth + 1)?;
+                    elem = self.unify_at(elem, id, path);
+                }
+                path.truncate(len);
+                self.array_at(elem, elems.len(), sampled.len(), path)
+            }
+            scalar => {
+                let typ = DataType::from_scalar(scalar, &self.options);
+                self.leaf(typ)
+            }
+        };
+        Ok(id)
+    }
+
+    /// Warn about the arrays in the type interned as `id`, at `path`,
+    /// whose element type is unknown. `path` is left as it was.
+    fn note_empty_arrays(&mut self, id: TypeId, path: &mut String) {
+        let len = path.len();
+        match self.nodes[id.0 as usize].clone() {
+            Node::Array(elem) if self.options(elem).is_empty() => {
+                self.warnings
+                    .push(path.as_str(), WarningKind::EmptyArray, "");
+            }
+            Node::Array(elem) | Node::FixedArray(elem, _) => {
+                path.push_str("[]");
+                self.note_empty_arrays(elem, path);
+            }
+            Node::Map(values) => {
+                path.push_str(".*");
+                self.note_empty_arrays(values, path);
+            }
+            Node::Object(members) => {
+                for (key, value) in members {
+                    path.push('.');
+                    path.push_str(&self.symbols[key.0 as usize]);
+                    self.note_empty_arrays(value, path);
+                    path.truncate(len);
+                }
+            }
+            Node::Variant(options) => {
+                for option in options {
+                    self.note_empty_arrays(option, path);
+                }
+            }
+            Node::Leaf(_) => {}
+        }
+        path.truncate(len);
+    }
+
+    /// Add a data type to the arena.
+    pub fn intern(&mut self, typ: &DataType) -> TypeId {
+        match typ {
+            DataType::Object(members) => {
+                let mut members = members
+                    .iter()
+                    .map(|(key, value)| (self.symbol(key), self.intern(value)))
+                    .collect::<Vec<_>>();
+                members.sort_unstable();
+                self.node(Node::Object(members))
+            }
+            DataType::Array(elem) => {
+                let elem = self.intern(elem);
+                self.node(Node::Array(elem))
+            }
+            DataType::FixedArray(elem, len) => {
+                let elem = self.intern(elem);
+                self.node(Node::FixedArray(elem, *len))
+            }
+            DataType::Map(values) => {
+                let values = self.intern(values);
+                self.node(Node::Map(values))
+            }
+            DataType::Variant(options) => {
+                let unknown = self.unknown();
+                options.iter().fold(unknown, |acc, option| {
+                    let option = self.intern(option);
+                    self.unify_noting(acc, option).0
+                })
+            }
+            leaf => self.leaf(leaf.clone()),
+        }
+    }
+
+    /// Build the owned data type for an interned type.
+    pub fn to_data_type(&self, id: TypeId) -> DataType {
+        match &self.nodes[id.0 as usize] {
+            Node::Leaf(typ) => typ.clone(),
+            Node::Object(members) => DataType::Object(
+                members
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            self.symbols[key.0 as usize].clone(),
+                            self.to_data_type(*value),
+                        )
+                    })
+                    .collect(),
+            ),
+            Node::Array(elem) => DataType::Array(Box::new(self.to_data_type(*elem))),
+            Node::FixedArray(elem, len) => {
+                DataType::FixedArray(Box::new(self.to_data_type(*elem)), *len)
+            }
+            Node::Map(values) => DataType::Map(Box::new(self.to_data_type(*values))),
+            Node::Variant(options) => DataType::Variant(
+                options
+                    .iter()
+                    .map(|option| self.to_data_type(*option))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Generate a type that could represent something of type `a` or
+    /// of type `b`, as `DataType::unify` does, for the types of two
+    /// documents.
+    pub fn unify(&mut self, a: TypeId, b: TypeId) -> TypeId {
+        self.unify_at(a, b, "$")
+    }
+
+    /// Unify the types of two elements of a document that's an array,
+    /// as inferred by `infer_element`.
+    pub fn unify_elements(&mut self, a: TypeId, b: TypeId) -> TypeId {
+        self.unify_at(a, b, "$[]")
+    }
+
+    /// Unify two types of the values at `path`, warning about where
+    /// that merged integers with non-integers.
+    fn unify_at(&mut self, a: TypeId, b: TypeId, path: &str) -> TypeId {
+        let (result, merged) = self.unify_noting(a, b);
+        for at in merged {
+            let at = format!("{}{}", path, at);
+            if !self.merged.contains(&at) {
+                self.warnings
+                    .push(at.as_str(), WarningKind::IntFloatMerged, "");
+                self.merged.insert(at);
+            }
+        }
+        result
+    }
+
+    /// Unify two types, returning the result and the paths, relative to
+    /// them, where integers and non-integers were merged.
+    fn unify_noting(&mut self, a: TypeId, b: TypeId) -> (TypeId, Vec<String>) {
+        if a == b {
+            return (a, Vec::new());
+        }
+
+        // Unification is commutative, so only remember each pair
+        // once.
+        let key = (a.min(b), a.max(b));
+        if let Some((result, merged)) = self.unified.get(&key) {
+            return (*result, merged.clone());
+        }
+
+        let mut merged = Vec::new();
+        let options = self.options(b);
+        let options = options.into_iter().fold(self.options(a), |acc, option| {
+            self.add_option(acc, option, &mut merged)
+        });
+        let result = if options.len() == 1 {
+            options[0]
+        } else {
+            self.node(Node::Variant(options))
+        };
+
+        self.unified.insert(key, (result, merged.clone()));
+        (result, merged)
+    }
+
+    /// Unify two types found at `step` from the types being merged,
+    /// adding the paths where that merged integers with non-integers to
+    /// `merged`.
+    fn unify_below(
+        &mut self,
+        a: TypeId,
+        b: TypeId,
+        step: &str,
+        merged: &mut Vec<String>,
+    ) -> TypeId {
+        let (result, below) = self.unify_noting(a, b);
+        merged.extend(below.into_iter().map(|at| format!("{}{}", step, at)));
+        result
+    }
+
+    /// The interned unknown type.
+    pub fn unknown(&mut self) -> TypeId {
+        self.node(Node::Variant(Vec::new()))
+    }
+
+    /// The options of a type, if it's a variant, or the type itself
+    /// otherwise.
+    fn options(&self, id: TypeId) -> Vec<TypeId> {
+        match &self.nodes[id.0 as usize] {
+            Node::Variant(options) => options.clone(),
+            _ => vec![id],
+        }
+    }
+
+    /// Add `id` as an option to a sorted list of variant options,
+    /// merging it with an existing option of the same kind if there
+    /// is one.
+    fn add_option(
+        &mut self,
+        mut options: Vec<TypeId>,
+        id: TypeId,
+        merged: &mut Vec<String>,
+    ) -> Vec<TypeId> {
+        match options
+            .iter()
+            .position(|&option| self.same_kind(option, id))
+        {
+            Some(idx) => options[idx] = self.merge(options[idx], id, merged),
+            None => options.push(id),
+        }
+        options.sort_unstable();
+        options.dedup();
+        options
+    }
+
+    /// Whether two non-variant types should be merged into one, rather
+    /// than kept as separate options of a variant.
+    fn same_kind(&self, a: TypeId, b: TypeId) -> bool {
+        match (&self.nodes[a.0 as usize], &self.nodes[b.0 as usize]) {
+            (Node::Leaf(a), Node::Leaf(b)) => a.same_kind(b),
+            (Node::Object(_), Node::Object(_))
+            | (Node::Array(_) | Node::FixedArray(..), Node::Array(_) | Node::FixedArray(..))
+            | (Node::Map(_), Node::Map(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether arrays of a type can have a fixed length, as
+    /// `DataType::fits_matrix` says.
+    fn fits_matrix(&self, id: TypeId) -> bool {
+        match &self.nodes[id.0 as usize] {
+            Node::Leaf(typ) => typ.fits_matrix(),
+            Node::FixedArray(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Merge two non-variant types of the same kind, adding the paths,
+    /// relative to them, where integers and non-integers were merged to
+    /// `merged`.
+    fn merge(&mut self, a: TypeId, b: TypeId, merged: &mut Vec<String>) -> TypeId {
+        if a == b {
+            return a;
+        }
+
+        match (
+            self.nodes[a.0 as usize].clone(),
+            self.nodes[b.0 as usize].clone(),
+        ) {
+            (Node::Leaf(a), Node::Leaf(b)) => {
+                let is_int = |typ: &DataType| matches!(typ, DataType::Int | DataType::IntEnum(_));
+                if (is_int(&a) && b == DataType::Float) || (a == DataType::Float && is_int(&b)) {
+                    merged.push(String::new());
+                }
+                self.leaf(a.unify(b))
+            }
+            (Node::FixedArray(a, n), Node::FixedArray(b, m)) if n == m => {
+                let elem = self.unify_below(a, b, "[]", merged);
+                match self.fits_matrix(elem) {
+                    true => self.node(Node::FixedArray(elem, n)),
+                    false => self.node(Node::Array(elem)),
+                }
+            }
+            // Arrays that don't all have the same length can only be
+            // declared as vectors.
+            (Node::Array(a) | Node::FixedArray(a, _), Node::Array(b) | Node::FixedArray(b, _)) => {
+                let elem = self.unify_below(a, b, "[]", merged);
+                self.node(Node::Array(elem))
+            }
+            (Node::Map(a), Node::Map(b)) => {
+                let values = self.unify_below(a, b, ".*", merged);
+                self.node(Node::Map(values))
+            }
+            (Node::Object(a), Node::Object(b)) => {
+                // Both member lists are sorted by symbol, so walk them
+                // in step; members missing from either side are
+                // unified with null.
+                let null = self.node(Node::Leaf(DataType::Null));
+                let mut members = Vec::with_capacity(a.len().max(b.len()));
+                let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+                loop {
+                    let member = match (a.peek(), b.peek()) {
+                        (Some(&(ka, ta)), Some(&(kb, tb))) if ka == kb => {
+                            a.next();
+                            b.next();
+                            let (member, below) = self.unify_noting(ta, tb);
+                            let key = &self.symbols[ka.0 as usize];
+                            merged.extend(below.into_iter().map(|at| format!(".{}{}", key, at)));
+                            (ka, member)
+                        }
+                        (Some(&(ka, ta)), Some(&(kb, _))) if ka < kb => {
+                            a.next();
+                            (ka, self.unify_noting(ta, null).0)
+                        }
+                        (_, Some(&(kb, tb))) => {
+                            b.next();
+                            (kb, self.unify_noting(tb, null).0)
+                        }
+                        (Some(&(ka, ta)), None) => {
+                            a.next();
+                            (ka, self.unify_noting(ta, null).0)
+                        }
+                        (None, None) => break,
+                    };
+                    members.push(member);
+                }
+                self.node(Node::Object(members))
+            }
+            _ => unreachable!("only types of the same kind are merged"),
+        }
+    }
+
+    /// Intern a type with no children, turning integer enums with more
+    /// codes than `Options::int_enum_threshold` into plain integers.
+    /// Codes are only ever added, so limiting them as they're merged
+    /// gives the same type as limiting them at the end.
+    fn leaf(&mut self, mut typ: DataType) -> TypeId {
+        if let Some(threshold) = self.options.int_enum_threshold {
+            typ.limit_int_enums(threshold);
+        }
+        self.node(Node::Leaf(typ))
+    }
+
+    /// Intern a node, returning the ID of an existing identical node if
+    /// there is one.
+    fn node(&mut self, node: Node) -> TypeId {
+        if let Some(&id) = self.ids.get(&node) {
+            return id;
+        }
+
+        let id = TypeId(self.nodes.len() as u32);
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        id
+    }
+
+    /// Intern an object key.
+    fn symbol(&mut self, key: &str) -> Symbol {
+        if let Some(&symbol) = self.symbol_ids.get(key) {
+            return symbol;
+        }
+
+        let symbol = Symbol(self.symbols.len() as u32);
+        self.symbols.push(key.to_string());
+        self.symbol_ids.insert(key.to_string(), symbol);
+        symbol
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use json::JsonValue;
+
+    #[test]
+    fn unification_matches_tree() {
+        let docs = [
+            json::object! {"name": "a", "id": 1, "tags": ["x"], "extra": {"deep": [1.5]}},
+            json::object! {"name": "b", "id": 2.5, "tags": []},
+            json::object! {"name": null, "id": 3, "tags": ["y", 4], "extra": {"by_id": {"7": 1, "8": 2.5}}},
+            "stray".into(),
+        ];
+
+        let mut interner = Interner::new();
+        let mut interned = interner.unknown();
+        let mut tree = DataType::unknown();
+        for doc in &docs {
+            let id = interner.infer(&Value::from_json(doc).unwrap()).unwrap();
+            interned = interner.unify(interned, id);
+            tree.unify_into(interner.to_data_type(id));
+        }
+        assert_eq!(interner.to_data_type(interned), tree);
+    }
+
+    #[test]
+    fn honors_options() {
+        let options = Options {
+            matrix: true,
+            sample_limit: Some(3),
+            int_enum_threshold: Some(2),
+            ..Options::default()
+        };
+        let data = json::object! {
+            "matrix": [[1, 2], [3, 4.5]],
+            "ragged": [[1, 2], [3]],
+            "status": [1, 2, 1, 2, 1, 2, 3],
+            "kind": [1, 2, 3, 4],
+        };
+
+        let mut interner = Interner::with_options(&options);
+        let id = interner.infer(&Value::from_json(&data).unwrap()).unwrap();
+        let fixed = |elem, len| DataType::FixedArray(Box::new(elem), len);
+        assert_eq!(
+            interner.to_data_type(id),
+            DataType::Object(
+                vec![
+                    ("matrix", fixed(fixed(DataType::Float, 2), 2)),
+                    (
+                        "ragged",
+                        DataType::Array(Box::new(DataType::Array(Box::new(DataType::Int)))),
+                    ),
+                    // Only the first three elements are sampled.
+                    ("status", fixed(DataType::IntEnum([1, 2].into()), 7)),
+                    ("kind", fixed(DataType::Int, 4)),
+                ]
+                .into_iter()
+                .map(|(key, typ)| (key.to_string(), typ))
+                .collect()
+            )
+        );
+    }
+
+    /// The distinct warnings raised inferring the type of `docs` with
+    /// `options`, with how many times each was raised.
+    fn warnings(docs: Vec<JsonValue>, options: &Options) -> Vec<(String, WarningKind, usize)> {
+        let mut interner = Interner::with_options(options);
+        let mut typ = interner.unknown();
+        for doc in docs {
+            let id = interner.infer(&Value::from_json(&doc).unwrap()).unwrap();
+            typ = interner.unify(typ, id);
+        }
+        interner
+            .finish(typ)
+            .1
+            .summary()
+            .into_iter()
+            .map(|(warning, count)| (warning.path.clone(), warning.kind, count))
+            .collect()
+    }
+
+    #[test]
+    fn inference_warnings() {
+        let options = Options::default();
+        assert_eq!(
+            warnings(
+                vec![json::array![{"x": 1, "tags": []}, {"x": 2.5, "tags": []}]],
+                &options
+            ),
+            vec![
+                ("$[].tags".to_string(), WarningKind::EmptyArray, 1),
+                ("$[].x".to_string(), WarningKind::IntFloatMerged, 1),
+            ]
+        );
+
+        // Int and float across separate documents still merge, and an
+        // array that was non-empty anywhere has a known element type.
+        assert_eq!(
+            warnings(
+                vec![
+                    json::object! {"n": 1, "tags": []},
+                    json::object! {"n": 1.5, "tags": ["a"]},
+                    json::object! {"n": 2.0},
+                ],
+                &options
+            ),
+            vec![("$.n".to_string(), WarningKind::IntFloatMerged, 1)]
+        );
+
+        // Merges are found below the values being unified, even when
+        // the unification was remembered from another path.
+        assert_eq!(
+            warnings(
+                vec![json::object! {
+                    "a": [{"v": [1]}, {"v": [1.5]}],
+                    "b": [{"v": [1]}, {"v": [1.5]}],
+                }],
+                &options
+            ),
+            vec![
+                ("$.a[].v[]".to_string(), WarningKind::IntFloatMerged, 1),
+                ("$.b[].v[]".to_string(), WarningKind::IntFloatMerged, 1),
+            ]
+        );
+
+        assert_eq!(
+            warnings(
+                vec![
+                    json::object! {"by_id": {"1": 1, "2": 2}},
+                    json::object! {"by_id": {"3": 3}},
+                ],
+                &options
+            ),
+            vec![("$.by_id".to_string(), WarningKind::MapDetected, 2)]
+        );
+
+        assert_eq!(
+            warnings(
+                vec![json::object! {"a": 1, "b": "x", "c": [true]}],
+                &options
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn sampling_warnings() {
+        let options = Options {
+            sample_limit: Some(2),
+            ..Options::default()
+        };
+        let mut interner = Interner::with_options(&options);
+        interner
+            .infer(&Value::from_json(&json::array![[1, 2, 3.5], [1]]).unwrap())
+            .unwrap();
+        assert_eq!(
+            interner
+                .warnings()
+                .summary()
+                .into_keys()
+                .map(|warning| (warning.path.as_str(), warning.kind, warning.detail.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("$[]", WarningKind::ArraySampled, "first 2 elements")]
+        );
+    }
+
+    #[test]
+    fn identical_types_share_ids() {
+        let mut interner = Interner::new();
+        let a = json::object! {"x": 1, "y": ["z"]};
+        let b = json::object! {"y": ["w"], "x": 2};
+        let a = interner.infer(&Value::from_json(&a).unwrap()).unwrap();
+        let b = interner.infer(&Value::from_json(&b).unwrap()).unwrap();
+        assert_eq!(a, b);
+
+        let typ = interner.to_data_type(a);
+        assert_eq!(interner.intern(&typ), a);
+    }
+
+    #[test]
+    fn repeated_elements_stay_small() {
+        let records = (0..1000)
+            .map(|n| json::object! {"id": n, "name": "record"})
+            .collect::<Vec<_>>();
+
+        let mut interner = Interner::new();
+        let records = Value::from_json(&JsonValue::Array(records)).unwrap();
+        interner.infer(&records).unwrap();
+        assert!(interner.len() < 10);
+    }
+}
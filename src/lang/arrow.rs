@@ -0,0 +1,310 @@
+//! Emission of Rust code constructing an Apache Arrow schema, with the
+//! `arrow` crate's `arrow::datatypes`.
+//!
+//! The top-level object, or each object of a top-level array, is a
+//! record batch row, and the schema is returned by a public function.
+//! Nested objects are `Struct`s whose fields are built by functions of
+//! their own, arrays are `List`s, and maps are `Map`s keyed by strings.
+//! Fields keep their keys, and are nullable if they were null
+//! somewhere. Arrow has unions, but `arrow-json` can't read them, so
+//! values of several kinds are strings, which it reads any scalar into
+//! when coercing primitives.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    namer::Case, options::NullFields, temporal::Temporal, types::Decls, warnings::WarningKind,
+    DataType,
+};
+
+/// Type emitted for values nothing is known about.
+const ARROW_UNKNOWN_TYPE: &str = "DataType::Utf8";
+
+/// Text that goes before the declarations: the `use`s they need.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    if imports.contains("Arc") {
+        s += "use std::sync::Arc;\n\n";
+    }
+    let datatypes = imports
+        .iter()
+        .filter(|import| *import != "Arc")
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    match datatypes.len() {
+        0 => {}
+        1 => writeln!(s, "use arrow::datatypes::{};\n", datatypes[0])
+            .expect("writing to a String can't fail"),
+        _ => writeln!(s, "use arrow::datatypes::{{{}}};\n", datatypes.join(", "))
+            .expect("writing to a String can't fail"),
+    }
+    s
+}
+
+/// Emit the functions building a schema for a data type, returning the
+/// name of the top-level object. Types other than objects and arrays of
+/// them are a row with a single field named `value`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let name = decls.name_at("$");
+    let types = &decls.options.types;
+    let fields = match typ {
+        DataType::Object(members) if !types.contains_key("$") => fields(&name, members, decls, "$"),
+        DataType::Array(elem) | DataType::FixedArray(elem, _)
+            if matches!(*elem, DataType::Object(_))
+                && !types.contains_key("$")
+                && !types.contains_key("$[]") =>
+        {
+            match *elem {
+                DataType::Object(members) => fields(&name, members, decls, "$[]"),
+                _ => unreachable!("checked above"),
+            }
+        }
+        typ => {
+            let (data_type, nullable) = data_type(typ, decls, "$");
+            vec![field("value", &data_type, nullable)]
+        }
+    };
+
+    decls.imports.insert("Schema".to_string());
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, "$");
+    write!(
+        s,
+        "pub fn {}_schema() -> Schema {{\n    Schema::new({})\n}}",
+        Case::Snake.apply(&name, true),
+        field_vec(decls, &fields)
+    )
+    .expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The fields of an object named `name` with the given members.
+fn fields(
+    name: &str,
+    members: BTreeMap<String, DataType>,
+    decls: &mut Decls,
+    path: &str,
+) -> Vec<String> {
+    let declared = decls.declare_object(path, name);
+    let mut fields = Vec::new();
+    let mut arrow_fields = Vec::new();
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        let member_path = format!("{}.{}", path, member);
+        let (data_type, nullable) = match (typ, decls.options.null_fields) {
+            (DataType::Null, NullFields::Drop)
+                if !decls.options.types.contains_key(&member_path) =>
+            {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (typ, _) => data_type(typ, decls, &member_path),
+        };
+        arrow_fields.push(field(&member, &data_type, nullable));
+        fields.push((member, data_type));
+    }
+    decls.declared[declared].fields = fields;
+    arrow_fields
+}
+
+/// Emit a function building the fields of a nested object, returning
+/// its `Struct` type.
+fn struct_type(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let function = format!("{}_fields", Case::Snake.apply(&name, true));
+    let fields = fields(&name, members, decls, path);
+    decls.imports.insert("Fields".to_string());
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(
+        s,
+        "fn {}() -> Fields {{\n    Fields::from({})\n}}",
+        function,
+        field_vec(decls, &fields)
+    )
+    .expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    format!("DataType::Struct({}())", function)
+}
+
+/// A `Field` for the values under `key`.
+fn field(key: &str, data_type: &str, nullable: bool) -> String {
+    format!(
+        "Field::new({}, {}, {})",
+        json::stringify(key),
+        data_type,
+        nullable
+    )
+}
+
+/// A `vec!` of `fields`, one to a line, as the argument of a function
+/// call in a function's body.
+fn field_vec(decls: &mut Decls, fields: &[String]) -> String {
+    if fields.is_empty() {
+        return "Vec::<Field>::new()".to_string();
+    }
+    decls.imports.insert("Field".to_string());
+    let mut s = "vec![\n".to_string();
+    for field in fields {
+        writeln!(s, "        {},", field).expect("writing to a String can't fail");
+    }
+    s += "    ]";
+    s
+}
+
+/// The Arrow type of the values at `path`, declaring the functions it
+/// uses first, and whether they can be null.
+fn data_type(typ: DataType, decls: &mut Decls, path: &str) -> (String, bool) {
+    let nullable = match &typ {
+        DataType::Null => true,
+        DataType::Variant(options) => options.contains(&DataType::Null),
+        _ => false,
+    };
+    if let Some(type_name) = decls.options.types.get(path) {
+        return (type_name.clone(), nullable);
+    }
+
+    decls.imports.insert("DataType".to_string());
+    let type_name = match typ {
+        DataType::Null => "DataType::Null".to_string(),
+        DataType::String => "DataType::Utf8".to_string(),
+        DataType::Temporal(Temporal::Date) => "DataType::Date32".to_string(),
+        DataType::Temporal(kind) => {
+            decls.imports.insert("TimeUnit".to_string());
+            match kind {
+                Temporal::Time => "DataType::Time64(TimeUnit::Microsecond)".to_string(),
+                Temporal::NaiveDateTime => {
+                    "DataType::Timestamp(TimeUnit::Microsecond, None)".to_string()
+                }
+                _ => "DataType::Timestamp(TimeUnit::Microsecond, Some(\"UTC\".into()))".to_string(),
+            }
+        }
+        DataType::Int | DataType::IntEnum(_) => "DataType::Int64".to_string(),
+        DataType::Float => "DataType::Float64".to_string(),
+        DataType::Bool => "DataType::Boolean".to_string(),
+        DataType::Bytes => "DataType::Binary".to_string(),
+        DataType::Object(members) => struct_type(members, decls, path),
+        DataType::Array(elem) => {
+            let item = item(*elem, decls, &format!("{}[]", path));
+            format!("DataType::List({})", item)
+        }
+        DataType::FixedArray(elem, len) => {
+            let item = item(*elem, decls, &format!("{}[]", path));
+            format!("DataType::FixedSizeList({}, {})", item, len)
+        }
+        DataType::Map(values) => {
+            decls.imports.insert("Arc".to_string());
+            decls.imports.insert("Field".to_string());
+            decls.imports.insert("Fields".to_string());
+            let (value_type, value_nullable) = data_type(*values, decls, &format!("{}.*", path));
+            let entries = format!(
+                "DataType::Struct(Fields::from(vec![{}, {}]))",
+                field("keys", "DataType::Utf8", false),
+                field("values", &value_type, value_nullable)
+            );
+            format!(
+                "DataType::Map(Arc::new({}), false)",
+                field("entries", &entries, false)
+            )
+        }
+        DataType::Variant(mut options) => {
+            options.remove(&DataType::Null);
+            match options.len() {
+                0 => unknown(decls),
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    data_type(typ, decls, path).0
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    unknown(decls)
+                }
+            }
+        }
+    };
+    (type_name, nullable)
+}
+
+/// The item field of a list of `elem`, declaring the functions it uses
+/// first.
+fn item(elem: DataType, decls: &mut Decls, path: &str) -> String {
+    decls.imports.insert("Arc".to_string());
+    decls.imports.insert("Field".to_string());
+    let (elem_type, elem_nullable) = data_type(elem, decls, path);
+    format!("Arc::new({})", field("item", &elem_type, elem_nullable))
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(ARROW_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn schema() {
+        assert_eq!(
+            declare_as(
+                Lang::Arrow,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1, null], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": "b", "name": null, "tags": [], "owner": {"ok": false}, "content-type": 2},
+                ]
+            ),
+            "use std::sync::Arc;\n\nuse arrow::datatypes::{DataType, Field, Fields, Schema};\n\n\
+             fn data1_fields() -> Fields {\n    Fields::from(vec![\n        \
+             Field::new(\"ok\", DataType::Boolean, false),\n    ])\n}\n\n\
+             pub fn data0_schema() -> Schema {\n    Schema::new(vec![\n        \
+             Field::new(\"content-type\", DataType::Float64, false),\n        \
+             Field::new(\"id\", DataType::Utf8, false),\n        \
+             Field::new(\"name\", DataType::Utf8, true),\n        \
+             Field::new(\"owner\", DataType::Struct(data1_fields()), false),\n        \
+             Field::new(\"tags\", DataType::List(Arc::new(Field::new(\"item\", DataType::Int64, \
+             true))), false),\n    ])\n}"
+        );
+    }
+
+    #[test]
+    fn timestamps_and_nulls() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Arrow,
+                    detect_dates: true,
+                    ..Options::default()
+                },
+                json::object! {"at": "2024-01-02T03:04:05Z", "gone": null},
+            ),
+            "use arrow::datatypes::{DataType, Field, Schema, TimeUnit};\n\n\
+             pub fn data0_schema() -> Schema {\n    Schema::new(vec![\n        \
+             Field::new(\"at\", DataType::Timestamp(TimeUnit::Microsecond, Some(\"UTC\".into())), \
+             false),\n        Field::new(\"gone\", DataType::Null, true),\n    ])\n}"
+        );
+    }
+}
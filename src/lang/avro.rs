@@ -0,0 +1,230 @@
+//! Emission of an Avro schema, in its JSON form.
+//!
+//! A schema is a single document, so records are defined where they're
+//! first used rather than declared one after another. Values of several
+//! kinds are unions, with null first so that nullable fields can default
+//! to it and be missing too. Avro has no type for values nothing is
+//! known about, so they're typed as null. Field names that aren't the
+//! keys they came from say so in their `doc`, and so do records with
+//! the paths they were found at, since JSON has no comments.
+
+use std::collections::BTreeMap;
+
+use json::JsonValue;
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const AVRO_UNKNOWN_TYPE: &str = "null";
+
+/// Emit the schema of a data type, returning the name of the top-level
+/// record, or of the schema if it isn't one.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let (name, schema) = match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            record(members, decls, "$")
+        }
+        typ => (decls.name_at("$"), schema(typ, decls, "$")),
+    };
+    decls
+        .begin_decl(&name)
+        .push_str(&json::stringify_pretty(schema, 2));
+    name
+}
+
+/// The schema of a record with the given members, and its name.
+fn record(
+    members: BTreeMap<String, DataType>,
+    decls: &mut Decls,
+    path: &str,
+) -> (String, JsonValue) {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut field_schemas = JsonValue::new_array();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let field_schema = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                JsonValue::from(decls.options.types[&member_path].as_str())
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => {
+                union(vec![JsonValue::from("null"), unknown(decls)])
+            }
+            (typ, _) => schema(typ, decls, &member_path),
+        };
+        let mut entry = json::object! {"name": field.as_str()};
+        if field != member {
+            entry["doc"] = format!("{} in JSON", json::stringify(member.as_str())).into();
+        }
+        // A field can only default to the first type of its union.
+        let nullable = field_schema == "null" || field_schema[0] == "null";
+        fields.push((member, json::stringify(field_schema.clone())));
+        entry["type"] = field_schema;
+        if nullable {
+            entry["default"] = JsonValue::Null;
+        }
+        field_schemas
+            .push(entry)
+            .expect("pushing to an array can't fail");
+    }
+    decls.declared[declared].fields = fields;
+
+    let mut schema = json::object! {"type": "record", "name": name.as_str()};
+    let mut occurs = decls.take_scratch();
+    decls.occurs_at(&mut occurs, "", &name, path);
+    if !occurs.is_empty() {
+        schema["doc"] = occurs.trim().into();
+    }
+    decls.put_scratch(occurs);
+    schema["fields"] = field_schemas;
+    (name, schema)
+}
+
+/// The schema of the values at `path`, defining the records it uses.
+fn schema(typ: DataType, decls: &mut Decls, path: &str) -> JsonValue {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return JsonValue::from(type_name.as_str());
+    }
+
+    match typ {
+        DataType::Null => "null".into(),
+        DataType::String | DataType::Temporal(_) => "string".into(),
+        DataType::Int | DataType::IntEnum(_) => "long".into(),
+        DataType::Float => "double".into(),
+        DataType::Bool => "boolean".into(),
+        DataType::Bytes => "bytes".into(),
+        DataType::Object(members) => record(members, decls, path).1,
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let items = schema(*elem, decls, &format!("{}[]", path));
+            json::object! {"type": "array", "items": items}
+        }
+        DataType::Map(values) => {
+            let values = schema(*values, decls, &format!("{}.*", path));
+            json::object! {"type": "map", "values": values}
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(options) => {
+            if options.iter().filter(|typ| **typ != DataType::Null).count() > 1 {
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .filter(|typ| **typ != DataType::Null)
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+            }
+            // Null comes first in the set, as Avro wants it.
+            let arms = options
+                .into_iter()
+                .map(|typ| schema(typ, decls, path))
+                .collect();
+            union(arms)
+        }
+    }
+}
+
+/// The union of `arms`. Avro doesn't allow a type in a union twice, as
+/// strings and dates would be, and a union of one type is just that
+/// type.
+fn union(arms: Vec<JsonValue>) -> JsonValue {
+    let mut union = Vec::<JsonValue>::new();
+    for arm in arms {
+        if !union.contains(&arm) {
+            union.push(arm);
+        }
+    }
+    match union.len() {
+        1 => union.pop().expect("checked above"),
+        _ => JsonValue::Array(union),
+    }
+}
+
+/// The schema to emit for unknown values.
+fn unknown(decls: &Decls) -> JsonValue {
+    decls.options.unknown_type(AVRO_UNKNOWN_TYPE).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{tests::declare_as, Lang};
+
+    #[test]
+    fn records() {
+        assert_eq!(
+            json::parse(&declare_as(
+                Lang::Avro,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": "2", "name": null, "tags": [2], "owner": {"ok": false}, "content-type": 2},
+                ],
+            ))
+            .unwrap(),
+            json::object! {
+                "type": "array",
+                "items": {
+                    "type": "record",
+                    "name": "Data1",
+                    "fields": [
+                        {
+                            "name": "content_type",
+                            "doc": "\"content-type\" in JSON",
+                            "type": "double",
+                        },
+                        {"name": "id", "type": ["string", "long"]},
+                        {"name": "name", "type": ["null", "string"], "default": null},
+                        {
+                            "name": "owner",
+                            "type": {
+                                "type": "record",
+                                "name": "Data2",
+                                "fields": [{"name": "ok", "type": "boolean"}],
+                            },
+                        },
+                        {"name": "tags", "type": {"type": "array", "items": "long"}},
+                    ],
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn maps_and_unknowns() {
+        assert_eq!(
+            json::parse(&declare_as(
+                Lang::Avro,
+                json::object! {"by_id": {"1": [], "2": []}, "gone": null},
+            ))
+            .unwrap(),
+            json::object! {
+                "type": "record",
+                "name": "Data0",
+                "fields": [
+                    {
+                        "name": "by_id",
+                        "type": {"type": "map", "values": {"type": "array", "items": "null"}},
+                    },
+                    {"name": "gone", "type": "null", "default": null},
+                ],
+            }
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Emission of a BigQuery table schema, the JSON array of fields that
+//! `bq load --schema` and the API take.
+//!
+//! The top-level object, or each object of a top-level array, is a row.
+//! Nested objects are `RECORD`s, arrays are `REPEATED`, and fields that
+//! were null somewhere are `NULLABLE`. BigQuery has no maps or unions,
+//! and can't repeat arrays or nulls, so anything of that shape is a
+//! `JSON` field. Fields that had to be renamed give their keys in their
+//! descriptions.
+
+use std::collections::BTreeMap;
+
+use json::JsonValue;
+
+use crate::{
+    options::NullFields, temporal::Temporal, types::Decls, warnings::WarningKind, DataType,
+};
+
+/// Type emitted for values nothing is known about, and for values that
+/// can't be fields of their own.
+const BIGQUERY_UNKNOWN_TYPE: &str = "JSON";
+
+/// Emit the schema of a data type, returning the name of the top-level
+/// object. Types other than objects and arrays of them are a row with a
+/// single field named `value`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let name = decls.name_at("$");
+    let types = &decls.options.types;
+    let fields = match typ {
+        DataType::Object(members) if !types.contains_key("$") => record(&name, members, decls, "$"),
+        DataType::Array(elem) | DataType::FixedArray(elem, _)
+            if matches!(*elem, DataType::Object(_))
+                && !types.contains_key("$")
+                && !types.contains_key("$[]") =>
+        {
+            match *elem {
+                DataType::Object(members) => record(&name, members, decls, "$[]"),
+                _ => unreachable!("checked above"),
+            }
+        }
+        typ => {
+            let mut fields = JsonValue::new_array();
+            if let Some(field) = field(decls, "$", "value", typ) {
+                fields.push(field).expect("pushing to an array can't fail");
+            }
+            fields
+        }
+    };
+    decls
+        .begin_decl(&name)
+        .push_str(&json::stringify_pretty(fields, 2));
+    name
+}
+
+/// The fields of a record named `name` with the given members.
+fn record(
+    name: &str,
+    members: BTreeMap<String, DataType>,
+    decls: &mut Decls,
+    path: &str,
+) -> JsonValue {
+    let declared = decls.declare_object(path, name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut schema = JsonValue::new_array();
+    for ((member, typ), name) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let mut field = match self::field(decls, &member_path, &name, typ) {
+            Some(field) => field,
+            None => continue,
+        };
+        if name != member {
+            field["description"] = format!("{} in JSON", json::stringify(member.as_str())).into();
+        }
+        fields.push((member, json::stringify(field.clone())));
+        schema.push(field).expect("pushing to an array can't fail");
+    }
+    decls.declared[declared].fields = fields;
+    schema
+}
+
+/// The field named `name` for the values at `path`, if they aren't
+/// left out.
+fn field(decls: &mut Decls, path: &str, name: &str, typ: DataType) -> Option<JsonValue> {
+    let (typ, mode) = match typ {
+        DataType::Null if decls.options.null_fields == NullFields::Drop => {
+            decls.warnings.push(path, WarningKind::NullFieldDropped, "");
+            return None;
+        }
+        DataType::Variant(mut options) if options.contains(&DataType::Null) => {
+            options.remove(&DataType::Null);
+            let typ = match options.len() {
+                1 => options.into_iter().next().expect("checked above"),
+                _ => DataType::Variant(options),
+            };
+            (typ, "NULLABLE")
+        }
+        DataType::Null => (DataType::Null, "NULLABLE"),
+        typ => (typ, "REQUIRED"),
+    };
+    if let Some(type_name) = decls.options.types.get(path) {
+        return Some(json::object! {"name": name, "type": type_name.as_str(), "mode": mode});
+    }
+    let (typ, mode) = match typ {
+        DataType::Array(elem) | DataType::FixedArray(elem, _) if repeatable(&elem) => {
+            (*elem, "REPEATED")
+        }
+        typ => (typ, mode),
+    };
+    let path = match mode {
+        "REPEATED" => format!("{}[]", path),
+        _ => path.to_string(),
+    };
+
+    let mut record_fields = None;
+    let mut description = None;
+    let type_name = match typ {
+        _ if decls.options.types.contains_key(&path) => decls.options.types[&path].clone(),
+        DataType::String => "STRING".to_string(),
+        DataType::Temporal(Temporal::Date) => "DATE".to_string(),
+        DataType::Temporal(Temporal::Time) => "TIME".to_string(),
+        DataType::Temporal(Temporal::NaiveDateTime) => "DATETIME".to_string(),
+        DataType::Temporal(Temporal::DateTime) => "TIMESTAMP".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "INTEGER".to_string(),
+        DataType::Float => "FLOAT".to_string(),
+        DataType::Bool => "BOOLEAN".to_string(),
+        DataType::Bytes => "BYTES".to_string(),
+        DataType::Object(members) if !members.is_empty() => {
+            let record_name = decls.name_at(&path);
+            record_fields = Some(record(&record_name, members, decls, &path));
+            let mut occurs = decls.take_scratch();
+            decls.occurs_at(&mut occurs, "", &record_name, &path);
+            if !occurs.is_empty() {
+                description = Some(occurs.trim().to_string());
+            }
+            decls.put_scratch(occurs);
+            "RECORD".to_string()
+        }
+        DataType::Variant(options) if options.len() > 1 => {
+            decls.warnings.push(
+                &path,
+                WarningKind::EnumFallback,
+                options
+                    .iter()
+                    .map(DataType::kind_name)
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            );
+            unknown(decls)
+        }
+        DataType::Null
+        | DataType::Object(_)
+        | DataType::Array(_)
+        | DataType::FixedArray(..)
+        | DataType::Map(_)
+        | DataType::Variant(_) => unknown(decls),
+    };
+    let mut field = json::object! {"name": name, "type": type_name, "mode": mode};
+    if let Some(description) = description {
+        field["description"] = description.into();
+    }
+    if let Some(fields) = record_fields {
+        field["fields"] = fields;
+    }
+    Some(field)
+}
+
+/// Whether an array of `elem` can be a `REPEATED` field: BigQuery has no
+/// arrays of arrays, and no nulls in arrays.
+fn repeatable(elem: &DataType) -> bool {
+    match elem {
+        DataType::Null | DataType::Array(_) | DataType::FixedArray(..) => false,
+        DataType::Variant(options) => !options.contains(&DataType::Null),
+        _ => true,
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls
+        .options
+        .unknown_type(BIGQUERY_UNKNOWN_TYPE)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn fields() {
+        assert_eq!(
+            json::parse(&declare_as(
+                Lang::BigQuery,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": 2, "name": null, "tags": [], "owner": null, "content-type": 2, "x": {"a": 1}},
+                ],
+            ))
+            .unwrap(),
+            json::array![
+                {
+                    "name": "content_type",
+                    "type": "FLOAT",
+                    "mode": "REQUIRED",
+                    "description": "\"content-type\" in JSON",
+                },
+                {"name": "id", "type": "INTEGER", "mode": "REQUIRED"},
+                {"name": "name", "type": "STRING", "mode": "NULLABLE"},
+                {
+                    "name": "owner",
+                    "type": "RECORD",
+                    "mode": "NULLABLE",
+                    "fields": [{"name": "ok", "type": "BOOLEAN", "mode": "REQUIRED"}],
+                },
+                {"name": "tags", "type": "INTEGER", "mode": "REPEATED"},
+                {
+                    "name": "x",
+                    "type": "RECORD",
+                    "mode": "NULLABLE",
+                    "fields": [{"name": "a", "type": "INTEGER", "mode": "REQUIRED"}],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn json_fields() {
+        assert_eq!(
+            json::parse(&declare_as(
+                Lang::BigQuery,
+                json::array![
+                    {"grid": [[1]], "v": [1, "a"], "m": {"1": 1.5, "2": 2.5}, "n": [1, null]},
+                    {"grid": [[2]], "v": [], "m": {"3": 3.5, "4": 4.5}, "n": []},
+                ],
+            ))
+            .unwrap(),
+            json::array![
+                {"name": "grid", "type": "JSON", "mode": "REQUIRED"},
+                {"name": "m", "type": "JSON", "mode": "REQUIRED"},
+                {"name": "n", "type": "JSON", "mode": "REQUIRED"},
+                {"name": "v", "type": "JSON", "mode": "REPEATED"},
+            ]
+        );
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(
+            json::parse(&declare_with(
+                Options {
+                    lang: Lang::BigQuery,
+                    detect_dates: true,
+                    ..Options::default()
+                },
+                json::object! {"at": "2024-01-02T03:04:05Z", "select": "a"},
+            ))
+            .unwrap(),
+            json::array![
+                {"name": "at", "type": "TIMESTAMP", "mode": "REQUIRED"},
+                {"name": "select", "type": "STRING", "mode": "REQUIRED"},
+            ]
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Emission of plain C structs, as a starting point for hand-written
+//! parsers.
+//!
+//! C has no containers, so arrays are a pointer to their first element
+//! followed by a `_len` member, and maps are arrays of key and value
+//! entries. Nested objects are pointed to, and so are nullable numbers
+//! and booleans, which are null when the value is. Values of several
+//! kinds are tagged unions. Arrays and maps that aren't members of an
+//! object, such as the elements of arrays of arrays, are wrapped in a
+//! struct of their own.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{namer::Case, options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const C_UNKNOWN_TYPE: &str = "void *";
+
+/// Text that goes before the structs: the headers they need.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "#include <{}>", import).expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit structs for a data type, returning the name of the top-level
+/// one. Scalars are given a name with a `typedef`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    if let Some(type_name) = decls.options.types.get("$") {
+        return type_name.clone();
+    }
+    match typ {
+        DataType::Object(members) => object(members, decls, "$"),
+        typ @ (DataType::Array(_)
+        | DataType::FixedArray(..)
+        | DataType::Map(_)
+        | DataType::Bytes) => {
+            let name = decls.name_at("$");
+            wrapper(typ, decls, "$", name)
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = value_type(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            write!(s, "typedef {};", declarator(&type_name, &name))
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a struct with the given members, returning its type.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut body = decls.take_scratch();
+    let mut fields = Vec::new();
+    for ((member, typ), field_name) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        if typ == DataType::Null && decls.options.null_fields == NullFields::Drop {
+            decls
+                .warnings
+                .push(member_path, WarningKind::NullFieldDropped, "");
+            continue;
+        }
+        if field_name != member {
+            writeln!(body, "    // {}", json::stringify(member.as_str()))
+                .expect("writing to a String can't fail");
+        }
+        let start = body.len();
+        field(&mut body, decls, &member_path, &field_name, typ);
+        fields.push((member, body[start..].trim().to_string()));
+    }
+    decls.declared[declared].fields = fields;
+    let type_name = format!("struct {}", name);
+    struct_decl(decls, path, &name, &body);
+    decls.put_scratch(body);
+    type_name
+}
+
+/// Emit a struct holding an array or a map, with the members `items`
+/// and `items_len`, returning its type.
+fn wrapper(typ: DataType, decls: &mut Decls, path: &str, name: String) -> String {
+    let mut body = decls.take_scratch();
+    field(&mut body, decls, path, "items", typ);
+    struct_decl(decls, path, &name, &body);
+    decls.put_scratch(body);
+    format!("struct {}", name)
+}
+
+/// Declare the struct for the values at `path`, given its members.
+fn struct_decl(decls: &mut Decls, path: &str, name: &str, body: &str) {
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", name, path);
+    write!(s, "struct {} {{\n{}}};", name, body).expect("writing to a String can't fail");
+    decls.begin_decl(name).push_str(&s);
+    decls.put_scratch(s);
+}
+
+/// Append the member or members for the object member `name`, whose
+/// values are at `path`, to `body`.
+fn field(body: &mut String, decls: &mut Decls, path: &str, name: &str, typ: DataType) {
+    if let Some(type_name) = decls.options.types.get(path) {
+        let type_name = type_name.clone();
+        push(body, &type_name, name);
+        return;
+    }
+
+    match typ {
+        DataType::Null if decls.options.null_fields == NullFields::Option => {
+            let type_name = unknown(decls);
+            push(body, &type_name, name);
+        }
+        DataType::Object(members) => {
+            let type_name = object(members, decls, path);
+            push(body, &pointer(&type_name), name);
+        }
+        DataType::FixedArray(elem, len) => {
+            let elem_name = value_type(*elem, decls, &format!("{}[]", path));
+            push(body, &elem_name, &format!("{}[{}]", name, len));
+        }
+        DataType::Array(elem) => {
+            let elem_name = value_type(*elem, decls, &format!("{}[]", path));
+            push(body, &pointer(&elem_name), name);
+            decls.imports.insert("stddef.h".to_string());
+            push(body, "size_t", &format!("{}_len", name));
+        }
+        DataType::Bytes => {
+            decls.imports.insert("stdint.h".to_string());
+            push(body, "uint8_t *", name);
+            decls.imports.insert("stddef.h".to_string());
+            push(body, "size_t", &format!("{}_len", name));
+        }
+        DataType::Map(values) => {
+            let entry = decls.next_name();
+            let mut entry_body = decls.take_scratch();
+            push(&mut entry_body, "char *", "key");
+            let value_name = value_type(*values, decls, &format!("{}.*", path));
+            push(&mut entry_body, &value_name, "value");
+            struct_decl(decls, path, &entry, &entry_body);
+            decls.put_scratch(entry_body);
+            push(body, &pointer(&format!("struct {}", entry)), name);
+            decls.imports.insert("stddef.h".to_string());
+            push(body, "size_t", &format!("{}_len", name));
+        }
+        DataType::Variant(mut options)
+            if options.len() == 2 && options.contains(&DataType::Null) =>
+        {
+            options.remove(&DataType::Null);
+            let typ = options.into_iter().next().expect("checked above");
+            match typ {
+                // These can already be null.
+                DataType::Object(_)
+                | DataType::Array(_)
+                | DataType::Map(_)
+                | DataType::String
+                | DataType::Temporal(_)
+                | DataType::Bytes => field(body, decls, path, name, typ),
+                typ => {
+                    let type_name = value_type(typ, decls, path);
+                    push(body, &pointer(&type_name), name);
+                }
+            }
+        }
+        typ => {
+            let type_name = value_type(typ, decls, path);
+            push(body, &type_name, name);
+        }
+    }
+}
+
+/// Append the declaration of the member `name` as a `type_name` to
+/// `body`.
+fn push(body: &mut String, type_name: &str, name: &str) {
+    writeln!(body, "    {};", declarator(type_name, name)).expect("writing to a String can't fail");
+}
+
+/// The type of a value at `path` that's held directly, as the elements
+/// of arrays and the arms of unions are, declaring it if need be.
+fn value_type(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "void *".to_string(),
+        DataType::String | DataType::Temporal(_) => "char *".to_string(),
+        DataType::Int | DataType::IntEnum(_) => {
+            decls.imports.insert("stdint.h".to_string());
+            "int64_t".to_string()
+        }
+        DataType::Float => "double".to_string(),
+        DataType::Bool => {
+            decls.imports.insert("stdbool.h".to_string());
+            "bool".to_string()
+        }
+        DataType::Object(members) => object(members, decls, path),
+        typ @ (DataType::Array(_)
+        | DataType::FixedArray(..)
+        | DataType::Map(_)
+        | DataType::Bytes) => {
+            let name = decls.next_name();
+            wrapper(typ, decls, path, name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(options) => tagged_union(options, decls, path),
+    }
+}
+
+/// Emit a tagged union of the given options, returning its type. The
+/// tag is named after the union and the kind of each option, and null
+/// has a tag but no member.
+fn tagged_union(options: BTreeSet<DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.next_name();
+    decls.warnings.push(
+        path,
+        WarningKind::EnumFallback,
+        options
+            .iter()
+            .map(DataType::kind_name)
+            .collect::<Vec<_>>()
+            .join(" | "),
+    );
+
+    let prefix = Case::Snake.apply(&name, true).to_uppercase();
+    let mut tags = Vec::new();
+    let mut arms = decls.take_scratch();
+    for typ in options {
+        let kind = typ.kind_name();
+        tags.push(format!("{}_{}", prefix, kind.to_uppercase()));
+        if typ == DataType::Null {
+            continue;
+        }
+        let arm_name = decls.namer.style().unkeyword(kind.to_string());
+        let type_name = match typ {
+            DataType::Object(members) => pointer(&object(members, decls, path)),
+            typ => value_type(typ, decls, path),
+        };
+        writeln!(arms, "        {};", declarator(&type_name, &arm_name))
+            .expect("writing to a String can't fail");
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(
+        s,
+        "struct {} {{\n    enum {{ {} }} kind;\n    union {{\n{}    }} value;\n}};",
+        name,
+        tags.join(", "),
+        arms
+    )
+    .expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(arms);
+    format!("struct {}", name)
+}
+
+/// The declaration of `name` as a `type_name`.
+fn declarator(type_name: &str, name: &str) -> String {
+    match type_name.ends_with('*') {
+        true => format!("{}{}", type_name, name),
+        false => format!("{} {}", type_name, name),
+    }
+}
+
+/// The type of a pointer to a `type_name`.
+fn pointer(type_name: &str) -> String {
+    match type_name.ends_with('*') {
+        true => format!("{}*", type_name),
+        false => format!("{} *", type_name),
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(C_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_type},
+            Lang,
+        },
+        DataType, Options,
+    };
+
+    #[test]
+    fn structs() {
+        assert_eq!(
+            declare_as(
+                Lang::C,
+                json::array![
+                    {"id": 1, "name": "a", "tags": ["x"], "owner": {"ok": true}, "score": 1.5, "int": 1},
+                    {"id": null, "name": null, "tags": [], "owner": null, "score": 2, "int": 2},
+                ],
+            ),
+            "#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n\
+             struct Data2 {\n    bool ok;\n};\n\n\
+             struct Data1 {\n    int64_t *id;\n    // \"int\"\n    int64_t int_;\n    char *name;\n    \
+             struct Data2 *owner;\n    double score;\n    char **tags;\n    size_t tags_len;\n};\n\n\
+             struct Data0 {\n    struct Data1 *items;\n    size_t items_len;\n};"
+        );
+    }
+
+    #[test]
+    fn unions_and_maps() {
+        assert_eq!(
+            declare_as(
+                Lang::C,
+                json::object! {
+                    "value": [1, "a", null],
+                    "by_id": {"1": [1.5], "2": [2.5]},
+                }
+            ),
+            "#include <stddef.h>\n#include <stdint.h>\n\n\
+             struct Data2 {\n    double *items;\n    size_t items_len;\n};\n\n\
+             struct Data1 {\n    char *key;\n    struct Data2 value;\n};\n\n\
+             struct Data3 {\n    enum { DATA3_NULL, DATA3_STRING, DATA3_INT } kind;\n    \
+             union {\n        char *string;\n        int64_t int_;\n    } value;\n};\n\n\
+             struct Data0 {\n    struct Data1 *by_id;\n    size_t by_id_len;\n    \
+             struct Data3 *value;\n    size_t value_len;\n};"
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(
+            declare_type(
+                Options {
+                    lang: Lang::C,
+                    ..Options::default()
+                },
+                DataType::Object(
+                    vec![("blob".to_string(), DataType::Bytes)]
+                        .into_iter()
+                        .collect()
+                ),
+            ),
+            "#include <stddef.h>\n#include <stdint.h>\n\nstruct Data0 {\n    uint8_t *blob;\n    \
+             size_t blob_len;\n};"
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! Emission of a Cap'n Proto schema.
+//!
+//! Field ordinals come from the same numbering as proto field numbers,
+//! less one, so they're assigned in order of the fields' names and kept
+//! stable by a schema state file. Cap'n Proto doesn't allow gaps
+//! between ordinals, so retired numbers are kept as `Void` fields.
+//! Values of several kinds, and nullable values that can't be null
+//! themselves, are unions in a group named after the field. Lists can't
+//! hold unions and there are no maps, so union elements are wrapped in
+//! a struct with a single field named `value`, and maps are lists of
+//! key and value structs. Field names that aren't their keys are given
+//! them with `$Json.name`, for the JSON codec.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    namer::Case, options::NullFields, schema_state::FieldNumbers, types::Decls,
+    warnings::WarningKind, DataType,
+};
+
+/// Type emitted for values nothing is known about.
+const CAPNP_UNKNOWN_TYPE: &str = "Json.Value";
+
+/// The file that declares the JSON codec's annotations and value type.
+const JSON_CAPNP: &str = "/capnp/compat/json.capnp";
+
+/// Text that goes before the declarations: the file's ID, and the JSON
+/// declarations if they're used.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for id in imports.iter().filter(|import| import.starts_with('@')) {
+        writeln!(s, "{};\n", id).expect("writing to a String can't fail");
+    }
+    if imports.contains(JSON_CAPNP) {
+        writeln!(s, "using Json = import \"{}\";\n", JSON_CAPNP)
+            .expect("writing to a String can't fail");
+    }
+    s
+}
+
+/// Emit structs for a data type, returning the name of the top-level
+/// struct. Types other than objects are wrapped in a struct, since
+/// they can't be named otherwise.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let name = match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            structure(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            wrapper(name, typ, decls, "$")
+        }
+    };
+    // Every file needs a unique ID. Hashing the declarations gives the
+    // same input the same ID, and different inputs different ones.
+    let id = decls
+        .output()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    decls.imports.insert(format!("@0x{:016x}", id | 1 << 63));
+    name
+}
+
+/// Emit a struct with the given members.
+fn structure(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut numbers = decls.field_numbers(path);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    // Field names have to be camel case, starting with a lower case
+    // letter and without underscores.
+    let wanted = members
+        .iter()
+        .map(|(member, _)| {
+            let field = Case::Camel.apply(member, true);
+            match field.starts_with(|c: char| c.is_ascii_lowercase())
+                && !decls.namer.style().keywords.contains(&field.as_str())
+            {
+                true => field,
+                false => format!("field{}", Case::Pascal.apply(&field, true)),
+            }
+        })
+        .collect::<Vec<_>>();
+    let field_names = decls
+        .namer
+        .fields(&wanted.iter().map(String::as_str).collect::<Vec<_>>());
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field_name) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let json_name = match field_name != member {
+            true => Some(member.as_str()),
+            false => None,
+        };
+        let start = body.len();
+        field(
+            &mut body,
+            &mut numbers,
+            decls,
+            &member_path,
+            (&field_name, json_name),
+            typ,
+        );
+        fields.push((member, body[start..].trim().to_string()));
+    }
+    decls.declared[declared].fields = fields;
+    let retired = decls.finish_field_numbers(path, numbers);
+    struct_decl(decls, path, &name, &retired, &body);
+    decls.put_scratch(body);
+    name
+}
+
+/// Emit a struct named `name` with a single field named `value`, for
+/// types that can't be used where they're needed.
+fn wrapper(name: String, typ: DataType, decls: &mut Decls, path: &str) -> String {
+    let mut body = decls.take_scratch();
+    let mut numbers = decls.field_numbers(path);
+    field(&mut body, &mut numbers, decls, path, ("value", None), typ);
+    let retired = decls.finish_field_numbers(path, numbers);
+    struct_decl(decls, path, &name, &retired, &body);
+    decls.put_scratch(body);
+    name
+}
+
+/// Declare the struct for the values at `path`, given its fields and
+/// the numbers of the fields it used to have.
+fn struct_decl(decls: &mut Decls, path: &str, name: &str, retired: &[u32], body: &str) {
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "#", name, path);
+    write!(s, "struct {} {{\n{}", name, body).expect("writing to a String can't fail");
+    for number in retired {
+        writeln!(s, "  obsolete{} @{} :Void;", number, number - 1)
+            .expect("writing to a String can't fail");
+    }
+    s += "}";
+    decls.begin_decl(name).push_str(&s);
+    decls.put_scratch(s);
+}
+
+/// Append the field or union group for a struct member to `body`,
+/// given the field's name and its key in JSON, if that's different.
+fn field(
+    body: &mut String,
+    numbers: &mut FieldNumbers,
+    decls: &mut Decls,
+    path: &str,
+    name: (&str, Option<&str>),
+    typ: DataType,
+) {
+    if let Some(type_name) = decls.options.types.get(path) {
+        let type_name = type_name.clone();
+        push_field(body, numbers, decls, &type_name, name);
+        return;
+    }
+
+    match typ {
+        DataType::Null if decls.options.null_fields == NullFields::Drop => {
+            decls.warnings.push(path, WarningKind::NullFieldDropped, "");
+        }
+        DataType::Null if decls.options.null_fields == NullFields::Option => {
+            let type_name = unknown(decls);
+            push_field(body, numbers, decls, &type_name, name);
+        }
+        DataType::Variant(mut options) if !options.is_empty() => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() == 1 {
+                let typ = options.iter().next().expect("checked above");
+                let pointer = !matches!(
+                    typ,
+                    DataType::Int | DataType::Float | DataType::Bool | DataType::IntEnum(_)
+                );
+                if !nullable || pointer {
+                    // Text, lists and structs can already be null.
+                    let typ = typ.clone();
+                    field(body, numbers, decls, path, name, typ);
+                    return;
+                }
+            } else {
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+            }
+            if nullable {
+                options.insert(DataType::Null);
+            }
+
+            let (field_name, json_name) = name;
+            write!(body, "  {} :union", field_name).expect("writing to a String can't fail");
+            annotate(body, decls, json_name);
+            body.push_str(" {\n");
+            for typ in options {
+                let arm = Case::Camel.apply(typ.kind_name(), true);
+                let type_name = element(typ, decls, path);
+                let key = format!("{}{}", field_name, Case::Pascal.apply(&arm, true));
+                writeln!(
+                    body,
+                    "    {} @{} :{};",
+                    arm,
+                    numbers.number(&key) - 1,
+                    type_name
+                )
+                .expect("writing to a String can't fail");
+            }
+            body.push_str("  }\n");
+        }
+        typ => {
+            let type_name = element(typ, decls, path);
+            push_field(body, numbers, decls, &type_name, name);
+        }
+    }
+}
+
+/// Append a single field declaration to `body`.
+fn push_field(
+    body: &mut String,
+    numbers: &mut FieldNumbers,
+    decls: &mut Decls,
+    type_name: &str,
+    (name, json_name): (&str, Option<&str>),
+) {
+    write!(
+        body,
+        "  {} @{} :{}",
+        name,
+        numbers.number(name) - 1,
+        type_name
+    )
+    .expect("writing to a String can't fail");
+    annotate(body, decls, json_name);
+    body.push_str(";\n");
+}
+
+/// Append the annotation giving a field's key in JSON, if it has to be
+/// given.
+fn annotate(body: &mut String, decls: &mut Decls, json_name: Option<&str>) {
+    if let Some(json_name) = json_name {
+        decls.imports.insert(JSON_CAPNP.to_string());
+        write!(body, " $Json.name({})", json::stringify(json_name))
+            .expect("writing to a String can't fail");
+    }
+}
+
+/// The type of the values at `path` for use other than as a union,
+/// declaring it first if need be.
+fn element(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "Void".to_string(),
+        DataType::String | DataType::Temporal(_) => "Text".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "Int64".to_string(),
+        DataType::Float => "Float64".to_string(),
+        DataType::Bool => "Bool".to_string(),
+        DataType::Bytes => "Data".to_string(),
+        DataType::Object(members) => structure(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_name = element(*elem, decls, &format!("{}[]", path));
+            format!("List({})", elem_name)
+        }
+        DataType::Map(values) => {
+            let entry = decls.next_name();
+            let mut body = decls.take_scratch();
+            let mut numbers = decls.field_numbers(path);
+            push_field(&mut body, &mut numbers, decls, "Text", ("key", None));
+            let values_path = format!("{}.*", path);
+            field(
+                &mut body,
+                &mut numbers,
+                decls,
+                &values_path,
+                ("value", None),
+                *values,
+            );
+            let retired = decls.finish_field_numbers(path, numbers);
+            struct_decl(decls, path, &entry, &retired, &body);
+            decls.put_scratch(body);
+            format!("List({})", entry)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        typ => {
+            let name = decls.next_name();
+            wrapper(name, typ, decls, path)
+        }
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert(JSON_CAPNP.to_string());
+            CAPNP_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{tests::declare_as, Lang};
+
+    /// `output` after its file ID, which is a hash, so is only checked
+    /// to be there.
+    fn without_id(output: String) -> String {
+        assert!(output.starts_with("@0x"));
+        output.split_once(";\n\n").unwrap().1.to_string()
+    }
+
+    #[test]
+    fn structs() {
+        assert_eq!(
+            without_id(declare_as(
+                Lang::Capnp,
+                json::array![
+                    {"id": 1, "name": "a", "tags": ["x"], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": null, "name": null, "tags": [], "owner": {"ok": false}, "content-type": 2},
+                ],
+            )),
+            "using Json = import \"/capnp/compat/json.capnp\";\n\n\
+             struct Data2 {\n  ok @0 :Bool;\n}\n\n\
+             struct Data1 {\n  contentType @0 :Float64 $Json.name(\"content-type\");\n  \
+             id :union {\n    null @1 :Void;\n    int @2 :Int64;\n  }\n  \
+             name @3 :Text;\n  owner @4 :Data2;\n  tags @5 :List(Text);\n}\n\n\
+             struct Data0 {\n  value @0 :List(Data1);\n}"
+        );
+    }
+
+    #[test]
+    fn unions_and_maps() {
+        assert_eq!(
+            without_id(declare_as(
+                Lang::Capnp,
+                json::object! {"by_id": {"1": [1, "a"], "2": []}, "struct": true},
+            )),
+            "using Json = import \"/capnp/compat/json.capnp\";\n\n\
+             struct Data2 {\n  value :union {\n    string @0 :Text;\n    int @1 :Int64;\n  }\n}\n\n\
+             struct Data1 {\n  key @0 :Text;\n  value @1 :List(Data2);\n}\n\n\
+             struct Data0 {\n  byId @0 :List(Data1) $Json.name(\"by_id\");\n  \
+             fieldStruct @1 :Bool $Json.name(\"struct\");\n}"
+        );
+    }
+
+    #[test]
+    fn unknowns_and_nulls() {
+        assert_eq!(
+            without_id(declare_as(
+                Lang::Capnp,
+                json::object! {"empty": [], "gone": null},
+            )),
+            "using Json = import \"/capnp/compat/json.capnp\";\n\nstruct Data0 {\n  \
+             empty @0 :List(Json.Value);\n  gone @1 :Void;\n}"
+        );
+    }
+}
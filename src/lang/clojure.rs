@@ -0,0 +1,241 @@
+//! Emission of `clojure.spec` definitions.
+//!
+//! Each object is an `s/keys` spec of its keys, read as unqualified
+//! keywords, and the spec for each key is named in a namespace after
+//! the object's, so that the same key can have different specs in
+//! different objects. Nullable values are `s/nilable`, and their keys
+//! are optional, since they can also be missing. Values of several
+//! kinds are `s/or`s tagged with their kinds. Keys that can't be
+//! keywords can't be specced, and say so in a comment.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{namer::Case, options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Spec emitted for values nothing is known about.
+const CLOJURE_UNKNOWN_TYPE: &str = "any?";
+
+/// Text that goes before the declarations: the namespace they're in,
+/// requiring spec.
+pub(crate) fn header(_imports: &BTreeSet<String>) -> String {
+    "(ns data\n  (:require [clojure.spec.alpha :as s]))\n\n".to_string()
+}
+
+/// Emit specs for a data type, returning the name of the top-level
+/// one.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let spec = spec(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, ";;", &name, "$");
+            write!(s, "(s/def ::{} {})", kebab(&name), spec)
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit an `s/keys` spec with the given members, after the specs of its
+/// keys.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let namespace = kebab(&name);
+    let mut fields = Vec::new();
+    let mut keys = Vec::new();
+    let mut opt_keys = Vec::new();
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, ";;", &name, path);
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        if !is_keyword(&member) {
+            writeln!(
+                s,
+                ";; {} can't be a keyword, so isn't specced.",
+                json::stringify(member.as_str())
+            )
+            .expect("writing to a String can't fail");
+            continue;
+        }
+        let member_path = format!("{}.{}", path, member);
+        let spec = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => format!("(s/nilable {})", unknown(decls)),
+            (typ, _) => spec(typ, decls, &member_path),
+        };
+        let key = format!(":{}/{}", namespace, member);
+        writeln!(s, "(s/def {} {})", key, spec).expect("writing to a String can't fail");
+        // Members that are nil in some documents are missing from
+        // others, so `:req-un` would reject them.
+        match spec == "nil?" || spec.starts_with("(s/nilable ") {
+            true => opt_keys.push(key),
+            false => keys.push(key),
+        }
+        fields.push((member, spec));
+    }
+    write!(s, "(s/def ::{} (s/keys", namespace).expect("writing to a String can't fail");
+    if !keys.is_empty() {
+        write!(s, " :req-un [{}]", keys.join(" ")).expect("writing to a String can't fail");
+    }
+    if !opt_keys.is_empty() {
+        write!(s, " :opt-un [{}]", opt_keys.join(" ")).expect("writing to a String can't fail");
+    }
+    s += "))";
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The spec of the values at `path`, declaring the specs it uses first.
+fn spec(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "nil?".to_string(),
+        DataType::String | DataType::Temporal(_) => "string?".to_string(),
+        DataType::Int => "int?".to_string(),
+        DataType::Float => "number?".to_string(),
+        DataType::IntEnum(codes) => format!(
+            "#{{{}}}",
+            codes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        DataType::Bool => "boolean?".to_string(),
+        DataType::Bytes => "bytes?".to_string(),
+        DataType::Object(members) => format!("::{}", kebab(&object(members, decls, path))),
+        DataType::Array(elem) => {
+            let elem_spec = spec(*elem, decls, &format!("{}[]", path));
+            format!("(s/coll-of {} :kind vector?)", elem_spec)
+        }
+        DataType::FixedArray(elem, len) => {
+            let elem_spec = spec(*elem, decls, &format!("{}[]", path));
+            format!("(s/coll-of {} :kind vector? :count {})", elem_spec, len)
+        }
+        DataType::Map(values) => {
+            // Keys are read as keywords, like those of objects.
+            let value_spec = spec(*values, decls, &format!("{}.*", path));
+            format!("(s/map-of keyword? {})", value_spec)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let spec = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    spec(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    let arms = options
+                        .into_iter()
+                        .map(|typ| {
+                            let tag = typ.kind_name();
+                            format!(":{} {}", tag, spec(typ, decls, path))
+                        })
+                        .collect::<Vec<_>>();
+                    format!("(s/or {})", arms.join(" "))
+                }
+            };
+            match nullable {
+                true => format!("(s/nilable {})", spec),
+                false => spec,
+            }
+        }
+    }
+}
+
+/// `name` in kebab case, as spec names are written.
+fn kebab(name: &str) -> String {
+    Case::Snake.apply(name, true).replace('_', "-")
+}
+
+/// Whether `key` can be read as a keyword without a namespace.
+fn is_keyword(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || "*+!-_'?<>=.".contains(c))
+}
+
+/// The spec to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(CLOJURE_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{tests::declare_as, Lang};
+
+    #[test]
+    fn specs() {
+        assert_eq!(
+            declare_as(
+                Lang::Clojure,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "a b": 1},
+                    {"id": "b", "name": null, "tags": [2], "owner": {}, "content-type": 2, "a b": 2},
+                ]
+            ),
+            "(ns data\n  (:require [clojure.spec.alpha :as s]))\n\n\
+             (s/def ::data2 (s/keys))\n\n\
+             ;; \"a b\" can't be a keyword, so isn't specced.\n\
+             (s/def :data1/content-type number?)\n\
+             (s/def :data1/id (s/or :string string? :int int?))\n\
+             (s/def :data1/name (s/nilable string?))\n\
+             (s/def :data1/owner ::data2)\n\
+             (s/def :data1/tags (s/coll-of int? :kind vector?))\n\
+             (s/def ::data1 (s/keys :req-un [:data1/content-type :data1/id :data1/owner \
+             :data1/tags] :opt-un [:data1/name]))\n\n\
+             (s/def ::data0 (s/coll-of ::data1 :kind vector?))"
+        );
+    }
+
+    #[test]
+    fn missing_members() {
+        assert_eq!(
+            declare_as(
+                Lang::Clojure,
+                json::array![{"id": 1, "note": "a", "gone": null}, {"id": 2}]
+            ),
+            "(ns data\n  (:require [clojure.spec.alpha :as s]))\n\n(s/def :data1/gone nil?)\n\
+             (s/def :data1/id int?)\n(s/def :data1/note (s/nilable string?))\n\
+             (s/def ::data1 (s/keys :req-un [:data1/id] :opt-un [:data1/gone :data1/note]))\n\n\
+             (s/def ::data0 (s/coll-of ::data1 :kind vector?))"
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Emission of C++ structs, with `from_json` and `to_json` functions for
+//! nlohmann::json.
+//!
+//! Each struct is followed by the functions that convert it, which
+//! nlohmann::json finds by argument-dependent lookup. Nullable values
+//! are `std::optional`s, converted by a serializer declared in the
+//! header, since nlohmann::json has none of its own, and may be missing
+//! as well as null. Values of several kinds are kept as
+//! `nlohmann::json`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const CPP_UNKNOWN_TYPE: &str = "nlohmann::json";
+
+/// Converts `std::optional`s to and from null or their value.
+const OPTIONAL_SERIALIZER: &str = "namespace nlohmann {
+template <typename T>
+struct adl_serializer<std::optional<T>> {
+    static void to_json(json &j, const std::optional<T> &value) {
+        if (value) {
+            j = *value;
+        } else {
+            j = nullptr;
+        }
+    }
+
+    static void from_json(const json &j, std::optional<T> &value) {
+        if (j.is_null()) {
+            value = std::nullopt;
+        } else {
+            value = j.get<T>();
+        }
+    }
+};
+}  // namespace nlohmann
+";
+
+/// Text that goes before the declarations: the headers they include,
+/// and the serializer for `std::optional` if they use it.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "#include <{}>", import).expect("writing to a String can't fail");
+    }
+    if imports.contains("optional") {
+        s += "\n";
+        s += OPTIONAL_SERIALIZER;
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit structs for a data type, returning the name of the top-level
+/// one. Types other than objects are given a name with a type alias.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    decls.imports.insert("nlohmann/json.hpp".to_string());
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            write!(s, "using {} = {};", name, type_name).expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a struct with the given members, and the functions converting
+/// it.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut names = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => unknown(decls),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        writeln!(body, "    {} {};", type_name, field).expect("writing to a String can't fail");
+        let optional = type_name.starts_with("std::optional<");
+        names.push((json::stringify(member.as_str()), field, optional));
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(s, "struct {} {{\n{}}};", name, body).expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    s.clear();
+
+    writeln!(
+        s,
+        "inline void from_json(const nlohmann::json &j, {} &value) {{",
+        name
+    )
+    .expect("writing to a String can't fail");
+    for (key, field, optional) in &names {
+        // Missing members are read as null if they can be.
+        if *optional {
+            write!(s, "    if (j.contains({})) ", key).expect("writing to a String can't fail");
+        } else {
+            s += "    ";
+        }
+        writeln!(s, "j.at({}).get_to(value.{});", key, field)
+            .expect("writing to a String can't fail");
+    }
+    s += "}\n\n";
+    writeln!(
+        s,
+        "inline void to_json(nlohmann::json &j, const {} &value) {{",
+        name
+    )
+    .expect("writing to a String can't fail");
+    s += "    j = nlohmann::json::object();\n";
+    for (key, field, _) in &names {
+        writeln!(s, "    j[{}] = value.{};", key, field).expect("writing to a String can't fail");
+    }
+    s += "}";
+    decls.begin_decl(&name).push_str(&s);
+
+    decls.declared[declared].fields = fields;
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the structs it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => {
+            decls.imports.insert("cstddef".to_string());
+            "std::nullptr_t".to_string()
+        }
+        DataType::String | DataType::Temporal(_) => {
+            decls.imports.insert("string".to_string());
+            "std::string".to_string()
+        }
+        DataType::Int | DataType::IntEnum(_) => {
+            decls.imports.insert("cstdint".to_string());
+            "std::int64_t".to_string()
+        }
+        DataType::Float => "double".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Bytes => {
+            decls.imports.insert("cstdint".to_string());
+            decls.imports.insert("vector".to_string());
+            "std::vector<std::uint8_t>".to_string()
+        }
+        DataType::Object(members) => object(members, decls, path),
+        DataType::Array(elem) => {
+            decls.imports.insert("vector".to_string());
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("std::vector<{}>", elem_name)
+        }
+        DataType::FixedArray(elem, len) => {
+            decls.imports.insert("array".to_string());
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("std::array<{}, {}>", elem_name, len)
+        }
+        DataType::Map(values) => {
+            decls.imports.insert("map".to_string());
+            decls.imports.insert("string".to_string());
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("std::map<std::string, {}>", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() > 1 {
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+                // A JSON value can already be null.
+                return unknown(decls);
+            }
+            let typ = options.into_iter().next().expect("checked above");
+            let type_name = type_expr(typ, decls, path);
+            match nullable {
+                true => {
+                    decls.imports.insert("optional".to_string());
+                    format!("std::optional<{}>", type_name)
+                }
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(CPP_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{tests::declare_as, Lang};
+
+    #[test]
+    fn structs() {
+        let output = declare_as(
+            Lang::Cpp,
+            json::array![
+                {"id": 1, "name": "a", "tags": [1], "owner": {"ok": true}, "x": "a", "class": 1.5},
+                {"id": 2, "name": null, "tags": [2], "owner": {"ok": false}, "x": 1, "class": 2},
+            ],
+        );
+        assert!(output.starts_with(
+            "#include <cstdint>\n#include <nlohmann/json.hpp>\n#include <optional>\n\
+             #include <string>\n#include <vector>\n\nnamespace nlohmann {\n"
+        ));
+        assert!(output.ends_with(
+            "}  // namespace nlohmann\n\n\
+             struct Data2 {\n    bool ok;\n};\n\n\
+             inline void from_json(const nlohmann::json &j, Data2 &value) {\n    \
+             j.at(\"ok\").get_to(value.ok);\n}\n\n\
+             inline void to_json(nlohmann::json &j, const Data2 &value) {\n    \
+             j = nlohmann::json::object();\n    j[\"ok\"] = value.ok;\n}\n\n\
+             struct Data1 {\n    double class_;\n    std::int64_t id;\n    \
+             std::optional<std::string> name;\n    Data2 owner;\n    \
+             std::vector<std::int64_t> tags;\n    nlohmann::json x;\n};\n\n\
+             inline void from_json(const nlohmann::json &j, Data1 &value) {\n    \
+             j.at(\"class\").get_to(value.class_);\n    j.at(\"id\").get_to(value.id);\n    \
+             if (j.contains(\"name\")) j.at(\"name\").get_to(value.name);\n    j.at(\"owner\").get_to(value.owner);\n    \
+             j.at(\"tags\").get_to(value.tags);\n    j.at(\"x\").get_to(value.x);\n}\n\n\
+             inline void to_json(nlohmann::json &j, const Data1 &value) {\n    \
+             j = nlohmann::json::object();\n    j[\"class\"] = value.class_;\n    \
+             j[\"id\"] = value.id;\n    j[\"name\"] = value.name;\n    \
+             j[\"owner\"] = value.owner;\n    j[\"tags\"] = value.tags;\n    j[\"x\"] = value.x;\n}\n\n\
+             using Data0 = std::vector<Data1>;"
+        ));
+    }
+
+    #[test]
+    fn maps_and_nulls() {
+        let output = declare_as(
+            Lang::Cpp,
+            json::object! {"by_id": {"1": 1, "2": 2}, "gone": null},
+        );
+        assert!(output.starts_with(
+            "#include <cstddef>\n#include <cstdint>\n#include <map>\n#include <nlohmann/json.hpp>\n\
+             #include <string>\n\nstruct Data0 {\n    \
+             std::map<std::string, std::int64_t> by_id;\n    std::nullptr_t gone;\n};\n\n"
+        ));
+    }
+}
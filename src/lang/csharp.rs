@@ -0,0 +1,279 @@
+//! Emission of C# classes, read with `System.Text.Json`.
+//!
+//! Properties are named in Pascal case, as C# properties are, with a
+//! `[JsonPropertyName]` attribute giving the key when it's different.
+//! C# has no unions, so values of several kinds are read as
+//! `JsonElement`s, and no type aliases, so a document that's an array
+//! or a map is declared as a class extending `List` or `Dictionary`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{namer::Case, options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const CSHARP_UNKNOWN_TYPE: &str = "JsonElement";
+
+/// The C# types that are values rather than references, which are only
+/// nullable when marked with `?`, and need no initializer.
+const VALUE_TYPES: &[&str] = &["long", "double", "bool", "JsonElement"];
+
+/// Text that goes before the declarations: the namespaces they use, and
+/// nullable reference types turned on so that `?` means nullable.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = "#nullable enable\n\n".to_string();
+    for import in imports {
+        writeln!(s, "using {};", import).expect("writing to a String can't fail");
+    }
+    if !imports.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit classes for a data type, returning the name of the top-level
+/// one. Arrays and maps are declared as classes extending the
+/// collection that holds them, and other types that aren't objects
+/// aren't declared at all: their C# type is returned instead.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    if decls.options.types.contains_key("$") {
+        return type_expr(typ, decls, "$");
+    }
+    let (name, base) = match typ {
+        DataType::Object(members) => return class(members, decls, "$"),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let name = decls.name_at("$");
+            let elem_name = type_expr(*elem, decls, "$[]");
+            (name, format!("List<{}>", elem_name))
+        }
+        DataType::Map(values) => {
+            let name = decls.name_at("$");
+            let value_name = type_expr(*values, decls, "$.*");
+            (name, format!("Dictionary<string, {}>", value_name))
+        }
+        typ => return type_expr(typ, decls, "$"),
+    };
+    decls
+        .imports
+        .insert("System.Collections.Generic".to_string());
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, "$");
+    write!(s, "{}class {} : {} {{ }}", visibility(decls), name, base)
+        .expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// Emit a class with a property for each of the given members.
+fn class(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let wanted = members
+        .iter()
+        .map(|(member, _)| Case::Pascal.apply(member, false))
+        .collect::<Vec<_>>();
+    let property_names = decls
+        .namer
+        .fields(&wanted.iter().map(String::as_str).collect::<Vec<_>>());
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), property) in members.into_iter().zip(property_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => format!("{}?", unknown(decls)),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        if !body.is_empty() {
+            body += "\n";
+        }
+        if property != member {
+            decls
+                .imports
+                .insert("System.Text.Json.Serialization".to_string());
+            writeln!(
+                body,
+                "    [JsonPropertyName({})]",
+                json::stringify(member.as_str())
+            )
+            .expect("writing to a String can't fail");
+        }
+        write!(
+            body,
+            "    public {} {} {{ get; set; }}",
+            type_name, property
+        )
+        .expect("writing to a String can't fail");
+        // Without an initializer, properties that can't be null would
+        // be warned about for starting out null.
+        if !type_name.ends_with('?') && !VALUE_TYPES.contains(&type_name.as_str()) {
+            body += " = default!;";
+        }
+        body += "\n";
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(s, "{}class {}\n{{\n{}}}", visibility(decls), name, body)
+        .expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the classes it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "object?".to_string(),
+        DataType::String | DataType::Temporal(_) => "string".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "long".to_string(),
+        DataType::Float => "double".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Bytes => "byte[]".to_string(),
+        DataType::Object(members) => class(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            decls
+                .imports
+                .insert("System.Collections.Generic".to_string());
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("List<{}>", elem_name)
+        }
+        DataType::Map(values) => {
+            decls
+                .imports
+                .insert("System.Collections.Generic".to_string());
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("Dictionary<string, {}>", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    unknown(decls)
+                }
+            };
+            match nullable && !type_name.ends_with('?') {
+                true => format!("{}?", type_name),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The modifier to declare types with, if they're public.
+fn visibility(decls: &Decls) -> &'static str {
+    match decls.options.pub_types {
+        true => "public ",
+        false => "",
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert("System.Text.Json".to_string());
+            CSHARP_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn classes() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::CSharp,
+                    pub_types: true,
+                    ..Options::default()
+                },
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+                    {"id": null, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+                ]
+            ),
+            "#nullable enable\n\n\
+             using System.Collections.Generic;\n\
+             using System.Text.Json;\n\
+             using System.Text.Json.Serialization;\n\n\
+             public class Data2\n{\n}\n\n\
+             public class Data1\n{\n    \
+             [JsonPropertyName(\"content-type\")]\n    \
+             public double ContentType { get; set; }\n\n    \
+             [JsonPropertyName(\"id\")]\n    \
+             public long? Id { get; set; }\n\n    \
+             [JsonPropertyName(\"name\")]\n    \
+             public string? Name { get; set; }\n\n    \
+             [JsonPropertyName(\"owner\")]\n    \
+             public Data2 Owner { get; set; } = default!;\n\n    \
+             [JsonPropertyName(\"tags\")]\n    \
+             public List<long> Tags { get; set; } = default!;\n\n    \
+             [JsonPropertyName(\"x\")]\n    \
+             public JsonElement X { get; set; }\n}\n\n\
+             public class Data0 : List<Data1> { }"
+        );
+    }
+
+    #[test]
+    fn dictionaries() {
+        assert_eq!(
+            declare_as(
+                Lang::CSharp,
+                json::object! {"by_id": {"1": true, "2": false}, "class": null},
+            ),
+            "#nullable enable\n\nusing System.Collections.Generic;\n\
+             using System.Text.Json.Serialization;\n\nclass Data0\n{\n    \
+             [JsonPropertyName(\"by_id\")]\n    \
+             public Dictionary<string, bool> ById { get; set; } = default!;\n\n    \
+             [JsonPropertyName(\"class\")]\n    public object? Class { get; set; }\n}"
+        );
+    }
+}
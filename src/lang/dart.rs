@@ -0,0 +1,237 @@
+//! Emission of Dart classes annotated for `json_serializable`.
+//!
+//! Each class has a constructor taking its fields by name, and
+//! `fromJson` and `toJson` members that call the functions
+//! `json_serializable` generates into the file's part, which has to be
+//! named after the file. Members whose keys had to be renamed are
+//! annotated with `@JsonKey` giving the key. Values of several kinds are
+//! left as `Object`s, and types that aren't objects are named with a
+//! `typedef`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{namer::Case, options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const DART_UNKNOWN_TYPE: &str = "Object?";
+
+/// Text that goes before the declarations: the libraries they import,
+/// and a reminder of the part `json_serializable` generates.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "import '{}';", import).expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n// Name the generated part after this file.\npart 'data.g.dart';\n\n";
+    }
+    s
+}
+
+/// Emit declarations for a data type, returning the name of the
+/// top-level one. Types other than objects are given a name with a
+/// `typedef`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            class(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            write!(s, "typedef {} = {};", name, type_name).expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a class with the given members.
+fn class(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    decls
+        .imports
+        .insert("package:json_annotation/json_annotation.dart".to_string());
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    // Fields starting with an underscore would be private, and left
+    // out by `json_serializable`.
+    let wanted = members
+        .iter()
+        .map(|(member, _)| match member.starts_with('_') {
+            true => Case::Camel.apply(member, false),
+            false => member.clone(),
+        })
+        .collect::<Vec<_>>();
+    let field_names = decls
+        .namer
+        .fields(&wanted.iter().map(String::as_str).collect::<Vec<_>>());
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    let mut params = decls.take_scratch();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, _) => unknown(decls),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        if field != member {
+            // Dart interpolates `$` even in double quotes.
+            let key = json::stringify(member.as_str()).replace('$', "\\$");
+            writeln!(body, "  @JsonKey(name: {})", key).expect("writing to a String can't fail");
+        }
+        writeln!(body, "  final {} {};", type_name, field).expect("writing to a String can't fail");
+        let required = match type_name.ends_with('?') {
+            true => "",
+            false => "required ",
+        };
+        writeln!(params, "    {}this.{},", required, field)
+            .expect("writing to a String can't fail");
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(s, "@JsonSerializable()\nclass {} {{\n", name).expect("writing to a String can't fail");
+    match params.is_empty() {
+        true => writeln!(s, "  {}();", name),
+        false => writeln!(s, "{}\n  {}({{\n{}  }});", body, name, params),
+    }
+    .expect("writing to a String can't fail");
+    write!(
+        s,
+        "\n  factory {name}.fromJson(Map<String, dynamic> json) => _${name}FromJson(json);\n\n  \
+         Map<String, dynamic> toJson() => _${name}ToJson(this);\n}}",
+        name = name
+    )
+    .expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(params);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the classes it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => unknown(decls),
+        // `json_serializable` can't read bytes, which are in base64
+        // in JSON.
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => "String".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "int".to_string(),
+        DataType::Float => "double".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Object(members) => class(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("List<{}>", elem_name)
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("Map<String, {}>", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    "Object".to_string()
+                }
+            };
+            match nullable && !type_name.ends_with('?') {
+                true => format!("{}?", type_name),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(DART_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{tests::declare_as, Lang};
+
+    #[test]
+    fn classes() {
+        assert_eq!(
+            declare_as(
+                Lang::Dart,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+                    {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+                ]
+            ),
+            "import 'package:json_annotation/json_annotation.dart';\n\n\
+             // Name the generated part after this file.\npart 'data.g.dart';\n\n\
+             @JsonSerializable()\nclass Data2 {\n  Data2();\n\n  \
+             factory Data2.fromJson(Map<String, dynamic> json) => _$Data2FromJson(json);\n\n  \
+             Map<String, dynamic> toJson() => _$Data2ToJson(this);\n}\n\n\
+             @JsonSerializable()\nclass Data1 {\n  \
+             @JsonKey(name: \"content-type\")\n  final double contentType;\n  \
+             final int id;\n  final String? name;\n  final Data2 owner;\n  \
+             final List<int> tags;\n  final Object x;\n\n  \
+             Data1({\n    required this.contentType,\n    required this.id,\n    \
+             this.name,\n    required this.owner,\n    required this.tags,\n    \
+             required this.x,\n  });\n\n  \
+             factory Data1.fromJson(Map<String, dynamic> json) => _$Data1FromJson(json);\n\n  \
+             Map<String, dynamic> toJson() => _$Data1ToJson(this);\n}\n\n\
+             typedef Data0 = List<Data1>;"
+        );
+    }
+
+    #[test]
+    fn keywords_and_nulls() {
+        assert_eq!(
+            declare_as(Lang::Dart, json::object! {"class": "a", "gone": null}),
+            "import 'package:json_annotation/json_annotation.dart';\n\n\
+             // Name the generated part after this file.\npart 'data.g.dart';\n\n\
+             @JsonSerializable()\nclass Data0 {\n  @JsonKey(name: \"class\")\n  \
+             final String class_;\n  final Object? gone;\n\n  Data0({\n    \
+             required this.class_,\n    this.gone,\n  });\n\n  \
+             factory Data0.fromJson(Map<String, dynamic> json) => _$Data0FromJson(json);\n\n  \
+             Map<String, dynamic> toJson() => _$Data0ToJson(this);\n}"
+        );
+    }
+}
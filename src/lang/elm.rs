@@ -0,0 +1,512 @@
+//! Emission of Elm types, with `Json.Decode` decoders and `Json.Encode`
+//! encoders for them.
+//!
+//! Objects are record type aliases and values of several kinds are
+//! custom types with a variant for each kind. Every declared type is
+//! followed by its decoder, named after it with a `Decoder` suffix, and
+//! its encoder, named after it with an `encode` prefix. Types given in
+//! the options are assumed to have a decoder and an encoder named the
+//! same way. Record fields are always renamed into camel case, since
+//! the decoders give the keys anyway.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{namer::Case, options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Applies a decoder for the next field of a record to a decoder of
+/// the record's constructor, as `Json.Decode.Pipeline.required` would.
+const AND_MAP: &str = "andMap : Decoder a -> Decoder (a -> b) -> Decoder b
+andMap =
+    Decode.map2 (|>)";
+
+/// Decodes a field that can be null or missing as `Nothing`, but fails
+/// if the field is there and can't be decoded.
+const OPTIONAL_FIELD: &str = "optionalField : String -> Decoder (Maybe a) -> Decoder (Maybe a)
+optionalField key decoder =
+    Decode.maybe (Decode.field key Decode.value)
+        |> Decode.andThen
+            (\\value ->
+                case value of
+                    Just _ ->
+                        Decode.field key decoder
+
+                    Nothing ->
+                        Decode.succeed Nothing
+            )";
+
+/// The functions the decoders can use, which are declared in the header
+/// when their names are among the imports.
+const HELPERS: &[(&str, &str)] = &[("andMap", AND_MAP), ("optionalField", OPTIONAL_FIELD)];
+
+/// An Elm type, with expressions for its decoder and encoder.
+struct Elm {
+    type_name: String,
+    decoder: String,
+    encoder: String,
+}
+
+impl Elm {
+    fn new(type_name: &str, decoder: &str, encoder: &str) -> Self {
+        Elm {
+            type_name: type_name.to_string(),
+            decoder: decoder.to_string(),
+            encoder: encoder.to_string(),
+        }
+    }
+
+    /// A type declared with its decoder and encoder named after it.
+    fn named(name: &str) -> Self {
+        let mut decoder = name.to_string();
+        if let Some(first) = decoder.get(..1) {
+            let first = first.to_lowercase();
+            decoder.replace_range(..1, &first);
+        }
+        Elm {
+            type_name: name.to_string(),
+            decoder: decoder + "Decoder",
+            encoder: format!("encode{}", name),
+        }
+    }
+
+    /// The same type, but also null.
+    fn nullable(self) -> Self {
+        if self.type_name.starts_with("Maybe ") {
+            return self;
+        }
+        Elm {
+            type_name: format!("Maybe {}", paren(&self.type_name)),
+            decoder: format!("Decode.nullable {}", paren(&self.decoder)),
+            encoder: format!(
+                "Maybe.map {} >> Maybe.withDefault Encode.null",
+                paren(&self.encoder)
+            ),
+        }
+    }
+}
+
+/// Text that goes before the declarations: the modules they import and
+/// the helpers their decoders use.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        if !HELPERS.iter().any(|(name, _)| name == import) {
+            writeln!(s, "import {}", import).expect("writing to a String can't fail");
+        }
+    }
+    for (name, helper) in HELPERS {
+        if imports.contains(*name) {
+            write!(s, "\n{}\n", helper).expect("writing to a String can't fail");
+        }
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit declarations for a data type, returning the name of the
+/// top-level one. Types other than objects are given a name with a type
+/// alias, and a decoder and encoder of their own.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    decls
+        .imports
+        .insert("Json.Decode as Decode exposing (Decoder)".to_string());
+    decls.imports.insert("Json.Encode as Encode".to_string());
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            record(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let elm = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "--", &name, "$");
+            write!(s, "type alias {} =\n    {}", name, elm.type_name)
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            s.clear();
+            let named = Elm::named(&name);
+            write!(
+                s,
+                "{decoder} : Decoder {name}\n{decoder} =\n    {}\n\n\
+                 {encoder} : {name} -> Encode.Value\n{encoder} =\n    {}",
+                elm.decoder,
+                elm.encoder,
+                name = name,
+                decoder = named.decoder,
+                encoder = named.encoder,
+            )
+            .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a record type alias with the given members, with its decoder
+/// and encoder.
+fn record(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    // Record fields have to start with a lower case letter.
+    let wanted = members
+        .iter()
+        .map(|(member, _)| {
+            let field = Case::Camel.apply(member, true);
+            match field.starts_with(|c: char| c.is_ascii_lowercase()) {
+                true => field,
+                false => format!("field{}", field),
+            }
+        })
+        .collect::<Vec<_>>();
+    let field_names = decls
+        .namer
+        .fields(&wanted.iter().map(String::as_str).collect::<Vec<_>>());
+    let mut fields = Vec::new();
+    let mut elms = Vec::new();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let elm = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                Elm::named(&decls.options.types[&member_path])
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => unknown(decls).nullable(),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        elms.push((
+            json::stringify(member.as_str()),
+            field,
+            elm.decoder,
+            elm.encoder,
+        ));
+        fields.push((member, elm.type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "--", &name, path);
+    write!(s, "type alias {} =\n    {{", name).expect("writing to a String can't fail");
+    for (idx, ((_, field, ..), (_, type_name))) in elms.iter().zip(&fields).enumerate() {
+        if idx > 0 {
+            s += "\n    ,";
+        }
+        write!(s, " {} : {}", field, type_name).expect("writing to a String can't fail");
+    }
+    match fields.is_empty() {
+        true => s += "}",
+        false => s += "\n    }",
+    }
+    decls.begin_decl(&name).push_str(&s);
+    s.clear();
+
+    let named = Elm::named(&name);
+    write!(
+        s,
+        "{decoder} : Decoder {name}\n{decoder} =\n",
+        name = name,
+        decoder = named.decoder
+    )
+    .expect("writing to a String can't fail");
+    match elms.is_empty() {
+        true => s += "    Decode.succeed {}",
+        false => {
+            decls.imports.insert("andMap".to_string());
+            write!(s, "    Decode.succeed {}", name).expect("writing to a String can't fail");
+        }
+    }
+    for ((key, _, decoder, _), (_, type_name)) in elms.iter().zip(&fields) {
+        // Fields that can be null can be missing too.
+        let field = match type_name.starts_with("Maybe ") {
+            true => {
+                decls.imports.insert("optionalField".to_string());
+                "optionalField"
+            }
+            false => "Decode.field",
+        };
+        write!(
+            s,
+            "\n        |> andMap ({} {} {})",
+            field,
+            key,
+            paren(decoder)
+        )
+        .expect("writing to a String can't fail");
+    }
+
+    write!(
+        s,
+        "\n\n{encoder} : {name} -> Encode.Value\n{encoder} {value} =\n    Encode.object\n",
+        name = name,
+        encoder = named.encoder,
+        value = if elms.is_empty() { "_" } else { "value" },
+    )
+    .expect("writing to a String can't fail");
+    match elms.is_empty() {
+        true => s += "        []",
+        false => {
+            for (idx, (key, field, _, encoder)) in elms.iter().enumerate() {
+                let bracket = if idx == 0 { '[' } else { ',' };
+                let encoder = match encoder.contains(">>") {
+                    true => format!("({})", encoder),
+                    false => encoder.clone(),
+                };
+                writeln!(
+                    s,
+                    "        {} ( {}, {} value.{} )",
+                    bracket, key, encoder, field
+                )
+                .expect("writing to a String can't fail");
+            }
+            s += "        ]";
+        }
+    }
+    decls.begin_decl(&name).push_str(&s);
+
+    decls.declared[declared].fields = fields;
+    decls.put_scratch(s);
+    name
+}
+
+/// Emit a custom type with a variant for each kind of value in
+/// `options`, with its decoder, which tries each kind in turn, and its
+/// encoder.
+fn custom_type(options: BTreeSet<DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.next_name();
+    decls.warnings.push(
+        path,
+        WarningKind::EnumFallback,
+        options
+            .iter()
+            .map(DataType::kind_name)
+            .collect::<Vec<_>>()
+            .join(" | "),
+    );
+
+    let arms = options
+        .into_iter()
+        .map(|typ| {
+            let variant = format!("{}{}", name, Case::Pascal.apply(typ.kind_name(), true));
+            (variant, type_expr(typ, decls, path))
+        })
+        .collect::<Vec<_>>();
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "--", &name, path);
+    write!(s, "type {}", name).expect("writing to a String can't fail");
+    for (idx, (variant, elm)) in arms.iter().enumerate() {
+        let separator = if idx == 0 { '=' } else { '|' };
+        write!(
+            s,
+            "\n    {} {} {}",
+            separator,
+            variant,
+            paren(&elm.type_name)
+        )
+        .expect("writing to a String can't fail");
+    }
+    decls.begin_decl(&name).push_str(&s);
+    s.clear();
+
+    let named = Elm::named(&name);
+    write!(
+        s,
+        "{decoder} : Decoder {name}\n{decoder} =\n    Decode.oneOf",
+        name = name,
+        decoder = named.decoder
+    )
+    .expect("writing to a String can't fail");
+    for (idx, (variant, elm)) in arms.iter().enumerate() {
+        let bracket = if idx == 0 { '[' } else { ',' };
+        write!(
+            s,
+            "\n        {} Decode.map {} {}",
+            bracket,
+            variant,
+            paren(&elm.decoder)
+        )
+        .expect("writing to a String can't fail");
+    }
+    write!(
+        s,
+        "\n        ]\n\n{encoder} : {name} -> Encode.Value\n{encoder} value =\n    \
+         case value of",
+        name = name,
+        encoder = named.encoder
+    )
+    .expect("writing to a String can't fail");
+    for (idx, (variant, elm)) in arms.iter().enumerate() {
+        if idx > 0 {
+            s += "\n";
+        }
+        write!(
+            s,
+            "\n        {} inner ->\n            {} inner",
+            variant,
+            paren(&elm.encoder)
+        )
+        .expect("writing to a String can't fail");
+    }
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The type of the values at `path`, declaring the types it uses first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> Elm {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return Elm::named(type_name);
+    }
+
+    match typ {
+        DataType::Null => Elm::new("()", "Decode.null ()", "always Encode.null"),
+        // Bytes are in base64 in JSON.
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => {
+            Elm::new("String", "Decode.string", "Encode.string")
+        }
+        DataType::Int | DataType::IntEnum(_) => Elm::new("Int", "Decode.int", "Encode.int"),
+        DataType::Float => Elm::new("Float", "Decode.float", "Encode.float"),
+        DataType::Bool => Elm::new("Bool", "Decode.bool", "Encode.bool"),
+        DataType::Object(members) => Elm::named(&record(members, decls, path)),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem = type_expr(*elem, decls, &format!("{}[]", path));
+            Elm {
+                type_name: format!("List {}", paren(&elem.type_name)),
+                decoder: format!("Decode.list {}", paren(&elem.decoder)),
+                encoder: format!("Encode.list {}", paren(&elem.encoder)),
+            }
+        }
+        DataType::Map(values) => {
+            decls.imports.insert("Dict exposing (Dict)".to_string());
+            let values = type_expr(*values, decls, &format!("{}.*", path));
+            Elm {
+                type_name: format!("Dict String {}", paren(&values.type_name)),
+                decoder: format!("Decode.dict {}", paren(&values.decoder)),
+                encoder: format!("Encode.dict identity {}", paren(&values.encoder)),
+            }
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let elm = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => Elm::named(&custom_type(options, decls, path)),
+            };
+            match nullable {
+                true => elm.nullable(),
+                false => elm,
+            }
+        }
+    }
+}
+
+/// `expr` in parentheses if it needs them to be an argument.
+fn paren(expr: &str) -> String {
+    match expr.contains(' ') && expr != "()" {
+        true => format!("({})", expr),
+        false => expr.to_string(),
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> Elm {
+    match &decls.options.unknown_type {
+        Some(unknown) => Elm::named(unknown),
+        None => Elm::new("Decode.Value", "Decode.value", "identity"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{tests::declare_as, Lang};
+
+    #[test]
+    fn records() {
+        let output = declare_as(
+            Lang::Elm,
+            json::array![
+                {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5},
+                {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2},
+            ],
+        );
+        assert!(output.starts_with(
+            "import Json.Decode as Decode exposing (Decoder)\n\
+             import Json.Encode as Encode\n\n\
+             andMap : Decoder a -> Decoder (a -> b) -> Decoder b\n"
+        ));
+        assert!(output.ends_with(
+            "type alias Data2 =\n    {}\n\n\
+             data2Decoder : Decoder Data2\ndata2Decoder =\n    Decode.succeed {}\n\n\
+             encodeData2 : Data2 -> Encode.Value\nencodeData2 _ =\n    Encode.object\n        []\n\n\
+             type alias Data1 =\n    { contentType : Float\n    , id : Int\n    \
+             , name : Maybe String\n    , owner : Data2\n    , tags : List Int\n    }\n\n\
+             data1Decoder : Decoder Data1\ndata1Decoder =\n    Decode.succeed Data1\n        \
+             |> andMap (Decode.field \"content-type\" Decode.float)\n        \
+             |> andMap (Decode.field \"id\" Decode.int)\n        \
+             |> andMap (optionalField \"name\" (Decode.nullable Decode.string))\n        \
+             |> andMap (Decode.field \"owner\" data2Decoder)\n        \
+             |> andMap (Decode.field \"tags\" (Decode.list Decode.int))\n\n\
+             encodeData1 : Data1 -> Encode.Value\nencodeData1 value =\n    Encode.object\n        \
+             [ ( \"content-type\", Encode.float value.contentType )\n        \
+             , ( \"id\", Encode.int value.id )\n        \
+             , ( \"name\", (Maybe.map Encode.string >> Maybe.withDefault Encode.null) value.name )\n        \
+             , ( \"owner\", encodeData2 value.owner )\n        \
+             , ( \"tags\", Encode.list Encode.int value.tags )\n        ]\n\n\
+             type alias Data0 =\n    List Data1\n\n\
+             data0Decoder : Decoder Data0\ndata0Decoder =\n    Decode.list data1Decoder\n\n\
+             encodeData0 : Data0 -> Encode.Value\nencodeData0 =\n    Encode.list encodeData1"
+        ));
+    }
+
+    #[test]
+    fn custom_types() {
+        let output = declare_as(
+            Lang::Elm,
+            json::object! {"value": [1, "a"], "by_id": {"1": null, "2": null}},
+        );
+        assert!(output.starts_with("import Dict exposing (Dict)\n"));
+        assert!(output.ends_with(
+            "type Data1\n    = Data1String String\n    | Data1Int Int\n\n\
+             data1Decoder : Decoder Data1\ndata1Decoder =\n    Decode.oneOf\n        \
+             [ Decode.map Data1String Decode.string\n        \
+             , Decode.map Data1Int Decode.int\n        ]\n\n\
+             encodeData1 : Data1 -> Encode.Value\nencodeData1 value =\n    case value of\n        \
+             Data1String inner ->\n            Encode.string inner\n\n        \
+             Data1Int inner ->\n            Encode.int inner\n\n\
+             type alias Data0 =\n    { byId : Dict String ()\n    , value : List Data1\n    }\n\n\
+             data0Decoder : Decoder Data0\ndata0Decoder =\n    Decode.succeed Data0\n        \
+             |> andMap (Decode.field \"by_id\" (Decode.dict (Decode.null ())))\n        \
+             |> andMap (Decode.field \"value\" (Decode.list data1Decoder))\n\n\
+             encodeData0 : Data0 -> Encode.Value\nencodeData0 value =\n    Encode.object\n        \
+             [ ( \"by_id\", Encode.dict identity (always Encode.null) value.byId )\n        \
+             , ( \"value\", Encode.list encodeData1 value.value )\n        ]"
+        ));
+    }
+
+    #[test]
+    fn keywords() {
+        let output = declare_as(Lang::Elm, json::object! {"type": "a"});
+        assert!(output.ends_with(
+            "type alias Data0 =\n    { type_ : String\n    }\n\n\
+             data0Decoder : Decoder Data0\ndata0Decoder =\n    Decode.succeed Data0\n        \
+             |> andMap (Decode.field \"type\" Decode.string)\n\n\
+             encodeData0 : Data0 -> Encode.Value\nencodeData0 value =\n    Encode.object\n        \
+             [ ( \"type\", Encode.string value.type_ )\n        ]"
+        ));
+    }
+}
@@ -0,0 +1,267 @@
+//! Emission of a FlatBuffers schema.
+//!
+//! Like proto3, FlatBuffers limits where types can go: vectors can't
+//! hold vectors, unions can only hold tables, and there are no maps.
+//! Types that can't go somewhere directly are wrapped in a table with a
+//! single field named `value`, and maps are vectors of tables keyed by
+//! their `key` field. Values nothing is known about are FlexBuffers.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Emit tables for a data type, returning the name of the root table.
+/// Types other than objects are wrapped in a table, since the root has
+/// to be one.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let name = match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            table(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            wrapper(name, typ, decls, "$")
+        }
+    };
+    write!(decls.begin_decl(&name), "root_type {};", name).expect("writing to a String can't fail");
+    name
+}
+
+/// Emit a table with the given members.
+fn table(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field_name) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        if typ == DataType::Null && decls.options.null_fields == NullFields::Drop {
+            decls
+                .warnings
+                .push(member_path, WarningKind::NullFieldDropped, "");
+            continue;
+        }
+        if field_name != member {
+            writeln!(body, "  // {} in JSON", json::stringify(member.as_str()))
+                .expect("writing to a String can't fail");
+        }
+        let start = body.len();
+        field(&mut body, decls, &member_path, &field_name, typ);
+        fields.push((member, body[start..].trim().to_string()));
+    }
+    decls.declared[declared].fields = fields;
+    table_decl(decls, path, &name, &body);
+    decls.put_scratch(body);
+    name
+}
+
+/// Emit a table named `name` with a single field named `value`, for
+/// types that can't be used where they're needed.
+fn wrapper(name: String, typ: DataType, decls: &mut Decls, path: &str) -> String {
+    let mut body = decls.take_scratch();
+    field(&mut body, decls, path, "value", typ);
+    table_decl(decls, path, &name, &body);
+    decls.put_scratch(body);
+    name
+}
+
+/// Declare the table for the values at `path`, given its fields.
+fn table_decl(decls: &mut Decls, path: &str, name: &str, body: &str) {
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", name, path);
+    write!(s, "table {} {{\n{}}}", name, body).expect("writing to a String can't fail");
+    decls.begin_decl(name).push_str(&s);
+    decls.put_scratch(s);
+}
+
+/// Append the field for a table member to `body`.
+fn field(body: &mut String, decls: &mut Decls, path: &str, name: &str, typ: DataType) {
+    if let Some(type_name) = decls.options.types.get(path) {
+        writeln!(body, "  {}:{};", name, type_name).expect("writing to a String can't fail");
+        return;
+    }
+
+    match typ {
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_name = element(*elem, decls, &format!("{}[]", path));
+            writeln!(body, "  {}:[{}];", name, elem_name)
+        }
+        DataType::Bytes => writeln!(body, "  {}:[ubyte];", name),
+        DataType::Map(values) => {
+            let entry = decls.next_name();
+            let mut entry_body = decls.take_scratch();
+            entry_body += "  key:string (key);\n";
+            field(
+                &mut entry_body,
+                decls,
+                &format!("{}.*", path),
+                "value",
+                *values,
+            );
+            table_decl(decls, path, &entry, &entry_body);
+            decls.put_scratch(entry_body);
+            writeln!(body, "  {}:[{}];", name, entry)
+        }
+        DataType::Variant(mut options) if !options.is_empty() => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() == 1 {
+                let typ = options.into_iter().next().expect("checked above");
+                let scalar = matches!(
+                    typ,
+                    DataType::Int | DataType::Float | DataType::Bool | DataType::IntEnum(_)
+                );
+                if nullable && scalar {
+                    let type_name = element(typ, decls, path);
+                    writeln!(body, "  {}:{} = null;", name, type_name)
+                        .expect("writing to a String can't fail");
+                } else {
+                    // Other fields are already optional.
+                    field(body, decls, path, name, typ);
+                }
+                return;
+            }
+            let type_name = union(options, decls, path);
+            writeln!(body, "  {}:{};", name, type_name)
+        }
+        DataType::Null | DataType::Variant(_) => {
+            writeln!(body, "  {}:[ubyte] (flexbuffer);", name)
+        }
+        typ => {
+            let type_name = element(typ, decls, path);
+            writeln!(body, "  {}:{};", name, type_name)
+        }
+    }
+    .expect("writing to a String can't fail");
+}
+
+/// Emit a union of tables for the given options, wrapping those that
+/// aren't tables.
+fn union(options: BTreeSet<DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.next_name();
+    decls.warnings.push(
+        path,
+        WarningKind::EnumFallback,
+        options
+            .iter()
+            .map(DataType::kind_name)
+            .collect::<Vec<_>>()
+            .join(" | "),
+    );
+    let arms = options
+        .into_iter()
+        .map(|typ| match typ {
+            DataType::Object(members) => table(members, decls, path),
+            typ => {
+                let name = decls.next_name();
+                wrapper(name, typ, decls, path)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(s, "union {} {{ {} }}", name, arms.join(", ")).expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The name of a type for use as the element of a vector, declaring it
+/// first if need be.
+fn element(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::String | DataType::Temporal(_) => "string".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "long".to_string(),
+        DataType::Float => "double".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Object(members) => table(members, decls, path),
+        typ => {
+            let name = decls.next_name();
+            wrapper(name, typ, decls, path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_type},
+            Lang,
+        },
+        DataType, Options,
+    };
+
+    #[test]
+    fn tables() {
+        assert_eq!(
+            declare_as(
+                Lang::FlatBuffers,
+                json::array![
+                    {"id": 1, "name": "a", "tags": ["x"], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": null, "name": null, "tags": [], "owner": {"ok": false}, "content-type": 2},
+                ]
+            ),
+            "table Data2 {\n  ok:bool;\n}\n\n\
+             table Data1 {\n  // \"content-type\" in JSON\n  content_type:double;\n  \
+             id:long = null;\n  name:string;\n  owner:Data2;\n  tags:[string];\n}\n\n\
+             table Data0 {\n  value:[Data1];\n}\n\n\
+             root_type Data0;"
+        );
+    }
+
+    #[test]
+    fn unions_and_maps() {
+        assert_eq!(
+            declare_as(
+                Lang::FlatBuffers,
+                json::object! {
+                    "value": [1, "a"],
+                    "by_id": {"1": [[1.5]], "2": [[2.5]]},
+                    "gone": null,
+                }
+            ),
+            "table Data2 {\n  value:[double];\n}\n\n\
+             table Data1 {\n  key:string (key);\n  value:[Data2];\n}\n\n\
+             table Data5 {\n  value:string;\n}\n\n\
+             table Data6 {\n  value:long;\n}\n\n\
+             union Data4 { Data5, Data6 }\n\n\
+             table Data3 {\n  value:Data4;\n}\n\n\
+             table Data0 {\n  by_id:[Data1];\n  gone:[ubyte] (flexbuffer);\n  value:[Data3];\n}\n\n\
+             root_type Data0;"
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(
+            declare_type(
+                Options {
+                    lang: Lang::FlatBuffers,
+                    ..Options::default()
+                },
+                DataType::Object(
+                    vec![("blob".to_string(), DataType::Bytes)]
+                        .into_iter()
+                        .collect()
+                ),
+            ),
+            "table Data0 {\n  blob:[ubyte];\n}\n\nroot_type Data0;"
+        );
+    }
+}
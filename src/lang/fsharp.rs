@@ -0,0 +1,333 @@
+//! Emission of F# records and discriminated unions, read with
+//! `System.Text.Json`.
+//!
+//! Objects are records, whose fields keep their keys where they can,
+//! with a `[<JsonPropertyName>]` attribute giving the key of any that
+//! were renamed, and nullable values are options. `System.Text.Json`
+//! can't read unions, so values of several kinds are unions with a
+//! converter written out to pick the case by the JSON token. F# has no
+//! empty records, so objects that never had any members are kept as
+//! JSON.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{namer::Case, options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const FSHARP_UNKNOWN_TYPE: &str = "JsonElement";
+
+/// Text that goes before the declarations: the namespaces they open.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "open {}", import).expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit records and unions for a data type, returning the name of the
+/// top-level one. Types other than objects are given a name with an
+/// abbreviation.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members)
+            if !members.is_empty() && !decls.options.types.contains_key("$") =>
+        {
+            record(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            write!(s, "type {} = {}", name, type_name).expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a record with the given members.
+fn record(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut body = Vec::new();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => format!("{} option", unknown(decls)),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        let mut line = String::new();
+        if field != member {
+            decls
+                .imports
+                .insert("System.Text.Json.Serialization".to_string());
+            line = format!(
+                "[<JsonPropertyName({})>]\n      ",
+                json::stringify(member.as_str())
+            );
+        }
+        write!(line, "{}: {}", field, type_name).expect("writing to a String can't fail");
+        body.push(line);
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(s, "type {} =\n    {{ {} }}", name, body.join("\n      "))
+        .expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The type of the values at `path`, declaring the records and unions
+/// it uses first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => unknown(decls),
+        DataType::String | DataType::Temporal(_) => "string".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "int64".to_string(),
+        DataType::Float => "float".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Bytes => "byte[]".to_string(),
+        DataType::Object(members) if members.is_empty() => unknown(decls),
+        DataType::Object(members) => record(members, decls, path),
+        DataType::Array(elem) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("{} list", elem_name)
+        }
+        DataType::FixedArray(elem, _) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("{} array", elem_name)
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("Map<string, {}>", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    union(options, decls, path)
+                }
+            };
+            match nullable && !type_name.ends_with(" option") {
+                true => format!("{} option", type_name),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// Emit a union with a case for each kind of value in `options`, and a
+/// converter that picks the case by the JSON token it's read from.
+fn union(options: BTreeSet<DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.next_name();
+    let arms = options
+        .into_iter()
+        .map(|typ| {
+            let case = Case::Pascal.apply(typ.kind_name(), true);
+            let patterns = patterns(&typ);
+            (case, patterns, type_expr(typ, decls, path))
+        })
+        .collect::<Vec<_>>();
+    decls.imports.insert("System.Text.Json".to_string());
+    decls
+        .imports
+        .insert("System.Text.Json.Serialization".to_string());
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(
+        s,
+        "[<JsonConverter(typeof<{name}Converter>)>]\ntype {name} =",
+        name = name
+    )
+    .expect("writing to a String can't fail");
+    for (case, _, type_name) in &arms {
+        write!(s, "\n    | {} of {}", case, type_name).expect("writing to a String can't fail");
+    }
+
+    write!(
+        s,
+        "\n\nand {name}Converter() =\n    inherit JsonConverter<{name}>()\n\n    \
+         override _.Read(reader, _, options) =\n        match reader.TokenType with",
+        name = name
+    )
+    .expect("writing to a String can't fail");
+    // Kinds that look alike in JSON, like strings and dates, are read
+    // as the first of them.
+    let mut seen = BTreeSet::new();
+    for (case, patterns, type_name) in &arms {
+        let patterns = patterns
+            .iter()
+            .filter(|pattern| seen.insert(**pattern))
+            .copied()
+            .collect::<Vec<_>>();
+        if !patterns.is_empty() {
+            write!(
+                s,
+                "\n        | {} ->\n            {} (JsonSerializer.Deserialize<{}>(&reader, options))",
+                patterns.join(" | "),
+                case,
+                type_name
+            )
+            .expect("writing to a String can't fail");
+        }
+    }
+    write!(
+        s,
+        "\n        | _ -> raise (JsonException \"expected one of {}\")",
+        arms.iter()
+            .map(|(case, ..)| case.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .expect("writing to a String can't fail");
+
+    s += "\n\n    override _.Write(writer, value, options) =\n        match value with";
+    for (case, ..) in &arms {
+        write!(
+            s,
+            "\n        | {} v -> JsonSerializer.Serialize(writer, v, options)",
+            case
+        )
+        .expect("writing to a String can't fail");
+    }
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The patterns on `JsonTokenType` that a value of `typ` can start
+/// with. Integers are told apart from other numbers by whether they fit
+/// an `int64`.
+fn patterns(typ: &DataType) -> &'static [&'static str] {
+    match typ {
+        DataType::Null => &["JsonTokenType.Null"],
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => &["JsonTokenType.String"],
+        DataType::Int | DataType::IntEnum(_) => {
+            &["JsonTokenType.Number when fst (reader.TryGetInt64())"]
+        }
+        DataType::Float => &["JsonTokenType.Number"],
+        DataType::Bool => &["JsonTokenType.True", "JsonTokenType.False"],
+        DataType::Object(_) | DataType::Map(_) => &["JsonTokenType.StartObject"],
+        DataType::Array(_) | DataType::FixedArray(..) => &["JsonTokenType.StartArray"],
+        DataType::Variant(_) => &[],
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert("System.Text.Json".to_string());
+            FSHARP_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{tests::declare_as, Lang};
+
+    #[test]
+    fn records() {
+        assert_eq!(
+            declare_as(
+                Lang::FSharp,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": 2, "name": null, "tags": [2], "owner": {"ok": false}, "content-type": 2},
+                ]
+            ),
+            "open System.Text.Json.Serialization\n\n\
+             type Data2 =\n    { ok: bool }\n\n\
+             type Data1 =\n    { [<JsonPropertyName(\"content-type\")>]\n      contentType: float\n      \
+             id: int64\n      name: string option\n      owner: Data2\n      tags: int64 list }\n\n\
+             type Data0 = Data1 list"
+        );
+    }
+
+    #[test]
+    fn unions() {
+        assert_eq!(
+            declare_as(Lang::FSharp, json::object! {"value": [1, "a", null]}),
+            "open System.Text.Json\nopen System.Text.Json.Serialization\n\n\
+             [<JsonConverter(typeof<Data1Converter>)>]\n\
+             type Data1 =\n    | String of string\n    | Int of int64\n\n\
+             and Data1Converter() =\n    inherit JsonConverter<Data1>()\n\n    \
+             override _.Read(reader, _, options) =\n        match reader.TokenType with\n        \
+             | JsonTokenType.String ->\n            \
+             String (JsonSerializer.Deserialize<string>(&reader, options))\n        \
+             | JsonTokenType.Number when fst (reader.TryGetInt64()) ->\n            \
+             Int (JsonSerializer.Deserialize<int64>(&reader, options))\n        \
+             | _ -> raise (JsonException \"expected one of string, int\")\n\n    \
+             override _.Write(writer, value, options) =\n        match value with\n        \
+             | String v -> JsonSerializer.Serialize(writer, v, options)\n        \
+             | Int v -> JsonSerializer.Serialize(writer, v, options)\n\n\
+             type Data0 =\n    { value: Data1 option list }"
+        );
+    }
+
+    #[test]
+    fn keywords_and_maps() {
+        assert_eq!(
+            declare_as(
+                Lang::FSharp,
+                json::object! {"by_id": {"1": 1, "2": 2}, "type": "a"}
+            ),
+            "open System.Text.Json.Serialization\n\ntype Data0 =\n    \
+             { by_id: Map<string, int64>\n      [<JsonPropertyName(\"type\")>]\n      \
+             type_: string }"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Emission of GraphQL SDL object types.
+//!
+//! Values that can't be null are marked with `!`. GraphQL's unions can
+//! only hold object types, and it has no maps, empty objects or types
+//! for values nothing is known about, so all of those are a `JSON`
+//! scalar, declared in the header. It can't name types other than
+//! objects either, so a top-level type that isn't one is only given in
+//! a comment.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const GRAPHQL_UNKNOWN_TYPE: &str = "JSON";
+
+/// Text that goes before the declarations: the scalars they use.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for scalar in imports {
+        writeln!(s, "scalar {}\n", scalar).expect("writing to a String can't fail");
+    }
+    s
+}
+
+/// Emit object types for a data type, returning the name of the
+/// top-level one.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members)
+            if !members.is_empty() && !decls.options.types.contains_key("$") =>
+        {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "#", &name, "$");
+            write!(s, "# {} = {}", name, type_name).expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit an object type with the given members.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        if field != member {
+            writeln!(body, "  # {} in JSON", json::stringify(member.as_str()))
+                .expect("writing to a String can't fail");
+        }
+        writeln!(body, "  {}: {}", field, type_name).expect("writing to a String can't fail");
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "#", &name, path);
+    write!(s, "type {} {{\n{}}}", name, body).expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, with `!` if they can't be null,
+/// declaring the object types it uses first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => unknown(decls),
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => union(options, decls, path) + "!",
+            };
+            match nullable {
+                true => type_name.trim_end_matches('!').to_string(),
+                false => type_name,
+            }
+        }
+        typ => {
+            let type_name = match typ {
+                DataType::String | DataType::Temporal(_) => "String".to_string(),
+                DataType::Int | DataType::IntEnum(_) => "Int".to_string(),
+                DataType::Float => "Float".to_string(),
+                DataType::Bool => "Boolean".to_string(),
+                // Bytes are in base64 in JSON.
+                DataType::Bytes => "String".to_string(),
+                DataType::Object(members) if !members.is_empty() => object(members, decls, path),
+                DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+                    let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+                    format!("[{}]", elem_name)
+                }
+                _ => unknown(decls),
+            };
+            type_name + "!"
+        }
+    }
+}
+
+/// Emit a union of the object types for `options`, if they're all
+/// objects, returning its name, or the name of the unknown type
+/// otherwise.
+fn union(options: BTreeSet<DataType>, decls: &mut Decls, path: &str) -> String {
+    let objects = options
+        .iter()
+        .all(|typ| matches!(typ, DataType::Object(members) if !members.is_empty()));
+    if !objects {
+        decls.warnings.push(
+            path,
+            WarningKind::EnumFallback,
+            options
+                .iter()
+                .map(DataType::kind_name)
+                .collect::<Vec<_>>()
+                .join(" | "),
+        );
+        return unknown(decls);
+    }
+
+    let name = decls.next_name();
+    let arms = options
+        .into_iter()
+        .map(|typ| match typ {
+            DataType::Object(members) => object(members, decls, path),
+            _ => unreachable!("checked above"),
+        })
+        .collect::<Vec<_>>();
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "#", &name, path);
+    write!(s, "union {} = {}", name, arms.join(" | ")).expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The type to emit for unknown values, declaring it if need be.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert(GRAPHQL_UNKNOWN_TYPE.to_string());
+            GRAPHQL_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{tests::declare_as, Lang},
+        DataType, Decls, Options,
+    };
+
+    #[test]
+    fn object_types() {
+        assert_eq!(
+            declare_as(
+                Lang::GraphQL,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+                    {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+                ]
+            ),
+            "scalar JSON\n\n\
+             type Data1 {\n  # \"content-type\" in JSON\n  content_type: Float!\n  \
+             id: Int!\n  name: String\n  owner: JSON!\n  tags: [Int!]!\n  x: JSON!\n}\n\n\
+             # Data0 = [Data1!]!"
+        );
+    }
+
+    #[test]
+    fn unions() {
+        // Objects are always unified when inferred, but schemas read
+        // from elsewhere can have unions of them.
+        let pet = DataType::Variant(
+            [
+                DataType::Null,
+                DataType::Object([("name".to_string(), DataType::String)].into()),
+                DataType::Object([("bark".to_string(), DataType::Bool)].into()),
+            ]
+            .into(),
+        );
+        let mut decls = Decls::new(Options {
+            lang: Lang::GraphQL,
+            ..Options::default()
+        });
+        DataType::Object([("pet".to_string(), pet)].into()).declare(&mut decls);
+        assert_eq!(
+            decls.output(),
+            "type Data2 {\n  bark: Boolean!\n}\n\n\
+             type Data3 {\n  name: String!\n}\n\n\
+             union Data1 = Data2 | Data3\n\n\
+             type Data0 {\n  pet: Data1\n}"
+        );
+    }
+
+    #[test]
+    fn maps_are_json() {
+        assert_eq!(
+            declare_as(
+                Lang::GraphQL,
+                json::object! {"by_id": {"1": 1, "2": 2}, "type": "a"}
+            ),
+            "scalar JSON\n\ntype Data0 {\n  by_id: JSON!\n  type: String!\n}"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Emission of io-ts codecs for TypeScript.
+//!
+//! Each codec is a constant, with a type of the same name taken from
+//! it, as io-ts's documentation does. Members keep their keys, quoted if
+//! they aren't identifiers, and values of several kinds, or that can be
+//! null, are `t.union`s. Members that can be null can also be missing,
+//! so they go in a `t.partial` intersected with the `t.type` of the rest.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Codec emitted for values nothing is known about.
+const IO_TS_UNKNOWN_TYPE: &str = "t.unknown";
+
+/// Text that goes before the declarations: the import of io-ts, if they
+/// use it.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    match imports.contains("io-ts") {
+        true => "import * as t from \"io-ts\";\n\n".to_string(),
+        false => String::new(),
+    }
+}
+
+/// Emit codecs for a data type, returning the name of the top-level
+/// one.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let codec = codec(typ, decls, "$");
+            codec_decl(decls, "$", &name, &codec);
+            name
+        }
+    }
+}
+
+/// Emit a `t.type` codec with the given members, intersected with a
+/// `t.partial` of those that can be missing.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut body = decls.take_scratch();
+    let mut optional = String::new();
+    let mut fields = Vec::new();
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        let member_path = format!("{}.{}", path, member);
+        let codec = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => {
+                format!("t.union([{}, t.null])", unknown(decls))
+            }
+            (typ, _) => codec(typ, decls, &member_path),
+        };
+        let key = match decls.namer.style().is_identifier(&member) {
+            true => member.clone(),
+            false => json::stringify(member.as_str()),
+        };
+        // Members that are null in some documents are missing from
+        // others, which `t.type` doesn't allow.
+        let out = match codec == "t.null" || codec.ends_with(", t.null])") {
+            true => &mut optional,
+            false => &mut body,
+        };
+        writeln!(out, "  {}: {},", key, codec).expect("writing to a String can't fail");
+        fields.push((member, codec));
+    }
+    decls.declared[declared].fields = fields;
+    let codec = match (body.is_empty(), optional.is_empty()) {
+        (true, true) => "t.type({})".to_string(),
+        (false, true) => format!("t.type({{\n{}}})", body),
+        (true, false) => format!("t.partial({{\n{}}})", optional),
+        (false, false) => format!(
+            "t.intersection([\n  t.type({{\n{}  }}),\n  t.partial({{\n{}  }}),\n])",
+            indent(&body),
+            indent(&optional)
+        ),
+    };
+    decls.put_scratch(body);
+    codec_decl(decls, path, &name, &codec);
+    name
+}
+
+/// Declare the codec for the values at `path`, and its type.
+fn codec_decl(decls: &mut Decls, path: &str, name: &str, codec: &str) {
+    decls.imports.insert("io-ts".to_string());
+    let export = match decls.options.pub_types {
+        true => "export ",
+        false => "",
+    };
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", name, path);
+    write!(
+        s,
+        "{export}const {name} = {codec};\n{export}type {name} = t.TypeOf<typeof {name}>;",
+        export = export,
+        name = name,
+        codec = codec
+    )
+    .expect("writing to a String can't fail");
+    decls.begin_decl(name).push_str(&s);
+    decls.put_scratch(s);
+}
+
+/// The codec for the values at `path`, declaring the codecs it uses
+/// first.
+fn codec(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "t.null".to_string(),
+        // Bytes are in base64 in JSON.
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => "t.string".to_string(),
+        DataType::Int | DataType::Float => "t.number".to_string(),
+        DataType::IntEnum(codes) => {
+            let literals = codes
+                .iter()
+                .map(|code| format!("t.literal({})", code))
+                .collect::<Vec<_>>();
+            union(literals)
+        }
+        DataType::Bool => "t.boolean".to_string(),
+        DataType::Object(members) => object(members, decls, path),
+        DataType::Array(elem) => {
+            let elem_codec = codec(*elem, decls, &format!("{}[]", path));
+            format!("t.array({})", elem_codec)
+        }
+        DataType::FixedArray(elem, len) => {
+            let elem_codec = codec(*elem, decls, &format!("{}[]", path));
+            format!("t.tuple([{}])", vec![elem_codec; len].join(", "))
+        }
+        DataType::Map(values) => {
+            let value_codec = codec(*values, decls, &format!("{}.*", path));
+            format!("t.record(t.string, {})", value_codec)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() > 1 {
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+            }
+            let mut arms = options
+                .into_iter()
+                .map(|typ| codec(typ, decls, path))
+                .collect::<Vec<_>>();
+            if nullable {
+                arms.push("t.null".to_string());
+            }
+            union(arms)
+        }
+    }
+}
+
+/// A codec for any of `arms`. `t.union` needs at least two.
+fn union(mut arms: Vec<String>) -> String {
+    match arms.len() {
+        1 => arms.pop().expect("checked above"),
+        _ => format!("t.union([{}])", arms.join(", ")),
+    }
+}
+
+/// `members` indented by one more level, to go inside `t.intersection`.
+fn indent(members: &str) -> String {
+    members
+        .lines()
+        .map(|line| format!("  {}\n", line))
+        .collect()
+}
+
+/// The codec to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(IO_TS_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn codecs() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::IoTs,
+                    pub_types: true,
+                    ..Options::default()
+                },
+                json::array![
+                    {"id": 1, "name": "a", "tags": ["x"], "owner": {}, "content-type": 1.5},
+                    {"id": "b", "name": null, "tags": [1], "owner": {}, "content-type": 2},
+                ]
+            ),
+            "import * as t from \"io-ts\";\n\n\
+             export const Data2 = t.type({});\n\
+             export type Data2 = t.TypeOf<typeof Data2>;\n\n\
+             export const Data1 = t.intersection([\n  \
+             t.type({\n    \"content-type\": t.number,\n    \
+             id: t.union([t.string, t.number]),\n    owner: Data2,\n    \
+             tags: t.array(t.union([t.string, t.number])),\n  }),\n  t.partial({\n    \
+             name: t.union([t.string, t.null]),\n  }),\n]);\n\
+             export type Data1 = t.TypeOf<typeof Data1>;\n\n\
+             export const Data0 = t.array(Data1);\n\
+             export type Data0 = t.TypeOf<typeof Data0>;"
+        );
+    }
+
+    #[test]
+    fn missing_members() {
+        assert_eq!(
+            declare_as(
+                Lang::IoTs,
+                json::array![{"id": 1, "note": "a", "gone": null}, {"id": 2}]
+            ),
+            "import * as t from \"io-ts\";\n\nconst Data1 = t.intersection([\n  t.type({\n    \
+             id: t.number,\n  }),\n  t.partial({\n    gone: t.null,\n    \
+             note: t.union([t.string, t.null]),\n  }),\n]);\n\
+             type Data1 = t.TypeOf<typeof Data1>;\n\nconst Data0 = t.array(Data1);\n\
+             type Data0 = t.TypeOf<typeof Data0>;"
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Emission of Java records, or classes with getters, read with
+//! Jackson.
+//!
+//! Java has no unions, so values of several kinds are read as
+//! `JsonNode`s, and no type aliases, so a document that's an array or
+//! a map is declared as a class extending `ArrayList` or `HashMap`.
+//! Members whose keys had to be renamed are annotated with
+//! `@JsonProperty` giving the key.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const JAVA_UNKNOWN_TYPE: &str = "JsonNode";
+
+/// The class of `JAVA_UNKNOWN_TYPE`.
+const JSON_NODE: &str = "com.fasterxml.jackson.databind.JsonNode";
+
+/// The annotation that gives the key of a renamed member.
+const JSON_PROPERTY: &str = "com.fasterxml.jackson.annotation.JsonProperty";
+
+/// Text that goes before the declarations: the classes they import.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "import {};", import).expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit declarations for a data type, returning the name of the
+/// top-level one. Arrays and maps are declared as classes extending
+/// the collection that holds them, and other types that aren't objects
+/// aren't declared at all: their Java type is returned instead.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    if decls.options.types.contains_key("$") {
+        return type_expr(typ, decls, "$", false);
+    }
+    let (name, class, args) = match typ {
+        DataType::Object(members) => return class(members, decls, "$"),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let name = decls.name_at("$");
+            let elem_name = type_expr(*elem, decls, "$[]", true);
+            (name, "java.util.ArrayList", elem_name)
+        }
+        DataType::Map(values) => {
+            let name = decls.name_at("$");
+            let value_name = type_expr(*values, decls, "$.*", true);
+            (name, "java.util.HashMap", format!("String, {}", value_name))
+        }
+        typ => return type_expr(typ, decls, "$", false),
+    };
+    decls.imports.insert(class.to_string());
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, "$");
+    write!(
+        s,
+        "{}class {} extends {}<{}> {{}}",
+        visibility(decls),
+        name,
+        simple_name(class),
+        args
+    )
+    .expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// Emit a record, or a class with getters, with the given members.
+fn class(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut components = Vec::new();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => unknown(decls),
+            (typ, _) => type_expr(typ, decls, &member_path, false),
+        };
+        let annotation = match field != member {
+            true => {
+                decls.imports.insert(JSON_PROPERTY.to_string());
+                format!("@JsonProperty({}) ", json::stringify(member.as_str()))
+            }
+            false => String::new(),
+        };
+        components.push((annotation, type_name.clone(), field));
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    if decls.options.java_pojos {
+        writeln!(s, "{}class {} {{", visibility(decls), name)
+            .expect("writing to a String can't fail");
+        for (annotation, type_name, field) in &components {
+            writeln!(s, "    {}private {} {};", annotation, type_name, field)
+                .expect("writing to a String can't fail");
+        }
+        for (_, type_name, field) in &components {
+            let mut chars = field.chars();
+            let capitalized = chars.next().map_or(String::new(), |first| {
+                first.to_uppercase().chain(chars).collect()
+            });
+            writeln!(
+                s,
+                "\n    public {} get{}() {{\n        return {};\n    }}",
+                type_name, capitalized, field
+            )
+            .expect("writing to a String can't fail");
+        }
+        s += "}";
+    } else {
+        write!(s, "{}record {}(", visibility(decls), name).expect("writing to a String can't fail");
+        for (idx, (annotation, type_name, field)) in components.iter().enumerate() {
+            if idx > 0 {
+                s += ",";
+            }
+            write!(s, "\n    {}{} {}", annotation, type_name, field)
+                .expect("writing to a String can't fail");
+        }
+        if !components.is_empty() {
+            s += "\n";
+        }
+        s += ") {}";
+    }
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The type of the values at `path`, declaring the classes it uses
+/// first. Primitive types are boxed if `boxed` is set, as they must be
+/// to be type arguments, or if the values can be null.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str, boxed: bool) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    let primitive = |unboxed: &str, boxed_name: &str| match boxed {
+        true => boxed_name.to_string(),
+        false => unboxed.to_string(),
+    };
+    match typ {
+        DataType::Null => "Object".to_string(),
+        DataType::String | DataType::Temporal(_) => "String".to_string(),
+        DataType::Int | DataType::IntEnum(_) => primitive("long", "Long"),
+        DataType::Float => primitive("double", "Double"),
+        DataType::Bool => primitive("boolean", "Boolean"),
+        DataType::Bytes => "byte[]".to_string(),
+        DataType::Object(members) => class(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            decls.imports.insert("java.util.List".to_string());
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path), true);
+            format!("List<{}>", elem_name)
+        }
+        DataType::Map(values) => {
+            decls.imports.insert("java.util.Map".to_string());
+            let value_name = type_expr(*values, decls, &format!("{}.*", path), true);
+            format!("Map<String, {}>", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() == 1 {
+                let typ = options.into_iter().next().expect("checked above");
+                return type_expr(typ, decls, path, boxed || nullable);
+            }
+            if options.len() > 1 {
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+            }
+            unknown(decls)
+        }
+    }
+}
+
+/// The modifier to declare types with, if they're public.
+fn visibility(decls: &Decls) -> &'static str {
+    match decls.options.pub_types {
+        true => "public ",
+        false => "",
+    }
+}
+
+/// The name of `class` without its package.
+fn simple_name(class: &str) -> &str {
+    class.rsplit('.').next().unwrap_or(class)
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert(JSON_NODE.to_string());
+            JAVA_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn records() {
+        assert_eq!(
+            declare_as(
+                Lang::Java,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+                    {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+                ],
+            ),
+            "import com.fasterxml.jackson.annotation.JsonProperty;\n\
+             import com.fasterxml.jackson.databind.JsonNode;\n\
+             import java.util.ArrayList;\n\
+             import java.util.List;\n\n\
+             record Data2() {}\n\n\
+             record Data1(\n    \
+             @JsonProperty(\"content-type\") double contentType,\n    \
+             long id,\n    String name,\n    Data2 owner,\n    List<Long> tags,\n    \
+             JsonNode x\n) {}\n\n\
+             class Data0 extends ArrayList<Data1> {}"
+        );
+    }
+
+    #[test]
+    fn maps_and_nulls() {
+        assert_eq!(
+            declare_as(
+                Lang::Java,
+                json::object! {"by_id": {"1": [1.5], "2": [2.5]}, "gone": null},
+            ),
+            "import java.util.List;\nimport java.util.Map;\n\nrecord Data0(\n    \
+             Map<String, List<Double>> by_id,\n    Object gone\n) {}"
+        );
+    }
+
+    #[test]
+    fn pojos() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Java,
+                    java_pojos: true,
+                    ..Options::default()
+                },
+                json::object! {"enabled": true, "class": null, "n": [null, 1]},
+            ),
+            "import com.fasterxml.jackson.annotation.JsonProperty;\n\
+             import java.util.List;\n\n\
+             class Data0 {\n    \
+             @JsonProperty(\"class\") private Object class_;\n    \
+             private boolean enabled;\n    \
+             private List<Long> n;\n\n    \
+             public Object getClass_() {\n        return class_;\n    }\n\n    \
+             public boolean getEnabled() {\n        return enabled;\n    }\n\n    \
+             public List<Long> getN() {\n        return n;\n    }\n}"
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Emission of a JSON Schema, in draft 2020-12.
+//!
+//! The schema is a single document, whose root describes the top-level
+//! value. Objects other than the root are defined in `$defs` and
+//! referred to with `$ref`, and the paths they were found at go in
+//! `$comment`s. Properties that can be null have `null` in their
+//! `type`, and aren't `required`, and values of several kinds are
+//! described with `anyOf`. Values nothing is known about can be
+//! anything.
+
+use std::collections::BTreeMap;
+
+use json::JsonValue;
+
+use crate::{
+    options::NullFields, temporal::Temporal, types::Decls, warnings::WarningKind, DataType,
+};
+
+/// The dialect of the emitted schema.
+const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Emit the schema of a data type, returning the name of the top-level
+/// object, or of the schema if it isn't one.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let mut defs = JsonValue::new_object();
+    let name = decls.name_at("$");
+    let root = match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(&name, members, decls, &mut defs, "$")
+        }
+        typ => schema(typ, decls, &mut defs, "$"),
+    };
+
+    let mut document = json::object! {"$schema": DRAFT_2020_12};
+    for (key, value) in root.entries() {
+        document[key] = value.clone();
+    }
+    if !defs.is_empty() {
+        document["$defs"] = defs;
+    }
+    decls
+        .begin_decl(&name)
+        .push_str(&json::stringify_pretty(document, 2));
+    name
+}
+
+/// The schema of an object named `name` with the given members, defining
+/// the objects it uses in `defs`.
+fn object(
+    name: &str,
+    members: BTreeMap<String, DataType>,
+    decls: &mut Decls,
+    defs: &mut JsonValue,
+    path: &str,
+) -> JsonValue {
+    let declared = decls.declare_object(path, name);
+    let mut fields = Vec::new();
+    let mut properties = JsonValue::new_object();
+    let mut required = JsonValue::new_array();
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        let member_path = format!("{}.{}", path, member);
+        let property = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                json::object! {"$ref": decls.options.types[&member_path].as_str()}
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (typ, _) => schema(typ, decls, defs, &member_path),
+        };
+        if !allows_null(&property) {
+            required
+                .push(member.as_str())
+                .expect("pushing to an array can't fail");
+        }
+        fields.push((member.clone(), json::stringify(property.clone())));
+        properties[member.as_str()] = property;
+    }
+    decls.declared[declared].fields = fields;
+
+    let mut schema = json::object! {"type": "object"};
+    let mut occurs = decls.take_scratch();
+    decls.occurs_at(&mut occurs, "", name, path);
+    if !occurs.is_empty() {
+        schema["$comment"] = occurs.trim().into();
+    }
+    decls.put_scratch(occurs);
+    schema["properties"] = properties;
+    if !required.is_empty() {
+        schema["required"] = required;
+    }
+    schema
+}
+
+/// The schema of the values at `path`, defining the objects it uses in
+/// `defs`.
+fn schema(typ: DataType, decls: &mut Decls, defs: &mut JsonValue, path: &str) -> JsonValue {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return json::object! {"$ref": type_name.as_str()};
+    }
+
+    match typ {
+        DataType::Null => json::object! {"type": "null"},
+        DataType::String => json::object! {"type": "string"},
+        DataType::Temporal(kind) => {
+            let format = match kind {
+                Temporal::Date => "date",
+                Temporal::Time => "time",
+                Temporal::DateTime => "date-time",
+                // `date-time` needs an offset.
+                Temporal::NaiveDateTime => return json::object! {"type": "string"},
+            };
+            json::object! {"type": "string", "format": format}
+        }
+        DataType::Int => json::object! {"type": "integer"},
+        DataType::IntEnum(codes) => {
+            let codes = codes.into_iter().map(JsonValue::from).collect::<Vec<_>>();
+            json::object! {"type": "integer", "enum": codes}
+        }
+        DataType::Float => json::object! {"type": "number"},
+        DataType::Bool => json::object! {"type": "boolean"},
+        DataType::Bytes => json::object! {"type": "string", "contentEncoding": "base64"},
+        DataType::Object(members) => {
+            let name = decls.name_at(path);
+            let def = object(&name, members, decls, defs, path);
+            defs[name.as_str()] = def;
+            json::object! {"$ref": format!("#/$defs/{}", name)}
+        }
+        DataType::Array(elem) => {
+            let items = schema(*elem, decls, defs, &format!("{}[]", path));
+            json::object! {"type": "array", "items": items}
+        }
+        DataType::FixedArray(elem, len) => {
+            let items = schema(*elem, decls, defs, &format!("{}[]", path));
+            json::object! {"type": "array", "items": items, "minItems": len, "maxItems": len}
+        }
+        DataType::Map(values) => {
+            let values = schema(*values, decls, defs, &format!("{}.*", path));
+            json::object! {"type": "object", "additionalProperties": values}
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let mut arms = options
+                .into_iter()
+                .map(|typ| schema(typ, decls, defs, path))
+                .collect::<Vec<_>>();
+            if arms.len() == 1 {
+                let arm = arms.pop().expect("checked above");
+                return match nullable {
+                    true => nullable_schema(arm),
+                    false => arm,
+                };
+            }
+            if nullable {
+                arms.insert(0, json::object! {"type": "null"});
+            }
+            json::object! {"anyOf": arms}
+        }
+    }
+}
+
+/// `schema`, also allowing null: in its `type` if it has one, or else
+/// as an alternative to it.
+fn nullable_schema(mut schema: JsonValue) -> JsonValue {
+    if !schema["type"].is_string() {
+        return json::object! {"anyOf": [{"type": "null"}, schema]};
+    }
+    let typ = schema["type"].take();
+    schema["type"] = json::array!["null", typ];
+    if schema["enum"].is_array() {
+        schema["enum"]
+            .push(JsonValue::Null)
+            .expect("pushing to an array can't fail");
+    }
+    schema
+}
+
+/// Whether `schema` allows null values.
+fn allows_null(schema: &JsonValue) -> bool {
+    schema["type"] == "null"
+        || schema["type"].contains("null")
+        || schema["anyOf"].members().any(|arm| arm["type"] == "null")
+}
+
+/// The schema for unknown values, which allows anything unless an
+/// unknown type is given.
+fn unknown(decls: &Decls) -> JsonValue {
+    match &decls.options.unknown_type {
+        Some(unknown) => json::object! {"$ref": unknown.as_str()},
+        None => JsonValue::new_object(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn objects() {
+        assert_eq!(
+            json::parse(&declare_as(
+                Lang::JsonSchema,
+                json::object! {
+                    "id": 1,
+                    "name": null,
+                    "owner": {"ok": true},
+                    "tags": ["x"],
+                },
+            ))
+            .unwrap(),
+            json::object! {
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "name": {"type": "null"},
+                    "owner": {"$ref": "#/$defs/Data1"},
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                },
+                "required": ["id", "owner", "tags"],
+                "$defs": {
+                    "Data1": {
+                        "type": "object",
+                        "properties": {"ok": {"type": "boolean"}},
+                        "required": ["ok"],
+                    },
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn variants() {
+        assert_eq!(
+            json::parse(&declare_as(
+                Lang::JsonSchema,
+                json::array![
+                    {"a": 1, "b": "x", "c": {}, "d": {"1": 1.5, "2": 2.5}},
+                    {"a": null, "b": 2, "c": null, "d": {"3": 3.5, "4": 4.5}},
+                ],
+            ))
+            .unwrap(),
+            json::object! {
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "array",
+                "items": {"$ref": "#/$defs/Data1"},
+                "$defs": {
+                    "Data2": {"type": "object", "properties": {}},
+                    "Data1": {
+                        "type": "object",
+                        "properties": {
+                            "a": {"type": ["null", "integer"]},
+                            "b": {"anyOf": [{"type": "string"}, {"type": "integer"}]},
+                            "c": {"anyOf": [{"type": "null"}, {"$ref": "#/$defs/Data2"}]},
+                            "d": {"type": "object", "additionalProperties": {"type": "number"}},
+                        },
+                        "required": ["b", "d"],
+                    },
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn formats_and_enums() {
+        assert_eq!(
+            json::parse(&declare_with(
+                Options {
+                    lang: Lang::JsonSchema,
+                    detect_dates: true,
+                    int_enum_threshold: Some(4),
+                    ..Options::default()
+                },
+                json::array![
+                    {"at": "2024-01-02T03:04:05Z", "status": 1},
+                    {"at": "2024-01-03T03:04:05Z", "status": 2},
+                ],
+            ))
+            .unwrap(),
+            json::object! {
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "array",
+                "items": {"$ref": "#/$defs/Data1"},
+                "$defs": {
+                    "Data1": {
+                        "type": "object",
+                        "properties": {
+                            "at": {"type": "string", "format": "date-time"},
+                            "status": {"type": "integer", "enum": [1, 2]},
+                        },
+                        "required": ["at", "status"],
+                    },
+                },
+            }
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Emission of Julia structs, optionally declared as `StructTypes.Struct`s
+//! for JSON3.jl.
+//!
+//! Julia has unions, so values of several kinds are `Union`s, and
+//! nullable values are unions with `Nothing`. Fields are renamed into
+//! snake case, with a comment giving the key, or with StructTypes a
+//! `StructTypes.names` method mapping the fields to the keys.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const JULIA_UNKNOWN_TYPE: &str = "Any";
+
+/// Text that goes before the declarations: the packages they use.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "using {}", import).expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit structs for a data type, returning the name of the top-level
+/// one. Types other than objects are given a name with a constant.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "#", &name, "$");
+            write!(s, "const {} = {}", name, type_name).expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a struct with the given members.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let struct_types = decls.options.julia_struct_types;
+    let mut fields = Vec::new();
+    let mut renames = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => {
+                format!("Union{{Nothing, {}}}", unknown(decls))
+            }
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        write!(body, "    {}::{}", field, type_name).expect("writing to a String can't fail");
+        if field != member {
+            // Julia interpolates `$` in strings.
+            let key = json::stringify(member.as_str()).replace('$', "\\$");
+            match struct_types {
+                true => renames.push(format!("(:{}, Symbol({}))", field, key)),
+                false => {
+                    write!(body, "  # {} in JSON", key).expect("writing to a String can't fail")
+                }
+            }
+        }
+        body += "\n";
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "#", &name, path);
+    match body.is_empty() {
+        true => write!(s, "struct {} end", name),
+        false => write!(s, "struct {}\n{}end", name, body),
+    }
+    .expect("writing to a String can't fail");
+    if struct_types {
+        decls.imports.insert("StructTypes".to_string());
+        write!(
+            s,
+            "\n\nStructTypes.StructType(::Type{{{}}}) = StructTypes.Struct()",
+            name
+        )
+        .expect("writing to a String can't fail");
+        if !renames.is_empty() {
+            // The trailing comma keeps a single pair a tuple.
+            write!(
+                s,
+                "\nStructTypes.names(::Type{{{}}}) = ({},)",
+                name,
+                renames.join(", ")
+            )
+            .expect("writing to a String can't fail");
+        }
+    }
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the structs it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "Nothing".to_string(),
+        DataType::String | DataType::Temporal(_) => "String".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "Int64".to_string(),
+        DataType::Float => "Float64".to_string(),
+        DataType::Bool => "Bool".to_string(),
+        DataType::Bytes => "Vector{UInt8}".to_string(),
+        DataType::Object(members) => object(members, decls, path),
+        DataType::Array(elem) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("Vector{{{}}}", elem_name)
+        }
+        DataType::FixedArray(elem, len) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("NTuple{{{}, {}}}", len, elem_name)
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("Dict{{String, {}}}", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() > 1 {
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+            }
+            // `Nothing` leads the union, as it's usually written.
+            let mut arms = match nullable {
+                true => vec!["Nothing".to_string()],
+                false => Vec::new(),
+            };
+            for typ in options {
+                arms.push(type_expr(typ, decls, path));
+            }
+            match arms.len() {
+                1 => arms.into_iter().next().expect("checked above"),
+                _ => format!("Union{{{}}}", arms.join(", ")),
+            }
+        }
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(JULIA_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    fn sample() -> json::JsonValue {
+        json::array![
+            {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+            {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+        ]
+    }
+
+    #[test]
+    fn structs() {
+        assert_eq!(
+            declare_as(Lang::Julia, sample()),
+            "struct Data2 end\n\n\
+             struct Data1\n    content_type::Float64  # \"content-type\" in JSON\n    \
+             id::Int64\n    name::Union{Nothing, String}\n    owner::Data2\n    \
+             tags::Vector{Int64}\n    x::Union{String, Int64}\nend\n\n\
+             const Data0 = Vector{Data1}"
+        );
+    }
+
+    #[test]
+    fn struct_types() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Julia,
+                    julia_struct_types: true,
+                    ..Options::default()
+                },
+                sample()
+            ),
+            "using StructTypes\n\n\
+             struct Data2 end\n\n\
+             StructTypes.StructType(::Type{Data2}) = StructTypes.Struct()\n\n\
+             struct Data1\n    content_type::Float64\n    \
+             id::Int64\n    name::Union{Nothing, String}\n    owner::Data2\n    \
+             tags::Vector{Int64}\n    x::Union{String, Int64}\nend\n\n\
+             StructTypes.StructType(::Type{Data1}) = StructTypes.Struct()\n\
+             StructTypes.names(::Type{Data1}) = ((:content_type, Symbol(\"content-type\")),)\n\n\
+             const Data0 = Vector{Data1}"
+        );
+    }
+
+    #[test]
+    fn keywords_and_nulls() {
+        assert_eq!(
+            declare_as(Lang::Julia, json::object! {"end": 1, "gone": null}),
+            "struct Data0\n    end_::Int64  # \"end\" in JSON\n    gone::Nothing\nend"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Emission of Kotlin data classes, read with `kotlinx.serialization`.
+//!
+//! Kotlin has no unions, so values of several kinds are read as
+//! `JsonElement`s. Types that aren't objects are named with a
+//! `typealias`. Members whose keys had to be renamed are annotated with
+//! `@SerialName` giving the key. Nullable members default to `null`,
+//! since they can also be missing.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const KOTLIN_UNKNOWN_TYPE: &str = "JsonElement";
+
+/// The class of `KOTLIN_UNKNOWN_TYPE`.
+const JSON_ELEMENT: &str = "kotlinx.serialization.json.JsonElement";
+
+/// Text that goes before the declarations: the classes they import.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "import {}", import).expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit declarations for a data type, returning the name of the
+/// top-level one. Types other than objects are given a name with a type
+/// alias.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            class(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            write!(s, "{}typealias {} = {}", visibility(decls), name, type_name)
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a data class with the given members.
+fn class(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    decls
+        .imports
+        .insert("kotlinx.serialization.Serializable".to_string());
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => format!("{}?", unknown(decls)),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        if !body.is_empty() {
+            body += ",\n";
+        }
+        body += "    ";
+        if field != member {
+            decls
+                .imports
+                .insert("kotlinx.serialization.SerialName".to_string());
+            write!(body, "@SerialName({}) ", json::stringify(member.as_str()))
+                .expect("writing to a String can't fail");
+        }
+        write!(body, "val {}: {}", field, type_name).expect("writing to a String can't fail");
+        // Members that are null in some documents are missing from
+        // others, which only a default lets them be.
+        if type_name.ends_with('?') {
+            body += " = null";
+        }
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    // Data classes need at least one property, so objects that never
+    // had any members are plain classes.
+    match fields.is_empty() {
+        true => write!(s, "@Serializable\n{}class {}", visibility(decls), name),
+        false => write!(
+            s,
+            "@Serializable\n{}data class {}(\n{}\n)",
+            visibility(decls),
+            name,
+            body
+        ),
+    }
+    .expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the classes it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        // `Nothing?` can't be serialized, so values that are always null
+        // are read as nullable elements.
+        DataType::Null => format!("{}?", unknown(decls)),
+        DataType::String | DataType::Temporal(_) => "String".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "Long".to_string(),
+        DataType::Float => "Double".to_string(),
+        DataType::Bool => "Boolean".to_string(),
+        DataType::Bytes => "ByteArray".to_string(),
+        DataType::Object(members) => class(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("List<{}>", elem_name)
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("Map<String, {}>", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    unknown(decls)
+                }
+            };
+            match nullable {
+                true => format!("{}?", type_name),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The modifier to declare types with. Kotlin's declarations are public
+/// unless they're made internal.
+fn visibility(decls: &Decls) -> &'static str {
+    match decls.options.pub_types {
+        true => "",
+        false => "internal ",
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert(JSON_ELEMENT.to_string());
+            KOTLIN_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn data_classes() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Kotlin,
+                    pub_types: true,
+                    ..Options::default()
+                },
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+                    {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+                ]
+            ),
+            "import kotlinx.serialization.SerialName\n\
+             import kotlinx.serialization.Serializable\n\
+             import kotlinx.serialization.json.JsonElement\n\n\
+             @Serializable\nclass Data2\n\n\
+             @Serializable\ndata class Data1(\n    \
+             @SerialName(\"content-type\") val contentType: Double,\n    \
+             val id: Long,\n    val name: String? = null,\n    val owner: Data2,\n    \
+             val tags: List<Long>,\n    val x: JsonElement\n)\n\n\
+             typealias Data0 = List<Data1>"
+        );
+    }
+
+    #[test]
+    fn missing_members() {
+        assert_eq!(
+            declare_as(
+                Lang::Kotlin,
+                json::array![{"id": 1, "note": "a", "gone": null}, {"id": 2}]
+            ),
+            "import kotlinx.serialization.Serializable\n\
+             import kotlinx.serialization.json.JsonElement\n\n@Serializable\n\
+             internal data class Data1(\n    val gone: JsonElement? = null,\n    \
+             val id: Long,\n    val note: String? = null\n)\n\n\
+             internal typealias Data0 = List<Data1>"
+        );
+    }
+}
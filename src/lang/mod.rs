@@ -0,0 +1,262 @@
+//! The languages that declarations can be emitted in, other than Rust,
+//! whose emitter lives alongside `DataType`.
+
+use std::{collections::BTreeSet, str::FromStr};
+
+pub(crate) mod arrow;
+pub(crate) mod avro;
+pub(crate) mod bigquery;
+pub(crate) mod c;
+pub(crate) mod capnp;
+pub(crate) mod clojure;
+pub(crate) mod cpp;
+pub(crate) mod csharp;
+pub(crate) mod dart;
+pub(crate) mod elm;
+pub(crate) mod flatbuffers;
+pub(crate) mod fsharp;
+pub(crate) mod graphql;
+pub(crate) mod io_ts;
+pub(crate) mod java;
+pub(crate) mod json_schema;
+pub(crate) mod julia;
+pub(crate) mod kotlin;
+pub(crate) mod mongoose;
+pub(crate) mod nim;
+pub(crate) mod ocaml;
+pub(crate) mod parquet;
+pub(crate) mod proto;
+pub(crate) mod pyspark;
+pub(crate) mod python;
+pub(crate) mod scala;
+pub(crate) mod sql;
+pub(crate) mod swift;
+pub(crate) mod thrift;
+pub(crate) mod typescript;
+pub(crate) mod zig;
+pub(crate) mod zod;
+
+/// A language to emit declarations in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    /// Rust structs and enums.
+    #[default]
+    Rust,
+
+    /// Protocol buffer messages, in proto3 syntax.
+    Proto,
+
+    /// TypeScript interfaces and type aliases.
+    TypeScript,
+
+    /// Python dataclasses.
+    Python,
+
+    /// Pydantic models.
+    Pydantic,
+
+    /// Java records, or classes with getters, read with Jackson.
+    Java,
+
+    /// Kotlin data classes, read with `kotlinx.serialization`.
+    Kotlin,
+
+    /// C# classes, read with `System.Text.Json`.
+    CSharp,
+
+    /// Plain C structs.
+    C,
+
+    /// C++ structs, converted with nlohmann::json.
+    Cpp,
+
+    /// Swift structs conforming to `Codable`.
+    Swift,
+
+    /// Elm types, with decoders and encoders.
+    Elm,
+
+    /// OCaml types deriving `ppx_yojson_conv` converters, or ATD
+    /// definitions.
+    OCaml,
+
+    /// Scala case classes, with codecs derived by circe or play-json.
+    Scala,
+
+    /// Dart classes annotated for `json_serializable`.
+    Dart,
+
+    /// Julia structs, optionally declared for StructTypes.jl.
+    Julia,
+
+    /// Zig structs, parsed with `std.json`.
+    Zig,
+
+    /// An Avro schema.
+    Avro,
+
+    /// A FlatBuffers schema.
+    FlatBuffers,
+
+    /// A Cap'n Proto schema.
+    Capnp,
+
+    /// Thrift IDL structs and unions.
+    Thrift,
+
+    /// A JSON Schema, in draft 2020-12.
+    JsonSchema,
+
+    /// GraphQL SDL object types.
+    GraphQL,
+
+    /// SQL `CREATE TABLE` statements.
+    Sql,
+
+    /// Zod schemas for TypeScript.
+    Zod,
+
+    /// io-ts codecs for TypeScript.
+    IoTs,
+
+    /// `clojure.spec` definitions.
+    Clojure,
+
+    /// Nim object types, for `std/json` or jsony.
+    Nim,
+
+    /// F# records and unions, read with `System.Text.Json`.
+    FSharp,
+
+    /// A BigQuery table schema.
+    BigQuery,
+
+    /// A PySpark `StructType` schema.
+    PySpark,
+
+    /// Rust code building an Arrow schema with the `arrow` crate.
+    Arrow,
+
+    /// A Parquet message type.
+    Parquet,
+
+    /// Mongoose schemas, and a model of the top-level document.
+    Mongoose,
+}
+
+impl Lang {
+    /// Text that goes before the declarations, given the imports they
+    /// need.
+    pub(crate) fn header(self, imports: &BTreeSet<String>) -> String {
+        match self {
+            Lang::Rust => String::new(),
+            Lang::Proto => proto::header(imports),
+            Lang::TypeScript => String::new(),
+            Lang::Python | Lang::Pydantic => python::header(imports),
+            Lang::Java => java::header(imports),
+            Lang::Kotlin => kotlin::header(imports),
+            Lang::CSharp => csharp::header(imports),
+            Lang::C => c::header(imports),
+            Lang::Cpp => cpp::header(imports),
+            Lang::Swift => swift::header(imports),
+            Lang::Elm => elm::header(imports),
+            Lang::OCaml => ocaml::header(imports),
+            Lang::Scala => scala::header(imports),
+            Lang::Dart => dart::header(imports),
+            Lang::Julia => julia::header(imports),
+            Lang::Zig => zig::header(imports),
+            Lang::Avro => String::new(),
+            Lang::FlatBuffers => String::new(),
+            Lang::Capnp => capnp::header(imports),
+            Lang::Thrift => String::new(),
+            Lang::JsonSchema => String::new(),
+            Lang::GraphQL => graphql::header(imports),
+            Lang::Sql => String::new(),
+            Lang::Zod => zod::header(imports),
+            Lang::IoTs => io_ts::header(imports),
+            Lang::Clojure => clojure::header(imports),
+            Lang::Nim => nim::header(imports),
+            Lang::FSharp => fsharp::header(imports),
+            Lang::BigQuery => String::new(),
+            Lang::PySpark => python::header(imports),
+            Lang::Arrow => arrow::header(imports),
+            Lang::Parquet => String::new(),
+            Lang::Mongoose => mongoose::header(imports),
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rust" => Ok(Lang::Rust),
+            "proto" => Ok(Lang::Proto),
+            "typescript" => Ok(Lang::TypeScript),
+            "python" => Ok(Lang::Python),
+            "pydantic" => Ok(Lang::Pydantic),
+            "java" => Ok(Lang::Java),
+            "kotlin" => Ok(Lang::Kotlin),
+            "csharp" => Ok(Lang::CSharp),
+            "c" => Ok(Lang::C),
+            "cpp" => Ok(Lang::Cpp),
+            "swift" => Ok(Lang::Swift),
+            "elm" => Ok(Lang::Elm),
+            "ocaml" => Ok(Lang::OCaml),
+            "scala" => Ok(Lang::Scala),
+            "dart" => Ok(Lang::Dart),
+            "julia" => Ok(Lang::Julia),
+            "zig" => Ok(Lang::Zig),
+            "avro" => Ok(Lang::Avro),
+            "flatbuffers" => Ok(Lang::FlatBuffers),
+            "capnp" => Ok(Lang::Capnp),
+            "thrift" => Ok(Lang::Thrift),
+            "json-schema" => Ok(Lang::JsonSchema),
+            "graphql" => Ok(Lang::GraphQL),
+            "sql" => Ok(Lang::Sql),
+            "zod" => Ok(Lang::Zod),
+            "io-ts" => Ok(Lang::IoTs),
+            "clojure" => Ok(Lang::Clojure),
+            "nim" => Ok(Lang::Nim),
+            "fsharp" => Ok(Lang::FSharp),
+            "bigquery" => Ok(Lang::BigQuery),
+            "pyspark" => Ok(Lang::PySpark),
+            "arrow" => Ok(Lang::Arrow),
+            "parquet" => Ok(Lang::Parquet),
+            "mongoose" => Ok(Lang::Mongoose),
+            _ => Err(format!("unknown language {:?}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::Lang;
+    use crate::{value::Value, DataType, Decls, Options};
+
+    /// The declarations emitted in `lang` for the type of `value`.
+    pub(crate) fn declare_as(lang: Lang, value: json::JsonValue) -> String {
+        declare_with(
+            Options {
+                lang,
+                ..Options::default()
+            },
+            value,
+        )
+    }
+
+    /// The declarations emitted with `options` for the type of `value`,
+    /// inferred with the same options.
+    pub(crate) fn declare_with(options: Options, value: json::JsonValue) -> String {
+        let typ = DataType::from_value_with(&Value::from_json(&value).unwrap(), &options).unwrap();
+        declare_type(options, typ)
+    }
+
+    /// The declarations emitted with `options` for `typ`.
+    pub(crate) fn declare_type(options: Options, typ: DataType) -> String {
+        let mut decls = Decls::new(options);
+        typ.declare(&mut decls);
+        decls.output()
+    }
+}
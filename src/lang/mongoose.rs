@@ -0,0 +1,278 @@
+//! Emission of Mongoose schemas, and a model for the top-level document.
+//!
+//! Each object is a `Schema` assigned to a variable, declared before the
+//! schemas that use it as a subdocument. Paths keep their keys, quoted
+//! if they aren't identifiers, and are `required` unless they were null
+//! or missing somewhere, or are arrays, which Mongoose defaults to
+//! empty. Objects that are MongoDB Extended JSON wrappers, as
+//! `mongoexport` writes ids and dates, are read as what they wrap, and
+//! values of several kinds are `Mixed`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{namer::Case, options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const MONGOOSE_UNKNOWN_TYPE: &str = "Schema.Types.Mixed";
+
+/// Text that goes before the declarations: the import of Mongoose.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    match imports.contains("mongoose") {
+        true => "import mongoose, { Schema } from \"mongoose\";\n\n".to_string(),
+        false => String::new(),
+    }
+}
+
+/// Emit schemas for a data type, and a model of the top-level one,
+/// returning its name. The top-level object, or each object of a
+/// top-level array, is a document; other types are a document with a
+/// single path named `value`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let types = &decls.options.types;
+    let name = match typ {
+        DataType::Object(members) if !types.contains_key("$") => schema(members, decls, "$"),
+        DataType::Array(elem) | DataType::FixedArray(elem, _)
+            if matches!(*elem, DataType::Object(_))
+                && !types.contains_key("$")
+                && !types.contains_key("$[]") =>
+        {
+            match *elem {
+                DataType::Object(members) => schema(members, decls, "$[]"),
+                _ => unreachable!("checked above"),
+            }
+        }
+        typ => {
+            let mut members = BTreeMap::new();
+            members.insert("value".to_string(), typ);
+            schema(members, decls, "$")
+        }
+    };
+
+    let mut s = decls.take_scratch();
+    write!(
+        s,
+        "export const {name} = mongoose.model({key}, {schema});",
+        name = name,
+        key = json::stringify(name.as_str()),
+        schema = variable(&name)
+    )
+    .expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// Emit a schema with the given members, returning the name of the type
+/// it's for.
+fn schema(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut body = decls.take_scratch();
+    let mut fields = Vec::new();
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        let member_path = format!("{}.{}", path, member);
+        let (type_name, required) = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                (decls.options.types[&member_path].clone(), true)
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Variant(mut options), _) if options.contains(&DataType::Null) => {
+                options.remove(&DataType::Null);
+                let typ = match options.len() {
+                    1 => options.into_iter().next().expect("checked above"),
+                    _ => DataType::Variant(options),
+                };
+                (schema_type(typ, decls, &member_path), false)
+            }
+            (typ, _) => {
+                let required = !matches!(
+                    typ,
+                    DataType::Null | DataType::Array(_) | DataType::FixedArray(..)
+                );
+                (schema_type(typ, decls, &member_path), required)
+            }
+        };
+        let key = match decls.namer.style().is_identifier(&member) {
+            true => member.clone(),
+            false => json::stringify(member.as_str()),
+        };
+        // Maps are already options, which `required` can go in.
+        let options = match type_name.strip_suffix(" }") {
+            Some(options) => options.to_string(),
+            None => format!("{{ type: {}", type_name),
+        };
+        let required = match required {
+            true => ", required: true",
+            false => "",
+        };
+        writeln!(body, "  {}: {}{} }},", key, options, required)
+            .expect("writing to a String can't fail");
+        fields.push((member, type_name));
+    }
+    decls.declared[declared].fields = fields;
+
+    decls.imports.insert("mongoose".to_string());
+    let export = match decls.options.pub_types {
+        true => "export ",
+        false => "",
+    };
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    match body.is_empty() {
+        true => write!(s, "{}const {} = new Schema({{}});", export, variable(&name)),
+        false => write!(
+            s,
+            "{}const {} = new Schema({{\n{}}});",
+            export,
+            variable(&name),
+            body
+        ),
+    }
+    .expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The schema type of the values at `path`, declaring the schemas it
+/// uses first.
+fn schema_type(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => unknown(decls),
+        DataType::String => "String".to_string(),
+        DataType::Temporal(_) => "Date".to_string(),
+        DataType::Int | DataType::IntEnum(_) | DataType::Float => "Number".to_string(),
+        DataType::Bool => "Boolean".to_string(),
+        DataType::Bytes => "Buffer".to_string(),
+        DataType::Object(members) => match extended_json(&members) {
+            Some(type_name) => type_name.to_string(),
+            None => variable(&schema(members, decls, path)),
+        },
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_type = schema_type(*elem, decls, &format!("{}[]", path));
+            format!("[{}]", elem_type)
+        }
+        DataType::Map(values) => {
+            let value_type = schema_type(*values, decls, &format!("{}.*", path));
+            format!("{{ type: Map, of: {} }}", value_type)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            options.remove(&DataType::Null);
+            match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    schema_type(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    unknown(decls)
+                }
+            }
+        }
+    }
+}
+
+/// The schema type of the value an object wraps, if it's a MongoDB
+/// Extended JSON wrapper.
+fn extended_json(members: &BTreeMap<String, DataType>) -> Option<&'static str> {
+    if members.len() != 1 {
+        return None;
+    }
+    match members.iter().next() {
+        Some((key, DataType::String)) if key == "$oid" => Some("Schema.Types.ObjectId"),
+        Some((key, _)) if key == "$date" => Some("Date"),
+        Some((key, _)) if key == "$numberLong" || key == "$numberInt" => Some("Number"),
+        Some((key, _)) if key == "$numberDecimal" => Some("Schema.Types.Decimal128"),
+        _ => None,
+    }
+}
+
+/// The variable holding the schema for the type `name`.
+fn variable(name: &str) -> String {
+    format!("{}Schema", Case::Camel.apply(name, true))
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls
+        .options
+        .unknown_type(MONGOOSE_UNKNOWN_TYPE)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn schemas() {
+        assert_eq!(
+            declare_as(
+                Lang::Mongoose,
+                json::array![
+                    {"_id": {"$oid": "a"}, "name": "a", "tags": [1], "owner": {"ok": true}, "content-type": 1.5, "at": {"$date": "x"}},
+                    {"_id": {"$oid": "b"}, "name": null, "tags": [2], "owner": {"ok": false}, "content-type": 2, "at": {"$date": "y"}},
+                ]
+            ),
+            "import mongoose, { Schema } from \"mongoose\";\n\n\
+             const data1Schema = new Schema({\n  ok: { type: Boolean, required: true },\n});\n\n\
+             const data0Schema = new Schema({\n  \
+             _id: { type: Schema.Types.ObjectId, required: true },\n  \
+             at: { type: Date, required: true },\n  \
+             \"content-type\": { type: Number, required: true },\n  \
+             name: { type: String },\n  owner: { type: data1Schema, required: true },\n  \
+             tags: { type: [Number] },\n});\n\n\
+             export const Data0 = mongoose.model(\"Data0\", data0Schema);"
+        );
+    }
+
+    #[test]
+    fn dates_maps_and_type_keys() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Mongoose,
+                    detect_dates: true,
+                    ..Options::default()
+                },
+                json::object! {"at": "2024-01-02T03:04:05Z", "by_id": {"1": 1, "2": 2}, "type": "a"},
+            ),
+            "import mongoose, { Schema } from \"mongoose\";\n\nconst data0Schema = new Schema({\n  \
+             at: { type: Date, required: true },\n  \
+             by_id: { type: Map, of: Number, required: true },\n  \
+             type: { type: String, required: true },\n});\n\n\
+             export const Data0 = mongoose.model(\"Data0\", data0Schema);"
+        );
+    }
+}
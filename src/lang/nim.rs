@@ -0,0 +1,256 @@
+//! Emission of Nim object types, for `std/json`'s `to` or jsony.
+//!
+//! Both match keys to field names, so fields are named after their keys
+//! where they can be, stropped with backticks if they're keywords.
+//! Fields that had to be renamed are given their keys by a jsony
+//! `renameHook`, which `std/json` has no equivalent of. Nullable fields
+//! are `Option`s, and values of several kinds are kept as `JsonNode`s.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const NIM_UNKNOWN_TYPE: &str = "JsonNode";
+
+/// Text that goes before the declarations: the modules they import.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "import {}", import).expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit object types for a data type, returning the name of the
+/// top-level one. Types other than objects are given a name with an
+/// alias.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "#", &name, "$");
+            write!(s, "type {}{} = {}", name, export(decls), type_name)
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit an object type with the given members, and a `renameHook` for
+/// those that were renamed.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut renames = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => {
+                decls.imports.insert("std/options".to_string());
+                format!("Option[{}]", unknown(decls))
+            }
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        let field = match decls.namer.style().keywords.contains(&member.as_str()) {
+            true => format!("`{}`", member),
+            false => field,
+        };
+        writeln!(body, "    {}{}: {}", field, export(decls), type_name)
+            .expect("writing to a String can't fail");
+        if field.trim_matches('`') != member {
+            renames.push((member.clone(), field));
+        }
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "#", &name, path);
+    write!(s, "type\n  {}{} = object\n{}", name, export(decls), body)
+        .expect("writing to a String can't fail");
+    if !renames.is_empty() {
+        decls.imports.insert("jsony".to_string());
+        write!(
+            s,
+            "\nproc renameHook{}(v: var {}, fieldName: var string) =\n  case fieldName\n",
+            export(decls),
+            name
+        )
+        .expect("writing to a String can't fail");
+        for (key, field) in renames {
+            writeln!(
+                s,
+                "  of {}: fieldName = \"{}\"",
+                json::stringify(key),
+                field.trim_matches('`')
+            )
+            .expect("writing to a String can't fail");
+        }
+        s += "  else: discard\n";
+    }
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(s.trim_end());
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the object types it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => unknown(decls),
+        DataType::String | DataType::Temporal(_) => "string".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "int".to_string(),
+        DataType::Float => "float".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Bytes => "seq[byte]".to_string(),
+        DataType::Object(members) => object(members, decls, path),
+        DataType::Array(elem) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("seq[{}]", elem_name)
+        }
+        DataType::FixedArray(elem, len) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("array[{}, {}]", len, elem_name)
+        }
+        DataType::Map(values) => {
+            decls.imports.insert("std/tables".to_string());
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("Table[string, {}]", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    unknown(decls)
+                }
+            };
+            match nullable {
+                true => {
+                    decls.imports.insert("std/options".to_string());
+                    format!("Option[{}]", type_name)
+                }
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The marker to put after declarations, if they're exported.
+fn export(decls: &Decls) -> &'static str {
+    match decls.options.pub_types {
+        true => "*",
+        false => "",
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert("std/json".to_string());
+            NIM_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn objects() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Nim,
+                    pub_types: true,
+                    ..Options::default()
+                },
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "type": "a"},
+                    {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "type": 1},
+                ]
+            ),
+            "import jsony\nimport std/json\nimport std/options\n\n\
+             type\n  Data2* = object\n\n\
+             type\n  Data1* = object\n    contentType*: float\n    id*: int\n    \
+             name*: Option[string]\n    owner*: Data2\n    tags*: seq[int]\n    \
+             `type`*: JsonNode\n\n\
+             proc renameHook*(v: var Data1, fieldName: var string) =\n  case fieldName\n  \
+             of \"content-type\": fieldName = \"contentType\"\n  else: discard\n\n\
+             type Data0* = seq[Data1]"
+        );
+    }
+
+    #[test]
+    fn tables() {
+        assert_eq!(
+            declare_as(
+                Lang::Nim,
+                json::object! {"by_id": {"1": 1, "2": 2}, "gone": null}
+            ),
+            "import std/json\nimport std/tables\n\ntype\n  Data0 = object\n    \
+             by_id: Table[string, int]\n    gone: JsonNode"
+        );
+    }
+}
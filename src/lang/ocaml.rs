@@ -0,0 +1,427 @@
+//! Emission of OCaml types deriving `ppx_yojson_conv` converters, or of
+//! ATD type definitions.
+//!
+//! Objects are records, with `[@key]` giving the key of any renamed
+//! field, and nullable fields are options, which may also be missing.
+//! OCaml's variants are tagged in JSON, so values of several kinds are
+//! variants with converters written out to read them by the shape of
+//! the value. ATD has no such escape, so they're left `abstract` there.
+//! OCaml has no empty records, so objects that never had any members
+//! are kept as JSON.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{namer::Case, options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const OCAML_UNKNOWN_TYPE: &str = "json";
+
+/// The declaration of `OCAML_UNKNOWN_TYPE`, which `ppx_yojson_conv` has
+/// no converters for.
+const JSON: &str = "type json = Yojson.Safe.t
+
+let json_of_yojson (json : Yojson.Safe.t) : json = json
+let yojson_of_json (json : json) : Yojson.Safe.t = json
+";
+
+/// The type of maps, which are objects in JSON rather than the lists of
+/// pairs that `ppx_yojson_conv` would make of them.
+const ASSOC: &str = "type 'a assoc = (string * 'a) list
+
+let assoc_of_yojson value_of_yojson (json : Yojson.Safe.t) : 'a assoc =
+  match json with
+  | `Assoc pairs -> List.map (fun (key, value) -> (key, value_of_yojson value)) pairs
+  | _ -> Ppx_yojson_conv_lib.Yojson_conv.of_yojson_error \"expected an object\" json
+
+let yojson_of_assoc yojson_of_value (pairs : 'a assoc) : Yojson.Safe.t =
+  `Assoc (List.map (fun (key, value) -> (key, yojson_of_value value)) pairs)
+";
+
+/// The declarations that can go in the header, by the name of the type
+/// they declare, which is among the imports when they're needed.
+const HELPERS: &[(&str, &str)] = &[(OCAML_UNKNOWN_TYPE, JSON), ("assoc", ASSOC)];
+
+/// Text that goes before the declarations: the converters of the
+/// primitive types, and the helpers the declarations use. ATD
+/// definitions need nothing.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    if imports.is_empty() {
+        return String::new();
+    }
+    let mut s = "open Ppx_yojson_conv_lib.Yojson_conv.Primitives\n\n".to_string();
+    for (name, helper) in HELPERS {
+        if imports.contains(*name) {
+            writeln!(s, "{}", helper).expect("writing to a String can't fail");
+        }
+    }
+    s
+}
+
+/// Emit types for a data type, returning the name of the top-level one.
+/// Types other than objects are given a name with an alias.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    if !decls.options.ocaml_atd {
+        decls.imports.insert("Primitives".to_string());
+    }
+    match typ {
+        DataType::Object(members)
+            if !members.is_empty() && !decls.options.types.contains_key("$") =>
+        {
+            record(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            occurs_at(decls, &mut s, &name, "$");
+            write!(s, "type {} = {}{}", name, type_name, deriving(decls))
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a record with the given members.
+fn record(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    // Fields can't start with a capital, which would make them
+    // constructors.
+    let wanted = members
+        .iter()
+        .map(|(member, _)| match member.starts_with(char::is_uppercase) {
+            true => Case::Snake.apply(member, true),
+            false => member.clone(),
+        })
+        .collect::<Vec<_>>();
+    let field_names = decls
+        .namer
+        .fields(&wanted.iter().map(String::as_str).collect::<Vec<_>>());
+    let atd = decls.options.ocaml_atd;
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => nullable(&unknown(decls), atd),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        let key = json::stringify(member.as_str());
+        match atd {
+            true if field != member => {
+                writeln!(body, "  {} <json name={}> : {};", field, key, type_name)
+            }
+            true => writeln!(body, "  {} : {};", field, type_name),
+            false => {
+                write!(body, "  {} : {}", field, type_name)
+                    .expect("writing to a String can't fail");
+                if field != member {
+                    write!(body, " [@key {}]", key).expect("writing to a String can't fail");
+                }
+                // Fields that can be null can be missing too.
+                if type_name.ends_with(" option") {
+                    body += " [@default None]";
+                }
+                writeln!(body, ";")
+            }
+        }
+        .expect("writing to a String can't fail");
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    occurs_at(decls, &mut s, &name, path);
+    write!(s, "type {} = {{\n{}}}{}", name, body, deriving(decls))
+        .expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// Emit a variant with a constructor for each kind of value in
+/// `options`, and converters that pick the constructor by the shape of
+/// the JSON value.
+fn variant(options: BTreeSet<DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.next_name();
+    let arms = options
+        .into_iter()
+        .map(|typ| {
+            let constructor = Case::Pascal.apply(typ.kind_name(), true);
+            let patterns = patterns(&typ);
+            (constructor, patterns, type_expr(typ, decls, path))
+        })
+        .collect::<Vec<_>>();
+
+    let mut s = decls.take_scratch();
+    occurs_at(decls, &mut s, &name, path);
+    write!(s, "type {} =", name).expect("writing to a String can't fail");
+    for (constructor, _, type_name) in &arms {
+        write!(s, "\n  | {} of {}", constructor, type_name)
+            .expect("writing to a String can't fail");
+    }
+
+    write!(
+        s,
+        "\n\nlet {name}_of_yojson (json : Yojson.Safe.t) : {name} =\n  match json with",
+        name = name
+    )
+    .expect("writing to a String can't fail");
+    // Kinds that look alike in JSON, like strings and dates, are read
+    // as the first of them.
+    let mut seen = BTreeSet::new();
+    for (constructor, patterns, type_name) in &arms {
+        let patterns = patterns
+            .iter()
+            .filter(|pattern| seen.insert(**pattern))
+            .copied()
+            .collect::<Vec<_>>();
+        if !patterns.is_empty() {
+            write!(
+                s,
+                "\n  | {} -> {} ([%of_yojson: {}] json)",
+                patterns.join(" | "),
+                constructor,
+                type_name
+            )
+            .expect("writing to a String can't fail");
+        }
+    }
+    write!(
+        s,
+        "\n  | _ -> Ppx_yojson_conv_lib.Yojson_conv.of_yojson_error \"expected one of {}\" json",
+        arms.iter()
+            .map(|(constructor, ..)| constructor.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .expect("writing to a String can't fail");
+
+    write!(
+        s,
+        "\n\nlet yojson_of_{name} : {name} -> Yojson.Safe.t = function",
+        name = name
+    )
+    .expect("writing to a String can't fail");
+    for (constructor, _, type_name) in &arms {
+        write!(
+            s,
+            "\n  | {} inner -> [%yojson_of: {}] inner",
+            constructor, type_name
+        )
+        .expect("writing to a String can't fail");
+    }
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The patterns matching the JSON values that can be read as `typ`.
+fn patterns(typ: &DataType) -> &'static [&'static str] {
+    match typ {
+        DataType::Null => &["`Null"],
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => &["`String _"],
+        DataType::Int | DataType::IntEnum(_) => &["`Int _"],
+        DataType::Float => &["`Float _", "`Int _"],
+        DataType::Bool => &["`Bool _"],
+        DataType::Object(_) | DataType::Map(_) => &["`Assoc _"],
+        DataType::Array(_) | DataType::FixedArray(..) => &["`List _"],
+        DataType::Variant(_) => &[],
+    }
+}
+
+/// The type of the values at `path`, declaring the types it uses first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "unit".to_string(),
+        // OCaml's strings are strings of bytes.
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => "string".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "int".to_string(),
+        DataType::Float => "float".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Object(members) if members.is_empty() => unknown(decls),
+        DataType::Object(members) => record(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("{} list", elem_name)
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            match decls.options.ocaml_atd {
+                true => format!("(string * {}) list <json repr=\"object\">", value_name),
+                false => {
+                    decls.imports.insert("assoc".to_string());
+                    format!("{} assoc", value_name)
+                }
+            }
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    match decls.options.ocaml_atd {
+                        true => unknown(decls),
+                        false => variant(options, decls, path),
+                    }
+                }
+            };
+            match nullable {
+                true => self::nullable(&type_name, decls.options.ocaml_atd),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The type of values that are either null or a `type_name`.
+fn nullable(type_name: &str, atd: bool) -> String {
+    match atd {
+        true if type_name.ends_with(" nullable") => type_name.to_string(),
+        true => format!("{} nullable", type_name),
+        false if type_name.ends_with(" option") => type_name.to_string(),
+        false => format!("{} option", type_name),
+    }
+}
+
+/// What goes after a type declaration for its converters to be derived.
+fn deriving(decls: &Decls) -> &'static str {
+    match decls.options.ocaml_atd {
+        true => "",
+        false => " [@@deriving yojson]",
+    }
+}
+
+/// Note the paths `name` was declared for in a comment, as
+/// `Decls::occurs_at` does, but with the comment closed.
+fn occurs_at(decls: &mut Decls, out: &mut String, name: &str, path: &str) {
+    let start = out.len();
+    decls.occurs_at(out, "(*", name, path);
+    if out.len() > start {
+        out.pop();
+        *out += " *)\n";
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match (&decls.options.unknown_type, decls.options.ocaml_atd) {
+        (Some(unknown), _) => unknown.clone(),
+        (None, true) => "abstract".to_string(),
+        (None, false) => {
+            decls.imports.insert(OCAML_UNKNOWN_TYPE.to_string());
+            OCAML_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    fn sample() -> json::JsonValue {
+        json::array![
+            {"id": 1, "name": "a", "tags": [1], "owner": {"ok": true}, "content-type": 1.5, "x": "a"},
+            {"id": 2, "name": null, "tags": [2], "owner": {"ok": false}, "content-type": 2, "x": 1},
+        ]
+    }
+
+    #[test]
+    fn ppx_yojson_conv() {
+        assert_eq!(
+            declare_as(Lang::OCaml, sample()),
+            "open Ppx_yojson_conv_lib.Yojson_conv.Primitives\n\n\
+             type data2 = {\n  ok : bool;\n} [@@deriving yojson]\n\n\
+             type data3 =\n  | String of string\n  | Int of int\n\n\
+             let data3_of_yojson (json : Yojson.Safe.t) : data3 =\n  match json with\n  \
+             | `String _ -> String ([%of_yojson: string] json)\n  \
+             | `Int _ -> Int ([%of_yojson: int] json)\n  \
+             | _ -> Ppx_yojson_conv_lib.Yojson_conv.of_yojson_error \
+             \"expected one of string, int\" json\n\n\
+             let yojson_of_data3 : data3 -> Yojson.Safe.t = function\n  \
+             | String inner -> [%yojson_of: string] inner\n  \
+             | Int inner -> [%yojson_of: int] inner\n\n\
+             type data1 = {\n  content_type : float [@key \"content-type\"];\n  id : int;\n  \
+             name : string option [@default None];\n  owner : data2;\n  tags : int list;\n  \
+             x : data3;\n} [@@deriving yojson]\n\n\
+             type data0 = data1 list [@@deriving yojson]"
+        );
+    }
+
+    #[test]
+    fn atd() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::OCaml,
+                    ocaml_atd: true,
+                    ..Options::default()
+                },
+                sample()
+            ),
+            "type data2 = {\n  ok : bool;\n}\n\n\
+             type data1 = {\n  content_type <json name=\"content-type\"> : float;\n  id : int;\n  \
+             name : string nullable;\n  owner : data2;\n  tags : int list;\n  x : abstract;\n}\n\n\
+             type data0 = data1 list"
+        );
+    }
+
+    #[test]
+    fn maps() {
+        assert_eq!(
+            declare_as(Lang::OCaml, json::object! {"by_id": {"1": 1, "2": 2}}),
+            "open Ppx_yojson_conv_lib.Yojson_conv.Primitives\n\n\
+             type 'a assoc = (string * 'a) list\n\n\
+             let assoc_of_yojson value_of_yojson (json : Yojson.Safe.t) : 'a assoc =\n  \
+             match json with\n  \
+             | `Assoc pairs -> List.map (fun (key, value) -> (key, value_of_yojson value)) \
+             pairs\n  \
+             | _ -> Ppx_yojson_conv_lib.Yojson_conv.of_yojson_error \"expected an object\" json\n\n\
+             let yojson_of_assoc yojson_of_value (pairs : 'a assoc) : Yojson.Safe.t =\n  \
+             `Assoc (List.map (fun (key, value) -> (key, yojson_of_value value)) pairs)\n\n\
+             type data0 = {\n  by_id : int assoc;\n} [@@deriving yojson]"
+        );
+    }
+}
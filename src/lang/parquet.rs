@@ -0,0 +1,252 @@
+//! Emission of a Parquet message type, in the text form that
+//! `MessageTypeParser` and `parquet-tools schema` use.
+//!
+//! The top-level object, or each object of a top-level array, is a
+//! record. Nested objects are groups, arrays are `LIST`s and maps are
+//! `MAP`s, in the three-level form the format's spec asks for, and
+//! fields that were null somewhere are `optional`. Parquet has no
+//! unions, so values of several kinds are `JSON` strings, as are
+//! objects without any members, since groups need at least one field.
+//! Parquet's schemas can't have comments, so fields whose keys had to
+//! be renamed can't say what they were.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    options::NullFields, temporal::Temporal, types::Decls, warnings::WarningKind, DataType,
+};
+
+/// Type emitted for values nothing is known about, and for values that
+/// can't be fields of their own.
+const PARQUET_UNKNOWN_TYPE: &str = "binary {} (JSON)";
+
+/// Emit the message type of a data type, returning its name. Types
+/// other than objects and arrays of them are a record with a single
+/// field named `value`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let name = decls.name_at("$");
+    let mut body = decls.take_scratch();
+    let types = &decls.options.types;
+    match typ {
+        DataType::Object(members) if !types.contains_key("$") => {
+            let declared = decls.declare_object("$", &name);
+            group(&mut body, members, decls, "$", declared, 1);
+        }
+        DataType::Array(elem) | DataType::FixedArray(elem, _)
+            if matches!(*elem, DataType::Object(_))
+                && !types.contains_key("$")
+                && !types.contains_key("$[]") =>
+        {
+            let declared = decls.declare_object("$[]", &name);
+            match *elem {
+                DataType::Object(members) => group(&mut body, members, decls, "$[]", declared, 1),
+                _ => unreachable!("checked above"),
+            }
+        }
+        typ => field(&mut body, decls, "$", "value", typ, 1),
+    }
+
+    let mut s = decls.take_scratch();
+    write!(s, "message {} {{\n{}}}", name, body).expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// Write the fields of a group with the given members to `s`, recording
+/// them as the fields of the object `declared`.
+fn group(
+    s: &mut String,
+    members: BTreeMap<String, DataType>,
+    decls: &mut Decls,
+    path: &str,
+    declared: usize,
+    depth: usize,
+) {
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    for ((member, typ), name) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        if typ == DataType::Null
+            && decls.options.null_fields == NullFields::Drop
+            && !decls.options.types.contains_key(&member_path)
+        {
+            decls
+                .warnings
+                .push(member_path, WarningKind::NullFieldDropped, "");
+            continue;
+        }
+        let start = s.len();
+        field(s, decls, &member_path, &name, typ, depth);
+        fields.push((member, s[start..].trim().to_string()));
+    }
+    decls.declared[declared].fields = fields;
+}
+
+/// Write the field named `name` for the values at `path` to `s`, at
+/// the given depth of nesting.
+fn field(s: &mut String, decls: &mut Decls, path: &str, name: &str, typ: DataType, depth: usize) {
+    let (typ, repetition) = match typ {
+        DataType::Variant(mut options) if options.contains(&DataType::Null) => {
+            options.remove(&DataType::Null);
+            let typ = match options.len() {
+                1 => options.into_iter().next().expect("checked above"),
+                _ => DataType::Variant(options),
+            };
+            (typ, "optional")
+        }
+        DataType::Null => (DataType::Null, "optional"),
+        typ => (typ, "required"),
+    };
+    let indent = "  ".repeat(depth);
+    if let Some(type_name) = decls.options.types.get(path) {
+        writeln!(s, "{}{} {} {};", indent, repetition, type_name, name)
+            .expect("writing to a String can't fail");
+        return;
+    }
+
+    let primitive = match typ {
+        DataType::String => "binary {} (STRING)".to_string(),
+        DataType::Temporal(Temporal::Date) => "int32 {} (DATE)".to_string(),
+        DataType::Temporal(Temporal::Time) => "int64 {} (TIME(MICROS,false))".to_string(),
+        DataType::Temporal(Temporal::NaiveDateTime) => {
+            "int64 {} (TIMESTAMP(MICROS,false))".to_string()
+        }
+        DataType::Temporal(Temporal::DateTime) => "int64 {} (TIMESTAMP(MICROS,true))".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "int64 {}".to_string(),
+        DataType::Float => "double {}".to_string(),
+        DataType::Bool => "boolean {}".to_string(),
+        DataType::Bytes => "binary {}".to_string(),
+        DataType::Object(members) if !members.is_empty() => {
+            writeln!(s, "{}{} group {} {{", indent, repetition, name)
+                .expect("writing to a String can't fail");
+            let group_name = decls.name_at(path);
+            let declared = decls.declare_object(path, &group_name);
+            group(s, members, decls, path, declared, depth + 1);
+            writeln!(s, "{}}}", indent).expect("writing to a String can't fail");
+            return;
+        }
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            writeln!(
+                s,
+                "{}{} group {} (LIST) {{\n{}  repeated group list {{",
+                indent, repetition, name, indent
+            )
+            .expect("writing to a String can't fail");
+            field(
+                s,
+                decls,
+                &format!("{}[]", path),
+                "element",
+                *elem,
+                depth + 2,
+            );
+            writeln!(s, "{}  }}\n{}}}", indent, indent).expect("writing to a String can't fail");
+            return;
+        }
+        DataType::Map(values) => {
+            writeln!(
+                s,
+                "{}{} group {} (MAP) {{\n{}  repeated group key_value {{\n{}    \
+                 required binary key (STRING);",
+                indent, repetition, name, indent, indent
+            )
+            .expect("writing to a String can't fail");
+            field(
+                s,
+                decls,
+                &format!("{}.*", path),
+                "value",
+                *values,
+                depth + 2,
+            );
+            writeln!(s, "{}  }}\n{}}}", indent, indent).expect("writing to a String can't fail");
+            return;
+        }
+        DataType::Variant(options) if options.len() > 1 => {
+            decls.warnings.push(
+                path,
+                WarningKind::EnumFallback,
+                options
+                    .iter()
+                    .map(DataType::kind_name)
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            );
+            unknown(decls)
+        }
+        DataType::Null | DataType::Object(_) | DataType::Variant(_) => unknown(decls),
+    };
+    writeln!(
+        s,
+        "{}{} {};",
+        indent,
+        repetition,
+        primitive.replace("{}", name)
+    )
+    .expect("writing to a String can't fail");
+}
+
+/// The type to emit for unknown values, with `{}` where the field's
+/// name goes.
+fn unknown(decls: &Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => format!("{} {{}}", unknown),
+        None => PARQUET_UNKNOWN_TYPE.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn message_type() {
+        assert_eq!(
+            declare_as(
+                Lang::Parquet,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1, null], "owner": {"ok": true}, "m": {"1": 1.5, "2": 2.5}},
+                    {"id": "b", "name": null, "tags": [], "m": {"3": 3.5, "4": 4.5}},
+                ]
+            ),
+            "message Data0 {\n  required binary id (JSON);\n  required group m (MAP) {\n    \
+             repeated group key_value {\n      required binary key (STRING);\n      \
+             required double value;\n    }\n  }\n  optional binary name (STRING);\n  \
+             optional group owner {\n    required boolean ok;\n  }\n  \
+             required group tags (LIST) {\n    repeated group list {\n      \
+             optional int64 element;\n    }\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn dates_and_timestamps() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Parquet,
+                    detect_dates: true,
+                    ..Options::default()
+                },
+                json::object! {"at": "2024-01-02T03:04:05Z", "on": "2024-01-02"},
+            ),
+            "message Data0 {\n  required int64 at (TIMESTAMP(MICROS,true));\n  \
+             required int32 on (DATE);\n}"
+        );
+    }
+}
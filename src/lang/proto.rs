@@ -0,0 +1,392 @@
+//! Emission of protocol buffer messages.
+//!
+//! Proto3 is stricter than Rust about where things can go: fields can't
+//! be repeated twice over, map values can't be repeated, and `oneof`s
+//! only exist as groups of fields. Types that can't go somewhere
+//! directly are wrapped in a message with a single field named `value`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    options::NullFields, schema_state::FieldNumbers, types::Decls, warnings::WarningKind, DataType,
+};
+
+/// Type emitted for values nothing is known about.
+pub const PROTO_UNKNOWN_TYPE: &str = "google.protobuf.Value";
+
+/// Type emitted for values that are always null.
+const PROTO_NULL_TYPE: &str = "google.protobuf.NullValue";
+
+/// The file that declares the well-known JSON types.
+const STRUCT_PROTO: &str = "google/protobuf/struct.proto";
+
+/// Text that goes before the messages.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = "syntax = \"proto3\";\n\n".to_string();
+    for import in imports {
+        writeln!(s, "import \"{}\";", import).expect("writing to a String can't fail");
+    }
+    if !imports.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit messages for a data type, returning the name of the top-level
+/// message. Types other than objects are wrapped in a message, since
+/// only messages can be top-level.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) => message(members, decls, "$"),
+        typ => wrapper(typ, decls, "$"),
+    }
+}
+
+/// Emit a message with the given members.
+fn message(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut body = decls.take_scratch();
+    let mut numbers = decls.field_numbers(path);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    for ((member, typ), field_name) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let start = body.len();
+        // Renamed fields keep their keys in proto3's JSON mapping.
+        let json_name = match field_name != member {
+            true => Some(member.as_str()),
+            false => None,
+        };
+        field(
+            &mut body,
+            &mut numbers,
+            decls,
+            &member_path,
+            (&field_name, json_name),
+            typ,
+        );
+        fields.push((member, body[start..].trim().to_string()));
+    }
+    decls.declared[declared].fields = fields;
+    let reserved = decls.finish_field_numbers(path, numbers);
+    message_decl(decls, path, &name, &reserved, &body);
+    decls.put_scratch(body);
+    name
+}
+
+/// Emit a message with a single field named `value`, for types that
+/// can't be used where they're needed.
+fn wrapper(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    let name = decls.next_name();
+    let mut body = decls.take_scratch();
+    let mut numbers = decls.field_numbers(path);
+    field(&mut body, &mut numbers, decls, path, ("value", None), typ);
+    let reserved = decls.finish_field_numbers(path, numbers);
+    message_decl(decls, path, &name, &reserved, &body);
+    decls.put_scratch(body);
+    name
+}
+
+/// Declare the message for the values at `path`, given its fields and
+/// the numbers it must not use.
+fn message_decl(decls: &mut Decls, path: &str, name: &str, reserved: &[u32], body: &str) {
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", name, path);
+    writeln!(s, "message {} {{", name).expect("writing to a String can't fail");
+    if !reserved.is_empty() {
+        s += "  reserved ";
+        for (idx, number) in reserved.iter().enumerate() {
+            if idx > 0 {
+                s += ", ";
+            }
+            write!(s, "{}", number).expect("writing to a String can't fail");
+        }
+        s += ";\n";
+    }
+    s += body;
+    s += "}";
+    decls.begin_decl(name).push_str(&s);
+    decls.put_scratch(s);
+}
+
+/// Append the field or fields for a message member to `body`, given the
+/// field's name and the key it should have in JSON, if that's different.
+fn field(
+    body: &mut String,
+    numbers: &mut FieldNumbers,
+    decls: &mut Decls,
+    path: &str,
+    name: (&str, Option<&str>),
+    typ: DataType,
+) {
+    if let Some(type_name) = decls.options.types.get(path) {
+        let type_name = type_name.clone();
+        push_field(body, numbers, "", "", &type_name, name);
+        return;
+    }
+
+    match typ {
+        DataType::Null if decls.options.null_fields == NullFields::Drop => {
+            decls.warnings.push(path, WarningKind::NullFieldDropped, "");
+        }
+        DataType::Null if decls.options.null_fields == NullFields::Option => {
+            let type_name = unknown(decls);
+            push_field(body, numbers, "", "", &type_name, name);
+        }
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let type_name = singular(*elem, decls, &format!("{}[]", path));
+            push_field(body, numbers, "", "repeated ", &type_name, name);
+        }
+        DataType::Map(values) => {
+            let type_name = singular(*values, decls, &format!("{}.*", path));
+            push_field(
+                body,
+                numbers,
+                "",
+                "",
+                &format!("map<string, {}>", type_name),
+                name,
+            );
+        }
+        DataType::Variant(mut options) if !options.is_empty() => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() == 1 {
+                let typ = options.into_iter().next().expect("checked above");
+                if nullable && typ.is_scalar() {
+                    let type_name = singular(typ, decls, path);
+                    push_field(body, numbers, "", "optional ", &type_name, name);
+                } else {
+                    // Message fields already track presence, and
+                    // repeated and map fields can just be empty.
+                    field(body, numbers, decls, path, name, typ);
+                }
+                return;
+            }
+
+            decls.warnings.push(
+                path,
+                WarningKind::EnumFallback,
+                options
+                    .iter()
+                    .map(DataType::kind_name)
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            );
+            writeln!(body, "  oneof {} {{", name.0).expect("writing to a String can't fail");
+            for typ in options {
+                let option_name = format!("{}_{}", name.0, typ.kind_name());
+                let type_name = singular(typ, decls, path);
+                push_field(body, numbers, "  ", "", &type_name, (&option_name, None));
+            }
+            writeln!(body, "  }}").expect("writing to a String can't fail");
+        }
+        typ => {
+            let type_name = singular(typ, decls, path);
+            push_field(body, numbers, "", "", &type_name, name);
+        }
+    }
+}
+
+/// Append a single field declaration to `body`.
+fn push_field(
+    body: &mut String,
+    numbers: &mut FieldNumbers,
+    indent: &str,
+    label: &str,
+    type_name: &str,
+    (name, json_name): (&str, Option<&str>),
+) {
+    write!(
+        body,
+        "{}  {}{} {} = {}",
+        indent,
+        label,
+        type_name,
+        name,
+        numbers.number(name)
+    )
+    .expect("writing to a String can't fail");
+    if let Some(json_name) = json_name {
+        write!(body, " [json_name = {}]", json::stringify(json_name))
+            .expect("writing to a String can't fail");
+    }
+    body.push_str(";\n");
+}
+
+/// The name of a type for use as a field that isn't repeated, a map or
+/// part of a `oneof`, declaring it first if need be.
+fn singular(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => {
+            decls.imports.insert(STRUCT_PROTO.to_string());
+            PROTO_NULL_TYPE.to_string()
+        }
+        DataType::String | DataType::Temporal(_) => "string".to_string(),
+        // Proto enums are 32-bit and must start at zero, so codes stay
+        // plain integers.
+        DataType::Int | DataType::IntEnum(_) => "int64".to_string(),
+        DataType::Float => "double".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Bytes => "bytes".to_string(),
+        DataType::Object(members) => message(members, decls, path),
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        typ => wrapper(typ, decls, path),
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert(STRUCT_PROTO.to_string());
+            PROTO_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{tests::declare_as, Lang},
+        DataType, Decls, Options, PathsFormat, SchemaState,
+    };
+
+    #[test]
+    fn messages() {
+        assert_eq!(
+            declare_as(
+                Lang::Proto,
+                json::object! {
+                    "foo": "a",
+                    "bar": 1,
+                    "score": 1.5,
+                    "ok": true,
+                    "tags": ["x"],
+                    "owner": {"id": 2},
+                }
+            ),
+            "syntax = \"proto3\";\n\n\
+             message Data1 {\n  int64 id = 1;\n}\n\n\
+             message Data0 {\n  int64 bar = 1;\n  string foo = 2;\n  bool ok = 3;\n  \
+             Data1 owner = 4;\n  double score = 5;\n  repeated string tags = 6;\n}"
+        );
+    }
+
+    #[test]
+    fn json_names() {
+        assert_eq!(
+            declare_as(
+                Lang::Proto,
+                json::object! {"content-type": "a", "class": 1, "id": [2]}
+            ),
+            "syntax = \"proto3\";\n\n\
+             message Data0 {\n  int64 class = 1;\n  \
+             string content_type = 2 [json_name = \"content-type\"];\n  repeated int64 id = 3;\n}"
+        );
+    }
+
+    #[test]
+    fn optional_and_oneof() {
+        assert_eq!(
+            declare_as(
+                Lang::Proto,
+                json::array![
+                    {"name": "a", "id": 1},
+                    {"name": null, "id": "b"},
+                ]
+            ),
+            "syntax = \"proto3\";\n\n\
+             message Data1 {\n  oneof id {\n    string id_string = 1;\n    int64 id_int = 2;\n  }\n  \
+             optional string name = 3;\n}\n\n\
+             message Data0 {\n  repeated Data1 value = 1;\n}"
+        );
+    }
+
+    #[test]
+    fn saved_field_numbers() {
+        let declare = |v: json::JsonValue, state: SchemaState| {
+            let mut decls = Decls::new(Options {
+                lang: Lang::Proto,
+                ..Options::default()
+            })
+            .with_schema_state(state);
+            DataType::from_json_value(&v).unwrap().declare(&mut decls);
+            (
+                decls.output(),
+                decls.schema_state().cloned().unwrap_or_default(),
+            )
+        };
+
+        let (output, state) = declare(json::object! {"b": 1, "c": "x"}, SchemaState::new());
+        assert_eq!(
+            output,
+            "syntax = \"proto3\";\n\n\
+             message Data0 {\n  int64 b = 1;\n  string c = 2;\n}"
+        );
+        let (output, _) = declare(json::object! {"a": true, "c": "x"}, state);
+        assert_eq!(
+            output,
+            "syntax = \"proto3\";\n\n\
+             message Data0 {\n  reserved 1;\n  bool a = 3;\n  string c = 2;\n}"
+        );
+    }
+
+    #[test]
+    fn wrapped_types() {
+        assert_eq!(
+            declare_as(
+                Lang::Proto,
+                json::object! {"grid": [[1]], "by_id": {"1": [true]}, "empty": []}
+            ),
+            "syntax = \"proto3\";\n\n\
+             import \"google/protobuf/struct.proto\";\n\n\
+             message Data1 {\n  repeated bool value = 1;\n}\n\n\
+             message Data2 {\n  repeated int64 value = 1;\n}\n\n\
+             message Data0 {\n  map<string, Data1> by_id = 1;\n  \
+             repeated google.protobuf.Value empty = 2;\n  repeated Data2 grid = 3;\n}"
+        );
+    }
+
+    #[test]
+    fn path_comments() {
+        let mut decls = Decls::new(Options {
+            lang: Lang::Proto,
+            annotate_paths: Some(PathsFormat::Dotted),
+            ..Options::default()
+        });
+        DataType::from_json_value(&json::object! {"grid": [[1]]})
+            .unwrap()
+            .declare(&mut decls);
+        assert!(decls.output().ends_with(
+            "// Occurs at: .grid[]\nmessage Data1 {\n  repeated int64 value = 1;\n}\n\n\
+             // Occurs at: .\nmessage Data0 {\n  repeated Data1 grid = 1;\n}"
+        ));
+    }
+
+    #[test]
+    fn well_known_types() {
+        assert_eq!(
+            declare_as(Lang::Proto, json::object! {"extra": [], "gone": null}),
+            "syntax = \"proto3\";\n\nimport \"google/protobuf/struct.proto\";\n\n\
+             message Data0 {\n  repeated google.protobuf.Value extra = 1;\n  \
+             google.protobuf.NullValue gone = 2;\n}"
+        );
+    }
+}
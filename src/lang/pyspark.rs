@@ -0,0 +1,268 @@
+//! Emission of a PySpark schema, to pass to `spark.read.json` instead of
+//! having Spark infer one.
+//!
+//! Each object is a `StructType` assigned to a variable, declared
+//! before the structs that use it. Fields keep their keys, since they're
+//! only ever strings, and are nullable if they were null somewhere.
+//! Spark reads values of any kind into a string column as the JSON text
+//! they were written as, so that's what values of several kinds, or
+//! that nothing is known about, are.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    options::NullFields, temporal::Temporal, types::Decls, warnings::WarningKind, DataType,
+};
+
+/// Type emitted for values nothing is known about.
+const PYSPARK_UNKNOWN_TYPE: &str = "StringType()";
+
+/// Emit `StructType`s for a data type, returning the name of the
+/// top-level one. The top-level object, or each object of a top-level
+/// array, is a row; other types are a row with a single field named
+/// `value`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let types = &decls.options.types;
+    match typ {
+        DataType::Object(members) if !types.contains_key("$") => struct_type(members, decls, "$"),
+        DataType::Array(elem) | DataType::FixedArray(elem, _)
+            if matches!(*elem, DataType::Object(_))
+                && !types.contains_key("$")
+                && !types.contains_key("$[]") =>
+        {
+            match *elem {
+                DataType::Object(members) => struct_type(members, decls, "$[]"),
+                _ => unreachable!("checked above"),
+            }
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let (data_type, nullable) = data_type(typ, decls, "$");
+            let field = struct_field("value", &data_type, nullable);
+            struct_decl(decls, "$", &name, &[field]);
+            name
+        }
+    }
+}
+
+/// Emit a `StructType` with the given members.
+fn struct_type(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut fields = Vec::new();
+    let mut struct_fields = Vec::new();
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        let member_path = format!("{}.{}", path, member);
+        let (data_type, nullable) = match (typ, decls.options.null_fields) {
+            (DataType::Null, NullFields::Drop)
+                if !decls.options.types.contains_key(&member_path) =>
+            {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (typ, _) => data_type(typ, decls, &member_path),
+        };
+        struct_fields.push(struct_field(&member, &data_type, nullable));
+        fields.push((member, data_type));
+    }
+    decls.declared[declared].fields = fields;
+    struct_decl(decls, path, &name, &struct_fields);
+    name
+}
+
+/// A `StructField` for the values under `key`.
+fn struct_field(key: &str, data_type: &str, nullable: bool) -> String {
+    format!(
+        "StructField({}, {}, {})",
+        json::stringify(key),
+        data_type,
+        python_bool(nullable)
+    )
+}
+
+/// Declare the `StructType` for the values at `path`, given its fields.
+fn struct_decl(decls: &mut Decls, path: &str, name: &str, fields: &[String]) {
+    import(decls, "StructType");
+    if !fields.is_empty() {
+        import(decls, "StructField");
+    }
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "#", name, path);
+    write!(s, "{} = StructType([", name).expect("writing to a String can't fail");
+    for field in fields {
+        write!(s, "\n    {},", field).expect("writing to a String can't fail");
+    }
+    if !fields.is_empty() {
+        s += "\n";
+    }
+    s += "])";
+    decls.begin_decl(name).push_str(&s);
+    decls.put_scratch(s);
+}
+
+/// The Spark type of the values at `path`, declaring the structs it
+/// uses first, and whether they can be null.
+fn data_type(typ: DataType, decls: &mut Decls, path: &str) -> (String, bool) {
+    let nullable = match &typ {
+        DataType::Null => true,
+        DataType::Variant(options) => options.contains(&DataType::Null),
+        _ => false,
+    };
+    if let Some(type_name) = decls.options.types.get(path) {
+        return (type_name.clone(), nullable);
+    }
+
+    let type_name = match typ {
+        DataType::Null => unknown(decls),
+        DataType::String | DataType::Temporal(Temporal::Time) => {
+            import(decls, "StringType");
+            "StringType()".to_string()
+        }
+        DataType::Temporal(Temporal::Date) => {
+            import(decls, "DateType");
+            "DateType()".to_string()
+        }
+        DataType::Temporal(Temporal::NaiveDateTime) => {
+            import(decls, "TimestampNTZType");
+            "TimestampNTZType()".to_string()
+        }
+        DataType::Temporal(Temporal::DateTime) => {
+            import(decls, "TimestampType");
+            "TimestampType()".to_string()
+        }
+        DataType::Int | DataType::IntEnum(_) => {
+            import(decls, "LongType");
+            "LongType()".to_string()
+        }
+        DataType::Float => {
+            import(decls, "DoubleType");
+            "DoubleType()".to_string()
+        }
+        DataType::Bool => {
+            import(decls, "BooleanType");
+            "BooleanType()".to_string()
+        }
+        DataType::Bytes => {
+            import(decls, "BinaryType");
+            "BinaryType()".to_string()
+        }
+        DataType::Object(members) => struct_type(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            import(decls, "ArrayType");
+            let (elem_type, elem_nullable) = data_type(*elem, decls, &format!("{}[]", path));
+            format!("ArrayType({}, {})", elem_type, python_bool(elem_nullable))
+        }
+        DataType::Map(values) => {
+            import(decls, "MapType");
+            import(decls, "StringType");
+            let (value_type, value_nullable) = data_type(*values, decls, &format!("{}.*", path));
+            format!(
+                "MapType(StringType(), {}, {})",
+                value_type,
+                python_bool(value_nullable)
+            )
+        }
+        DataType::Variant(mut options) => {
+            options.remove(&DataType::Null);
+            match options.len() {
+                0 => unknown(decls),
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    data_type(typ, decls, path).0
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    unknown(decls)
+                }
+            }
+        }
+    };
+    (type_name, nullable)
+}
+
+/// `value` as a Python literal.
+fn python_bool(value: bool) -> &'static str {
+    match value {
+        true => "True",
+        false => "False",
+    }
+}
+
+/// Import `name` from `pyspark.sql.types`.
+fn import(decls: &mut Decls, name: &str) {
+    decls.imports.insert(format!("pyspark.sql.types.{}", name));
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            import(decls, "StringType");
+            PYSPARK_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn struct_types() {
+        assert_eq!(
+            declare_as(
+                Lang::PySpark,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1, null], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": "b", "name": null, "tags": [], "owner": {"ok": false}, "content-type": 2},
+                ]
+            ),
+            "from pyspark.sql.types import ArrayType, BooleanType, DoubleType, LongType, \
+             StringType, StructField, StructType\n\n\n\
+             Data1 = StructType([\n    StructField(\"ok\", BooleanType(), False),\n])\n\n\
+             Data0 = StructType([\n    StructField(\"content-type\", DoubleType(), False),\n    \
+             StructField(\"id\", StringType(), False),\n    \
+             StructField(\"name\", StringType(), True),\n    \
+             StructField(\"owner\", Data1, False),\n    \
+             StructField(\"tags\", ArrayType(LongType(), True), False),\n])"
+        );
+    }
+
+    #[test]
+    fn maps_and_timestamps() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::PySpark,
+                    detect_dates: true,
+                    ..Options::default()
+                },
+                json::object! {"at": "2024-01-02T03:04:05Z", "by_id": {"1": 1, "2": 2}},
+            ),
+            "from pyspark.sql.types import LongType, MapType, StringType, StructField, StructType, \
+             TimestampType\n\n\nData0 = StructType([\n    \
+             StructField(\"at\", TimestampType(), False),\n    \
+             StructField(\"by_id\", MapType(StringType(), LongType(), False), False),\n])"
+        );
+    }
+}
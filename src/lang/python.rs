@@ -0,0 +1,330 @@
+//! Emission of Python dataclasses and Pydantic models.
+//!
+//! Classes are declared before the classes that use them, so their
+//! annotations can name them without being quoted. Members whose keys
+//! aren't identifiers are renamed: Pydantic models read them by the
+//! key with an alias, and dataclasses have a comment giving the key.
+//! Nullable members of models default to `None`, since Pydantic would
+//! otherwise require them to be present.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    lang::Lang,
+    options::NullFields,
+    types::{is_small_object, Decls},
+    warnings::WarningKind,
+    DataType,
+};
+
+/// Type emitted for values nothing is known about.
+const PYTHON_UNKNOWN_TYPE: &str = "Any";
+
+/// Text that goes before the classes: the imports they need, given as
+/// `module.name`, grouped by module.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut modules = BTreeMap::<&str, Vec<&str>>::new();
+    for import in imports {
+        if let Some((module, name)) = import.rsplit_once('.') {
+            modules.entry(module).or_default().push(name);
+        }
+    }
+    let mut s = String::new();
+    for (module, names) in &modules {
+        writeln!(s, "from {} import {}", module, names.join(", "))
+            .expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n\n";
+    }
+    s
+}
+
+/// Emit classes for a data type, returning the name of the top-level
+/// one. Types other than objects are given a name with an alias.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            class(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "#", &name, "$");
+            write!(s, "{} = {}", name, type_name).expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a dataclass or model with the given members.
+fn class(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let pydantic = decls.options.lang == Lang::Pydantic;
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "#", &name, path);
+    if pydantic {
+        decls.imports.insert("pydantic.BaseModel".to_string());
+        writeln!(s, "class {}(BaseModel):", name).expect("writing to a String can't fail");
+    } else {
+        decls.imports.insert("dataclasses.dataclass".to_string());
+        writeln!(s, "@dataclass\nclass {}:", name).expect("writing to a String can't fail");
+    }
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => {
+                let unknown = unknown(decls);
+                optional(decls, unknown)
+            }
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        write!(s, "    {}: {}", field, type_name).expect("writing to a String can't fail");
+        let nullable = type_name.starts_with("Optional[");
+        match (pydantic, field != member) {
+            (true, true) => {
+                decls.imports.insert("pydantic.Field".to_string());
+                let default = match nullable {
+                    true => "default=None, ",
+                    false => "",
+                };
+                write!(
+                    s,
+                    " = Field({}alias={})",
+                    default,
+                    json::stringify(member.as_str())
+                )
+                .expect("writing to a String can't fail");
+            }
+            (true, false) if nullable => s += " = None",
+            (false, true) => write!(s, "  # {} in JSON", json::stringify(member.as_str()))
+                .expect("writing to a String can't fail"),
+            _ => {}
+        }
+        s += "\n";
+        fields.push((member, type_name));
+    }
+    if fields.is_empty() {
+        s += "    pass\n";
+    }
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(s.trim_end());
+    decls.put_scratch(s);
+    name
+}
+
+/// The type of the values at `path`, declaring the classes it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "None".to_string(),
+        DataType::String | DataType::Temporal(_) => "str".to_string(),
+        DataType::Bytes => "bytes".to_string(),
+        DataType::Int => "int".to_string(),
+        DataType::IntEnum(codes) => {
+            decls.imports.insert("typing.Literal".to_string());
+            format!(
+                "Literal[{}]",
+                codes
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+        DataType::Float => "float".to_string(),
+        DataType::Bool => "bool".to_string(),
+        // Python has no inline classes, but the members all being
+        // scalars, a dict describes them well enough.
+        DataType::Object(members)
+            if decls.options.inline_small_types && is_small_object(&members) =>
+        {
+            let values = members
+                .into_iter()
+                .filter(|(member, _)| !decls.omits(path, member))
+                .fold(DataType::unknown(), |values, (_, typ)| values.unify(typ));
+            let value_name = type_expr(values, decls, &format!("{}.*", path));
+            format!("dict[str, {}]", value_name)
+        }
+        DataType::Object(members) => class(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("list[{}]", elem_name)
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("dict[str, {}]", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                0 => "None".to_string(),
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    decls.imports.insert("typing.Union".to_string());
+                    let arms = options
+                        .into_iter()
+                        .map(|typ| type_expr(typ, decls, path))
+                        .collect::<Vec<_>>();
+                    format!("Union[{}]", arms.join(", "))
+                }
+            };
+            match nullable {
+                true => optional(decls, type_name),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The type of values that are either of type `type_name` or null.
+fn optional(decls: &mut Decls, type_name: String) -> String {
+    decls.imports.insert("typing.Optional".to_string());
+    format!("Optional[{}]", type_name)
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert("typing.Any".to_string());
+            PYTHON_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn dataclasses() {
+        assert_eq!(
+            declare_as(
+                Lang::Python,
+                json::array![
+                    {"id": 1, "name": "a", "tags": ["x"], "owner": {}, "content-type": 1.5, "class": true},
+                    {"id": "b", "name": null, "tags": [1], "owner": {}, "content-type": 2, "class": false},
+                ],
+            ),
+            "from dataclasses import dataclass\n\
+             from typing import Optional, Union\n\n\n\
+             @dataclass\nclass Data2:\n    pass\n\n\
+             @dataclass\nclass Data1:\n    class_: bool  # \"class\" in JSON\n    \
+             content_type: float  # \"content-type\" in JSON\n    id: Union[str, int]\n    \
+             name: Optional[str]\n    owner: Data2\n    tags: list[Union[str, int]]\n\n\
+             Data0 = list[Data1]"
+        );
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Python,
+                    int_enum_threshold: Some(4),
+                    ..Options::default()
+                },
+                json::array![{"status": 1, "lambda": "a"}, {"status": 2, "lambda": "b"}],
+            ),
+            "from dataclasses import dataclass\nfrom typing import Literal\n\n\n@dataclass\n\
+             class Data1:\n    lambda_: str  # \"lambda\" in JSON\n    status: Literal[1, 2]\n\n\
+             Data0 = list[Data1]"
+        );
+    }
+
+    #[test]
+    fn pydantic_models() {
+        assert_eq!(
+            declare_as(
+                Lang::Pydantic,
+                json::array![
+                    {"id": 1, "name": "a", "owner": {}, "content-type": 1.5, "from": "x"},
+                    {"id": 2, "name": null, "owner": {}, "content-type": 2, "from": null},
+                ],
+            ),
+            "from pydantic import BaseModel, Field\n\
+             from typing import Optional\n\n\n\
+             class Data2(BaseModel):\n    pass\n\n\
+             class Data1(BaseModel):\n    \
+             content_type: float = Field(alias=\"content-type\")\n    \
+             from_: Optional[str] = Field(default=None, alias=\"from\")\n    \
+             id: int\n    name: Optional[str] = None\n    owner: Data2\n\n\
+             Data0 = list[Data1]"
+        );
+    }
+
+    #[test]
+    fn inline_small_types() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Python,
+                    inline_small_types: true,
+                    ..Options::default()
+                },
+                json::object! {
+                    "coords": {"lat": 1.5, "lon": 2},
+                    "label": {"text": "a", "lang": null},
+                    "owner": {"id": 1, "name": "b", "email": "c", "admin": true},
+                },
+            ),
+            "from dataclasses import dataclass\nfrom typing import Optional\n\n\n\
+             @dataclass\nclass Data1:\n    admin: bool\n    email: str\n    id: int\n    \
+             name: str\n\n\
+             @dataclass\nclass Data0:\n    coords: dict[str, float]\n    \
+             label: dict[str, Optional[str]]\n    owner: Data1"
+        );
+    }
+}
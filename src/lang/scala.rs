@@ -0,0 +1,269 @@
+//! Emission of Scala case classes, with codecs derived by circe, or by
+//! play-json.
+//!
+//! Each case class has a companion object holding its derived codecs.
+//! Both libraries name keys after the fields, so keys that aren't
+//! identifiers are kept as fields in backticks rather than renamed.
+//! Scala 2 has no unions, so values of several kinds are kept as JSON.
+//! Types other than objects are named with a top-level type alias, as
+//! Scala 3 allows.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Text that goes before the declarations: the classes they import.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    for import in imports {
+        writeln!(s, "import {}", import).expect("writing to a String can't fail");
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+/// Emit declarations for a data type, returning the name of the
+/// top-level one. Types other than objects are given a name with a type
+/// alias.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            case_class(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            write!(s, "type {} = {}", name, type_name).expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a case class with the given members, and its companion object.
+fn case_class(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => format!("Option[{}]", unknown(decls)),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        // Keys that can't go in backticks have to be renamed, and won't
+        // be read.
+        let field = match field != member && !member.is_empty() && !member.contains(['`', '\n']) {
+            true => format!("`{}`", member),
+            false => field,
+        };
+        if !body.is_empty() {
+            body += ",\n";
+        }
+        write!(body, "  {}: {}", field, type_name).expect("writing to a String can't fail");
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    match body.is_empty() {
+        true => write!(s, "case class {}()\n\n", name),
+        false => write!(s, "case class {}(\n{}\n)\n\n", name, body),
+    }
+    .expect("writing to a String can't fail");
+    match decls.options.scala_play_json {
+        true => {
+            decls
+                .imports
+                .insert("play.api.libs.json.{Json, OFormat}".to_string());
+            write!(
+                s,
+                "object {name} {{\n  \
+                 implicit val format: OFormat[{name}] = Json.format[{name}]\n}}",
+                name = name
+            )
+        }
+        false => {
+            decls
+                .imports
+                .insert("io.circe.{Decoder, Encoder}".to_string());
+            decls
+                .imports
+                .insert("io.circe.generic.semiauto.{deriveDecoder, deriveEncoder}".to_string());
+            write!(
+                s,
+                "object {name} {{\n  \
+                 implicit val decoder: Decoder[{name}] = deriveDecoder\n  \
+                 implicit val encoder: Encoder[{name}] = deriveEncoder\n}}",
+                name = name
+            )
+        }
+    }
+    .expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the classes it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => format!("Option[{}]", unknown(decls)),
+        DataType::String | DataType::Temporal(_) => "String".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "Long".to_string(),
+        DataType::Float => "Double".to_string(),
+        DataType::Bool => "Boolean".to_string(),
+        DataType::Bytes => "Array[Byte]".to_string(),
+        DataType::Object(members) => case_class(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("List[{}]", elem_name)
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("Map[String, {}]", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    unknown(decls)
+                }
+            };
+            match nullable && !type_name.starts_with("Option[") {
+                true => format!("Option[{}]", type_name),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The type to emit for unknown values: circe's `Json`, or play-json's
+/// `JsValue`.
+fn unknown(decls: &mut Decls) -> String {
+    if let Some(unknown) = &decls.options.unknown_type {
+        return unknown.clone();
+    }
+    let (import, type_name) = match decls.options.scala_play_json {
+        true => ("play.api.libs.json.JsValue", "JsValue"),
+        false => ("io.circe.Json", "Json"),
+    };
+    decls.imports.insert(import.to_string());
+    type_name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn circe() {
+        assert_eq!(
+            declare_as(
+                Lang::Scala,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+                    {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+                ]
+            ),
+            "import io.circe.Json\n\
+             import io.circe.generic.semiauto.{deriveDecoder, deriveEncoder}\n\
+             import io.circe.{Decoder, Encoder}\n\n\
+             case class Data2()\n\n\
+             object Data2 {\n  \
+             implicit val decoder: Decoder[Data2] = deriveDecoder\n  \
+             implicit val encoder: Encoder[Data2] = deriveEncoder\n}\n\n\
+             case class Data1(\n  `content-type`: Double,\n  id: Long,\n  \
+             name: Option[String],\n  owner: Data2,\n  tags: List[Long],\n  x: Json\n)\n\n\
+             object Data1 {\n  \
+             implicit val decoder: Decoder[Data1] = deriveDecoder\n  \
+             implicit val encoder: Encoder[Data1] = deriveEncoder\n}\n\n\
+             type Data0 = List[Data1]"
+        );
+    }
+
+    #[test]
+    fn play_json() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Scala,
+                    scala_play_json: true,
+                    ..Options::default()
+                },
+                json::object! {"type": {"1": null, "2": 1}}
+            ),
+            "import play.api.libs.json.{Json, OFormat}\n\n\
+             case class Data0(\n  `type`: Map[String, Option[Long]]\n)\n\n\
+             object Data0 {\n  \
+             implicit val format: OFormat[Data0] = Json.format[Data0]\n}"
+        );
+    }
+
+    #[test]
+    fn nulls() {
+        assert_eq!(
+            declare_as(Lang::Scala, json::object! {"gone": null, "n": [null, 1]}),
+            "import io.circe.Json\n\
+             import io.circe.generic.semiauto.{deriveDecoder, deriveEncoder}\n\
+             import io.circe.{Decoder, Encoder}\n\ncase class Data0(\n  gone: Option[Json],\n  \
+             n: List[Option[Long]]\n)\n\nobject Data0 {\n  \
+             implicit val decoder: Decoder[Data0] = deriveDecoder\n  \
+             implicit val encoder: Encoder[Data0] = deriveEncoder\n}"
+        );
+    }
+}
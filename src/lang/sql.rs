@@ -0,0 +1,366 @@
+//! Emission of SQL `CREATE TABLE` statements.
+//!
+//! The top-level object, or each object of a top-level array, is a row
+//! of a table, and every table has a surrogate primary key. Nested
+//! objects are flattened into columns prefixed with their field's name,
+//! or with `--sql-child-tables` are rows of tables of their own, which
+//! the column refers to. Arrays of objects are always tables of their
+//! own, whose rows refer to the row they're in. Everything else that
+//! isn't a scalar is a `JSON` column.
+//!
+//! Types are PostgreSQL's, and names that are reserved words are
+//! quoted.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    namer::{Case, SQL_KEYWORDS},
+    options::NullFields,
+    temporal::Temporal,
+    types::Decls,
+    warnings::WarningKind,
+    DataType,
+};
+
+/// Type emitted for values nothing is known about, and for values that
+/// can't be columns of their own.
+const SQL_UNKNOWN_TYPE: &str = "JSON";
+
+/// Emit tables for a data type, returning the name of the top-level
+/// one. Types other than objects and arrays of them are a table with a
+/// single column named `value`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    let types = &decls.options.types;
+    match typ {
+        DataType::Object(members) if !types.contains_key("$") => table(members, decls, "$", None).0,
+        DataType::Array(elem) | DataType::FixedArray(elem, _)
+            if matches!(*elem, DataType::Object(_))
+                && !types.contains_key("$")
+                && !types.contains_key("$[]") =>
+        {
+            match *elem {
+                DataType::Object(members) => table(members, decls, "$[]", None).0,
+                _ => unreachable!("checked above"),
+            }
+        }
+        typ => value_table(typ, decls),
+    }
+}
+
+/// Emit a table with a single column holding the top-level value.
+fn value_table(typ: DataType, decls: &mut Decls) -> String {
+    let name = decls.name_at("$");
+    let mut columns = Vec::new();
+    let mut children = Vec::new();
+    column(
+        &mut Tables {
+            columns: &mut columns,
+            children: &mut children,
+        },
+        decls,
+        "$",
+        "value",
+        typ,
+        false,
+    );
+    create_table(decls, "$", &name, &columns);
+    name
+}
+
+/// Where the columns of a table being emitted, and the tables for its
+/// arrays of objects, go.
+struct Tables<'a> {
+    columns: &'a mut Vec<String>,
+    children: &'a mut Vec<(String, BTreeMap<String, DataType>)>,
+}
+
+/// Emit a table with the given members, and the tables for the arrays
+/// of objects in them, returning its name and the name of its primary
+/// key. Rows of the table belong to rows of `parent`, given with the
+/// name of its key, if there is one.
+fn table(
+    members: BTreeMap<String, DataType>,
+    decls: &mut Decls,
+    path: &str,
+    parent: Option<(&str, &str)>,
+) -> (String, &'static str) {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let key = match field_names.iter().any(|field| field == "id") {
+        true => "row_id",
+        false => "id",
+    };
+
+    let mut columns = vec![format!("{} BIGINT PRIMARY KEY", key)];
+    if let Some((parent, parent_key)) = parent {
+        columns.push(format!(
+            "{} BIGINT NOT NULL REFERENCES {} ({})",
+            quote(&format!("{}_id", Case::Snake.apply(parent, true))),
+            quote(parent),
+            parent_key
+        ));
+    }
+    let mut children = Vec::new();
+    let mut fields = Vec::new();
+    for ((member, typ), field) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let start = columns.len();
+        column(
+            &mut Tables {
+                columns: &mut columns,
+                children: &mut children,
+            },
+            decls,
+            &member_path,
+            &field,
+            typ,
+            false,
+        );
+        if columns.len() > start {
+            fields.push((member, columns[start..].join(", ")));
+        }
+    }
+    decls.declared[declared].fields = fields;
+    create_table(decls, path, &name, &columns);
+
+    // Rows of child tables refer to this table's, so come after it.
+    for (child_path, child_members) in children {
+        table(child_members, decls, &child_path, Some((&name, key)));
+    }
+    (name, key)
+}
+
+/// Declare the table for the values at `path`, given its columns.
+fn create_table(decls: &mut Decls, path: &str, name: &str, columns: &[String]) {
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "--", name, path);
+    write!(
+        s,
+        "CREATE TABLE {} (\n  {}\n);",
+        quote(name),
+        columns.join(",\n  ")
+    )
+    .expect("writing to a String can't fail");
+    decls.begin_decl(name).push_str(&s);
+    decls.put_scratch(s);
+}
+
+/// Add the column or columns for a value at `path` to `tables`, with
+/// the given name, or the tables it needs if it's an array of objects.
+/// Columns of a value that can be null can be null too.
+fn column(
+    tables: &mut Tables,
+    decls: &mut Decls,
+    path: &str,
+    name: &str,
+    typ: DataType,
+    nullable: bool,
+) {
+    let not_null = match nullable {
+        true => "",
+        false => " NOT NULL",
+    };
+    if let Some(type_name) = decls.options.types.get(path) {
+        let column = format!("{} {}{}", quote(name), type_name, not_null);
+        tables.columns.push(column);
+        return;
+    }
+
+    let type_name = match typ {
+        DataType::Null if decls.options.null_fields == NullFields::Drop => {
+            decls.warnings.push(path, WarningKind::NullFieldDropped, "");
+            return;
+        }
+        DataType::Null => {
+            let column = format!("{} {}", quote(name), unknown(decls));
+            tables.columns.push(column);
+            return;
+        }
+        DataType::Variant(mut options) if !options.is_empty() => {
+            let nullable = options.remove(&DataType::Null) || nullable;
+            if options.len() == 1 {
+                let typ = options.into_iter().next().expect("checked above");
+                column(tables, decls, path, name, typ, nullable);
+                return;
+            }
+            decls.warnings.push(
+                path,
+                WarningKind::EnumFallback,
+                options
+                    .iter()
+                    .map(DataType::kind_name)
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            );
+            let column = match nullable {
+                true => format!("{} {}", quote(name), unknown(decls)),
+                false => format!("{} {} NOT NULL", quote(name), unknown(decls)),
+            };
+            tables.columns.push(column);
+            return;
+        }
+        DataType::Object(members) if decls.options.sql_child_tables => {
+            let (child, key) = table(members, decls, path, None);
+            let column = format!(
+                "{} BIGINT{} REFERENCES {} ({})",
+                quote(&format!("{}_id", name)),
+                not_null,
+                quote(&child),
+                key
+            );
+            tables.columns.push(column);
+            return;
+        }
+        DataType::Object(members) => {
+            let members = members
+                .into_iter()
+                .filter(|(member, _)| !decls.omits(path, member))
+                .collect::<Vec<_>>();
+            let keys = members
+                .iter()
+                .map(|(member, _)| member.as_str())
+                .collect::<Vec<_>>();
+            let field_names = decls.namer.fields(&keys);
+            for ((member, typ), field) in members.into_iter().zip(field_names) {
+                let member_path = format!("{}.{}", path, member);
+                let prefixed = format!("{}_{}", name, field);
+                column(tables, decls, &member_path, &prefixed, typ, nullable);
+            }
+            return;
+        }
+        DataType::Array(elem) | DataType::FixedArray(elem, _)
+            if matches!(*elem, DataType::Object(_)) =>
+        {
+            let elem_path = format!("{}[]", path);
+            match *elem {
+                DataType::Object(members) if !decls.options.types.contains_key(&elem_path) => {
+                    tables.children.push((elem_path, members));
+                    return;
+                }
+                _ => unknown(decls),
+            }
+        }
+        DataType::String => "TEXT".to_string(),
+        DataType::Temporal(Temporal::Date) => "DATE".to_string(),
+        DataType::Temporal(Temporal::Time) => "TIME".to_string(),
+        DataType::Temporal(Temporal::NaiveDateTime) => "TIMESTAMP".to_string(),
+        DataType::Temporal(Temporal::DateTime) => "TIMESTAMP WITH TIME ZONE".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "BIGINT".to_string(),
+        DataType::Float => "DOUBLE PRECISION".to_string(),
+        DataType::Bool => "BOOLEAN".to_string(),
+        DataType::Bytes => "BYTEA".to_string(),
+        DataType::Array(_) | DataType::FixedArray(..) | DataType::Map(_) | DataType::Variant(_) => {
+            unknown(decls)
+        }
+    };
+    let column = format!("{} {}{}", quote(name), type_name, not_null);
+    tables.columns.push(column);
+}
+
+/// `name` as it's written in a statement: in double quotes if it's a
+/// reserved word, in any case.
+fn quote(name: &str) -> String {
+    match SQL_KEYWORDS.contains(&name.to_ascii_lowercase().as_str()) {
+        true => format!("\"{}\"", name),
+        false => name.to_string(),
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(SQL_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_type, declare_with},
+            Lang,
+        },
+        DataType, Options,
+    };
+
+    fn sample() -> json::JsonValue {
+        json::array![
+            {"id": 1, "name": "a", "tags": [1], "owner": {"ok": true}, "items": [{"n": 1.5}]},
+            {"id": 2, "name": null, "tags": [], "owner": null, "items": []},
+        ]
+    }
+
+    #[test]
+    fn flattened() {
+        assert_eq!(
+            declare_as(Lang::Sql, sample()),
+            "CREATE TABLE Data0 (\n  row_id BIGINT PRIMARY KEY,\n  id BIGINT NOT NULL,\n  \
+             name TEXT,\n  owner_ok BOOLEAN,\n  tags JSON NOT NULL\n);\n\n\
+             CREATE TABLE Data1 (\n  id BIGINT PRIMARY KEY,\n  \
+             data0_id BIGINT NOT NULL REFERENCES Data0 (row_id),\n  n DOUBLE PRECISION NOT NULL\n);"
+        );
+    }
+
+    #[test]
+    fn child_tables() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Sql,
+                    sql_child_tables: true,
+                    ..Options::default()
+                },
+                sample()
+            ),
+            "CREATE TABLE Data1 (\n  id BIGINT PRIMARY KEY,\n  ok BOOLEAN NOT NULL\n);\n\n\
+             CREATE TABLE Data0 (\n  row_id BIGINT PRIMARY KEY,\n  id BIGINT NOT NULL,\n  \
+             name TEXT,\n  owner_id BIGINT REFERENCES Data1 (id),\n  tags JSON NOT NULL\n);\n\n\
+             CREATE TABLE Data2 (\n  id BIGINT PRIMARY KEY,\n  \
+             data0_id BIGINT NOT NULL REFERENCES Data0 (row_id),\n  n DOUBLE PRECISION NOT NULL\n);"
+        );
+    }
+
+    #[test]
+    fn reserved_words() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Sql,
+                    sql_child_tables: true,
+                    ..Options::default()
+                },
+                json::object! {"select": "a", "order": {"group": 1}, "user": [{"by": true}]}
+            ),
+            "CREATE TABLE Data1 (\n  id BIGINT PRIMARY KEY,\n  \"group\" BIGINT NOT NULL\n);\n\n\
+             CREATE TABLE Data0 (\n  id BIGINT PRIMARY KEY,\n  \
+             order_id BIGINT NOT NULL REFERENCES Data1 (id),\n  \"select\" TEXT NOT NULL\n);\n\n\
+             CREATE TABLE Data2 (\n  id BIGINT PRIMARY KEY,\n  \
+             data0_id BIGINT NOT NULL REFERENCES Data0 (id),\n  \"by\" BOOLEAN NOT NULL\n);"
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(
+            declare_type(
+                Options {
+                    lang: Lang::Sql,
+                    ..Options::default()
+                },
+                DataType::Object(
+                    vec![("blob".to_string(), DataType::Bytes)]
+                        .into_iter()
+                        .collect()
+                ),
+            ),
+            "CREATE TABLE Data0 (\n  id BIGINT PRIMARY KEY,\n  blob BYTEA NOT NULL\n);"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Emission of Swift structs conforming to `Codable`.
+//!
+//! Properties are named in camel case, with `CodingKeys` giving the keys
+//! of structs with any renamed members. Values of several kinds are
+//! enums with an associated value for each kind, and values nothing is
+//! known about are `JSONValue`s, an enum declared in the header that
+//! holds any JSON.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const SWIFT_UNKNOWN_TYPE: &str = "JSONValue";
+
+/// The declaration of `SWIFT_UNKNOWN_TYPE`.
+const JSON_VALUE: &str = "indirect enum JSONValue: Codable {
+    case null
+    case bool(Bool)
+    case number(Double)
+    case string(String)
+    case array([JSONValue])
+    case object([String: JSONValue])
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.singleValueContainer()
+        if container.decodeNil() {
+            self = .null
+        } else if let value = try? container.decode(Bool.self) {
+            self = .bool(value)
+        } else if let value = try? container.decode(Double.self) {
+            self = .number(value)
+        } else if let value = try? container.decode(String.self) {
+            self = .string(value)
+        } else if let value = try? container.decode([JSONValue].self) {
+            self = .array(value)
+        } else {
+            self = .object(try container.decode([String: JSONValue].self))
+        }
+    }
+
+    func encode(to encoder: Encoder) throws {
+        var container = encoder.singleValueContainer()
+        switch self {
+        case .null: try container.encodeNil()
+        case .bool(let value): try container.encode(value)
+        case .number(let value): try container.encode(value)
+        case .string(let value): try container.encode(value)
+        case .array(let value): try container.encode(value)
+        case .object(let value): try container.encode(value)
+        }
+    }
+}
+";
+
+/// Text that goes before the declarations: the import of Foundation
+/// and the declaration of `JSONValue`, if they use them.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    let mut s = String::new();
+    if imports.contains("Foundation") {
+        s += "import Foundation\n\n";
+    }
+    if imports.contains(SWIFT_UNKNOWN_TYPE) {
+        s += JSON_VALUE;
+        s += "\n";
+    }
+    s
+}
+
+/// Emit declarations for a data type, returning the name of the
+/// top-level one. Types other than objects are given a name with a type
+/// alias.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "///", &name, "$");
+            write!(s, "{}typealias {} = {}", visibility(decls), name, type_name)
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a struct with the given members.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let property_names = decls.namer.fields(
+        &members
+            .iter()
+            .map(|(member, _)| member.as_str())
+            .collect::<Vec<_>>(),
+    );
+    let visibility = visibility(decls);
+    let mut fields = Vec::new();
+    let mut keys = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), property) in members.into_iter().zip(property_names) {
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, _) => format!("{}?", unknown(decls)),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        writeln!(body, "    {}let {}: {}", visibility, property, type_name)
+            .expect("writing to a String can't fail");
+        keys.push((property, member.clone()));
+        fields.push((member, type_name));
+    }
+    if keys.iter().any(|(property, member)| property != member) {
+        body += "\n    enum CodingKeys: String, CodingKey {\n";
+        for (property, member) in &keys {
+            match property == member {
+                true => writeln!(body, "        case {}", property),
+                false => writeln!(
+                    body,
+                    "        case {} = {}",
+                    property,
+                    json::stringify(member.as_str())
+                ),
+            }
+            .expect("writing to a String can't fail");
+        }
+        body += "    }\n";
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "///", &name, path);
+    write!(s, "{}struct {}: Codable {{\n{}}}", visibility, name, body)
+        .expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// Emit an enum with a case for each kind of value in `options`,
+/// decoded by trying each kind in turn.
+fn one_of(options: BTreeSet<DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.next_name();
+    decls.warnings.push(
+        path,
+        WarningKind::EnumFallback,
+        options
+            .iter()
+            .map(DataType::kind_name)
+            .collect::<Vec<_>>()
+            .join(" | "),
+    );
+
+    let arms = options
+        .into_iter()
+        .map(|typ| {
+            let case = decls.namer.style().unkeyword(typ.kind_name().to_string());
+            (case, type_expr(typ, decls, path))
+        })
+        .collect::<Vec<_>>();
+    let visibility = visibility(decls);
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "///", &name, path);
+    writeln!(s, "{}enum {}: Codable {{", visibility, name).expect("writing to a String can't fail");
+    for (case, type_name) in &arms {
+        writeln!(s, "    case {}({})", case, type_name).expect("writing to a String can't fail");
+    }
+    writeln!(
+        s,
+        "\n    {}init(from decoder: Decoder) throws {{\n        \
+         let container = try decoder.singleValueContainer()",
+        visibility
+    )
+    .expect("writing to a String can't fail");
+    for (case, type_name) in &arms {
+        writeln!(
+            s,
+            "        if let value = try? container.decode({}.self) {{\n            \
+             self = .{}(value)\n            return\n        }}",
+            type_name, case
+        )
+        .expect("writing to a String can't fail");
+    }
+    writeln!(
+        s,
+        "        throw DecodingError.typeMismatch({}.self, DecodingError.Context(\n            \
+         codingPath: decoder.codingPath,\n            \
+         debugDescription: \"expected one of {}\"))\n    }}",
+        name,
+        arms.iter()
+            .map(|(case, _)| case.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .expect("writing to a String can't fail");
+    writeln!(
+        s,
+        "\n    {}func encode(to encoder: Encoder) throws {{\n        \
+         var container = encoder.singleValueContainer()\n        switch self {{",
+        visibility
+    )
+    .expect("writing to a String can't fail");
+    for (case, _) in &arms {
+        writeln!(
+            s,
+            "        case .{}(let value): try container.encode(value)",
+            case
+        )
+        .expect("writing to a String can't fail");
+    }
+    s += "        }\n    }\n}";
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The type of the values at `path`, declaring the types it uses first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => format!("{}?", unknown(decls)),
+        DataType::String | DataType::Temporal(_) => "String".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "Int".to_string(),
+        DataType::Float => "Double".to_string(),
+        DataType::Bool => "Bool".to_string(),
+        DataType::Bytes => {
+            decls.imports.insert("Foundation".to_string());
+            "Data".to_string()
+        }
+        DataType::Object(members) => object(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("[{}]", elem_name)
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("[String: {}]", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => one_of(options, decls, path),
+            };
+            match nullable && !type_name.ends_with('?') {
+                true => format!("{}?", type_name),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// The modifier to declare types and their members with, if they're
+/// public.
+fn visibility(decls: &Decls) -> &'static str {
+    match decls.options.pub_types {
+        true => "public ",
+        false => "",
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert(SWIFT_UNKNOWN_TYPE.to_string());
+            SWIFT_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_type},
+            Lang,
+        },
+        DataType, Options,
+    };
+
+    #[test]
+    fn codable_structs() {
+        assert_eq!(
+            declare_as(
+                Lang::Swift,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": 2, "name": null, "tags": [2], "owner": {"ok": false}, "content-type": 2},
+                ]
+            ),
+            "struct Data2: Codable {\n    let ok: Bool\n}\n\n\
+             struct Data1: Codable {\n    let contentType: Double\n    let id: Int\n    \
+             let name: String?\n    let owner: Data2\n    let tags: [Int]\n\n    \
+             enum CodingKeys: String, CodingKey {\n        \
+             case contentType = \"content-type\"\n        case id\n        case name\n        \
+             case owner\n        case tags\n    }\n}\n\n\
+             typealias Data0 = [Data1]"
+        );
+    }
+
+    #[test]
+    fn enums() {
+        let output = declare_as(
+            Lang::Swift,
+            json::object! {"value": [1, "a", null], "empty": []},
+        );
+        assert!(output.starts_with("indirect enum JSONValue: Codable {\n"));
+        assert!(output.ends_with(
+            "enum Data1: Codable {\n    case string(String)\n    case int(Int)\n\n    \
+             init(from decoder: Decoder) throws {\n        \
+             let container = try decoder.singleValueContainer()\n        \
+             if let value = try? container.decode(String.self) {\n            \
+             self = .string(value)\n            return\n        }\n        \
+             if let value = try? container.decode(Int.self) {\n            \
+             self = .int(value)\n            return\n        }\n        \
+             throw DecodingError.typeMismatch(Data1.self, DecodingError.Context(\n            \
+             codingPath: decoder.codingPath,\n            \
+             debugDescription: \"expected one of string, int\"))\n    }\n\n    \
+             func encode(to encoder: Encoder) throws {\n        \
+             var container = encoder.singleValueContainer()\n        switch self {\n        \
+             case .string(let value): try container.encode(value)\n        \
+             case .int(let value): try container.encode(value)\n        }\n    }\n}\n\n\
+             struct Data0: Codable {\n    let empty: [JSONValue]\n    let value: [Data1?]\n}"
+        ));
+    }
+
+    #[test]
+    fn data_imports_foundation() {
+        assert_eq!(
+            declare_type(
+                Options {
+                    lang: Lang::Swift,
+                    ..Options::default()
+                },
+                DataType::Object(
+                    vec![("blob".to_string(), DataType::Bytes)]
+                        .into_iter()
+                        .collect()
+                ),
+            ),
+            "import Foundation\n\nstruct Data0: Codable {\n    let blob: Data\n}"
+        );
+    }
+}
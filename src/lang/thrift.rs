@@ -0,0 +1,207 @@
+//! Emission of Thrift IDL.
+//!
+//! Field IDs use the same numbering as proto field numbers, so they
+//! follow the fields' names and stay stable with a schema state file.
+//! Nullable fields are `optional`, and values of several kinds are
+//! unions with a field for each kind. Thrift has no type for values
+//! nothing is known about, so they're kept as strings of JSON.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const THRIFT_UNKNOWN_TYPE: &str = "string";
+
+/// Emit structs for a data type, returning the name of the top-level
+/// one. Types other than objects are given a name with a `typedef`.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            structure(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let (type_name, _) = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            write!(s, "typedef {} {}", type_name, name).expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a struct with the given members.
+fn structure(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut numbers = decls.field_numbers(path);
+    let members = members
+        .into_iter()
+        .filter(|(member, _)| !decls.omits(path, member))
+        .collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|(member, _)| member.as_str())
+        .collect::<Vec<_>>();
+    let field_names = decls.namer.fields(&keys);
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for ((member, typ), field_name) in members.into_iter().zip(field_names) {
+        let member_path = format!("{}.{}", path, member);
+        let (type_name, optional) = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                (decls.options.types[&member_path].clone(), false)
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        if field_name != member {
+            writeln!(body, "  // {} in JSON", json::stringify(member.as_str()))
+                .expect("writing to a String can't fail");
+        }
+        let start = body.len();
+        writeln!(
+            body,
+            "  {}: {}{} {},",
+            numbers.number(&field_name),
+            if optional { "optional " } else { "" },
+            type_name,
+            field_name
+        )
+        .expect("writing to a String can't fail");
+        fields.push((member, body[start..].trim().to_string()));
+    }
+    decls.declared[declared].fields = fields;
+    // Thrift doesn't mind gaps, so retired IDs just aren't used again.
+    decls.finish_field_numbers(path, numbers);
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(s, "struct {} {{\n{}}}", name, body).expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// Emit a union with a field for each of `options`.
+fn union(options: BTreeSet<DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.next_name();
+    decls.warnings.push(
+        path,
+        WarningKind::EnumFallback,
+        options
+            .iter()
+            .map(DataType::kind_name)
+            .collect::<Vec<_>>()
+            .join(" | "),
+    );
+    let mut body = decls.take_scratch();
+    // Objects in the union share its path, so its fields are numbered
+    // in order rather than by the schema state.
+    for (number, typ) in (1..).zip(options) {
+        let field_name = format!("{}_value", typ.kind_name());
+        let (type_name, _) = type_expr(typ, decls, path);
+        writeln!(body, "  {}: {} {},", number, type_name, field_name)
+            .expect("writing to a String can't fail");
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    write!(s, "union {} {{\n{}}}", name, body).expect("writing to a String can't fail");
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the structs and unions
+/// it uses first, and whether they can be null.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> (String, bool) {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return (type_name.clone(), false);
+    }
+
+    let type_name = match typ {
+        DataType::Null => return (unknown(decls), true),
+        DataType::String | DataType::Temporal(_) => "string".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "i64".to_string(),
+        DataType::Float => "double".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Bytes => "binary".to_string(),
+        DataType::Object(members) => structure(members, decls, path),
+        DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+            let (elem_name, _) = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("list<{}>", elem_name)
+        }
+        DataType::Map(values) => {
+            let (value_name, _) = type_expr(*values, decls, &format!("{}.*", path));
+            format!("map<string, {}>", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path).0
+                }
+                _ => union(options, decls, path),
+            };
+            return (type_name, nullable);
+        }
+    };
+    (type_name, false)
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(THRIFT_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{tests::declare_as, Lang};
+
+    #[test]
+    fn structs_and_unions() {
+        assert_eq!(
+            declare_as(
+                Lang::Thrift,
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+                    {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+                ]
+            ),
+            "struct Data2 {\n}\n\n\
+             union Data3 {\n  1: string string_value,\n  2: i64 int_value,\n}\n\n\
+             struct Data1 {\n  // \"content-type\" in JSON\n  1: double content_type,\n  \
+             2: i64 id,\n  3: optional string name,\n  4: Data2 owner,\n  \
+             5: list<i64> tags,\n  6: Data3 x,\n}\n\n\
+             typedef list<Data1> Data0"
+        );
+    }
+
+    #[test]
+    fn reserved_words() {
+        assert_eq!(
+            declare_as(
+                Lang::Thrift,
+                json::object! {"class": true, "def": 1, "import": "x"}
+            ),
+            "struct Data0 {\n  // \"class\" in JSON\n  1: bool class_,\n  // \"def\" in JSON\n  \
+             2: i64 def_,\n  // \"import\" in JSON\n  3: string import_,\n}"
+        );
+    }
+}
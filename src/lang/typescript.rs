@@ -0,0 +1,327 @@
+//! Emission of TypeScript interfaces and type aliases.
+//!
+//! TypeScript can describe JSON as it is, so members keep their keys,
+//! quoted if they aren't identifiers, and values of several kinds are
+//! given a union type rather than a declaration of their own. Members
+//! that can be null are optional, since they can also be missing.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    options::NullFields,
+    types::{is_small_object, Decls},
+    warnings::WarningKind,
+    DataType,
+};
+
+/// Type emitted for values nothing is known about.
+const TS_UNKNOWN_TYPE: &str = "unknown";
+
+/// Emit declarations for a data type, returning the name of the
+/// top-level one. Types other than objects are given a name with a type
+/// alias.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            interface(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            writeln!(s, "{}type {} = {};", export(decls), name, type_name)
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(s.trim_end());
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit an interface with the given members.
+fn interface(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    writeln!(s, "{}interface {} {{", export(decls), name).expect("writing to a String can't fail");
+    let mut fields = Vec::new();
+    for (member, type_name, optional) in members_of(members, decls, path) {
+        writeln!(s, "  {}: {};", key(decls, &member, optional), type_name)
+            .expect("writing to a String can't fail");
+        fields.push((member, type_name));
+    }
+    s += "}";
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    name
+}
+
+/// The type of objects with the given members, written inline.
+fn inline_interface(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let fields = members_of(members, decls, path)
+        .into_iter()
+        .map(|(member, type_name, optional)| {
+            format!("{}: {}", key(decls, &member, optional), type_name)
+        })
+        .collect::<Vec<_>>();
+    format!("{{ {} }}", fields.join("; "))
+}
+
+/// The members of objects at `path` that aren't left out, each with the
+/// type of its values and whether it can be missing.
+fn members_of(
+    members: BTreeMap<String, DataType>,
+    decls: &mut Decls,
+    path: &str,
+) -> Vec<(String, String, bool)> {
+    let mut fields = Vec::new();
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        // Members that are null in some documents are missing from
+        // others.
+        let optional = match &typ {
+            DataType::Null => true,
+            DataType::Variant(options) => options.contains(&DataType::Null),
+            _ => false,
+        };
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => unknown(decls),
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        fields.push((member, type_name, optional));
+    }
+    fields
+}
+
+/// How `member` is written as the key of a property, quoted if it
+/// isn't an identifier, and marked if it's `optional`.
+fn key(decls: &Decls, member: &str, optional: bool) -> String {
+    let key = match decls.namer.style().is_identifier(member) {
+        true => member.to_string(),
+        false => json::stringify(member),
+    };
+    match optional {
+        true => key + "?",
+        false => key,
+    }
+}
+
+/// The type of the values at `path`, declaring the interfaces it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "null".to_string(),
+        // Bytes are in base64 in JSON.
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => "string".to_string(),
+        DataType::Int | DataType::Float => "number".to_string(),
+        DataType::IntEnum(codes) => codes
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        DataType::Bool => "boolean".to_string(),
+        DataType::Object(members)
+            if decls.options.inline_small_types && is_small_object(&members) =>
+        {
+            inline_interface(members, decls, path)
+        }
+        DataType::Object(members) => interface(members, decls, path),
+        DataType::Array(elem) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            match is_union(&elem_name) {
+                true => format!("({})[]", elem_name),
+                false => format!("{}[]", elem_name),
+            }
+        }
+        DataType::FixedArray(elem, len) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("[{}]", vec![elem_name; len].join(", "))
+        }
+        DataType::Map(values) => {
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("Record<string, {}>", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() > 1 {
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+            }
+            // Null reads best last, as in `string | null`.
+            let mut arms = options
+                .into_iter()
+                .map(|typ| type_expr(typ, decls, path))
+                .collect::<Vec<_>>();
+            if nullable {
+                arms.push("null".to_string());
+            }
+            arms.join(" | ")
+        }
+    }
+}
+
+/// Whether the type `type_name` is a union at the top level, and so
+/// needs parentheses to be made into an array.
+fn is_union(type_name: &str) -> bool {
+    let mut depth = 0;
+    for c in type_name.chars() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth -= 1,
+            '|' if depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// The keyword to put before declarations, if they're public.
+fn export(decls: &Decls) -> &'static str {
+    match decls.options.pub_types {
+        true => "export ",
+        false => "",
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(TS_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        DataType, Decls, Options, WarningKind,
+    };
+
+    #[test]
+    fn interfaces() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::TypeScript,
+                    pub_types: true,
+                    ..Options::default()
+                },
+                json::array![
+                    {"id": 1, "name": "a", "tags": ["x"], "owner": {"ok": true}, "content-type": 1.5},
+                    {"id": "b", "name": null, "tags": [1], "owner": {"ok": false}, "content-type": 2},
+                ],
+            ),
+            "export interface Data2 {\n  ok: boolean;\n}\n\n\
+             export interface Data1 {\n  \"content-type\": number;\n  id: string | number;\n  \
+             name?: string | null;\n  owner: Data2;\n  tags: (string | number)[];\n}\n\n\
+             export type Data0 = Data1[];"
+        );
+    }
+
+    #[test]
+    fn other_types() {
+        let mut decls = Decls::new(Options {
+            lang: Lang::TypeScript,
+            ..Options::default()
+        });
+        DataType::from_json_value(&json::object! {
+            "by_id": {"1": [true], "2": [false]},
+            "empty": [],
+            "gone": null,
+        })
+        .unwrap()
+        .declare(&mut decls);
+        assert_eq!(
+            decls.output(),
+            "interface Data0 {\n  by_id: Record<string, boolean[]>;\n  empty: unknown[];\n  \
+             gone?: null;\n}"
+        );
+        assert!(decls
+            .warnings()
+            .summary()
+            .keys()
+            .all(|warning| warning.kind != WarningKind::EnumFallback));
+
+        assert_eq!(
+            declare_as(Lang::TypeScript, json::from(1.5)),
+            "type Data0 = number;"
+        );
+    }
+
+    #[test]
+    fn int_enums() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::TypeScript,
+                    int_enum_threshold: Some(4),
+                    ..Options::default()
+                },
+                json::array![{"status": 1, "delete": true}, {"status": 2, "delete": false}],
+            ),
+            "interface Data1 {\n  delete: boolean;\n  status: 1 | 2;\n}\n\ntype Data0 = Data1[];"
+        );
+    }
+
+    #[test]
+    fn inline_small_types() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::TypeScript,
+                    inline_small_types: true,
+                    ..Options::default()
+                },
+                json::object! {
+                    "coords": {"lat": 1.5, "lon": 2.5},
+                    "id": {"content-type": "a"},
+                    "owner": {"id": 1, "name": "b", "email": "c", "admin": true},
+                },
+            ),
+            "interface Data1 {\n  admin: boolean;\n  email: string;\n  id: number;\n  \
+             name: string;\n}\n\n\
+             interface Data0 {\n  coords: { lat: number; lon: number };\n  \
+             id: { \"content-type\": string };\n  owner: Data1;\n}"
+        );
+    }
+
+    #[test]
+    fn missing_members() {
+        assert_eq!(
+            declare_as(
+                Lang::TypeScript,
+                json::array![{"id": 1, "note": "a", "gone": null}, {"id": 2}]
+            ),
+            "interface Data1 {\n  gone?: null;\n  id: number;\n  note?: string | null;\n}\n\n\
+             type Data0 = Data1[];"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Emission of Zig structs, parsed with `std.json`.
+//!
+//! `std.json` matches keys to field names exactly, so fields are named
+//! after their keys, written as `@"..."` when they aren't identifiers.
+//! Nullable fields are optionals defaulting to null, so that they can
+//! be missing too. Maps are `std.json.ArrayHashMap`s, and values of
+//! several kinds are kept as `std.json.Value`s.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Type emitted for values nothing is known about.
+const ZIG_UNKNOWN_TYPE: &str = "std.json.Value";
+
+/// Text that goes before the declarations: the standard library, if
+/// they use it.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    match imports.contains("std") {
+        true => "const std = @import(\"std\");\n\n".to_string(),
+        false => String::new(),
+    }
+}
+
+/// Emit structs for a data type, returning the name of the top-level
+/// one. Types other than objects are given a name with a constant.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let type_name = type_expr(typ, decls, "$");
+            let mut s = decls.take_scratch();
+            decls.occurs_at(&mut s, "//", &name, "$");
+            write!(s, "{}const {} = {};", visibility(decls), name, type_name)
+                .expect("writing to a String can't fail");
+            decls.begin_decl(&name).push_str(&s);
+            decls.put_scratch(s);
+            name
+        }
+    }
+}
+
+/// Emit a struct with the given members.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut fields = Vec::new();
+    let mut body = decls.take_scratch();
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        let member_path = format!("{}.{}", path, member);
+        let type_name = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (typ, _) => type_expr(typ, decls, &member_path),
+        };
+        let style = decls.namer.style();
+        match style.is_identifier(&member) && !style.keywords.contains(&member.as_str()) {
+            true => write!(body, "    {}: {}", member, type_name),
+            false => write!(body, "    @{}: {}", string(&member), type_name),
+        }
+        .expect("writing to a String can't fail");
+        if type_name.starts_with('?') {
+            body += " = null";
+        }
+        body += ",\n";
+        fields.push((member, type_name));
+    }
+
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", &name, path);
+    match body.is_empty() {
+        true => write!(s, "{}const {} = struct {{}};", visibility(decls), name),
+        false => write!(
+            s,
+            "{}const {} = struct {{\n{}}};",
+            visibility(decls),
+            name,
+            body
+        ),
+    }
+    .expect("writing to a String can't fail");
+    decls.declared[declared].fields = fields;
+    decls.begin_decl(&name).push_str(&s);
+    decls.put_scratch(s);
+    decls.put_scratch(body);
+    name
+}
+
+/// The type of the values at `path`, declaring the structs it uses
+/// first.
+fn type_expr(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => format!("?{}", unknown(decls)),
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => "[]const u8".to_string(),
+        DataType::Int | DataType::IntEnum(_) => "i64".to_string(),
+        DataType::Float => "f64".to_string(),
+        DataType::Bool => "bool".to_string(),
+        DataType::Object(members) => object(members, decls, path),
+        DataType::Array(elem) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("[]const {}", elem_name)
+        }
+        DataType::FixedArray(elem, len) => {
+            let elem_name = type_expr(*elem, decls, &format!("{}[]", path));
+            format!("[{}]{}", len, elem_name)
+        }
+        DataType::Map(values) => {
+            decls.imports.insert("std".to_string());
+            let value_name = type_expr(*values, decls, &format!("{}.*", path));
+            format!("std.json.ArrayHashMap({})", value_name)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            let type_name = match options.len() {
+                1 => {
+                    let typ = options.into_iter().next().expect("checked above");
+                    type_expr(typ, decls, path)
+                }
+                _ => {
+                    decls.warnings.push(
+                        path,
+                        WarningKind::EnumFallback,
+                        options
+                            .iter()
+                            .map(DataType::kind_name)
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                    );
+                    unknown(decls)
+                }
+            };
+            match nullable && !type_name.starts_with('?') {
+                true => format!("?{}", type_name),
+                false => type_name,
+            }
+        }
+    }
+}
+
+/// `s` as a Zig string literal.
+fn string(s: &str) -> String {
+    let mut out = "\"".to_string();
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\r' => out += "\\r",
+            '\t' => out += "\\t",
+            c if c.is_control() => {
+                write!(out, "\\u{{{:x}}}", c as u32).expect("writing to a String can't fail")
+            }
+            c => out.push(c),
+        }
+    }
+    out + "\""
+}
+
+/// The modifier to declare types with, if they're public.
+fn visibility(decls: &Decls) -> &'static str {
+    match decls.options.pub_types {
+        true => "pub ",
+        false => "",
+    }
+}
+
+/// The type to emit for unknown values.
+fn unknown(decls: &mut Decls) -> String {
+    match &decls.options.unknown_type {
+        Some(unknown) => unknown.clone(),
+        None => {
+            decls.imports.insert("std".to_string());
+            ZIG_UNKNOWN_TYPE.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{tests::declare_with, Lang},
+        Options,
+    };
+
+    #[test]
+    fn structs() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Zig,
+                    pub_types: true,
+                    ..Options::default()
+                },
+                json::array![
+                    {"id": 1, "name": "a", "tags": [1], "owner": {}, "content-type": 1.5, "x": "a"},
+                    {"id": 2, "name": null, "tags": [2], "owner": {}, "content-type": 2, "x": 1},
+                ]
+            ),
+            "const std = @import(\"std\");\n\n\
+             pub const Data2 = struct {};\n\n\
+             pub const Data1 = struct {\n    @\"content-type\": f64,\n    id: i64,\n    \
+             name: ?[]const u8 = null,\n    owner: Data2,\n    tags: []const i64,\n    \
+             x: std.json.Value,\n};\n\n\
+             pub const Data0 = []const Data1;"
+        );
+    }
+
+    #[test]
+    fn keywords_and_maps() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Zig,
+                    pub_types: true,
+                    ..Options::default()
+                },
+                json::object! {"const": {"1": [1.5], "2": [2.5]}},
+            ),
+            "const std = @import(\"std\");\n\n\
+             pub const Data0 = struct {\n    \
+             @\"const\": std.json.ArrayHashMap([]const f64),\n};"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Emission of Zod schemas for TypeScript.
+//!
+//! Each schema is a constant, with a type of the same name inferred
+//! from it, so that values can be validated and typed at once. Members
+//! keep their keys, quoted if they aren't identifiers, as they would in
+//! an interface, and values of several kinds are `z.union`s. Members
+//! that can be null can also be missing, so they're `nullish`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{options::NullFields, types::Decls, warnings::WarningKind, DataType};
+
+/// Schema emitted for values nothing is known about.
+const ZOD_UNKNOWN_TYPE: &str = "z.unknown()";
+
+/// Text that goes before the declarations: the import of Zod, if they
+/// use it.
+pub(crate) fn header(imports: &BTreeSet<String>) -> String {
+    match imports.contains("zod") {
+        true => "import { z } from \"zod\";\n\n".to_string(),
+        false => String::new(),
+    }
+}
+
+/// Emit schemas for a data type, returning the name of the top-level
+/// one.
+pub(crate) fn declare(typ: DataType, decls: &mut Decls) -> String {
+    match typ {
+        DataType::Object(members) if !decls.options.types.contains_key("$") => {
+            object(members, decls, "$")
+        }
+        typ => {
+            let name = decls.name_at("$");
+            let schema = schema(typ, decls, "$");
+            schema_decl(decls, "$", &name, &schema);
+            name
+        }
+    }
+}
+
+/// Emit a `z.object` schema with the given members.
+fn object(members: BTreeMap<String, DataType>, decls: &mut Decls, path: &str) -> String {
+    let name = decls.name_at(path);
+    let declared = decls.declare_object(path, &name);
+    let mut body = decls.take_scratch();
+    let mut fields = Vec::new();
+    for (member, typ) in members {
+        if decls.omits(path, &member) {
+            continue;
+        }
+        let member_path = format!("{}.{}", path, member);
+        let schema = match (typ, decls.options.null_fields) {
+            _ if decls.options.types.contains_key(&member_path) => {
+                decls.options.types[&member_path].clone()
+            }
+            (DataType::Null, NullFields::Drop) => {
+                decls
+                    .warnings
+                    .push(member_path, WarningKind::NullFieldDropped, "");
+                continue;
+            }
+            (DataType::Null, NullFields::Option) => format!("z.nullable({})", unknown(decls)),
+            (typ, _) => schema(typ, decls, &member_path),
+        };
+        // Members that are null in some documents are missing from
+        // others, which `z.nullable` alone doesn't allow.
+        let schema = match schema.strip_prefix("z.nullable(") {
+            Some(inner) => format!("{}.nullish()", &inner[..inner.len() - 1]),
+            None if schema == "z.null()" => "z.null().optional()".to_string(),
+            None => schema,
+        };
+        let key = match decls.namer.style().is_identifier(&member) {
+            true => member.clone(),
+            false => json::stringify(member.as_str()),
+        };
+        writeln!(body, "  {}: {},", key, schema).expect("writing to a String can't fail");
+        fields.push((member, schema));
+    }
+    decls.declared[declared].fields = fields;
+    let schema = match body.is_empty() {
+        true => "z.object({})".to_string(),
+        false => format!("z.object({{\n{}}})", body),
+    };
+    decls.put_scratch(body);
+    schema_decl(decls, path, &name, &schema);
+    name
+}
+
+/// Declare the schema for the values at `path`, and its type.
+fn schema_decl(decls: &mut Decls, path: &str, name: &str, schema: &str) {
+    decls.imports.insert("zod".to_string());
+    let export = match decls.options.pub_types {
+        true => "export ",
+        false => "",
+    };
+    let mut s = decls.take_scratch();
+    decls.occurs_at(&mut s, "//", name, path);
+    write!(
+        s,
+        "{export}const {name} = {schema};\n{export}type {name} = z.infer<typeof {name}>;",
+        export = export,
+        name = name,
+        schema = schema
+    )
+    .expect("writing to a String can't fail");
+    decls.begin_decl(name).push_str(&s);
+    decls.put_scratch(s);
+}
+
+/// The schema of the values at `path`, declaring the schemas it uses
+/// first.
+fn schema(typ: DataType, decls: &mut Decls, path: &str) -> String {
+    if let Some(type_name) = decls.options.types.get(path) {
+        return type_name.clone();
+    }
+
+    match typ {
+        DataType::Null => "z.null()".to_string(),
+        // Bytes are in base64 in JSON.
+        DataType::String | DataType::Temporal(_) | DataType::Bytes => "z.string()".to_string(),
+        DataType::Int => "z.number().int()".to_string(),
+        DataType::Float => "z.number()".to_string(),
+        DataType::IntEnum(codes) => {
+            let literals = codes
+                .iter()
+                .map(|code| format!("z.literal({})", code))
+                .collect::<Vec<_>>();
+            match literals.len() {
+                1 => literals.join(""),
+                _ => format!("z.union([{}])", literals.join(", ")),
+            }
+        }
+        DataType::Bool => "z.boolean()".to_string(),
+        DataType::Object(members) => object(members, decls, path),
+        DataType::Array(elem) => {
+            let elem_schema = schema(*elem, decls, &format!("{}[]", path));
+            format!("z.array({})", elem_schema)
+        }
+        DataType::FixedArray(elem, len) => {
+            let elem_schema = schema(*elem, decls, &format!("{}[]", path));
+            format!("z.tuple([{}])", vec![elem_schema; len].join(", "))
+        }
+        DataType::Map(values) => {
+            let value_schema = schema(*values, decls, &format!("{}.*", path));
+            format!("z.record(z.string(), {})", value_schema)
+        }
+        DataType::Variant(options) if options.is_empty() => unknown(decls),
+        DataType::Variant(mut options) => {
+            let nullable = options.remove(&DataType::Null);
+            if options.len() > 1 {
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+            }
+            let arms = options
+                .into_iter()
+                .map(|typ| schema(typ, decls, path))
+                .collect::<Vec<_>>();
+            let schema = match arms.len() {
+                0 => return "z.null()".to_string(),
+                1 => arms.join(""),
+                _ => format!("z.union([{}])", arms.join(", ")),
+            };
+            match nullable {
+                true => format!("z.nullable({})", schema),
+                false => schema,
+            }
+        }
+    }
+}
+
+/// The schema to emit for unknown values.
+fn unknown(decls: &Decls) -> String {
+    decls.options.unknown_type(ZOD_UNKNOWN_TYPE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lang::{
+            tests::{declare_as, declare_with},
+            Lang,
+        },
+        Options,
+    };
+
+    #[test]
+    fn schemas() {
+        assert_eq!(
+            declare_with(
+                Options {
+                    lang: Lang::Zod,
+                    pub_types: true,
+                    ..Options::default()
+                },
+                json::array![
+                    {"id": 1, "name": "a", "tags": ["x"], "owner": {}, "content-type": 1.5},
+                    {"id": "b", "name": null, "tags": [1], "owner": {}, "content-type": 2},
+                ]
+            ),
+            "import { z } from \"zod\";\n\n\
+             export const Data2 = z.object({});\n\
+             export type Data2 = z.infer<typeof Data2>;\n\n\
+             export const Data1 = z.object({\n  \"content-type\": z.number(),\n  \
+             id: z.union([z.string(), z.number().int()]),\n  name: z.string().nullish(),\n  \
+             owner: Data2,\n  tags: z.array(z.union([z.string(), z.number().int()])),\n});\n\
+             export type Data1 = z.infer<typeof Data1>;\n\n\
+             export const Data0 = z.array(Data1);\n\
+             export type Data0 = z.infer<typeof Data0>;"
+        );
+    }
+
+    #[test]
+    fn missing_members() {
+        assert_eq!(
+            declare_as(
+                Lang::Zod,
+                json::array![{"id": 1, "note": "a", "gone": null}, {"id": 2}],
+            ),
+            "import { z } from \"zod\";\n\nconst Data1 = z.object({\n  \
+             gone: z.null().optional(),\n  id: z.number().int(),\n  note: z.string().nullish(),\n\
+             });\ntype Data1 = z.infer<typeof Data1>;\n\nconst Data0 = z.array(Data1);\n\
+             type Data0 = z.infer<typeof Data0>;"
+        );
+    }
+}
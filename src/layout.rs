@@ -0,0 +1,314 @@
+//! Writing Rust declarations out as a directory of modules, one per
+//! type, for schemas too big to read comfortably as a single file.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    namer::{Case, Scope, Style},
+    Decls, Error, Lang,
+};
+
+/// Names that can't be used for a type's module, either because
+/// they're taken by the files written alongside or because they can't
+/// be module names even as raw identifiers.
+const RESERVED_MODULES: &[&str] = &["mod", "main", "self", "super", "crate"];
+
+/// A file of declarations for one type.
+struct Module<'a> {
+    /// The name of the module, which is also the file's name.
+    name: String,
+
+    /// The type the module is for.
+    owner: &'a str,
+
+    /// The declarations in the module.
+    items: Vec<&'a str>,
+}
+
+/// Write each type declared in `decls` to its own file in `dir`, along
+/// with a `mod.rs` that declares the modules and re-exports the types.
+/// Files are named after their types in snake case, with a number
+/// added to tell apart names that only differ in case. Types that use
+/// each other import each other with `use super::...`, so they need to
+/// have been declared with `Options::pub_types`. If the declarations
+/// include a `main`, it's written to `main.rs` along with its own
+/// module declarations, so that `dir` can also serve as the source
+/// directory of a binary.
+///
+/// Only Rust declarations can be written this way. Unless `force` is
+/// set, fails if `dir` exists and isn't empty. Returns the paths of the
+/// files written.
+pub fn write_modules(decls: &Decls, dir: &Path, force: bool) -> Result<Vec<PathBuf>, Error> {
+    if decls.options.lang != Lang::Rust {
+        return Err(Error::Unsupported {
+            feature: "writing a module per type",
+            lang: decls.options.lang,
+        });
+    }
+    match fs::read_dir(dir) {
+        Ok(mut entries) => {
+            if !force && entries.next().is_some() {
+                return Err(Error::NotEmpty(dir.to_owned()));
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            fs::create_dir_all(dir).map_err(Error::io(dir))?
+        }
+        Err(e) => return Err(Error::io(dir)(e)),
+    }
+
+    let (modules, main) = modules(decls);
+    let module_of = modules
+        .iter()
+        .map(|module| (module.owner, module.name.as_str()))
+        .collect::<BTreeMap<_, _>>();
+
+    let style = decls.namer.style();
+    let mut files = Vec::new();
+    for module in &modules {
+        let mut text = imports(&module.items, module.owner, &module_of, "super::", style);
+        text += &module.items.join("\n\n");
+        text += "\n";
+        files.push((format!("{}.rs", module.name), text));
+    }
+
+    let mut declarations = String::new();
+    for module in &modules {
+        declarations += &format!("mod {};\n", style.unkeyword(module.name.clone()));
+    }
+    let mut mod_rs = declarations.clone();
+    if !modules.is_empty() {
+        mod_rs += "\n";
+    }
+    for module in &modules {
+        mod_rs += &format!(
+            "pub use {}::{};\n",
+            style.unkeyword(module.name.clone()),
+            module.owner
+        );
+    }
+    files.push(("mod.rs".to_string(), mod_rs));
+
+    if !main.is_empty() {
+        let mut main_rs = declarations;
+        main_rs += "\n";
+        main_rs += &imports(&main, "main", &module_of, "", style);
+        main_rs += &main.join("\n\n");
+        main_rs += "\n";
+        files.push(("main.rs".to_string(), main_rs));
+    }
+
+    let mut written = Vec::new();
+    for (name, text) in files {
+        let path = dir.join(name);
+        fs::write(&path, text).map_err(Error::io(&path))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Group the declarations into one module per type, sorted by name,
+/// separating out the items of a `main`.
+fn modules(decls: &Decls) -> (Vec<Module<'_>>, Vec<&str>) {
+    let mut modules: Vec<Module> = Vec::new();
+    let mut main = Vec::new();
+    // Some filesystems ignore case, so names that only differ in case
+    // count as the same.
+    let mut used = Scope::ignoring_case();
+    for name in RESERVED_MODULES {
+        used.reserve(name);
+    }
+    for (owner, item) in decls.items() {
+        if owner == "main" {
+            main.push(item);
+            continue;
+        }
+        if let Some(module) = modules.iter_mut().find(|module| module.owner == owner) {
+            module.items.push(item);
+            continue;
+        }
+
+        modules.push(Module {
+            name: used.claim(&module_name(owner), "_"),
+            owner,
+            items: vec![item],
+        });
+    }
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+    (modules, main)
+}
+
+/// The base name of the module for the type `owner`: its name in
+/// snake case, which has to start with an underscore if it starts with
+/// a digit once the ones before it are dropped.
+fn module_name(owner: &str) -> String {
+    let name = Case::Snake.apply(owner, false);
+    match name.starts_with(char::is_numeric) {
+        true => format!("_{}", name),
+        false => name,
+    }
+}
+
+/// The `use` declarations, followed by a blank line, needed by `items`
+/// belonging to `owner` to refer to the other types in `module_of`,
+/// whose modules are found under `parent`. Module names that are
+/// keywords are written as `style` has them.
+fn imports(
+    items: &[&str],
+    owner: &str,
+    module_of: &BTreeMap<&str, &str>,
+    parent: &str,
+    style: &Style,
+) -> String {
+    let used = items
+        .iter()
+        .flat_map(|item| item.split(|c: char| !c.is_alphanumeric() && c != '_'))
+        .filter(|word| *word != owner)
+        .filter_map(|word| module_of.get_key_value(word))
+        .collect::<BTreeSet<_>>();
+
+    let mut s = String::new();
+    for (name, module) in &used {
+        s += &format!(
+            "use {}{}::{};\n",
+            parent,
+            style.unkeyword(module.to_string()),
+            name
+        );
+    }
+    if !s.is_empty() {
+        s += "\n";
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{DataType, MainErrors, Options};
+
+    fn declare(v: json::JsonValue, options: Options) -> Decls {
+        let mut decls = Decls::new(Options {
+            pub_types: true,
+            ..options
+        });
+        DataType::from_json_value(&v).unwrap().declare(&mut decls);
+        decls
+    }
+
+    fn file_names(written: &[PathBuf]) -> Vec<String> {
+        let mut names = written
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn modules_per_type() {
+        let mut options = Options {
+            explicit_default: true,
+            ..Options::default()
+        };
+        options.names.insert("$".to_string(), "User".to_string());
+        options
+            .names
+            .insert("$.address".to_string(), "Address".to_string());
+        options
+            .names
+            .insert("$.alias".to_string(), "USER".to_string());
+        options
+            .names
+            .insert("$.kind".to_string(), "Type".to_string());
+        let decls = declare(
+            json::object! {
+                "address": {"city": "a"},
+                "alias": {"name": "b"},
+                "kind": {"id": 1},
+                "tags": [1, "a"],
+            },
+            options,
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let written = write_modules(&decls, dir.path(), false).unwrap();
+        assert_eq!(
+            file_names(&written),
+            [
+                "address.rs",
+                "data0.rs",
+                "mod.rs",
+                "type.rs",
+                "user.rs",
+                "user_2.rs"
+            ]
+        );
+
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+        let mod_rs = read("mod.rs");
+        for path in &written {
+            let module = path.file_stem().unwrap().to_string_lossy();
+            if module != "mod" {
+                let module = Style::new(&Options::default()).unkeyword(module.to_string());
+                assert!(mod_rs.contains(&format!("mod {};\n", module)));
+            }
+        }
+        assert!(mod_rs.contains("mod r#type;\n"));
+        assert!(mod_rs.contains("pub use r#type::Type;\n"));
+
+        // USER is declared before the type that uses it, so it gets the
+        // first claim on the name.
+        let user = read("user_2.rs");
+        assert!(user.starts_with(
+            "use super::address::Address;\n\
+             use super::data0::Data0;\n\
+             use super::r#type::Type;\n\
+             use super::user::USER;\n\n\
+             pub struct User {\n"
+        ));
+        assert!(user.contains("impl Default for User {"));
+        assert_eq!(
+            read("address.rs").lines().next(),
+            Some("pub struct Address {")
+        );
+
+        assert!(matches!(
+            write_modules(&decls, dir.path(), false),
+            Err(Error::NotEmpty(_))
+        ));
+        write_modules(&decls, dir.path(), true).unwrap();
+    }
+
+    #[test]
+    fn colliding_names_and_main() {
+        let mut options = Options {
+            emit_main: Some(MainErrors::Expect),
+            ..Options::default()
+        };
+        options.names.insert("$".to_string(), "Main".to_string());
+        options
+            .names
+            .insert("$.a".to_string(), "data_1".to_string());
+        options
+            .names
+            .insert("$.b".to_string(), "Data_1".to_string());
+        let decls = declare(json::object! {"a": {"x": 1}, "b": {"y": 1}}, options);
+
+        let dir = tempfile::tempdir().unwrap();
+        let written = write_modules(&decls, dir.path(), false).unwrap();
+        assert_eq!(
+            file_names(&written),
+            ["data_1.rs", "data_1_2.rs", "main.rs", "main_2.rs", "mod.rs"]
+        );
+        let main_rs = fs::read_to_string(dir.path().join("main.rs")).unwrap();
+        assert!(main_rs.starts_with(
+            "mod data_1;\nmod data_1_2;\nmod main_2;\n\nuse main_2::Main;\n\nfn main() {\n"
+        ));
+    }
+}
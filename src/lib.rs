@@ -0,0 +1,68 @@
+//! A library for inferring the structure of JSON data and generating
+//! data structure declarations from it.
+
+mod avro;
+mod compression;
+mod config;
+mod diagnostic;
+mod diff;
+mod drift;
+mod error;
+mod format;
+mod har;
+mod intern;
+mod lang;
+mod layout;
+mod logger;
+mod namer;
+mod names;
+mod openapi;
+mod options;
+mod review;
+mod schema;
+mod schema_state;
+mod state;
+mod stats;
+mod stream;
+mod temporal;
+mod transform;
+mod types;
+mod value;
+mod warnings;
+
+#[cfg(all(test, feature = "proptest"))]
+mod arbitrary;
+
+pub use compression::Compression;
+pub use config::{ConfigError, CONFIG_FILE};
+pub use diagnostic::{render, render_warnings, suggestion, use_color};
+pub use diff::{diff, DiffKind, Difference, SchemaDiff};
+pub use drift::{Change, Drift, DriftReport};
+pub use error::Error;
+pub use format::Format;
+pub use har::{Endpoint, Har};
+pub use intern::{Interner, TypeId};
+pub use lang::{proto::PROTO_UNKNOWN_TYPE, Lang};
+pub use layout::write_modules;
+pub use logger::{Level, Logger};
+pub use namer::{Case, Edition, Namer, Scope, Style, RUST_KEYWORDS};
+pub use names::singularize;
+pub use openapi::{read_openapi, ApiType};
+pub use options::{
+    MainErrors, MapType, NullFields, Options, PathsFormat, StringType, WarningsFormat,
+    RUST_UNKNOWN_TYPE,
+};
+pub use review::{config_snippet, review};
+pub use schema::SchemaType;
+pub use schema_state::{SchemaState, SchemaStateError};
+pub use state::{State, StateError, STATE_VERSION};
+pub use stats::{FieldStats, ObjectStats, Stats};
+pub use stream::{stream_json, Piece};
+pub use temporal::Temporal;
+pub use transform::{LimitIntEnums, Prune, Transform};
+pub use types::{
+    DataType, Declared, Decls, DepthExceeded, Sampler, MAX_DEPTH, MAX_INT_ENUM_CODES,
+    MAX_MATRIX_LEN, MIN_PRESENCE_SAMPLES,
+};
+pub use value::{Number, ParseNumberError, Value};
+pub use warnings::{Warning, WarningKind, Warnings};
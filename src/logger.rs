@@ -0,0 +1,134 @@
+//! Writing diagnostics to stderr at a chosen level of detail, so that
+//! everything the command line tool says besides its output can be
+//! silenced or made more talkative in one place.
+
+use std::{
+    error::Error as StdError,
+    fmt,
+    io::{self, IsTerminal, Write},
+};
+
+use crate::{render, render_warnings, use_color, Warnings};
+
+/// How much to write to stderr. Each level includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Level {
+    /// Only errors, each on a single line.
+    Quiet,
+
+    /// Warnings and reports, and errors with what's needed to fix
+    /// them.
+    #[default]
+    Normal,
+
+    /// Also notes on what was read and written.
+    Verbose,
+
+    /// Also details that help track down problems, like how long each
+    /// step took.
+    Debug,
+}
+
+impl Level {
+    /// The level asked for by the number of times `-q` and `-v` were
+    /// given. Each `-v` past the default adds a level.
+    pub fn from_flags(quiet: bool, verbose: u64) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Level::Quiet,
+            (false, 0) => Level::Normal,
+            (false, 1) => Level::Verbose,
+            (false, _) => Level::Debug,
+        }
+    }
+}
+
+/// Writes diagnostics to stderr, leaving out the ones more detailed
+/// than its level.
+#[derive(Debug, Clone, Copy)]
+pub struct Logger {
+    level: Level,
+    color: bool,
+}
+
+impl Logger {
+    /// Create a logger at `level`, writing in color if stderr is a
+    /// terminal that allows it.
+    pub fn new(level: Level) -> Self {
+        Logger {
+            level,
+            color: use_color(io::stderr().is_terminal()),
+        }
+    }
+
+    /// Whether diagnostics at `level` are written.
+    pub fn enabled(&self, level: Level) -> bool {
+        level <= self.level
+    }
+
+    /// Write the error that ended the run: on a single line if quiet,
+    /// so that it's easy for scripts to pick out, and otherwise with
+    /// what's needed to fix it.
+    pub fn error(&self, error: &(dyn StdError + 'static)) {
+        match self.level {
+            Level::Quiet => {
+                let mut s = format!("error: {}", error);
+                let mut cause = error.source();
+                while let Some(e) = cause {
+                    s += &format!(": {}", e);
+                    cause = e.source();
+                }
+                self.write(&format!("{}\n", s));
+            }
+            _ => self.write(&render(error, self.color)),
+        }
+    }
+
+    /// Write the distinct warnings in `warnings`.
+    pub fn warnings(&self, warnings: &Warnings) {
+        if self.enabled(Level::Normal) {
+            self.write(&render_warnings(warnings, self.color));
+        }
+    }
+
+    /// Write `text` as it is if diagnostics at `level` are written.
+    pub fn report(&self, level: Level, text: &str) {
+        if self.enabled(level) {
+            self.write(text);
+        }
+    }
+
+    /// Write a note about what's being done, if verbose.
+    pub fn note(&self, message: impl fmt::Display) {
+        self.report(Level::Verbose, &format!("note: {}\n", message));
+    }
+
+    /// Write a detail for tracking down problems, if debugging.
+    pub fn debug(&self, message: impl fmt::Display) {
+        self.report(Level::Debug, &format!("debug: {}\n", message));
+    }
+
+    /// Write `text` to stderr. Failing to is ignored, since there's
+    /// nowhere left to report it.
+    fn write(&self, text: &str) {
+        let _ = io::stderr().lock().write_all(text.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels() {
+        assert_eq!(Level::from_flags(false, 0), Level::Normal);
+        assert_eq!(Level::from_flags(false, 1), Level::Verbose);
+        assert_eq!(Level::from_flags(false, 3), Level::Debug);
+        assert_eq!(Level::from_flags(true, 0), Level::Quiet);
+
+        let logger = Logger::new(Level::Verbose);
+        assert!(logger.enabled(Level::Quiet));
+        assert!(logger.enabled(Level::Verbose));
+        assert!(!logger.enabled(Level::Debug));
+        assert!(!Logger::new(Level::Quiet).enabled(Level::Normal));
+    }
+}
@@ -2,361 +2,1788 @@
 //! JSON file.
 
 use std::{
-    collections::{BTreeMap, BTreeSet},
-    fs::read_to_string,
-    iter::once,
-    path::Path,
+    collections::BTreeMap,
+    fs::{read_to_string, File},
+    io::{self, BufRead, BufReader, Read, Write},
+    mem,
+    path::{Path, PathBuf},
+    process::exit,
+    str::FromStr,
+    time::Instant,
 };
 
+use anyhow::bail;
 use anyhow::{Context, Result};
-use clap::Arg;
-use json::JsonValue;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use glob::{glob, Pattern};
+use json_analyzer::{
+    config_snippet, diff, read_openapi, review, stream_json, write_modules, Compression, DataType,
+    Decls, DriftReport, Error, Format, Har, Interner, Lang, Level, Logger, Options, Piece, Sampler,
+    SchemaState, SchemaType, State, Stats, TypeId, Value, Warnings, WarningsFormat, CONFIG_FILE,
+    MAX_INT_ENUM_CODES,
+};
+
+/// Exit status of `merge` when the merged type differs from the saved
+/// one.
+const EXIT_CHANGED: i32 = 2;
+
+/// The file name that stands for stdin.
+const STDIN: &str = "-";
+
+/// The flags `--har` can't be used with.
+const HAR_CONFLICTS: &[&str] = &[
+    "name",
+    "format",
+    "lenient",
+    "stream",
+    "dump-type",
+    "example-doc",
+    "diff",
+    "interactive",
+    "stats",
+    "schema-state",
+    "emit-main",
+    "min-presence",
+    "detect-drift",
+];
+
+/// The flags `--from-schema` can't be used with.
+const FROM_SCHEMA_CONFLICTS: &[&str] = &[
+    "har",
+    "stream",
+    "interactive",
+    "stats",
+    "min-presence",
+    "detect-drift",
+];
+
+/// The flags `--openapi` can't be used with.
+const OPENAPI_CONFLICTS: &[&str] = &[
+    "har",
+    "from-schema",
+    "name",
+    "lenient",
+    "stream",
+    "dump-type",
+    "example-doc",
+    "diff",
+    "interactive",
+    "stats",
+    "schema-state",
+    "emit-main",
+    "min-presence",
+    "detect-drift",
+];
+
+/// The flags that turn on features the configuration file can also turn
+/// on, each with the flag that turns it off again.
+const NEGATED_FLAGS: &[(&str, &str)] = &[
+    ("explicit-default", "no-explicit-default"),
+    ("inline-small-types", "no-inline-small-types"),
+    ("emit-helpers", "no-emit-helpers"),
+    ("serde", "no-serde"),
+    ("newtype-leaves", "no-newtype-leaves"),
+    ("java-pojos", "no-java-pojos"),
+    ("ocaml-atd", "no-ocaml-atd"),
+    ("scala-play-json", "no-scala-play-json"),
+    ("julia-struct-types", "no-julia-struct-types"),
+    ("sql-child-tables", "no-sql-child-tables"),
+    ("annotate-uncertain", "no-annotate-uncertain"),
+    ("sample-random", "no-sample-random"),
+    ("no-singularize", "singularize"),
+    ("detect-dates", "no-detect-dates"),
+    ("matrix", "no-matrix"),
+    ("annotate-paths", "no-annotate-paths"),
+    ("emit-main", "no-emit-main"),
+    ("lenient", "no-lenient"),
+    ("stream", "no-stream"),
+    ("strict", "no-strict"),
+    ("har", "no-har"),
+    ("from-schema", "no-from-schema"),
+    ("openapi", "no-openapi"),
+    ("operations", "no-operations"),
+    ("stats", "no-stats"),
+    ("detect-drift", "no-detect-drift"),
+    ("dump-type", "no-dump-type"),
+    ("example-doc", "no-example-doc"),
+    ("interactive", "no-interactive"),
+    ("force", "no-force"),
+    ("deny-warnings", "no-deny-warnings"),
+];
+
+fn main() {
+    let app = app().get_matches();
+
+    let matches = app.subcommand_matches("merge").unwrap_or(&app);
+    let logger = Logger::new(Level::from_flags(
+        matches.is_present("quiet"),
+        matches.occurrences_of("verbose"),
+    ));
+    let result = match app.subcommand_name() {
+        Some("merge") => run_merge(matches, &logger),
+        _ => run(&app, &logger),
+    };
+    if let Err(e) = result {
+        logger.error(e.as_ref());
+        exit(1);
+    }
+}
 
-fn main() -> Result<()> {
-    let app = clap::App::new("json-analyzer")
+/// The command line's arguments.
+fn app() -> App<'static, 'static> {
+    App::new("json-analyzer")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(Arg::with_name("files").index(1).multiple(true).help(
+            "The files to analyze, as samples of the same type: files, directories to \
+             read every file in the format under, glob patterns, HTTP or HTTPS URLs to \
+             fetch, or - to read one from stdin, as is the default. Files compressed with gzip or Zstandard are \
+             decompressed first",
+        ))
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&[
+                    "json", "json5", "yaml", "toml", "csv", "tsv", "xml", "msgpack", "cbor", "bson", "parquet",
+                    "avro",
+                ])
+                .help(
+                    "Format of the documents, rather than going by the extensions of \
+                     their files; JSON if they don't have one",
+                ),
+        )
+        .arg(Arg::with_name("lenient").long("lenient").help(
+            "Read JSON as JSON5, allowing comments, trailing commas, unquoted keys and \
+             the like, as in JSONC files",
+        ))
+        .arg(Arg::with_name("stream").long("stream").help(
+            "Read JSON a piece at a time, so that of a document that's an array, only \
+             one element is held in memory at once, for documents too big to read whole",
+        ))
+        .arg(
+            Arg::with_name("har")
+                .long("har")
+                .conflicts_with_all(HAR_CONFLICTS)
+                .help(
+                    "Read the files as HAR captures, as browsers' developer tools save \
+                     them, and declare a type for the JSON responses to each endpoint, \
+                     going by the method and the path with ids left out",
+                ),
+        )
+        .arg(
+            Arg::with_name("from-schema")
+                .long("from-schema")
+                .conflicts_with_all(FROM_SCHEMA_CONFLICTS)
+                .help(
+                    "Read the files as JSON Schemas, and declare the types of the values \
+                     they describe rather than inferring them from samples",
+                ),
+        )
+        .arg(
+            Arg::with_name("openapi")
+                .long("openapi")
+                .conflicts_with_all(OPENAPI_CONFLICTS)
+                .help(
+                    "Read the files as OpenAPI 3 documents, and declare a type for each \
+                     of their component schemas, named after it",
+                ),
+        )
+        .arg(
+            Arg::with_name("operations")
+                .long("operations")
+                .requires("openapi")
+                .help(
+                    "With --openapi, also declare types for the JSON bodies of the \
+                     requests and successful responses of each operation",
+                ),
+        )
+        .arg(
+            Arg::with_name("header")
+                .long("header")
+                .short("H")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("NAME: VALUE")
+                .help("A header to send when fetching URLs, like an Authorization header"),
+        )
+        .arg(
+            Arg::with_name("dump-type")
+                .long("dump-type")
+                .help("Print the inferred type tree instead of declarations"),
+        )
+        .arg(Arg::with_name("example-doc").long("example-doc").help(
+            "Print a JSON document with every field of the inferred type instead of \
+                     declarations",
+        ))
+        .arg(
+            Arg::with_name("diff")
+                .long("diff")
+                .takes_value(true)
+                .value_name("OTHER_FILE")
+                .help(
+                    "Print how the inferred type differs from that of OTHER_FILE, an \
+                     older sample, instead of declarations",
+                ),
+        )
+        .arg(
+            Arg::with_name("interactive")
+                .long("interactive")
+                .help("Review the declared types and choose names and overrides"),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("Fail if the document is null or empty, rather than warning"),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .global(true)
+                .help("Print how often each field is present, null or absent"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .global(true)
+                .takes_value(true)
+                .value_name("FILE")
+                .help(
+                    "Configuration file to read options from, instead of json-analyzer.toml \
+                     in the working directory. Options given here override it",
+                ),
+        )
+        .arg(
+            Arg::with_name("name")
+                .long("name")
+                .global(true)
+                .takes_value(true)
+                .value_name("NAME")
+                .help("Name of the top-level type"),
+        )
         .arg(
-            Arg::with_name("file")
-                .index(1)
-                .help("The JSON file to analyze")
-                .required(true),
+            Arg::with_name("type-case")
+                .long("type-case")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["pascal", "snake", "camel"])
+                .default_value("pascal")
+                .help("Letter case of the names made up for types"),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("type-prefix")
+                .long("type-prefix")
+                .global(true)
+                .takes_value(true)
+                .value_name("PREFIX")
+                .help("Text to start the names made up for types with"),
+        )
+        .arg(
+            Arg::with_name("type-suffix")
+                .long("type-suffix")
+                .global(true)
+                .takes_value(true)
+                .value_name("SUFFIX")
+                .help("Text to end the names made up for types with"),
+        )
+        .arg(
+            Arg::with_name("lang")
+                .long("lang")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&[
+                    "rust",
+                    "proto",
+                    "typescript",
+                    "python",
+                    "pydantic",
+                    "java",
+                    "kotlin",
+                    "csharp",
+                    "c",
+                    "cpp",
+                    "swift",
+                    "elm",
+                    "ocaml",
+                    "scala",
+                    "dart",
+                    "julia",
+                    "zig",
+                    "avro",
+                    "flatbuffers",
+                    "capnp",
+                    "thrift",
+                    "json-schema",
+                    "graphql",
+                    "sql",
+                    "zod",
+                    "io-ts",
+                    "clojure",
+                    "nim",
+                    "fsharp",
+                    "bigquery",
+                    "pyspark",
+                    "arrow",
+                    "parquet",
+                    "mongoose",
+                ])
+                .default_value("rust")
+                .help("Language to emit declarations in"),
+        )
+        .arg(
+            Arg::with_name("schema-state")
+                .long("schema-state")
+                .global(true)
+                .takes_value(true)
+                .value_name("FILE")
+                .help(
+                    "File of field numbers to keep stable across runs, created if it \
+                     doesn't exist and updated with new fields",
+                ),
+        )
+        .arg(
+            Arg::with_name("unknown-type")
+                .long("unknown-type")
+                .global(true)
+                .takes_value(true)
+                .value_name("TYPE")
+                .help("Type to emit for values nothing is known about"),
+        )
+        .arg(
+            Arg::with_name("map-type-impl")
+                .long("map-type-impl")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["btree", "hash", "index"])
+                .default_value("hash")
+                .help("Map implementation to use for objects with dynamic keys"),
+        )
+        .arg(
+            Arg::with_name("string-type")
+                .long("string-type")
+                .global(true)
+                .takes_value(true)
+                .value_name("TYPE")
+                .help(
+                    "Type to declare strings with: String, Cow to borrow them from the \
+                     input, or the path of another string type",
+                ),
+        )
+        .arg(
+            Arg::with_name("rust-edition")
+                .long("rust-edition")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["2015", "2018", "2021"])
+                .default_value("2021")
+                .help("Edition of Rust to write declarations for"),
+        )
+        .arg(
+            Arg::with_name("newtype-leaves")
+                .long("newtype-leaves")
+                .global(true)
+                .help(
+                    "Wrap each member that's a string, number, boolean or date in a \
+                     newtype named after its path",
+                ),
+        )
+        .arg(
+            Arg::with_name("java-pojos")
+                .long("java-pojos")
+                .global(true)
+                .help("Emit Java classes with getters rather than records"),
+        )
+        .arg(
+            Arg::with_name("ocaml-atd")
+                .long("ocaml-atd")
+                .global(true)
+                .help("Emit ATD definitions rather than OCaml types"),
+        )
+        .arg(
+            Arg::with_name("scala-play-json")
+                .long("scala-play-json")
+                .global(true)
+                .help("Derive Scala codecs with play-json rather than circe"),
+        )
+        .arg(
+            Arg::with_name("julia-struct-types")
+                .long("julia-struct-types")
+                .global(true)
+                .help("Declare Julia structs for StructTypes.jl"),
+        )
+        .arg(
+            Arg::with_name("sql-child-tables")
+                .long("sql-child-tables")
+                .global(true)
+                .help(
+                    "Emit nested objects as SQL tables of their own, rather than flattening them",
+                ),
+        )
+        .arg(
+            Arg::with_name("explicit-default")
+                .long("explicit-default")
+                .global(true)
+                .help("Write out an `impl Default` for each declared type"),
+        )
+        .arg(
+            Arg::with_name("serde")
+                .long("serde")
+                .global(true)
+                .help(
+                    "Derive serde's Serialize and Deserialize, along with Debug and \
+                     Clone, for every declared type",
+                ),
+        )
+        .arg(
+            Arg::with_name("derive")
+                .long("derive")
+                .global(true)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("TRAIT")
+                .help("A trait for every declared Rust type to derive, like PartialEq"),
+        )
+        .arg(
+            Arg::with_name("emit-helpers")
+                .long("emit-helpers")
+                .global(true)
+                .help("Write out `is_*` and `as_*` accessors for each declared enum"),
+        )
+        .arg(
+            Arg::with_name("annotate-uncertain")
+                .long("annotate-uncertain")
+                .global(true)
+                .help("Comment on members whose types are guesses, suggesting better ones"),
+        )
+        .arg(
+            Arg::with_name("annotate-paths")
+                .long("annotate-paths")
+                .global(true)
+                .help("Comment on each declaration with the paths its type was found at"),
+        )
+        .arg(
+            Arg::with_name("paths-format")
+                .long("paths-format")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["jsonpath", "dotted"])
+                .default_value("jsonpath")
+                .help("Syntax of the paths written by --annotate-paths"),
+        )
+        .arg(
+            Arg::with_name("emit-main")
+                .long("emit-main")
+                .global(true)
+                .help("Write out a `main` that deserializes a file into the top-level type"),
+        )
+        .arg(
+            Arg::with_name("main-errors")
+                .long("main-errors")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["expect", "anyhow"])
+                .default_value("expect")
+                .help("How the `main` written by --emit-main handles errors"),
+        )
+        .arg(
+            Arg::with_name("output-dir")
+                .long("output-dir")
+                .global(true)
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Write one Rust module per type into DIR instead of printing them"),
+        )
+        .arg(
+            Arg::with_name("force")
+                .long("force")
+                .global(true)
+                .requires("output-dir")
+                .help("Overwrite the files in the --output-dir even if it isn't empty"),
+        )
+        .arg(
+            Arg::with_name("inline-small-types")
+                .long("inline-small-types")
+                .global(true)
+                .help("Avoid declaring types for trivially small objects"),
+        )
+        .arg(
+            Arg::with_name("null-fields")
+                .long("null-fields")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["keep-unit", "option", "drop"])
+                .default_value("keep-unit")
+                .help("How to emit members that were always null"),
+        )
+        .arg(
+            Arg::with_name("min-presence")
+                .long("min-presence")
+                .global(true)
+                .takes_value(true)
+                .value_name("FRACTION")
+                .help("Drop members present in less than this fraction of their objects"),
+        )
+        .arg(
+            Arg::with_name("sample-limit")
+                .long("sample-limit")
+                .global(true)
+                .takes_value(true)
+                .value_name("N")
+                .help("Infer the types of array elements from at most N elements of each array"),
+        )
+        .arg(
+            Arg::with_name("sample-random")
+                .long("sample-random")
+                .global(true)
+                .requires("sample-limit")
+                .help("Inspect a random selection of elements, rather than the first ones"),
+        )
+        .arg(
+            Arg::with_name("int-enum-threshold")
+                .long("int-enum-threshold")
+                .global(true)
+                .takes_value(true)
+                .value_name("N")
+                .help("Declare integers that take at most N distinct values as enums"),
+        )
+        .arg(
+            Arg::with_name("int-enum-names")
+                .long("int-enum-names")
+                .global(true)
+                .takes_value(true)
+                .value_name("FILE")
+                .help("JSON file naming the codes of integer enums, by path and code"),
+        )
+        .arg(
+            Arg::with_name("detect-dates")
+                .long("detect-dates")
+                .global(true)
+                .help(
+                    "Declare strings that always hold dates or times with chrono types, \
+                     which need chrono's serde feature",
+                ),
+        )
+        .arg(
+            Arg::with_name("detect-drift")
+                .long("detect-drift")
+                .global(true)
+                .help(
+                    "Report where the records, the elements of the document or the \
+                     records merged, first change shape",
+                ),
+        )
+        .arg(Arg::with_name("matrix").long("matrix").global(true).help(
+            "Declare arrays of numbers that always have the same length, and \
+                     arrays of those, as fixed-length arrays",
+        ))
+        .arg(
+            Arg::with_name("no-singularize")
+                .long("no-singularize")
+                .global(true)
+                .help("Don't singularize array keys when naming their element types"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .long("quiet")
+                .short("q")
+                .global(true)
+                .conflicts_with("verbose")
+                .help("Only print errors, each on a single line"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .global(true)
+                .multiple(true)
+                .help("Print notes on what's being done, and with -vv details for debugging"),
+        )
+        .arg(
+            Arg::with_name("warnings-format")
+                .long("warnings-format")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .help("Format to print warnings to stderr in"),
+        )
+        .arg(
+            Arg::with_name("deny-warnings")
+                .long("deny-warnings")
+                .global(true)
+                .help("Fail if any warnings were raised"),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about(
+                    "Fold newline-delimited JSON records into a saved type. Exits with \
+                     status 2 if the type changed, and 0 if it didn't.",
+                )
+                .arg(
+                    Arg::with_name("state")
+                        .long("state")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .required(true)
+                        .help("State file to update, created if it doesn't exist"),
+                )
+                .arg(
+                    Arg::with_name("reset")
+                        .long("reset")
+                        .help("Ignore the existing state file and start over"),
+                )
+                .arg(
+                    Arg::with_name("emit")
+                        .long("emit")
+                        .help("Print the declarations if the type changed"),
+                )
+                .arg(
+                    Arg::with_name("files")
+                        .index(1)
+                        .multiple(true)
+                        .required(true)
+                        .help("Files of newline-delimited JSON records to merge, or - for stdin"),
+                ),
+        )
+        .args(&negations())
+}
 
-    let filename = Path::new(app.value_of_os("file").expect("Required option"));
-    let data = json::parse(
-        &read_to_string(filename).with_context(|| format!("failed to read file {:?}", filename))?,
-    )
-    .with_context(|| "unable to parse JSON file")?;
+/// The flags that turn off what the flags they're paired with turn
+/// on, for features the configuration file can turn on.
+fn negations() -> Vec<Arg<'static, 'static>> {
+    NEGATED_FLAGS
+        .iter()
+        .map(|&(flag, negation)| {
+            Arg::with_name(negation)
+                .long(negation)
+                .global(true)
+                .hidden(true)
+                .overrides_with(flag)
+        })
+        .collect()
+}
 
-    let typ = DataType::from_json_value(&data);
-    // println!("{:?}", typ);
+/// Analyze the documents given on the command line, as samples of the
+/// same type.
+fn run(app: &ArgMatches, logger: &Logger) -> Result<()> {
+    let mut options = options(app, logger)?;
+    let filenames = input_paths(
+        app.values_of_os("files").map(|args| args.map(Path::new)),
+        &options,
+    )?;
+    if filenames.iter().any(|path| path == Path::new(STDIN)) && options.interactive {
+        bail!("--interactive reads answers from stdin, so it can't read the document too");
+    }
+    let start = Instant::now();
+    let headers = options.headers.clone();
+    let headers = headers
+        .iter()
+        .map(|header| parse_header(header))
+        .collect::<Result<Vec<_>>>()?;
+    if options.har {
+        return run_har(&options, logger, &filenames, &headers);
+    }
+    if options.openapi {
+        return run_openapi(&options, logger, &filenames, &headers);
+    }
+    let mut analysis = Analysis::new(
+        &options,
+        options.stats || options.min_presence.is_some(),
+        options.detect_drift,
+    );
+    read_documents(&options, logger, &filenames, &headers, &mut analysis)?;
+    let names = mem::take(&mut analysis.names);
+    let stats = analysis.stats.take();
+    let drift = analysis.drift.take();
+    let (typ, mut warnings) = analysis.finish();
+    for (path, name) in names {
+        options.names.entry(path).or_insert(name);
+    }
+    let stats = stats.unwrap_or_default();
 
-    let mut decls = Decls {
-        next_index: 0,
-        decls: Vec::new(),
-    };
+    if options.stats {
+        print!("{}", stats.report());
+        return Ok(());
+    }
+
+    logger.debug(format_args!(
+        "read the documents and inferred the type in {:?}",
+        start.elapsed()
+    ));
+    if let Some((drift, ..)) = drift {
+        report_drift(&options, logger, &drift);
+    }
+    if let Some(other) = &options.diff {
+        // The other side is read just as the inputs were.
+        let others = expand_inputs(std::iter::once(other.as_path()), options.format)?;
+        let mut other = Analysis::new(&options, false, false);
+        read_documents(&options, logger, &others, &headers, &mut other)?;
+        let diff = diff(&other.typ(), &typ);
+        match diff.is_empty() {
+            true => println!("no differences"),
+            false => print!("{}", diff.to_text()),
+        }
+        return report_warnings(&options, logger, &warnings);
+    }
+    if options.dump_type {
+        print!("{}", typ.dump());
+        return report_warnings(&options, logger, &warnings);
+    }
+    if options.example_doc {
+        println!("{}", typ.example().pretty(2));
+        return report_warnings(&options, logger, &warnings);
+    }
+
+    if options.interactive {
+        review(
+            &typ,
+            &mut options,
+            &stats,
+            &mut io::stdin().lock(),
+            &mut io::stderr(),
+        )
+        .with_context(|| "failed to read answers")?;
+    }
+
+    let snippet = config_snippet(&options);
+    let mut decls = new_decls(&options, stats)?;
+    let start = Instant::now();
     let _top_name = typ.declare(&mut decls);
-    println!("{}", decls.decls.join("\n\n"));
+    logger.debug(format_args!("declared the types in {:?}", start.elapsed()));
+    save_schema_state(&options, logger, &decls)?;
+    write_decls(&options, logger, &decls)?;
+    warnings.extend(decls.warnings().clone());
 
-    Ok(())
+    if options.interactive && !snippet.is_empty() {
+        logger.report(
+            Level::Normal,
+            &format!(
+                "\nTo make the same choices without --interactive, add this to the \
+                 configuration file ({} by default):\n\n{}\n",
+                CONFIG_FILE, snippet
+            ),
+        );
+    }
+
+    report_warnings(&options, logger, &warnings)
 }
 
-/// Types of data in a JSON structure.
-#[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
-enum DataType {
-    /// Data that is always Null. In practice, this is usually
-    /// combined with `Variant` to create an optional value.
-    Null,
+/// Analyze the JSON responses in HAR captures, declaring a type for the
+/// responses to each endpoint.
+fn run_har(
+    options: &Options,
+    logger: &Logger,
+    filenames: &[PathBuf],
+    headers: &[(&str, &str)],
+) -> Result<()> {
+    let mut har = Har::new();
+    for filename in filenames {
+        let input = read_input(filename, headers)?;
+        har.read(&input).map_err(|e| in_input(e, filename))?;
+    }
+    let (endpoints, mut warnings) = har.finish();
+    if endpoints.is_empty() {
+        bail!("there are no successful JSON responses in the HAR captures");
+    }
+
+    let mut decls = new_decls(options, Stats::new())?;
+    for endpoint in endpoints {
+        let mut analysis = Analysis::new(options, false, false);
+        for body in &endpoint.bodies {
+            analysis.document(body)?;
+        }
+        logger.note(format_args!(
+            "read {} responses to {}",
+            endpoint.bodies.len(),
+            endpoint
+        ));
+        // Each endpoint's type has paths starting from `$`, so say
+        // which endpoint warnings are about.
+        let endpoint_name = endpoint.to_string();
+        let (typ, analysis_warnings) = analysis.finish();
+        warnings.extend(analysis_warnings.prefixed(&endpoint_name));
+        typ.declare_as(&mut decls, &endpoint.type_name());
+        warnings.extend(decls.take_warnings().prefixed(&endpoint_name));
+    }
+    write_decls(options, logger, &decls)?;
+    report_warnings(options, logger, &warnings)
+}
 
-    /// A string of characters.
-    String,
+/// Declare the types OpenAPI documents describe.
+fn run_openapi(
+    options: &Options,
+    logger: &Logger,
+    filenames: &[PathBuf],
+    headers: &[(&str, &str)],
+) -> Result<()> {
+    let mut decls = new_decls(options, Stats::new())?;
+    let mut warnings = Warnings::new();
+    let mut any = false;
+    for filename in filenames {
+        let format = options
+            .format
+            .or_else(|| Format::of_path(filename))
+            .unwrap_or_default();
+        let input = read_input(filename, headers)?;
+        for spec in format.parse(&input).map_err(|e| in_input(e, filename))? {
+            let types = read_openapi(&spec, options, options.operations)
+                .map_err(|e| in_input(e, filename))?;
+            logger.note(format_args!(
+                "read {} types from {}",
+                types.len(),
+                input_name(filename)
+            ));
+            for typ in types {
+                // Each type has paths starting from `$`, so say which
+                // one warnings are about.
+                let name = typ.name.clone();
+                typ.declare(&mut decls);
+                warnings.extend(decls.take_warnings().prefixed(&name));
+                any = true;
+            }
+        }
+    }
+    if !any {
+        bail!("there are no types in the OpenAPI documents");
+    }
+    write_decls(options, logger, &decls)?;
+    report_warnings(options, logger, &warnings)
+}
 
-    /// A number that must always be an integer.
-    Int,
+/// What's been learned from the documents read so far.
+struct Analysis {
+    options: Options,
+    /// The types inferred so far.
+    interner: Interner,
+    /// The type of the documents.
+    typ: TypeId,
+    /// Names for the types at paths in the documents, from the schemas
+    /// read, if they're schemas.
+    names: BTreeMap<String, String>,
+    /// The counts of the fields of objects, if they're needed.
+    stats: Option<Stats>,
+    /// The report of drift between the records of documents that are
+    /// arrays of them, the type of the records so far and how many of
+    /// them there have been, if drift is being detected.
+    drift: Option<(DriftReport, DataType, u64)>,
+    /// The type of the elements sampled so far of the document that's
+    /// an array being read a piece at a time, how many were sampled
+    /// and the sampler choosing them.
+    elements: Option<(TypeId, usize, Sampler<Value>)>,
+}
 
-    /// A number that can be either a float or an integer.
-    Float,
+impl Analysis {
+    /// Start learning from documents, keeping the counts of fields if
+    /// `stats` and detecting drift if `drift`.
+    fn new(options: &Options, stats: bool, drift: bool) -> Self {
+        let mut interner = Interner::with_options(options);
+        Analysis {
+            options: options.clone(),
+            typ: interner.unknown(),
+            interner,
+            names: BTreeMap::new(),
+            stats: stats.then(Stats::new),
+            drift: drift.then(|| (DriftReport::new("$[]"), DataType::unknown(), 0)),
+            elements: None,
+        }
+    }
 
-    /// A boolean.
-    Bool,
+    /// Learn from a whole document.
+    fn document(&mut self, data: &Value) -> Result<()> {
+        if let Some(stats) = &mut self.stats {
+            stats.observe(data);
+        }
+        let typ = self.interner.infer(data)?;
+        self.unify_id(typ);
+        if self.drift.is_some() {
+            let records = match data {
+                Value::Array(records) => records,
+                _ => bail!("--detect-drift needs documents that are arrays of records"),
+            };
+            for record in records {
+                self.record(record)?;
+            }
+        }
+        Ok(())
+    }
 
-    /// A heterogeneous data structure with named elements, like a
-    /// struct.
-    Object(BTreeMap<String, DataType>),
+    /// Learn the type of the documents a JSON Schema describes.
+    fn schema(&mut self, schema: &Value) -> Result<(), Error> {
+        let SchemaType { typ, names } = SchemaType::from_schema(schema, &self.options)?;
+        self.unify(typ);
+        for (path, name) in names {
+            self.names.entry(path).or_insert(name);
+        }
+        Ok(())
+    }
 
-    /// An array of elements with the same type.
-    Array(Box<DataType>),
+    /// Learn from a piece of a document read a piece at a time.
+    fn piece(&mut self, piece: Piece) -> Result<()> {
+        let elem = match piece {
+            Piece::Document(data) => return self.document(&data),
+            Piece::Element(elem) => elem,
+            Piece::End(len) => {
+                let (mut typ, mut sampled, sampler) = match self.elements.take() {
+                    Some(elements) => elements,
+                    None => (self.interner.unknown(), 0, Sampler::new(&self.options)),
+                };
+                for elem in sampler.finish().1 {
+                    let elem = self.interner.infer_element(&elem)?;
+                    typ = self.interner.unify_elements(typ, elem);
+                    sampled += 1;
+                }
+                let array = self.interner.array_of(typ, len, sampled);
+                self.unify_id(array);
+                return Ok(());
+            }
+        };
 
-    /// One of several possible types. An empty Variant is also used
-    /// to represent an unknown type.
-    Variant(BTreeSet<DataType>),
+        if let Some(stats) = &mut self.stats {
+            stats.observe_element(&elem);
+        }
+        self.record(&elem)?;
+        if self.elements.is_none() {
+            let unknown = self.interner.unknown();
+            self.elements = Some((unknown, 0, Sampler::new(&self.options)));
+        }
+        let (typ, sampled, sampler) = self.elements.as_mut().expect("just set");
+        if let Some(elem) = sampler.push(elem) {
+            let elem = self.interner.infer_element(&elem)?;
+            *typ = self.interner.unify_elements(*typ, elem);
+            *sampled += 1;
+        }
+        Ok(())
+    }
+
+    /// Learn from a record of a document that's an array of them, if
+    /// drift is being detected.
+    fn record(&mut self, record: &Value) -> Result<()> {
+        if let Some((drift, elem, index)) = &mut self.drift {
+            let record = DataType::from_value_with(record, &self.options)?;
+            drift.unify(elem, record, *index);
+            *index += 1;
+        }
+        Ok(())
+    }
+
+    /// Unify the type of a document into the type of the documents.
+    fn unify(&mut self, typ: DataType) {
+        let typ = self.interner.intern(&typ);
+        self.unify_id(typ);
+    }
+
+    /// Unify the interned type of a document into the type of the
+    /// documents.
+    fn unify_id(&mut self, typ: TypeId) {
+        self.typ = self.interner.unify(self.typ, typ);
+    }
+
+    /// The type of the documents.
+    fn typ(&self) -> DataType {
+        self.interner.to_data_type(self.typ)
+    }
+
+    /// Stop learning, and return the type of the documents with the
+    /// warnings raised while inferring it.
+    fn finish(self) -> (DataType, Warnings) {
+        self.interner.finish(self.typ)
+    }
 }
 
-impl DataType {
-    /// Generate a data type that could represent something of this
-    /// type, or of the `other` type.
-    pub fn unify(self, other: DataType) -> Self {
-        match (self, other) {
-            (t1, t2) if t1 == t2 => t1,
-            (DataType::Variant(types), t2) => {
-                if types.is_empty() {
-                    t2
-                } else if types.contains(&t2) {
-                    DataType::Variant(types)
-                } else {
-                    DataType::Variant(types.into_iter().chain(once(t2)).collect())
+/// The inputs to read, given the `files` on the command line: stdin if
+/// there are none, or else the files they name.
+fn input_paths<'a>(
+    files: Option<impl Iterator<Item = &'a Path>>,
+    options: &Options,
+) -> Result<Vec<PathBuf>> {
+    match files {
+        Some(files) => expand_inputs(files, options.format),
+        None => Ok(vec![PathBuf::from(STDIN)]),
+    }
+}
+
+/// The files named by the inputs given on the command line: files as
+/// they are, the files in `format`, or JSON, anywhere under
+/// directories, and the files matching glob patterns, for shells that
+/// don't expand them. Files from a directory or pattern come in order
+/// of their paths.
+fn expand_inputs<'a>(
+    args: impl Iterator<Item = &'a Path>,
+    format: Option<Format>,
+) -> Result<Vec<PathBuf>> {
+    let extensions = format.unwrap_or_default().extensions();
+    let mut filenames = Vec::new();
+    for arg in args {
+        if arg.is_dir() {
+            let dir = Pattern::escape(&arg.to_string_lossy());
+            let mut in_dir = Vec::new();
+            for extension in extensions {
+                in_dir.extend(matching(&format!("{}/**/*.{}", dir, extension))?);
+                for compression in Compression::ALL {
+                    in_dir.extend(matching(&format!(
+                        "{}/**/*.{}.{}",
+                        dir,
+                        extension,
+                        compression.extension()
+                    ))?);
                 }
             }
-            (DataType::Float, DataType::Int) | (DataType::Int, DataType::Float) => DataType::Float,
-            (DataType::Object(a), DataType::Object(b)) => {
-                // Partition `b` into the elements that occur in both
-                // objects (`shared`) and the elements that only occur
-                // in `b` (`b_only`).
-                let (mut shared, b_only) = b
-                    .into_iter()
-                    .partition::<BTreeMap<_, _>, _>(|(name, _)| a.contains_key(name));
-
-                let data = a
-                    .into_iter()
-                    .map(|(key, value)| {
-                        // Now we unify each element that occurs in
-                        // `a` with its corresponding representation
-                        // in `b`, if it exists; use `null` for
-                        // missing elements.
-                        let b_value = shared.remove(&key).unwrap_or(DataType::Null);
-                        (key, value.unify(b_value))
-                    })
-                    .chain(
-                        // And that just leaves the elements that only
-                        // occur in `b`.
-                        b_only
-                            .into_iter()
-                            .map(|(key, value)| (key, value.unify(DataType::Null))),
-                    )
-                    .collect::<BTreeMap<_, _>>();
-
-                // By now all the elements of `shared` should have
-                // ended up unified inside of `data`, and thus
-                // consumed.
-                debug_assert!(shared.is_empty());
-
-                DataType::Object(data)
+            if in_dir.is_empty() {
+                bail!(
+                    "there are no .{} files in {:?}",
+                    extensions.join(" or ."),
+                    arg
+                );
+            }
+            in_dir.sort();
+            filenames.extend(in_dir);
+        } else if arg.exists() || arg == Path::new(STDIN) || is_url(arg) {
+            filenames.push(arg.to_owned());
+        } else {
+            let pattern = arg
+                .to_str()
+                .with_context(|| format!("{:?} isn't a file, or a pattern in UTF-8", arg))?;
+            let start = filenames.len();
+            filenames.extend(matching(pattern)?);
+            if filenames.len() == start {
+                bail!("{:?} isn't a file, and no files match it", arg);
             }
-            (t1, t2) => DataType::Variant(vec![t1, t2].into_iter().collect()),
         }
     }
+    Ok(filenames)
+}
+
+/// The files matching the glob `pattern`, in order of their paths.
+fn matching(pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut filenames = Vec::new();
+    for entry in glob(pattern).with_context(|| format!("bad pattern {:?}", pattern))? {
+        let path = entry?;
+        if path.is_file() {
+            filenames.push(path);
+        }
+    }
+    filenames.sort();
+    Ok(filenames)
+}
 
-    /// Create a data type that can reprent the given value.
-    pub fn from_json_value(v: &JsonValue) -> Self {
-        match v {
-            JsonValue::Null => Self::Null,
-            JsonValue::Short(_) => Self::String,
-            JsonValue::String(_) => Self::String,
-            JsonValue::Number(n) => {
-                let float = f64::from(*n);
-                if float == float.floor() {
-                    Self::Int
-                } else {
-                    Self::Float
+/// Read and parse the documents in the files at `filenames`, in the
+/// format `options` gives or the one their extensions say, leaving out
+/// empty ones, and learn from them with `analysis`. If they're all
+/// empty, the document is null.
+fn read_documents(
+    options: &Options,
+    logger: &Logger,
+    filenames: &[PathBuf],
+    headers: &[(&str, &str)],
+    analysis: &mut Analysis,
+) -> Result<()> {
+    let mut any = false;
+    for filename in filenames {
+        let format = match options
+            .format
+            .or_else(|| Format::of_path(filename))
+            .unwrap_or_default()
+        {
+            Format::Json if options.lenient => Format::Json5,
+            format => format,
+        };
+        let check = |data: &Value| {
+            if *data == Value::Null && options.strict {
+                bail!(
+                    "the document in {} is null, so its type is unknown",
+                    input_name(filename)
+                );
+            }
+            Ok(())
+        };
+        let mut in_file = false;
+        if options.stream {
+            if format != Format::Json {
+                bail!(
+                    "--stream only reads JSON, but {} is read as {}",
+                    input_name(filename),
+                    format
+                );
+            }
+            let input = open_input(filename, headers)?;
+            stream_json(input, |piece| {
+                in_file = true;
+                if let Piece::Document(data) = &piece {
+                    check(data)?;
                 }
+                analysis.piece(piece)
+            })
+            .map_err(|e| match e.downcast::<Error>() {
+                Ok(e) => in_input(e, filename).into(),
+                Err(e) => e,
+            })?;
+        } else if options.from_schema {
+            let input = read_input(filename, headers)?;
+            for schema in format.parse(&input).map_err(|e| in_input(e, filename))? {
+                in_file = true;
+                analysis
+                    .schema(&schema)
+                    .map_err(|e| in_input(e, filename))?;
+            }
+        } else {
+            let input = read_input(filename, headers)?;
+            for data in format.parse(&input).map_err(|e| in_input(e, filename))? {
+                in_file = true;
+                check(&data)?;
+                analysis.document(&data)?;
+            }
+            // A file whose schema declares a type isn't empty, even if
+            // it has no documents.
+            if let Some(typ) = format
+                .declared_type(&input)
+                .map_err(|e| in_input(e, filename))?
+            {
+                in_file = true;
+                analysis.unify(typ);
             }
-            JsonValue::Boolean(_) => Self::Bool,
-            JsonValue::Object(obj) => Self::Object(
-                obj.iter()
-                    .map(|(key, value)| (key.to_string(), Self::from_json_value(value)))
-                    .collect(),
-            ),
-            JsonValue::Array(elems) => Self::Array(Box::new(
-                elems
-                    .iter()
-                    .map(Self::from_json_value)
-                    .reduce(Self::unify)
-                    .unwrap_or(Self::Variant(BTreeSet::new())),
-            )),
         }
+        if !in_file && options.strict {
+            bail!(
+                "{} is empty, so there's no document to analyze",
+                input_name(filename)
+            );
+        }
+        any |= in_file;
+        logger.note(format_args!("read {}", input_name(filename)));
+    }
+    if !any {
+        analysis.document(&Value::Null)?;
     }
+    Ok(())
+}
 
-    /// Emit a Rust representation of the data type. Return the
-    /// (automatically-generated) name of the newly-declared type.
-    fn declare(self, decls: &mut Decls) -> String {
-        match self {
-            DataType::Null => "()".to_string(),
-            DataType::String => "String".to_string(),
-            DataType::Int => "i32".to_string(),
-            DataType::Float => "f64".to_string(),
-            DataType::Bool => "bool".to_string(),
-            DataType::Object(members) => {
-                use std::fmt::Write;
-
-                let name = format!("Data{}", decls.next_index);
-                decls.next_index += 1;
-
-                let mut s = format!("struct {} {{\n", name);
-                for (member, member_type) in members.into_iter() {
-                    let type_name = member_type.declare(decls);
-                    write!(s, "    pub {}: {},\n", member, type_name)
-                        .expect("writing to a String can't fail");
-                }
-                s += "}";
+/// Open the file at `path`, or stdin if it's `-`, or the body of the
+/// URL it is, fetched with `headers`. Inputs whose extension or the
+/// bytes they start with say they're compressed are decompressed as
+/// they're read.
+fn open_input(path: &Path, headers: &[(&str, &str)]) -> Result<Box<dyn BufRead>> {
+    open_input_from(path, headers, || Box::new(io::stdin().lock()))
+}
+
+/// Open the input at `path` as `open_input` does, reading it from
+/// `stdin` if it's `-`.
+fn open_input_from<'a>(
+    path: &Path,
+    headers: &[(&str, &str)],
+    stdin: impl FnOnce() -> Box<dyn BufRead + 'a>,
+) -> Result<Box<dyn BufRead + 'a>> {
+    let mut input: Box<dyn BufRead + 'a> = if path == Path::new(STDIN) {
+        stdin()
+    } else if is_url(path) {
+        let url = path.to_string_lossy();
+        let mut request = ureq::get(&url);
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        let response = request
+            .call()
+            .with_context(|| format!("failed to fetch {}", url))?;
+        Box::new(BufReader::new(response.into_reader()))
+    } else {
+        let file = File::open(path).with_context(|| format!("failed to read file {:?}", path))?;
+        Box::new(BufReader::new(file))
+    };
+
+    let compression = match path == Path::new(STDIN) {
+        true => None,
+        false => Compression::of_path(path),
+    };
+    let start = input
+        .fill_buf()
+        .with_context(|| format!("failed to read {}", input_name(path)))?;
+    match compression.or_else(|| Compression::of_input(start)) {
+        Some(compression) => {
+            let decoder = compression.decoder(input).with_context(|| {
+                format!(
+                    "failed to decompress {} as {}",
+                    input_name(path),
+                    compression
+                )
+            })?;
+            Ok(Box::new(BufReader::new(decoder)))
+        }
+        None => Ok(input),
+    }
+}
+
+/// Read all of the input at `path`, opened as by `open_input`.
+fn read_input(path: &Path, headers: &[(&str, &str)]) -> Result<Vec<u8>> {
+    let mut input = Vec::new();
+    open_input(path, headers)?
+        .read_to_end(&mut input)
+        .with_context(|| format!("failed to read {}", input_name(path)))?;
+    Ok(input)
+}
+
+/// Whether `path` is an HTTP or HTTPS URL rather than a file.
+fn is_url(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.starts_with("http://") || path.starts_with("https://"))
+}
+
+/// Parse a header given with `--header` into its name and value.
+fn parse_header(header: &str) -> Result<(&str, &str)> {
+    match header.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim(), value.trim())),
+        _ => bail!("header {:?} isn't of the form NAME: VALUE", header),
+    }
+}
+
+/// How to refer to the input at `path` in messages.
+fn input_name(path: &Path) -> String {
+    match path == Path::new(STDIN) {
+        true => "stdin".to_string(),
+        false => format!("{:?}", path),
+    }
+}
 
-                decls.decls.push(s);
-                name
+/// Record that an error in parsing came from the input at `path`, if
+/// it's a file.
+fn in_input(e: Error, path: &Path) -> Error {
+    match path == Path::new(STDIN) {
+        true => e,
+        false => e.in_file(path, 1),
+    }
+}
+
+/// Start a set of declarations, loading the schema state if there is
+/// one.
+fn new_decls(options: &Options, stats: Stats) -> Result<Decls> {
+    let decls = Decls::new(options.clone()).with_stats(stats);
+    Ok(match &options.schema_state {
+        Some(path) => decls.with_schema_state(SchemaState::load(path)?),
+        None => decls,
+    })
+}
+
+/// Save the schema state as updated by declaring types, if there is
+/// one.
+fn save_schema_state(options: &Options, logger: &Logger, decls: &Decls) -> Result<()> {
+    if let (Some(path), Some(state)) = (&options.schema_state, decls.schema_state()) {
+        state.save(path)?;
+        logger.note(format_args!("saved the schema state to {:?}", path));
+    }
+    Ok(())
+}
+
+/// Write the declarations to the output directory if there is one, or
+/// else to stdout.
+fn write_decls(options: &Options, logger: &Logger, decls: &Decls) -> Result<()> {
+    if let Some(dir) = &options.output_dir {
+        return match write_modules(decls, dir, options.force) {
+            Ok(written) => {
+                logger.note(format_args!("wrote {} files to {:?}", written.len(), dir));
+                Ok(())
             }
-            DataType::Array(elems) => {
-                let elem_name = elems.declare(decls);
-                format!("Vec<{}>", elem_name)
-            },
-            DataType::Variant(options) => {
-                use std::fmt::Write;
-
-                let name = format!("Data{}", decls.next_index);
-                decls.next_index += 1;
-
-                let mut s = format!("enum {} {{\n", name);
-                for (idx, option_type) in options.into_iter().enumerate() {
-                    let type_name = option_type.declare(decls);
-                    write!(s, "    Option{}({}),\n", idx, type_name)
-                        .expect("writing to a String can't fail");
-                }
-                s += "}";
+            Err(e @ Error::NotEmpty(_)) => {
+                bail!("{}, pass --force to overwrite its files", e)
+            }
+            Err(e) => Err(e.into()),
+        };
+    }
+
+    let mut stdout = io::stdout().lock();
+    decls.write_output(&mut stdout)?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Print where the records changed shape, in the format chosen for
+/// warnings.
+fn report_drift(options: &Options, logger: &Logger, drift: &DriftReport) {
+    match options.warnings_format {
+        WarningsFormat::Json => logger.report(Level::Normal, &format!("{}\n", drift.to_json())),
+        WarningsFormat::Text => logger.report(Level::Normal, &drift.to_text()),
+    }
+}
+
+/// Print the warnings raised during the run, and fail if they're
+/// denied.
+fn report_warnings(options: &Options, logger: &Logger, warnings: &Warnings) -> Result<()> {
+    match options.warnings_format {
+        WarningsFormat::Json => logger.report(Level::Normal, &format!("{}\n", warnings.to_json())),
+        WarningsFormat::Text => logger.warnings(warnings),
+    }
+
+    if options.deny_warnings && !warnings.is_empty() {
+        bail!("{} distinct warnings were raised", warnings.summary().len());
+    }
+    Ok(())
+}
+
+/// Build the options from the configuration file, if there is one,
+/// and the command line, which takes precedence.
+fn options(app: &ArgMatches, logger: &Logger) -> Result<Options> {
+    let config = match app.value_of_os("config") {
+        Some(path) => Some(Path::new(path)),
+        None => Some(Path::new(CONFIG_FILE)).filter(|path| path.exists()),
+    };
+    let mut options = match config {
+        Some(path) => {
+            logger.note(format_args!("loaded the configuration from {:?}", path));
+            Options::load(path)?
+        }
+        None => Options::default(),
+    };
+
+    if let Some(lang) = given(app, "lang") {
+        options.lang = parse(lang)?;
+    }
+    if let Some(name) = app.value_of("name") {
+        options.names.insert("$".to_string(), name.to_string());
+    }
+    if let Some(type_case) = given(app, "type-case") {
+        options.type_case = parse(type_case)?;
+    }
+    if let Some(prefix) = app.value_of("type-prefix") {
+        options.type_prefix = prefix.to_string();
+    }
+    if let Some(suffix) = app.value_of("type-suffix") {
+        options.type_suffix = suffix.to_string();
+    }
+    if let Some(unknown_type) = app.value_of("unknown-type") {
+        options.unknown_type = Some(unknown_type.to_string());
+    }
+    if let Some(map_type) = given(app, "map-type-impl") {
+        options.map_type = parse(map_type)?;
+    }
+    if let Some(string_type) = app.value_of("string-type") {
+        options.string_type = parse(string_type)?;
+    }
+    if let Some(edition) = given(app, "rust-edition") {
+        options.rust_edition = parse(edition)?;
+    }
+    if let Some(null_fields) = given(app, "null-fields") {
+        options.null_fields = parse(null_fields)?;
+    }
+    options.explicit_default = flag(app, "explicit-default", options.explicit_default);
+    options.inline_small_types = flag(app, "inline-small-types", options.inline_small_types);
+    options.emit_helpers = flag(app, "emit-helpers", options.emit_helpers);
+    options.serde = flag(app, "serde", options.serde);
+    // Derives on the command line add to the ones in the file.
+    for derive in app.values_of("derive").into_iter().flatten() {
+        if !options.derives.iter().any(|derived| derived == derive) {
+            options.derives.push(derive.to_string());
+        }
+    }
+    options.newtype_leaves = flag(app, "newtype-leaves", options.newtype_leaves);
+    options.java_pojos = flag(app, "java-pojos", options.java_pojos);
+    options.ocaml_atd = flag(app, "ocaml-atd", options.ocaml_atd);
+    options.scala_play_json = flag(app, "scala-play-json", options.scala_play_json);
+    options.julia_struct_types = flag(app, "julia-struct-types", options.julia_struct_types);
+    options.sql_child_tables = flag(app, "sql-child-tables", options.sql_child_tables);
+    options.annotate_uncertain = flag(app, "annotate-uncertain", options.annotate_uncertain);
+    options.sample_random = flag(app, "sample-random", options.sample_random);
+    options.no_singularize = flag(app, "no-singularize", options.no_singularize);
+    options.detect_dates = flag(app, "detect-dates", options.detect_dates);
+    options.matrix = flag(app, "matrix", options.matrix);
+    options.annotate_paths = switch(
+        options.annotate_paths,
+        flag(app, "annotate-paths", options.annotate_paths.is_some()),
+        given(app, "paths-format").map(parse).transpose()?,
+    );
+    options.emit_main = switch(
+        options.emit_main,
+        flag(app, "emit-main", options.emit_main.is_some()),
+        given(app, "main-errors").map(parse).transpose()?,
+    );
+
+    if let Some(fraction) = app.value_of("min-presence") {
+        options.min_presence = match fraction.parse::<f64>() {
+            Ok(fraction) if (0.0..=1.0).contains(&fraction) => Some(fraction),
+            _ => bail!(
+                "--min-presence must be a number from 0 to 1, not {:?}",
+                fraction
+            ),
+        };
+    }
+    if let Some(limit) = app.value_of("sample-limit") {
+        options.sample_limit = match limit.parse::<usize>() {
+            Ok(limit) if limit > 0 => Some(limit),
+            _ => bail!("--sample-limit must be a positive integer, not {:?}", limit),
+        };
+    }
+    if let Some(threshold) = app.value_of("int-enum-threshold") {
+        options.int_enum_threshold = match threshold.parse::<usize>() {
+            Ok(threshold) if (1..=MAX_INT_ENUM_CODES).contains(&threshold) => Some(threshold),
+            _ => bail!(
+                "--int-enum-threshold must be an integer from 1 to {}, not {:?}",
+                MAX_INT_ENUM_CODES,
+                threshold
+            ),
+        };
+    }
+    if let Some(path) = app.value_of_os("int-enum-names") {
+        options.code_names = load_code_names(Path::new(path))?;
+    }
+    if !options.code_names.is_empty() && options.int_enum_threshold.is_none() {
+        bail!("naming the codes of integer enums needs --int-enum-threshold");
+    }
+
+    if let Some(format) = app.value_of("format") {
+        options.format = Some(parse(format)?);
+    }
+    options.lenient = flag(app, "lenient", options.lenient);
+    options.stream = flag(app, "stream", options.stream);
+    options.strict = flag(app, "strict", options.strict);
+    options.har = flag(app, "har", options.har);
+    options.from_schema = flag(app, "from-schema", options.from_schema);
+    options.openapi = flag(app, "openapi", options.openapi);
+    options.operations = flag(app, "operations", options.operations);
+    // Headers on the command line add to the ones in the file.
+    for header in app.values_of("header").into_iter().flatten() {
+        options.headers.push(header.to_string());
+    }
+    options.stats = flag(app, "stats", options.stats);
+    options.detect_drift = flag(app, "detect-drift", options.detect_drift);
+    options.dump_type = flag(app, "dump-type", options.dump_type);
+    options.example_doc = flag(app, "example-doc", options.example_doc);
+    if let Some(path) = app.value_of_os("diff") {
+        options.diff = Some(PathBuf::from(path));
+    }
+    options.interactive = flag(app, "interactive", options.interactive);
+    if let Some(path) = app.value_of_os("schema-state") {
+        options.schema_state = Some(PathBuf::from(path));
+    }
+    if let Some(dir) = app.value_of_os("output-dir") {
+        options.output_dir = Some(PathBuf::from(dir));
+    }
+    options.force = flag(app, "force", options.force);
+    if let Some(format) = given(app, "warnings-format") {
+        options.warnings_format = parse(format)?;
+    }
+    options.deny_warnings = flag(app, "deny-warnings", options.deny_warnings);
 
-                decls.decls.push(s);
-                name
-            },
+    // The command line can't combine these, but the configuration file
+    // could have set either side.
+    for (mode, on, conflicts) in [
+        ("har", options.har, HAR_CONFLICTS),
+        ("from-schema", options.from_schema, FROM_SCHEMA_CONFLICTS),
+        ("openapi", options.openapi, OPENAPI_CONFLICTS),
+    ] {
+        if let Some(other) = conflicts.iter().find(|flag| on && is_set(&options, flag)) {
+            bail!("--{} can't be used with --{}", mode, other);
+        }
+    }
+    if options.operations && !options.openapi {
+        bail!("--operations needs --openapi");
+    }
+    if options.force && options.output_dir.is_none() {
+        bail!("--force needs --output-dir");
+    }
+    if options.output_dir.is_some() {
+        if options.lang != Lang::Rust {
+            bail!("--output-dir only works with --lang rust");
         }
+        options.pub_types = true;
     }
+    Ok(options)
 }
 
-struct Decls {
-    next_index: usize,
-    decls: Vec<String>,
+/// Whether the setting of `options` that the flag `flag` gives is set.
+fn is_set(options: &Options, flag: &str) -> bool {
+    match flag {
+        "name" => options.names.contains_key("$"),
+        "format" => options.format.is_some(),
+        "lenient" => options.lenient,
+        "stream" => options.stream,
+        "har" => options.har,
+        "from-schema" => options.from_schema,
+        "dump-type" => options.dump_type,
+        "example-doc" => options.example_doc,
+        "diff" => options.diff.is_some(),
+        "interactive" => options.interactive,
+        "stats" => options.stats,
+        "schema-state" => options.schema_state.is_some(),
+        "emit-main" => options.emit_main.is_some(),
+        "min-presence" => options.min_presence.is_some(),
+        "detect-drift" => options.detect_drift,
+        _ => unreachable!("no setting for --{}", flag),
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// The value of an option if it was given on the command line, rather
+/// than left to its default.
+fn given<'a>(app: &'a ArgMatches, name: &str) -> Option<&'a str> {
+    app.value_of(name).filter(|_| app.occurrences_of(name) > 0)
+}
 
-    use json::short::Short;
+/// Parse the value of an option.
+fn parse<T: FromStr<Err = String>>(value: &str) -> Result<T> {
+    value.parse().map_err(anyhow::Error::msg)
+}
 
-    #[test]
-    fn basic_types() {
-        assert_eq!(DataType::from_json_value(&JsonValue::Null), DataType::Null);
-        assert_eq!(
-            DataType::from_json_value(&JsonValue::String("hello".to_string())),
-            DataType::String
-        );
+/// Combine the setting of a feature from the configuration file,
+/// `config`, with the flags that turn it on and off from the command
+/// line, which take precedence.
+fn flag(app: &ArgMatches, name: &str, config: bool) -> bool {
+    let (_, negation) = NEGATED_FLAGS
+        .iter()
+        .find(|(flag, _)| *flag == name)
+        .unwrap_or_else(|| unreachable!("--{} can't be turned off", name));
+    // Only the last of the two given is present.
+    match (app.is_present(name), app.is_present(negation)) {
+        (true, _) => true,
+        (_, true) => false,
+        _ => config,
+    }
+}
 
-        let s = "foo";
-        if json::short::MAX_LEN >= s.len() {
-            // SAFETY: A Short is defined to be able to store at least
-            // MAX_LEN bytes, and MAX_LEN >= the length of s,
-            // therefore a Short is able to store a string of s's
-            // length.
-            let s = JsonValue::Short(unsafe { Short::from_slice(s) });
+/// Combine the setting of a feature from the configuration file with
+/// whether it's `on`, as `flag` gives, and the choice of how it works
+/// from the command line, which takes precedence.
+fn switch<T: Default>(config: Option<T>, on: bool, choice: Option<T>) -> Option<T> {
+    match on {
+        false => None,
+        true => Some(choice.or(config).unwrap_or_default()),
+    }
+}
 
-            assert_eq!(DataType::from_json_value(&s), DataType::String);
-        } else {
-            panic!("Failed to test Short");
+/// Load the names of integer enum codes from a JSON file of the form
+/// `{"$.status": {"0": "Active", "1": "Inactive"}}`.
+fn load_code_names(path: &Path) -> Result<BTreeMap<String, BTreeMap<i64, String>>> {
+    let text = read_to_string(path).with_context(|| format!("unable to read {:?}", path))?;
+    let root = json::parse(&text).with_context(|| format!("unable to parse {:?}", path))?;
+    let mut code_names = BTreeMap::new();
+    for (enum_path, names) in root.entries() {
+        let mut by_code = BTreeMap::new();
+        for (code, name) in names.entries() {
+            match (code.parse::<i64>(), name.as_str()) {
+                (Ok(code), Some(name)) => by_code.insert(code, name.to_string()),
+                _ => bail!(
+                    "bad name for code {:?} of {:?} in {:?}",
+                    code,
+                    enum_path,
+                    path
+                ),
+            };
+        }
+        code_names.insert(enum_path.to_string(), by_code);
+    }
+    Ok(code_names)
+}
+
+/// Run the `merge` subcommand.
+fn run_merge(app: &ArgMatches, logger: &Logger) -> Result<()> {
+    let state_path = Path::new(app.value_of_os("state").expect("Required option"));
+    let old = if app.is_present("reset") {
+        None
+    } else {
+        State::load(state_path)?
+    };
+    let mut state = old.clone().unwrap_or_default();
+    let options = options(app, logger)?;
+    let mut drift = DriftReport::new("$");
+    let mut interner = Interner::with_options(&options);
+    let mut typ = interner.intern(&state.typ);
+
+    for filename in app.values_of_os("files").expect("Required option") {
+        let filename = Path::new(filename);
+        let contents = String::from_utf8(read_input(filename, &[])?)
+            .with_context(|| format!("{} isn't UTF-8", input_name(filename)))?;
+        let first = state.records;
+        for record in Value::parse_json_lines(&contents) {
+            let record = record.map_err(|e| in_input(e, filename))?;
+            let record_type = interner.infer(&record)?;
+            if options.detect_drift {
+                let record_type = interner.to_data_type(record_type);
+                drift.unify(&mut state.typ, record_type, state.records);
+                if let Some(threshold) = options.int_enum_threshold {
+                    state.typ.limit_int_enums(threshold);
+                }
+            }
+            typ = interner.unify(typ, record_type);
+            state.stats.observe(&record);
+            state.records += 1;
         }
+        logger.note(format_args!(
+            "merged {} records from {}",
+            state.records - first,
+            input_name(filename)
+        ));
+    }
 
-        assert_eq!(
-            DataType::from_json_value(&JsonValue::Boolean(true)),
-            DataType::Bool
-        );
+    let (typ, mut warnings) = interner.finish(typ);
+    state.typ = typ;
+    state.save(state_path)?;
+    logger.note(format_args!(
+        "saved the type of {} records to {:?}",
+        state.records, state_path
+    ));
+    if options.stats {
+        print!("{}", state.stats.report());
+    }
+    if options.detect_drift {
+        report_drift(&options, logger, &drift);
+    }
+
+    let changed = old.is_none_or(|old| old.typ != state.typ);
+    if changed && app.is_present("emit") {
+        let mut decls = new_decls(&options, state.stats.clone())?;
+        state.typ.declare(&mut decls);
+        save_schema_state(&options, logger, &decls)?;
+        write_decls(&options, logger, &decls)?;
+        warnings.extend(decls.warnings().clone());
+    }
+
+    report_warnings(&options, logger, &warnings)?;
+    if changed {
+        exit(EXIT_CHANGED);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All of the input at `path`, with stdin reading `stdin`.
+    fn read_from(path: &str, stdin: &'static [u8]) -> String {
+        let mut text = String::new();
+        open_input_from(Path::new(path), &[], || Box::new(stdin))
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
     }
 
     #[test]
-    fn numbers() {
+    fn stdin() {
+        let options = Options::default();
         assert_eq!(
-            DataType::from_json_value(&JsonValue::Number(10.into())),
-            DataType::Int
+            input_paths(None::<std::iter::Empty<&Path>>, &options).unwrap(),
+            vec![PathBuf::from(STDIN)]
         );
         assert_eq!(
-            DataType::from_json_value(&JsonValue::Number((10.5).into())),
-            DataType::Float
+            input_paths(Some(std::iter::once(Path::new(STDIN))), &options).unwrap(),
+            vec![PathBuf::from(STDIN)]
         );
+        assert_eq!(read_from(STDIN, b"[1, 2]\n"), "[1, 2]\n");
+
+        // Compressed input on stdin is told apart by how it starts.
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(b"{\"a\": 1}").unwrap();
+        let compressed = encoder.finish().unwrap().leak();
+        assert_eq!(read_from(STDIN, compressed), "{\"a\": 1}");
     }
 
     #[test]
-    fn unification() {
-        assert_eq!(
-            DataType::unify(DataType::String, DataType::Bool),
-            DataType::Variant(vec![DataType::String, DataType::Bool].into_iter().collect())
-        );
+    fn files_with_different_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.json"), dir.path().join("b.json"));
+        std::fs::write(&a, r#"{"id": 1, "name": "x"}"#).unwrap();
+        std::fs::write(&b, r#"{"id": 2, "admin": true}"#).unwrap();
+
+        let options = Options::default();
+        let mut analysis = Analysis::new(&options, false, false);
+        read_documents(
+            &options,
+            &Logger::new(Level::Quiet),
+            &[a, b],
+            &[],
+            &mut analysis,
+        )
+        .unwrap();
+        let optional = |typ| DataType::Variant(vec![DataType::Null, typ].into_iter().collect());
         assert_eq!(
-            DataType::unify(
-                DataType::Variant(vec![DataType::String, DataType::Bool].into_iter().collect()),
-                DataType::Null
-            ),
-            DataType::Variant(
-                vec![DataType::String, DataType::Bool, DataType::Null]
-                    .into_iter()
-                    .collect()
+            analysis.typ(),
+            DataType::Object(
+                vec![
+                    ("admin".to_string(), optional(DataType::Bool)),
+                    ("id".to_string(), DataType::Int),
+                    ("name".to_string(), optional(DataType::String)),
+                ]
+                .into_iter()
+                .collect()
             )
         );
     }
 
     #[test]
-    fn floats_override_ints() {
+    fn expanding_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("sub/deep")).unwrap();
+        for file in [
+            "a.json",
+            "notes.txt",
+            "e.yml",
+            "sub/c.json.gz",
+            "sub/d.json.zst",
+            "sub/deep/b.json",
+            "sub/deep/f.yaml.gz",
+        ] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        let expand = |arg: &Path, format| {
+            expand_inputs(std::iter::once(arg), format)
+                .unwrap()
+                .into_iter()
+                .map(|path| path.strip_prefix(root).unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+
         assert_eq!(
-            DataType::unify(DataType::Int, DataType::Float),
-            DataType::Float
+            expand(root, None),
+            paths(&[
+                "a.json",
+                "sub/c.json.gz",
+                "sub/d.json.zst",
+                "sub/deep/b.json"
+            ])
         );
-    }
-
-    #[test]
-    fn structs() {
-        let a = DataType::from_json_value(&json::object! {
-            "null": null,
-            "string": "hello",
-            "number": 123,
-            "bool": true,
-            "object": {
-                "hello": "world"
-            },
-            "arr": [1, 2, 3]
-        });
-        let b = DataType::Object(
-            [
-                ("null", DataType::Null),
-                ("string", DataType::String),
-                ("number", DataType::Int),
-                ("bool", DataType::Bool),
-                (
-                    "object",
-                    DataType::Object(
-                        [("hello", DataType::String)]
-                            .iter()
-                            .map(|(name, typ)| (name.to_string(), (*typ).clone()))
-                            .collect::<BTreeMap<String, DataType>>(),
-                    ),
-                ),
-                ("arr", DataType::Array(Box::new(DataType::Int))),
-            ]
-            .iter()
-            .map(|(name, typ)| (name.to_string(), (*typ).clone()))
-            .collect::<BTreeMap<String, DataType>>(),
+        assert_eq!(
+            expand(root, Some(Format::Yaml)),
+            paths(&["e.yml", "sub/deep/f.yaml.gz"])
+        );
+        assert_eq!(expand(&root.join("notes.txt"), None), paths(&["notes.txt"]));
+        assert_eq!(
+            expand(&root.join("sub/*.json.*"), None),
+            paths(&["sub/c.json.gz", "sub/d.json.zst"])
+        );
+        assert_eq!(
+            expand(&root.join("**/b.json"), None),
+            paths(&["sub/deep/b.json"])
         );
 
-        assert_eq!(a, b);
+        assert!(expand_inputs(std::iter::once(&*root.join("*.toml")), None).is_err());
+        assert!(
+            expand_inputs(std::iter::once(&*root.join("sub/deep")), Some(Format::Csv)).is_err()
+        );
     }
 
     #[test]
-    fn object_unification() {
-        let arr = JsonValue::Array(vec![
-            JsonValue::Number(1.into()),
-            JsonValue::String("hello".to_string()),
-        ]);
-        let arr_typ = DataType::Array(Box::new(DataType::Variant(
-            vec![DataType::Int, DataType::String].into_iter().collect(),
-        )));
-        assert_eq!(DataType::from_json_value(&arr), arr_typ);
-
-        let objs = JsonValue::Array(vec![
-            json::object! {
-                "foo": "bar"
-            },
-            json::object! {
-                "foo": 123,
-                "baz": true
-            },
-        ]);
-        let objs_type = DataType::Array(Box::new(DataType::Object(
-            [
-                (
-                    "foo",
-                    DataType::Variant(vec![DataType::String, DataType::Int].into_iter().collect()),
-                ),
-                (
-                    "baz",
-                    DataType::Variant(vec![DataType::Bool, DataType::Null].into_iter().collect()),
-                ),
-            ]
-            .iter()
-            .map(|(name, typ)| (name.to_string(), (*typ).clone()))
-            .collect::<BTreeMap<String, DataType>>(),
-        )));
+    fn flags_override_the_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        std::fs::write(
+            &config,
+            "serde = true\ndetect_dates = true\nno_singularize = true\n",
+        )
+        .unwrap();
+        let options_from = |args: &[&str]| {
+            let config = config.to_str().unwrap();
+            let app =
+                app().get_matches_from(["json-analyzer", "--config", config].iter().chain(args));
+            options(&app, &Logger::new(Level::Quiet)).unwrap()
+        };
 
-        assert_eq!(DataType::from_json_value(&objs), objs_type);
+        let options = options_from(&[]);
+        assert!(options.serde && options.detect_dates && options.no_singularize);
+        let options = options_from(&["--no-serde", "--singularize", "--matrix"]);
+        assert!(
+            !options.serde && options.detect_dates && !options.no_singularize && options.matrix
+        );
+        // The last of a flag and its opposite wins.
+        assert!(options_from(&["--no-serde", "--serde"]).serde);
+        assert!(!options_from(&["--serde", "--no-serde"]).serde);
     }
 }
@@ -0,0 +1,1728 @@
+//! Producing the identifiers used in declarations: type names, field
+//! names and enum variant names.
+//!
+//! Names come from the input's keys, from the options, or are made up,
+//! and any of them can be unusable as they are: keys can have spaces
+//! or start with digits, and can be keywords. Every identifier a
+//! backend writes goes through a [`Namer`], which fixes them up in the
+//! backend's style and keeps them from colliding.
+
+use std::{collections::BTreeSet, str::FromStr};
+
+use crate::{lang::Lang, Options};
+
+/// Rust's keywords, including the reserved ones, which can only be
+/// used as identifiers in their raw form.
+pub const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Rust's keywords in the 2015 edition, which doesn't have the ones
+/// added for `async`, `dyn` and `try` yet.
+const RUST_2015_KEYWORDS: &[&str] = &[
+    "abstract", "as", "become", "box", "break", "const", "continue", "crate", "do", "else", "enum",
+    "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "true", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Python's keywords, which can't be used as identifiers.
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Java's keywords and literals, which can't be used as identifiers.
+const JAVA_KEYWORDS: &[&str] = &[
+    "_",
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Kotlin's hard keywords, which can't be used as identifiers.
+const KOTLIN_KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "class",
+    "continue",
+    "do",
+    "else",
+    "false",
+    "for",
+    "fun",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "return",
+    "super",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typealias",
+    "typeof",
+    "val",
+    "var",
+    "when",
+    "while",
+];
+
+/// C's keywords, which can't be used as identifiers.
+const C_KEYWORDS: &[&str] = &[
+    "auto", "bool", "break", "case", "char", "const", "continue", "default", "do", "double",
+    "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int", "long",
+    "register", "restrict", "return", "short", "signed", "sizeof", "static", "struct", "switch",
+    "true", "typedef", "union", "unsigned", "void", "volatile", "while",
+];
+
+/// C++'s keywords and alternative operators, which can't be used as
+/// identifiers.
+const CPP_KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "and",
+    "and_eq",
+    "asm",
+    "auto",
+    "bitand",
+    "bitor",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "char8_t",
+    "char16_t",
+    "char32_t",
+    "class",
+    "compl",
+    "concept",
+    "const",
+    "consteval",
+    "constexpr",
+    "constinit",
+    "const_cast",
+    "continue",
+    "co_await",
+    "co_return",
+    "co_yield",
+    "decltype",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "dynamic_cast",
+    "else",
+    "enum",
+    "explicit",
+    "export",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "not",
+    "not_eq",
+    "nullptr",
+    "operator",
+    "or",
+    "or_eq",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "reinterpret_cast",
+    "requires",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "static_cast",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "thread_local",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typeid",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "wchar_t",
+    "while",
+    "xor",
+    "xor_eq",
+];
+
+/// Swift's keywords, which can't be used as identifiers without
+/// backticks.
+const SWIFT_KEYWORDS: &[&str] = &[
+    "Any",
+    "as",
+    "associatedtype",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "continue",
+    "default",
+    "defer",
+    "deinit",
+    "do",
+    "else",
+    "enum",
+    "extension",
+    "fallthrough",
+    "false",
+    "fileprivate",
+    "for",
+    "func",
+    "guard",
+    "if",
+    "import",
+    "in",
+    "init",
+    "inout",
+    "internal",
+    "is",
+    "let",
+    "nil",
+    "open",
+    "operator",
+    "private",
+    "protocol",
+    "public",
+    "repeat",
+    "rethrows",
+    "return",
+    "self",
+    "Self",
+    "static",
+    "struct",
+    "subscript",
+    "super",
+    "switch",
+    "throw",
+    "throws",
+    "true",
+    "try",
+    "typealias",
+    "var",
+    "where",
+    "while",
+];
+
+/// Elm's keywords.
+const ELM_KEYWORDS: &[&str] = &[
+    "alias", "as", "case", "else", "exposing", "if", "import", "in", "infix", "let", "module",
+    "of", "port", "then", "type", "where",
+];
+
+/// OCaml's keywords.
+const OCAML_KEYWORDS: &[&str] = &[
+    "and",
+    "as",
+    "assert",
+    "asr",
+    "begin",
+    "class",
+    "constraint",
+    "do",
+    "done",
+    "downto",
+    "else",
+    "end",
+    "exception",
+    "external",
+    "false",
+    "for",
+    "fun",
+    "function",
+    "functor",
+    "if",
+    "in",
+    "include",
+    "inherit",
+    "initializer",
+    "land",
+    "lazy",
+    "let",
+    "lor",
+    "lsl",
+    "lsr",
+    "lxor",
+    "match",
+    "method",
+    "mod",
+    "module",
+    "mutable",
+    "new",
+    "nonrec",
+    "object",
+    "of",
+    "open",
+    "or",
+    "private",
+    "rec",
+    "sig",
+    "struct",
+    "then",
+    "to",
+    "true",
+    "try",
+    "type",
+    "val",
+    "virtual",
+    "when",
+    "while",
+    "with",
+];
+
+/// Scala's keywords, which can only be used as identifiers in
+/// backticks.
+const SCALA_KEYWORDS: &[&str] = &[
+    "abstract",
+    "case",
+    "catch",
+    "class",
+    "def",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "for",
+    "forSome",
+    "given",
+    "if",
+    "implicit",
+    "import",
+    "lazy",
+    "macro",
+    "match",
+    "new",
+    "null",
+    "object",
+    "override",
+    "package",
+    "private",
+    "protected",
+    "return",
+    "sealed",
+    "super",
+    "then",
+    "this",
+    "throw",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "val",
+    "var",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Dart's reserved words. Its built-in identifiers can name fields.
+const DART_KEYWORDS: &[&str] = &[
+    "assert", "break", "case", "catch", "class", "const", "continue", "default", "do", "else",
+    "enum", "extends", "false", "final", "finally", "for", "if", "in", "is", "new", "null",
+    "rethrow", "return", "super", "switch", "this", "throw", "true", "try", "var", "void", "while",
+    "with",
+];
+
+/// Julia's keywords.
+const JULIA_KEYWORDS: &[&str] = &[
+    "abstract",
+    "baremodule",
+    "begin",
+    "break",
+    "catch",
+    "const",
+    "continue",
+    "do",
+    "else",
+    "elseif",
+    "end",
+    "export",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "global",
+    "if",
+    "import",
+    "let",
+    "local",
+    "macro",
+    "module",
+    "mutable",
+    "primitive",
+    "quote",
+    "return",
+    "struct",
+    "true",
+    "try",
+    "type",
+    "using",
+    "where",
+    "while",
+];
+
+/// Zig's keywords and the names of its primitive types, which can only
+/// be used as identifiers in `@"..."`.
+const ZIG_KEYWORDS: &[&str] = &[
+    "addrspace",
+    "align",
+    "allowzero",
+    "and",
+    "anyframe",
+    "anytype",
+    "asm",
+    "async",
+    "await",
+    "bool",
+    "break",
+    "callconv",
+    "catch",
+    "comptime",
+    "const",
+    "continue",
+    "defer",
+    "else",
+    "enum",
+    "errdefer",
+    "error",
+    "export",
+    "extern",
+    "f16",
+    "f32",
+    "f64",
+    "false",
+    "fn",
+    "for",
+    "i32",
+    "i64",
+    "if",
+    "inline",
+    "isize",
+    "linksection",
+    "noalias",
+    "noinline",
+    "nosuspend",
+    "null",
+    "opaque",
+    "or",
+    "orelse",
+    "packed",
+    "pub",
+    "resume",
+    "return",
+    "struct",
+    "suspend",
+    "switch",
+    "test",
+    "threadlocal",
+    "true",
+    "try",
+    "type",
+    "u8",
+    "u32",
+    "u64",
+    "undefined",
+    "union",
+    "unreachable",
+    "usize",
+    "var",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Cap'n Proto's keywords, and the names of its builtin constants.
+const CAPNP_KEYWORDS: &[&str] = &[
+    "annotation",
+    "const",
+    "embed",
+    "enum",
+    "extends",
+    "false",
+    "group",
+    "import",
+    "inf",
+    "interface",
+    "nan",
+    "struct",
+    "true",
+    "union",
+    "using",
+    "void",
+];
+
+/// Thrift's keywords, the names of its base types, and the words it
+/// reserves because they're keywords in the languages it generates.
+const THRIFT_KEYWORDS: &[&str] = &[
+    "BEGIN",
+    "END",
+    "__CLASS__",
+    "__DIR__",
+    "__FILE__",
+    "__FUNCTION__",
+    "__LINE__",
+    "__METHOD__",
+    "__NAMESPACE__",
+    "abstract",
+    "alias",
+    "and",
+    "args",
+    "as",
+    "assert",
+    "begin",
+    "binary",
+    "bool",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "class",
+    "clone",
+    "const",
+    "continue",
+    "cpp_include",
+    "declare",
+    "def",
+    "default",
+    "del",
+    "delete",
+    "do",
+    "double",
+    "dynamic",
+    "elif",
+    "else",
+    "elseif",
+    "elsif",
+    "end",
+    "enddeclare",
+    "endfor",
+    "endforeach",
+    "endif",
+    "endswitch",
+    "endwhile",
+    "ensure",
+    "enum",
+    "except",
+    "exception",
+    "exec",
+    "extends",
+    "false",
+    "finally",
+    "float",
+    "for",
+    "foreach",
+    "from",
+    "function",
+    "global",
+    "goto",
+    "i16",
+    "i32",
+    "i64",
+    "i8",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "include",
+    "inline",
+    "instanceof",
+    "interface",
+    "is",
+    "lambda",
+    "list",
+    "map",
+    "module",
+    "namespace",
+    "native",
+    "new",
+    "next",
+    "nil",
+    "not",
+    "oneway",
+    "optional",
+    "or",
+    "package",
+    "pass",
+    "print",
+    "private",
+    "protected",
+    "public",
+    "raise",
+    "redo",
+    "register",
+    "required",
+    "rescue",
+    "retry",
+    "return",
+    "self",
+    "service",
+    "set",
+    "sizeof",
+    "static",
+    "string",
+    "struct",
+    "super",
+    "switch",
+    "synchronized",
+    "then",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "typedef",
+    "undef",
+    "union",
+    "unless",
+    "unsigned",
+    "until",
+    "use",
+    "uuid",
+    "var",
+    "virtual",
+    "void",
+    "volatile",
+    "when",
+    "while",
+    "with",
+    "xor",
+    "yield",
+];
+
+/// SQL's reserved words, in the standard and in common databases.
+pub(crate) const SQL_KEYWORDS: &[&str] = &[
+    "all",
+    "alter",
+    "and",
+    "any",
+    "as",
+    "asc",
+    "between",
+    "by",
+    "case",
+    "check",
+    "column",
+    "constraint",
+    "create",
+    "cross",
+    "current_date",
+    "current_time",
+    "current_timestamp",
+    "current_user",
+    "default",
+    "delete",
+    "desc",
+    "distinct",
+    "drop",
+    "else",
+    "end",
+    "except",
+    "exists",
+    "false",
+    "fetch",
+    "for",
+    "foreign",
+    "from",
+    "full",
+    "grant",
+    "group",
+    "having",
+    "in",
+    "index",
+    "inner",
+    "insert",
+    "intersect",
+    "into",
+    "is",
+    "join",
+    "key",
+    "left",
+    "like",
+    "limit",
+    "not",
+    "null",
+    "offset",
+    "on",
+    "or",
+    "order",
+    "outer",
+    "primary",
+    "references",
+    "right",
+    "select",
+    "session_user",
+    "set",
+    "some",
+    "table",
+    "then",
+    "to",
+    "true",
+    "union",
+    "unique",
+    "update",
+    "user",
+    "using",
+    "values",
+    "when",
+    "where",
+    "with",
+];
+
+/// Nim's keywords, which can only be used as identifiers in backticks.
+const NIM_KEYWORDS: &[&str] = &[
+    "addr",
+    "and",
+    "as",
+    "asm",
+    "bind",
+    "block",
+    "break",
+    "case",
+    "cast",
+    "concept",
+    "const",
+    "continue",
+    "converter",
+    "defer",
+    "discard",
+    "distinct",
+    "div",
+    "do",
+    "elif",
+    "else",
+    "end",
+    "enum",
+    "except",
+    "export",
+    "finally",
+    "for",
+    "from",
+    "func",
+    "if",
+    "import",
+    "in",
+    "include",
+    "interface",
+    "is",
+    "isnot",
+    "iterator",
+    "let",
+    "macro",
+    "method",
+    "mixin",
+    "mod",
+    "nil",
+    "not",
+    "notin",
+    "object",
+    "of",
+    "or",
+    "out",
+    "proc",
+    "ptr",
+    "raise",
+    "ref",
+    "return",
+    "shl",
+    "shr",
+    "static",
+    "template",
+    "try",
+    "tuple",
+    "type",
+    "using",
+    "var",
+    "when",
+    "while",
+    "xor",
+    "yield",
+];
+
+/// F#'s keywords, including the reserved ones, which can only be used
+/// as identifiers in double backticks.
+const FSHARP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "and",
+    "as",
+    "assert",
+    "base",
+    "begin",
+    "break",
+    "checked",
+    "class",
+    "component",
+    "const",
+    "constraint",
+    "continue",
+    "default",
+    "delegate",
+    "do",
+    "done",
+    "downcast",
+    "downto",
+    "elif",
+    "else",
+    "end",
+    "event",
+    "exception",
+    "extern",
+    "external",
+    "false",
+    "finally",
+    "fixed",
+    "for",
+    "fun",
+    "function",
+    "global",
+    "if",
+    "in",
+    "include",
+    "inherit",
+    "inline",
+    "interface",
+    "internal",
+    "lazy",
+    "let",
+    "match",
+    "member",
+    "mixin",
+    "module",
+    "mutable",
+    "namespace",
+    "new",
+    "not",
+    "null",
+    "of",
+    "open",
+    "or",
+    "override",
+    "parallel",
+    "private",
+    "process",
+    "protected",
+    "public",
+    "pure",
+    "rec",
+    "return",
+    "sealed",
+    "select",
+    "sig",
+    "static",
+    "struct",
+    "tailcall",
+    "then",
+    "to",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "upcast",
+    "use",
+    "val",
+    "virtual",
+    "void",
+    "when",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Rust keywords that can't be used as identifiers even in raw form.
+const RUST_UNRAWABLE: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Editions of Rust that declarations can be written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Edition {
+    /// Rust 2015.
+    E2015,
+
+    /// Rust 2018.
+    E2018,
+
+    /// Rust 2021.
+    #[default]
+    E2021,
+}
+
+impl Edition {
+    /// The keywords of this edition.
+    pub fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Edition::E2015 => RUST_2015_KEYWORDS,
+            Edition::E2018 | Edition::E2021 => RUST_KEYWORDS,
+        }
+    }
+}
+
+impl FromStr for Edition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2015" => Ok(Edition::E2015),
+            "2018" => Ok(Edition::E2018),
+            "2021" => Ok(Edition::E2021),
+            _ => Err(format!("unknown Rust edition {:?}", s)),
+        }
+    }
+}
+
+/// Letter case conventions for identifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Case {
+    /// `PascalCase`.
+    #[default]
+    Pascal,
+
+    /// `snake_case`.
+    Snake,
+
+    /// `camelCase`.
+    Camel,
+}
+
+impl Case {
+    /// Write `name` in this case, treating anything but letters and
+    /// digits as separators between words, as well as changes from
+    /// lower to upper case. Only ASCII letters and digits are kept if
+    /// `ascii` is set.
+    pub fn apply(self, name: &str, ascii: bool) -> String {
+        let mut s = String::new();
+        for (idx, word) in words(name, ascii).into_iter().enumerate() {
+            let mut chars = word.chars();
+            let first = chars.next().expect("words aren't empty");
+            match self {
+                Case::Snake => {
+                    if idx > 0 {
+                        s.push('_');
+                    }
+                    s.extend(word.chars().flat_map(char::to_lowercase));
+                }
+                Case::Camel if idx == 0 => s.extend(word.chars().flat_map(char::to_lowercase)),
+                Case::Pascal | Case::Camel => {
+                    s.extend(first.to_uppercase());
+                    s.extend(chars.flat_map(char::to_lowercase));
+                }
+            }
+        }
+        s
+    }
+
+    /// What goes between a name and the number added to tell it apart
+    /// from another.
+    fn joiner(self) -> &'static str {
+        match self {
+            Case::Snake => "_",
+            Case::Pascal | Case::Camel => "",
+        }
+    }
+}
+
+impl FromStr for Case {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pascal" => Ok(Case::Pascal),
+            "snake" => Ok(Case::Snake),
+            "camel" => Ok(Case::Camel),
+            _ => Err(format!("unknown case {:?}", s)),
+        }
+    }
+}
+
+/// Split `name` into words. A word ends at anything but a letter or
+/// digit, before an upper case letter that follows a lower case one,
+/// and before the last of a run of capitals that's followed by a lower
+/// case letter, so that `HTTPServer` is `HTTP` and `Server`. Digits
+/// stay with the word before them.
+fn words(name: &str, ascii: bool) -> Vec<String> {
+    let chars = name
+        .chars()
+        .map(|c| match c.is_alphanumeric() && (c.is_ascii() || !ascii) {
+            true => Some(c),
+            false => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (idx, c) in chars.iter().enumerate() {
+        let c = match c {
+            Some(c) => *c,
+            None => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+        };
+        let prev = idx.checked_sub(1).and_then(|idx| chars[idx]);
+        let next = chars.get(idx + 1).copied().flatten();
+        let boundary = c.is_uppercase()
+            && match prev {
+                Some(prev) if prev.is_lowercase() || prev.is_numeric() => true,
+                Some(prev) if prev.is_uppercase() => next.is_some_and(char::is_lowercase),
+                _ => false,
+            };
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// How a backend writes identifiers.
+#[derive(Debug, Clone)]
+pub struct Style {
+    /// The case of type names that are made up.
+    pub type_case: Case,
+
+    /// Text to put before and after type names that are made up,
+    /// which is then put in `type_case` along with the rest.
+    pub type_affixes: (String, String),
+
+    /// The case that field names are put in when they have to be
+    /// changed.
+    pub field_case: Case,
+
+    /// The case that variant names are put in when they have to be
+    /// changed.
+    pub variant_case: Case,
+
+    /// Words that can't be used as identifiers as they are.
+    pub keywords: &'static [&'static str],
+
+    /// Keywords that can't be made into raw identifiers either.
+    pub unrawable: &'static [&'static str],
+
+    /// The prefix that makes a keyword usable as an identifier, if
+    /// there is one.
+    pub raw_prefix: Option<&'static str>,
+
+    /// Whether identifiers can only have ASCII letters and digits.
+    pub ascii: bool,
+}
+
+impl Style {
+    /// The style of the backend chosen by `options`, with the type name
+    /// conventions it asks for.
+    pub fn new(options: &Options) -> Self {
+        let type_affixes = (options.type_prefix.clone(), options.type_suffix.clone());
+        match options.lang {
+            Lang::Rust | Lang::Arrow => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: options.rust_edition.keywords(),
+                unrawable: RUST_UNRAWABLE,
+                raw_prefix: Some("r#"),
+                ascii: false,
+            },
+            Lang::Proto => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: &[],
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::TypeScript | Lang::Zod | Lang::IoTs | Lang::Mongoose => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Pascal,
+                keywords: &[],
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: false,
+            },
+            Lang::Java => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Pascal,
+                keywords: JAVA_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: false,
+            },
+            Lang::Kotlin => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Pascal,
+                keywords: KOTLIN_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: false,
+            },
+            Lang::CSharp => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Pascal,
+                variant_case: Case::Pascal,
+                keywords: &[],
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: false,
+            },
+            Lang::C => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: C_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Cpp => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: CPP_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Swift => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Camel,
+                keywords: SWIFT_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: false,
+            },
+            Lang::Elm => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Pascal,
+                keywords: ELM_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            // Type names that start with a capital would be
+            // constructors.
+            Lang::OCaml => Style {
+                type_case: Case::Snake,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: OCAML_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Scala => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Pascal,
+                keywords: SCALA_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: false,
+            },
+            Lang::Dart => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Pascal,
+                keywords: DART_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Julia => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: JULIA_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: false,
+            },
+            Lang::Zig => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Snake,
+                keywords: ZIG_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Avro | Lang::JsonSchema | Lang::Clojure | Lang::BigQuery | Lang::Parquet => {
+                Style {
+                    type_case: options.type_case,
+                    type_affixes,
+                    field_case: Case::Snake,
+                    variant_case: Case::Pascal,
+                    keywords: &[],
+                    unrawable: &[],
+                    raw_prefix: None,
+                    ascii: true,
+                }
+            }
+            // SQL quotes keywords where it writes them, rather than
+            // renaming them.
+            Lang::FlatBuffers | Lang::GraphQL | Lang::Sql => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: &[],
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Capnp => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Camel,
+                keywords: CAPNP_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Thrift => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: THRIFT_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Nim => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Pascal,
+                keywords: NIM_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::FSharp => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Camel,
+                variant_case: Case::Pascal,
+                keywords: FSHARP_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: true,
+            },
+            Lang::Python | Lang::Pydantic | Lang::PySpark => Style {
+                type_case: options.type_case,
+                type_affixes,
+                field_case: Case::Snake,
+                variant_case: Case::Pascal,
+                keywords: PYTHON_KEYWORDS,
+                unrawable: &[],
+                raw_prefix: None,
+                ascii: false,
+            },
+        }
+    }
+
+    /// Whether `name` can be used as an identifier as it is, other than
+    /// by being a keyword.
+    pub fn is_identifier(&self, name: &str) -> bool {
+        let allowed = |c: char| c == '_' || (c.is_alphanumeric() && (c.is_ascii() || !self.ascii));
+        let mut chars = name.chars();
+        name != "_"
+            && chars.next().is_some_and(|c| allowed(c) && !c.is_numeric())
+            && chars.all(allowed)
+    }
+
+    /// Make `name`, which is a valid identifier apart from possibly
+    /// being a keyword, usable: raw if it's a keyword that can be, and
+    /// followed by an underscore if it's one that can't.
+    pub fn unkeyword(&self, name: String) -> String {
+        if !self.keywords.contains(&name.as_str()) {
+            return name;
+        }
+        match self.raw_prefix {
+            Some(prefix) if !self.unrawable.contains(&name.as_str()) => {
+                format!("{}{}", prefix, name)
+            }
+            _ => name + "_",
+        }
+    }
+
+    /// Make `name` into a usable identifier in `case`, or into
+    /// `fallback` if nothing's left of it. Names that already are
+    /// identifiers are kept as they are, apart from keywords.
+    pub fn sanitize(&self, name: &str, case: Case, fallback: &str) -> String {
+        if self.is_identifier(name) {
+            return self.unkeyword(name.to_string());
+        }
+        let mut s = case.apply(name, self.ascii);
+        if s.is_empty() {
+            s = fallback.to_string();
+        } else if s.starts_with(char::is_numeric) {
+            s.insert(0, '_');
+        }
+        self.unkeyword(s)
+    }
+
+    /// Name each of a set of things that share a namespace, such as
+    /// the fields of a struct, given the names they'd like. Names that
+    /// can be used as they are keep them, and the rest are sanitized
+    /// into `case` and numbered to tell them apart from the others.
+    pub fn unique(&self, names: &[&str], case: Case, fallback: &str) -> Vec<String> {
+        let mut scope = Scope::new();
+        for name in names {
+            if self.is_identifier(name) && !self.keywords.contains(name) {
+                scope.reserve(name);
+            }
+        }
+        names
+            .iter()
+            .map(|name| {
+                if self.is_identifier(name) && !self.keywords.contains(name) {
+                    return name.to_string();
+                }
+                scope.claim(&self.sanitize(name, case, fallback), case.joiner())
+            })
+            .collect()
+    }
+}
+
+/// A set of names that are taken.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    used: BTreeSet<String>,
+    ignore_case: bool,
+}
+
+impl Scope {
+    /// Create a scope with no names taken.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a scope where names that only differ in case count as
+    /// the same, as they do for file names on some systems.
+    pub fn ignoring_case() -> Self {
+        Scope {
+            used: BTreeSet::new(),
+            ignore_case: true,
+        }
+    }
+
+    /// Mark `name` as taken.
+    pub fn reserve(&mut self, name: &str) {
+        let key = self.key(name);
+        self.used.insert(key);
+    }
+
+    /// Whether `name` is taken.
+    pub fn contains(&self, name: &str) -> bool {
+        self.used.contains(&self.key(name))
+    }
+
+    /// Take `name` if it's free, or else the first of `name`, then
+    /// `joiner` and 2, 3 and so on, that is.
+    pub fn claim(&mut self, name: &str, joiner: &str) -> String {
+        let mut claimed = name.to_string();
+        let mut n = 1;
+        while self.contains(&claimed) {
+            n += 1;
+            claimed = format!("{}{}{}", name, joiner, n);
+        }
+        self.reserve(&claimed);
+        claimed
+    }
+
+    /// The form of `name` that's compared with the others.
+    fn key(&self, name: &str) -> String {
+        match self.ignore_case {
+            true => name.to_lowercase(),
+            false => name.to_string(),
+        }
+    }
+}
+
+/// Names the types declared for a data type, keeping the names that
+/// are made up from colliding with each other or with those given.
+#[derive(Debug, Clone)]
+pub struct Namer {
+    style: Style,
+    types: Scope,
+    next_index: usize,
+}
+
+impl Namer {
+    /// Create a namer for the backend and conventions chosen by
+    /// `options`. The names the options give types are reserved up
+    /// front, so that no made-up name takes them first.
+    pub fn new(options: &Options) -> Self {
+        let mut types = Scope::new();
+        for name in options.names.values() {
+            types.reserve(name);
+        }
+        Namer {
+            style: Style::new(options),
+            types,
+            next_index: 0,
+        }
+    }
+
+    /// The style names are written in.
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    /// The name for a type: `given`, made usable, or else a new name
+    /// that isn't taken.
+    pub fn type_name(&mut self, given: Option<&str>) -> String {
+        if let Some(given) = given {
+            let name = self.style.sanitize(given, self.style.type_case, "");
+            if !name.is_empty() {
+                self.types.reserve(&name);
+                return name;
+            }
+        }
+
+        loop {
+            let (prefix, suffix) = &self.style.type_affixes;
+            let base = self.style.type_case.apply(
+                &format!("{} Data{} {}", prefix, self.next_index, suffix),
+                false,
+            );
+            self.next_index += 1;
+            let name = self.style.sanitize(&base, self.style.type_case, "");
+            if !self.types.contains(&name) {
+                self.types.reserve(&name);
+                return name;
+            }
+        }
+    }
+
+    /// A name for a helper function or module, in snake case, which
+    /// no other helper is given.
+    pub fn helper_name(&mut self, name: &str) -> String {
+        let name = self
+            .style
+            .sanitize(&Case::Snake.apply(name, true), Case::Snake, "helper");
+        self.types.claim(&name, Case::Snake.joiner())
+    }
+
+    /// Whether `name` has already been given to a type.
+    pub fn is_taken(&self, name: &str) -> bool {
+        self.types.contains(name)
+    }
+
+    /// Names for the fields of a type, given the keys of the members
+    /// they hold.
+    pub fn fields(&self, keys: &[&str]) -> Vec<String> {
+        self.style.unique(keys, self.style.field_case, "field")
+    }
+
+    /// Names for the fields of a type, like `fields`, but all in the
+    /// case of field names, for languages where fields can be named
+    /// apart from the keys they're read from. Keys that already are
+    /// such names keep them, and the rest are numbered to tell them
+    /// apart from the others.
+    pub fn cased_fields(&self, keys: &[&str]) -> Vec<String> {
+        let case = self.style.field_case;
+        let cased = keys
+            .iter()
+            .map(|key| {
+                self.style
+                    .sanitize(&case.apply(key, self.style.ascii), case, "field")
+            })
+            .collect::<Vec<_>>();
+        let mut scope = Scope::new();
+        for (key, name) in keys.iter().zip(&cased) {
+            if key == name {
+                scope.reserve(name);
+            }
+        }
+        keys.iter()
+            .zip(cased)
+            .map(|(key, name)| match *key == name {
+                true => name,
+                false => scope.claim(&name, case.joiner()),
+            })
+            .collect()
+    }
+
+    /// Names for the variants of an enum, given the names they'd like.
+    pub fn variants(&self, names: &[&str]) -> Vec<String> {
+        self.style.unique(names, self.style.variant_case, "Variant")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust() -> Style {
+        Style::new(&Options::default())
+    }
+
+    fn proto() -> Style {
+        Style::new(&Options {
+            lang: Lang::Proto,
+            ..Options::default()
+        })
+    }
+
+    #[test]
+    fn cases() {
+        for (name, pascal, snake, camel) in [
+            ("user_id", "UserId", "user_id", "userId"),
+            ("userID", "UserId", "user_id", "userId"),
+            ("HTTPServer", "HttpServer", "http_server", "httpServer"),
+            ("content-type", "ContentType", "content_type", "contentType"),
+            ("Data12", "Data12", "data12", "data12"),
+            ("  first   name ", "FirstName", "first_name", "firstName"),
+            ("USER", "User", "user", "user"),
+            (
+                "straße nummer",
+                "StraßeNummer",
+                "straße_nummer",
+                "straßeNummer",
+            ),
+        ] {
+            assert_eq!(Case::Pascal.apply(name, false), pascal, "{:?}", name);
+            assert_eq!(Case::Snake.apply(name, false), snake, "{:?}", name);
+            assert_eq!(Case::Camel.apply(name, false), camel, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn unicode() {
+        assert!(rust().is_identifier("café"));
+        assert!(rust().is_identifier("名前"));
+        assert!(!proto().is_identifier("café"));
+        assert_eq!(
+            rust().sanitize("café au lait", Case::Snake, "x"),
+            "café_au_lait"
+        );
+        assert_eq!(
+            proto().sanitize("café au lait", Case::Snake, "x"),
+            "caf_au_lait"
+        );
+        assert_eq!(proto().sanitize("名前", Case::Snake, "field"), "field");
+        assert_eq!(Case::Pascal.apply("éclair", false), "Éclair");
+    }
+
+    #[test]
+    fn digits_first() {
+        assert_eq!(
+            rust().sanitize("2021-01-01", Case::Snake, "x"),
+            "_2021_01_01"
+        );
+        assert_eq!(rust().sanitize("3d", Case::Pascal, "x"), "_3d");
+        assert_eq!(rust().sanitize("_3d", Case::Pascal, "x"), "_3d");
+        assert!(!rust().is_identifier("1"));
+    }
+
+    #[test]
+    fn empty_after_sanitization() {
+        for name in ["", "_", "!!!", "-", " "] {
+            assert_eq!(rust().sanitize(name, Case::Snake, "field"), "field");
+        }
+        assert_eq!(
+            rust().unique(&["", "?"], Case::Snake, "field"),
+            ["field", "field_2"]
+        );
+
+        let mut options = Options::default();
+        options.names.insert("$".to_string(), "???".to_string());
+        assert_eq!(Namer::new(&options).type_name(Some("???")), "Data0");
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(rust().sanitize("type", Case::Snake, "x"), "r#type");
+        assert_eq!(rust().sanitize("self", Case::Snake, "x"), "self_");
+        assert_eq!(rust().sanitize("Self", Case::Pascal, "x"), "Self_");
+        assert_eq!(proto().sanitize("type", Case::Snake, "x"), "type");
+        assert_eq!(
+            rust().unique(&["type", "r#type"], Case::Snake, "x"),
+            ["r#type", "r_type"]
+        );
+    }
+
+    #[test]
+    fn editions() {
+        let style = |edition: &str| {
+            Style::new(&Options {
+                rust_edition: edition.parse().unwrap(),
+                ..Options::default()
+            })
+        };
+        assert_eq!(style("2015").sanitize("dyn", Case::Snake, "x"), "dyn");
+        assert_eq!(style("2015").sanitize("async", Case::Snake, "x"), "async");
+        assert_eq!(style("2015").sanitize("type", Case::Snake, "x"), "r#type");
+        for edition in ["2018", "2021"] {
+            assert_eq!(style(edition).sanitize("dyn", Case::Snake, "x"), "r#dyn");
+            assert_eq!(style(edition).sanitize("try", Case::Snake, "x"), "r#try");
+        }
+        assert!("2024".parse::<Edition>().is_err());
+    }
+
+    #[test]
+    fn collision_cascades() {
+        // Keys that are fine as they are keep their names, and the rest
+        // are numbered around them.
+        assert_eq!(
+            rust().unique(&["a b", "a-b", "a_b", "a_b_2"], Case::Snake, "field"),
+            ["a_b_3", "a_b_4", "a_b", "a_b_2"]
+        );
+        assert_eq!(
+            rust().unique(
+                &["in progress", "In-Progress", "InProgress"],
+                Case::Pascal,
+                "V"
+            ),
+            ["InProgress2", "InProgress3", "InProgress"]
+        );
+
+        let mut scope = Scope::ignoring_case();
+        scope.reserve("mod");
+        assert_eq!(scope.claim("Mod", "_"), "Mod_2");
+        assert_eq!(scope.claim("user", "_"), "user");
+        assert_eq!(scope.claim("USER", "_"), "USER_2");
+        assert_eq!(scope.claim("user", "_"), "user_3");
+    }
+
+    #[test]
+    fn type_names() {
+        let mut options = Options::default();
+        options.names.insert("$".to_string(), "Data1".to_string());
+        let mut namer = Namer::new(&options);
+        assert_eq!(namer.type_name(None), "Data0");
+        assert_eq!(namer.type_name(Some("Data1")), "Data1");
+        assert_eq!(namer.type_name(None), "Data2");
+        assert_eq!(namer.type_name(Some("first name")), "FirstName");
+
+        let namer = |type_case, prefix: &str, suffix: &str| {
+            Namer::new(&Options {
+                type_case,
+                type_prefix: prefix.to_string(),
+                type_suffix: suffix.to_string(),
+                ..Options::default()
+            })
+        };
+        assert_eq!(namer(Case::Pascal, "T", "").type_name(None), "TData0");
+        assert_eq!(namer(Case::Pascal, "", "Dto").type_name(None), "Data0Dto");
+        assert_eq!(namer(Case::Snake, "", "").type_name(None), "data0");
+        assert_eq!(namer(Case::Camel, "my", "").type_name(None), "myData0");
+    }
+}
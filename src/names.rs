@@ -0,0 +1,163 @@
+//! Rules for deriving type names from the keys of the input.
+
+/// Words whose plural is irregular, as `(plural, singular)` pairs.
+const IRREGULAR: &[(&str, &str)] = &[
+    ("people", "person"),
+    ("children", "child"),
+    ("men", "man"),
+    ("women", "woman"),
+    ("mice", "mouse"),
+    ("geese", "goose"),
+    ("feet", "foot"),
+    ("teeth", "tooth"),
+    ("oxen", "ox"),
+    ("indices", "index"),
+    ("vertices", "vertex"),
+    ("matrices", "matrix"),
+    ("criteria", "criterion"),
+    ("phenomena", "phenomenon"),
+    ("analyses", "analysis"),
+    ("crises", "crisis"),
+    ("theses", "thesis"),
+    ("leaves", "leaf"),
+    ("halves", "half"),
+    ("knives", "knife"),
+    ("lives", "life"),
+    ("wives", "wife"),
+    ("shelves", "shelf"),
+    ("wolves", "wolf"),
+    ("movies", "movie"),
+    ("cookies", "cookie"),
+    ("quizzes", "quiz"),
+];
+
+/// Words that are the same in the singular and plural, or that are
+/// normally used without a singular.
+const UNCOUNTABLE: &[&str] = &[
+    "data",
+    "metadata",
+    "series",
+    "species",
+    "news",
+    "info",
+    "information",
+    "equipment",
+    "media",
+    "sheep",
+    "fish",
+    "deer",
+];
+
+/// Guess the singular form of an English plural, such as the key of an
+/// array, for naming the type of its elements. Only the last word of a
+/// `snake_case`, `kebab-case` or `camelCase` key is changed, and words
+/// that don't look plural are returned unchanged.
+pub fn singularize(word: &str) -> String {
+    let start = last_word_start(word);
+    let (prefix, last) = word.split_at(start);
+    let lower = last.to_lowercase();
+
+    if UNCOUNTABLE.contains(&lower.as_str()) {
+        return word.to_string();
+    }
+    if let Some(&(_, singular)) = IRREGULAR.iter().find(|(plural, _)| *plural == lower) {
+        return format!("{}{}", prefix, match_case(last, singular));
+    }
+
+    // Only ASCII suffixes are stripped below, so byte offsets into
+    // `last` line up with those into `lower`.
+    let stem = |n: usize| &last[..last.len() - n];
+    let singular = if lower.len() > 3 && lower.ends_with("ies") {
+        format!("{}{}", stem(3), match_case(&last[last.len() - 3..], "y"))
+    } else if ["sses", "shes", "ches", "xes", "zes", "uses"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+    {
+        stem(2).to_string()
+    } else if lower.ends_with("ss") || lower.ends_with("us") || lower.ends_with("is") {
+        last.to_string()
+    } else if lower.len() > 1 && lower.ends_with('s') {
+        stem(1).to_string()
+    } else {
+        last.to_string()
+    };
+    format!("{}{}", prefix, singular)
+}
+
+/// The byte index at which the last word of an identifier starts.
+fn last_word_start(word: &str) -> usize {
+    let mut start = 0;
+    let mut prev = None;
+    for (idx, c) in word.char_indices() {
+        if matches!(prev, Some('_' | '-' | ' ' | '.'))
+            || (c.is_uppercase() && prev.is_some_and(char::is_lowercase))
+        {
+            start = idx;
+        }
+        prev = Some(c);
+    }
+    start
+}
+
+/// Write `replacement` in the case of `original`: all capitals if
+/// `original` is, capitalized if `original` starts with a capital, and
+/// lower case otherwise.
+fn match_case(original: &str, replacement: &str) -> String {
+    if original.chars().count() > 1 && !original.chars().any(char::is_lowercase) {
+        replacement.to_uppercase()
+    } else if original.starts_with(char::is_uppercase) {
+        let mut chars = replacement.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    } else {
+        replacement.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn singular_words() {
+        for (plural, singular) in [
+            ("users", "user"),
+            ("addresses", "address"),
+            ("statuses", "status"),
+            ("status", "status"),
+            ("series", "series"),
+            ("people", "person"),
+            ("children", "child"),
+            ("data", "data"),
+            ("boxes", "box"),
+            ("matches", "match"),
+            ("buses", "bus"),
+            ("companies", "company"),
+            ("categories", "category"),
+            ("movies", "movie"),
+            ("analysis", "analysis"),
+            ("leaves", "leaf"),
+            ("s", "s"),
+            ("", ""),
+        ] {
+            assert_eq!(singularize(plural), singular, "{}", plural);
+        }
+    }
+
+    #[test]
+    fn compound_words() {
+        for (plural, singular) in [
+            ("user_addresses", "user_address"),
+            ("billingAddresses", "billingAddress"),
+            ("ChildNodes", "ChildNode"),
+            ("MemberPeople", "MemberPerson"),
+            ("order-statuses", "order-status"),
+            ("CATEGORIES", "CATEGORY"),
+            ("raw_data", "raw_data"),
+        ] {
+            assert_eq!(singularize(plural), singular, "{}", plural);
+        }
+    }
+}
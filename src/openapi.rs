@@ -0,0 +1,304 @@
+//! Reading of OpenAPI 3 documents, for the types of the component
+//! schemas in them and of the JSON bodies of the requests and responses
+//! of their operations.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    har::is_json,
+    schema::{escape, get, invalid, Reader},
+    DataType, Decls, Error, Options, Value,
+};
+
+/// Where the component schemas are, which are each declared as a type.
+const COMPONENT_SCHEMAS: &str = "#/components/schemas/";
+
+/// The methods operations can be for, as the keys of path items.
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// A type an OpenAPI document describes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiType {
+    /// The name of the type, before it's put in the case of type
+    /// names: the name of the component schema, or the operation and
+    /// whether it's for the request or response, like
+    /// `getUser response`.
+    pub name: String,
+
+    /// The type of the values.
+    pub typ: DataType,
+
+    /// Names for the types at paths in the values.
+    names: BTreeMap<String, String>,
+
+    /// The component schemas the values at paths refer to, by name,
+    /// which are declared as types of their own.
+    refs: BTreeMap<String, String>,
+}
+
+impl ApiType {
+    /// Emit the type as `DataType::declare_as` does, naming it `name`,
+    /// with the values that refer to component schemas declared as the
+    /// types declared for them.
+    pub fn declare(self, decls: &mut Decls) -> String {
+        let (names, types) = (decls.options.names.clone(), decls.options.types.clone());
+        for (path, name) in self.names {
+            decls.options.names.entry(path).or_insert(name);
+        }
+        for (path, component) in self.refs {
+            let type_name = decls.namer.type_name(Some(&component));
+            decls.options.types.entry(path).or_insert(type_name);
+        }
+        let declared = self.typ.declare_as(decls, &self.name);
+        decls.options.names = names;
+        decls.options.types = types;
+        declared
+    }
+}
+
+/// Read the types an OpenAPI 3 document describes, as they would be
+/// inferred from the values with `options`: one for each component
+/// schema, and if `operations`, one for the JSON body of the requests
+/// to each operation, and one for the bodies of its successful
+/// responses.
+pub fn read_openapi(
+    spec: &Value,
+    options: &Options,
+    operations: bool,
+) -> Result<Vec<ApiType>, Error> {
+    let members = match spec {
+        Value::Object(members) => members,
+        _ => return Err(invalid("#", "an OpenAPI document must be an object")),
+    };
+    match get(members, "openapi") {
+        Some(Value::String(version)) if version.starts_with("3.") => {}
+        _ => return Err(invalid("#/openapi", "only OpenAPI 3 documents can be read")),
+    }
+
+    let mut reader = Reader::new(spec, options);
+    reader.declared_defs = Some(COMPONENT_SCHEMAS);
+    let mut types = Vec::new();
+    let components = get(members, "components").and_then(|components| match components {
+        Value::Object(components) => get(components, "schemas"),
+        _ => None,
+    });
+    if let Some(Value::Object(schemas)) = components {
+        for (name, _) in schemas {
+            let pointer = format!("{}{}", COMPONENT_SCHEMAS, escape(name));
+            let typ = reader.definition(&pointer, "$", 0)?;
+            types.push(api_type(name.clone(), typ, &mut reader));
+        }
+    }
+    if !operations {
+        return Ok(types);
+    }
+
+    let paths = match get(members, "paths") {
+        Some(Value::Object(paths)) => &paths[..],
+        _ => &[],
+    };
+    for (path, item) in paths {
+        for &method in METHODS {
+            let operation = match item {
+                Value::Object(item) => get(item, method),
+                _ => None,
+            };
+            let operation = match operation {
+                Some(Value::Object(operation)) => operation,
+                _ => continue,
+            };
+            let pointer = format!("#/paths/{}/{}", escape(path), method);
+            let name = match get(operation, "operationId") {
+                Some(Value::String(id)) => id.clone(),
+                _ => operation_name(method, path),
+            };
+
+            if let Some(body) = get(operation, "requestBody") {
+                let pointer = format!("{}/requestBody", pointer);
+                if let Some(typ) = body_type(&mut reader, body, &pointer)? {
+                    types.push(api_type(format!("{} request", name), typ, &mut reader));
+                }
+            }
+            let responses = match get(operation, "responses") {
+                Some(Value::Object(responses)) => &responses[..],
+                _ => &[],
+            };
+            let mut response = None;
+            for (status, body) in responses {
+                if !status.starts_with('2') {
+                    continue;
+                }
+                let pointer = format!("{}/responses/{}", pointer, escape(status));
+                if let Some(typ) = body_type(&mut reader, body, &pointer)? {
+                    response
+                        .get_or_insert_with(DataType::unknown)
+                        .unify_into(typ);
+                }
+            }
+            if let Some(typ) = response {
+                types.push(api_type(format!("{} response", name), typ, &mut reader));
+            }
+        }
+    }
+    Ok(types)
+}
+
+/// A type read by `reader`, taking the names and references it found
+/// reading it.
+fn api_type(name: String, typ: DataType, reader: &mut Reader) -> ApiType {
+    ApiType {
+        name,
+        typ,
+        names: std::mem::take(&mut reader.names),
+        refs: std::mem::take(&mut reader.refs),
+    }
+}
+
+/// The type of the JSON in the request body or response `body`, at
+/// `pointer`, if it has any.
+fn body_type<'a>(
+    reader: &mut Reader<'a>,
+    body: &'a Value,
+    pointer: &str,
+) -> Result<Option<DataType>, Error> {
+    // Bodies can be components of their own.
+    let (body, pointer) = match body {
+        Value::Object(members) => match get(members, "$ref") {
+            Some(Value::String(reference)) => match reader.resolve(reference) {
+                Some(body) => (body, reference.clone()),
+                None => {
+                    return Err(invalid(
+                        &format!("{}/$ref", pointer),
+                        &format!("there's nothing at {:?}", reference),
+                    ))
+                }
+            },
+            _ => (body, pointer.to_string()),
+        },
+        _ => return Err(invalid(pointer, "a body must be an object")),
+    };
+    let content = match body {
+        Value::Object(members) => match get(members, "content") {
+            Some(Value::Object(content)) => &content[..],
+            _ => &[],
+        },
+        _ => &[],
+    };
+    let mut typ = None;
+    for (media_type, content) in content {
+        let schema = match content {
+            Value::Object(content) if is_json(media_type) => get(content, "schema"),
+            _ => None,
+        };
+        if let Some(schema) = schema {
+            let pointer = format!("{}/content/{}/schema", pointer, escape(media_type));
+            let body = reader.typ(schema, "$", &pointer, 0)?;
+            typ.get_or_insert_with(DataType::unknown).unify_into(body);
+        }
+    }
+    Ok(typ)
+}
+
+/// A name for the operation for `method` requests to `path`, which has
+/// no id, such as `get users id`.
+fn operation_name(method: &str, path: &str) -> String {
+    let mut name = method.to_string();
+    for segment in path.split(|c: char| !c.is_alphanumeric()) {
+        if !segment.is_empty() {
+            name.push(' ');
+            name.push_str(segment);
+        }
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Lang;
+
+    #[test]
+    fn openapi_types() {
+        let spec = Value::parse_json(
+            r##"{
+                "openapi": "3.0.3",
+                "paths": {
+                    "/orders/{id}": {
+                        "get": {
+                            "operationId": "getOrder",
+                            "responses": {
+                                "200": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/Order"}}}},
+                                "404": {"content": {"application/json": {"schema": {"type": "object"}}}}
+                            }
+                        },
+                        "put": {
+                            "requestBody": {"$ref": "#/components/requestBodies/Order"},
+                            "responses": {"204": {"description": "saved"}}
+                        }
+                    }
+                },
+                "components": {
+                    "schemas": {
+                        "Order": {
+                            "type": "object",
+                            "required": ["id", "items"],
+                            "properties": {
+                                "id": {"type": "integer"},
+                                "items": {"type": "array", "items": {"$ref": "#/components/schemas/LineItem"}}
+                            }
+                        },
+                        "LineItem": {
+                            "type": "object",
+                            "required": ["sku"],
+                            "properties": {"sku": {"type": "string"}}
+                        }
+                    },
+                    "requestBodies": {
+                        "Order": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/Order"}}}}
+                    }
+                }
+            }"##,
+        )
+        .unwrap()
+        .unwrap();
+
+        let types = read_openapi(&spec, &Options::default(), true).unwrap();
+        let names = types
+            .iter()
+            .map(|typ| typ.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "Order",
+                "LineItem",
+                "getOrder response",
+                "put orders id request"
+            ]
+        );
+
+        let mut decls = Decls::new(Options {
+            lang: Lang::TypeScript,
+            ..Options::default()
+        });
+        for typ in types {
+            typ.declare(&mut decls);
+        }
+        assert_eq!(
+            decls.output(),
+            "interface Order {\n  id: number;\n  items: LineItem[];\n}\n\n\
+             interface LineItem {\n  sku: string;\n}\n\n\
+             type GetOrderResponse = Order;\n\n\
+             type PutOrdersIdRequest = Order;"
+        );
+
+        let swagger = Value::parse_json(r#"{"swagger": "2.0"}"#).unwrap().unwrap();
+        assert!(matches!(
+            read_openapi(&swagger, &Options::default(), false),
+            Err(Error::Schema { pointer, .. }) if pointer == "#/openapi"
+        ));
+    }
+}
@@ -0,0 +1,691 @@
+//! Settings that control how types are inferred and emitted.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{
+    format::Format,
+    lang::Lang,
+    namer::{Case, Edition},
+    names::singularize,
+};
+
+/// Type emitted by the Rust backend for values nothing is known about,
+/// such as the elements of arrays that were always empty.
+pub const RUST_UNKNOWN_TYPE: &str = "serde_json::Value";
+
+/// Settings that control how types are inferred and emitted. They can
+/// be loaded from a configuration file, which has a key for each field.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Options {
+    /// Language to emit declarations in.
+    pub lang: Lang,
+
+    /// Type to emit for values nothing is known about, overriding the
+    /// backend's default.
+    pub unknown_type: Option<String>,
+
+    /// Map implementation to emit for objects with dynamic keys.
+    pub map_type: MapType,
+
+    /// Type to emit for strings.
+    pub string_type: StringType,
+
+    /// Edition of Rust to write declarations for, which decides which
+    /// words are keywords.
+    pub rust_edition: Edition,
+
+    /// Whether to emit Java classes with private fields and getters
+    /// rather than records.
+    pub java_pojos: bool,
+
+    /// Whether to emit ATD definitions rather than OCaml types when
+    /// emitting OCaml.
+    pub ocaml_atd: bool,
+
+    /// Whether to derive Scala codecs with play-json rather than circe.
+    pub scala_play_json: bool,
+
+    /// Whether to declare Julia structs as `StructTypes.Struct`s, with
+    /// their keys, for JSON3.jl.
+    pub julia_struct_types: bool,
+
+    /// Whether to emit nested objects as tables of their own, rather
+    /// than flattening them into columns, when emitting SQL.
+    pub sql_child_tables: bool,
+
+    /// Whether to write out `impl Default` for each declared type,
+    /// rather than leaving defaults to the user.
+    pub explicit_default: bool,
+
+    /// Whether to avoid declaring trivially small object types.
+    /// TypeScript writes objects of a few scalars inline, and Python as
+    /// dicts. Rust has no anonymous structs, so there this only
+    /// collapses objects whose single member is a scalar into that
+    /// scalar, read and written through helper functions with serde.
+    pub inline_small_types: bool,
+
+    /// Whether to write out an `impl` block of accessors for each
+    /// declared enum.
+    pub emit_helpers: bool,
+
+    /// Whether to wrap each object member that's a string, number,
+    /// boolean or date in a newtype of its own, named after its path,
+    /// so that values that mean different things can't be mixed up.
+    pub newtype_leaves: bool,
+
+    /// Whether to explain, in a comment above each object member whose
+    /// type is a guess, what was seen and what it might better be.
+    pub annotate_uncertain: bool,
+
+    /// Whether to write out a `main` that deserializes a file into the
+    /// top-level type and prints it, and how it should handle errors.
+    pub emit_main: Option<MainErrors>,
+
+    /// Whether to write a comment above each declaration listing the
+    /// paths its type was found at, and how to write the paths.
+    pub annotate_paths: Option<PathsFormat>,
+
+    /// Whether declared types are public, as they need to be when
+    /// they're split across modules.
+    pub pub_types: bool,
+
+    /// Traits to derive for every declared type.
+    pub derives: Vec<String>,
+
+    /// Whether to derive serde's `Serialize` and `Deserialize`, along
+    /// with `Debug` and `Clone`, for every declared type, besides the
+    /// traits in `derives`.
+    pub serde: bool,
+
+    /// How to emit object members that were null in every sample.
+    pub null_fields: NullFields,
+
+    /// Fraction of the objects at a path that a member must be present
+    /// in to be declared, if rare members should be dropped.
+    pub min_presence: Option<f64>,
+
+    /// The most elements of each array to infer the element type from.
+    /// Elements that aren't inspected can't contribute members or
+    /// types, which is the price of speed on large inputs.
+    pub sample_limit: Option<usize>,
+
+    /// Whether to inspect a random selection of the elements of arrays
+    /// longer than `sample_limit`, rather than the first ones.
+    pub sample_random: bool,
+
+    /// The most distinct values an integer can take and still be
+    /// declared as an enum of codes, if integers should be checked for
+    /// that. It can't be more than `MAX_INT_ENUM_CODES`.
+    pub int_enum_threshold: Option<usize>,
+
+    /// Whether to check strings for dates and times, and declare the
+    /// ones that always hold the same kind with `chrono` types.
+    pub detect_dates: bool,
+
+    /// Whether to declare arrays of numbers, and arrays of those, that
+    /// always have the same length as fixed-length arrays, such as
+    /// `[[f64; 3]; 3]` for a 3x3 matrix.
+    pub matrix: bool,
+
+    /// The case to write made-up type names in, such as `Data0`.
+    pub type_case: Case,
+
+    /// Text to put before made-up type names.
+    pub type_prefix: String,
+
+    /// Text to put after made-up type names.
+    pub type_suffix: String,
+
+    /// Whether to keep the keys of arrays as they are when naming the
+    /// types of their elements, rather than singularizing them.
+    pub no_singularize: bool,
+
+    /// Names for the types found at particular paths, which take
+    /// precedence over names derived from the input.
+    pub names: BTreeMap<String, String>,
+
+    /// Types to emit, as written, for the values at particular paths
+    /// instead of the inferred ones.
+    pub types: BTreeMap<String, String>,
+
+    /// Names for the variants of the integer enums found at particular
+    /// paths, by code.
+    #[serde(deserialize_with = "deserialize_code_names")]
+    pub code_names: BTreeMap<String, BTreeMap<i64, String>>,
+
+    /// Paths of object members to leave out.
+    pub ignore: BTreeSet<String>,
+
+    /// Format to read the inputs in, rather than going by their
+    /// extensions.
+    pub format: Option<Format>,
+
+    /// Whether to read JSON as JSON5, allowing comments, trailing
+    /// commas and the like.
+    pub lenient: bool,
+
+    /// Whether to read JSON a piece at a time, holding only one
+    /// element of a document that's an array in memory at once.
+    pub stream: bool,
+
+    /// Whether to fail on documents that are null or empty, rather
+    /// than warning.
+    pub strict: bool,
+
+    /// Whether to read the inputs as HAR captures, declaring a type for
+    /// the JSON responses to each endpoint.
+    pub har: bool,
+
+    /// Whether to read the inputs as JSON Schemas, declaring the types
+    /// they describe.
+    pub from_schema: bool,
+
+    /// Whether to read the inputs as OpenAPI 3 documents, declaring
+    /// their component schemas.
+    pub openapi: bool,
+
+    /// Whether to also declare the request and response bodies of the
+    /// operations in OpenAPI documents.
+    pub operations: bool,
+
+    /// Headers to send when fetching URLs, each as `NAME: VALUE`.
+    pub headers: Vec<String>,
+
+    /// Whether to print how often each field is present, null or
+    /// absent, instead of declarations.
+    pub stats: bool,
+
+    /// Whether to report where the records first change shape.
+    pub detect_drift: bool,
+
+    /// Whether to print the inferred type tree instead of
+    /// declarations.
+    pub dump_type: bool,
+
+    /// Whether to print a document with every field of the inferred
+    /// type instead of declarations.
+    pub example_doc: bool,
+
+    /// An older sample to print how the inferred type differs from,
+    /// instead of declarations.
+    pub diff: Option<PathBuf>,
+
+    /// Whether to review the declared types and choose names and
+    /// overrides, reading the answers from stdin.
+    pub interactive: bool,
+
+    /// File of field numbers to keep stable across runs.
+    pub schema_state: Option<PathBuf>,
+
+    /// Directory to write a Rust module per type into, instead of
+    /// printing the declarations.
+    pub output_dir: Option<PathBuf>,
+
+    /// Whether to overwrite the files in `output_dir` even if it isn't
+    /// empty.
+    pub force: bool,
+
+    /// Format to print warnings in.
+    pub warnings_format: WarningsFormat,
+
+    /// Whether to fail if any warnings were raised.
+    pub deny_warnings: bool,
+}
+
+/// Formats to print warnings in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WarningsFormat {
+    /// Lines of text for people to read.
+    #[default]
+    Text,
+
+    /// A JSON document, for tools to read.
+    Json,
+}
+
+impl FromStr for WarningsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(WarningsFormat::Text),
+            "json" => Ok(WarningsFormat::Json),
+            _ => Err(format!("unknown warnings format {:?}", s)),
+        }
+    }
+}
+
+/// Ways of emitting object members that were always null.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NullFields {
+    /// Emit them with the unit type.
+    #[default]
+    KeepUnit,
+
+    /// Emit them as an optional unknown type, since their real type
+    /// was never seen.
+    Option,
+
+    /// Leave them out entirely.
+    Drop,
+}
+
+impl FromStr for NullFields {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep-unit" => Ok(NullFields::KeepUnit),
+            "option" => Ok(NullFields::Option),
+            "drop" => Ok(NullFields::Drop),
+            _ => Err(format!("unknown null field handling {:?}", s)),
+        }
+    }
+}
+
+/// Ways for an emitted `main` to handle errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MainErrors {
+    /// Panic with a message, using `expect`.
+    #[default]
+    Expect,
+
+    /// Return an `anyhow::Result` with context attached.
+    Anyhow,
+}
+
+impl FromStr for MainErrors {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "expect" => Ok(MainErrors::Expect),
+            "anyhow" => Ok(MainErrors::Anyhow),
+            _ => Err(format!("unknown error handling {:?}", s)),
+        }
+    }
+}
+
+/// Syntaxes for writing the paths of values in comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathsFormat {
+    /// JSONPath, as in `$.users[*].name`.
+    #[default]
+    JsonPath,
+
+    /// Keys separated by dots, as in `.users[].name`.
+    Dotted,
+}
+
+impl PathsFormat {
+    /// Write a path, in the form used throughout (`$.users[].name`,
+    /// with `.*` for the values of maps), in this syntax.
+    pub fn format(self, path: &str) -> String {
+        let mut s = match self {
+            PathsFormat::JsonPath => "$".to_string(),
+            PathsFormat::Dotted => String::new(),
+        };
+        for segment in PathSegment::parse(path) {
+            match (self, segment) {
+                (PathsFormat::JsonPath, PathSegment::Elements) => s += "[*]",
+                (PathsFormat::Dotted, PathSegment::Elements) => s += "[]",
+                (_, PathSegment::Values) => s += ".*",
+                (_, PathSegment::Key(key)) if is_plain_key(key) => {
+                    s += ".";
+                    s += key;
+                }
+                (PathsFormat::JsonPath, PathSegment::Key(key)) => {
+                    s += "['";
+                    s += &key.replace('\\', "\\\\").replace('\'', "\\'");
+                    s += "']";
+                }
+                (PathsFormat::Dotted, PathSegment::Key(key)) => {
+                    s += &format!(".{:?}", key);
+                }
+            }
+        }
+        if s.is_empty() {
+            s += ".";
+        }
+        s
+    }
+}
+
+impl FromStr for PathsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonpath" => Ok(PathsFormat::JsonPath),
+            "dotted" => Ok(PathsFormat::Dotted),
+            _ => Err(format!("unknown paths format {:?}", s)),
+        }
+    }
+}
+
+/// A step from a value to one of its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathSegment<'a> {
+    /// The member of an object with the given key.
+    Key(&'a str),
+
+    /// The elements of an array.
+    Elements,
+
+    /// The values of a map.
+    Values,
+}
+
+impl<'a> PathSegment<'a> {
+    /// Split a path into its steps from the root. Keys are taken to
+    /// run up to the next `.` or `[]`, so keys containing those can't
+    /// be told apart from several steps.
+    fn parse(path: &'a str) -> Vec<Self> {
+        let mut segments = Vec::new();
+        let mut rest = path.strip_prefix('$').unwrap_or(path);
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("[]") {
+                segments.push(PathSegment::Elements);
+                rest = after;
+                continue;
+            }
+            let after = rest.strip_prefix('.').unwrap_or(rest);
+            let end = after
+                .char_indices()
+                .find(|&(idx, c)| c == '.' || after[idx..].starts_with("[]"))
+                .map_or(after.len(), |(idx, _)| idx);
+            segments.push(match &after[..end] {
+                "*" => PathSegment::Values,
+                key => PathSegment::Key(key),
+            });
+            rest = &after[end..];
+        }
+        segments
+    }
+}
+
+/// Whether `key` can be written after a dot in a path without quoting.
+fn is_plain_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Deserialize settings from the same strings as they're given in on
+/// the command line.
+macro_rules! deserialize_from_str {
+    ($($typ:ty),*) => {
+        $(
+            impl<'de> Deserialize<'de> for $typ {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    String::deserialize(deserializer)?
+                        .parse()
+                        .map_err(de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+deserialize_from_str!(
+    Lang,
+    MapType,
+    StringType,
+    NullFields,
+    MainErrors,
+    PathsFormat,
+    Case,
+    Edition,
+    Format,
+    WarningsFormat
+);
+
+/// Deserialize the names of integer enum codes, whose codes are keys
+/// and so are written as strings.
+fn deserialize_code_names<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, BTreeMap<i64, String>>, D::Error> {
+    BTreeMap::<String, BTreeMap<String, String>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(path, names)| {
+            let names = names
+                .into_iter()
+                .map(|(code, name)| match code.parse() {
+                    Ok(code) => Ok((code, name)),
+                    Err(_) => Err(de::Error::custom(format!(
+                        "bad code {:?} for {:?}",
+                        code, path
+                    ))),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok((path, names))
+        })
+        .collect()
+}
+
+/// A Rust map implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapType {
+    /// `std::collections::BTreeMap`, which iterates in key order.
+    BTree,
+
+    /// `std::collections::HashMap`.
+    #[default]
+    Hash,
+
+    /// `indexmap::IndexMap`, which iterates in insertion order.
+    Index,
+}
+
+impl MapType {
+    /// The path of the map type, without type arguments.
+    pub fn path(self) -> &'static str {
+        match self {
+            MapType::BTree => "std::collections::BTreeMap",
+            MapType::Hash => "std::collections::HashMap",
+            MapType::Index => "indexmap::IndexMap",
+        }
+    }
+}
+
+impl FromStr for MapType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "btree" => Ok(MapType::BTree),
+            "hash" => Ok(MapType::Hash),
+            "index" => Ok(MapType::Index),
+            _ => Err(format!("unknown map type {:?}", s)),
+        }
+    }
+}
+
+/// A Rust string type.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum StringType {
+    /// `String`.
+    #[default]
+    String,
+
+    /// `std::borrow::Cow<'a, str>`, which can borrow from the input,
+    /// so the types that hold one take a lifetime parameter.
+    Cow,
+
+    /// Another type, such as `compact_str::CompactString`, given by
+    /// its path and emitted as written.
+    Custom(String),
+}
+
+impl StringType {
+    /// The type to emit for strings.
+    pub fn path(&self) -> &str {
+        match self {
+            StringType::String => "String",
+            StringType::Cow => "std::borrow::Cow<'a, str>",
+            StringType::Custom(path) => path,
+        }
+    }
+
+    /// The expression that borrows a string of this type named `value`
+    /// as a `&str`.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            StringType::Cow => "value.as_ref()",
+            StringType::String | StringType::Custom(_) => "value.as_str()",
+        }
+    }
+}
+
+impl FromStr for StringType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("the string type can't be empty".to_string()),
+            "String" => Ok(StringType::String),
+            "Cow" => Ok(StringType::Cow),
+            path => Ok(StringType::Custom(path.to_string())),
+        }
+    }
+}
+
+impl Options {
+    /// The type to emit for unknown values, given the backend's
+    /// default.
+    pub fn unknown_type<'a>(&'a self, default: &'a str) -> &'a str {
+        self.unknown_type.as_deref().unwrap_or(default)
+    }
+
+    /// The traits to derive for every declared type: those asked for,
+    /// plus serde's and the usual ones if `serde` is set, and the ones
+    /// an emitted `main` needs. serde's traits are only derived once,
+    /// whether they're written with their paths or not.
+    pub fn derives(&self) -> Vec<&str> {
+        let mut derives = self.derives.iter().map(String::as_str).collect::<Vec<_>>();
+        let mut needed = Vec::new();
+        if self.serde {
+            needed.extend(["Debug", "Clone", "serde::Serialize", "serde::Deserialize"]);
+        }
+        if self.emit_main.is_some() {
+            needed.extend(["Debug", "serde::Deserialize"]);
+        }
+        let name = |derive: &str| derive.trim_start_matches("serde::").to_string();
+        for needed in needed {
+            if !derives.iter().any(|derive| name(derive) == name(needed)) {
+                derives.push(needed);
+            }
+        }
+        derives
+    }
+
+    /// The name for the type of the elements at `path` of an array
+    /// whose key in its parent object is `key`.
+    pub fn element_name(&self, path: &str, key: &str) -> String {
+        match self.names.get(path) {
+            Some(name) => name.clone(),
+            None if self.no_singularize => key.to_string(),
+            None => singularize(key),
+        }
+    }
+
+    /// The name for the variant with the given code of the integer enum
+    /// at `path`: `Code3`, or `CodeNeg3` for negative codes, unless
+    /// it's been given a name.
+    pub fn code_name(&self, path: &str, code: i64) -> String {
+        match self.code_names.get(path).and_then(|names| names.get(&code)) {
+            Some(name) => name.clone(),
+            None if code < 0 => format!("CodeNeg{}", code.unsigned_abs()),
+            None => format!("Code{}", code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn element_names() {
+        let mut options = Options::default();
+        assert_eq!(options.element_name("$.statuses[]", "statuses"), "status");
+
+        options
+            .names
+            .insert("$.statuses[]".to_string(), "StatusUpdate".to_string());
+        assert_eq!(
+            options.element_name("$.statuses[]", "statuses"),
+            "StatusUpdate"
+        );
+        assert_eq!(options.element_name("$.boxes[]", "boxes"), "box");
+
+        options.no_singularize = true;
+        assert_eq!(options.element_name("$.boxes[]", "boxes"), "boxes");
+        assert_eq!(
+            options.element_name("$.statuses[]", "statuses"),
+            "StatusUpdate"
+        );
+    }
+
+    #[test]
+    fn derives() {
+        let mut options = Options {
+            derives: vec!["PartialEq".to_string(), "Deserialize".to_string()],
+            ..Options::default()
+        };
+        assert_eq!(options.derives(), ["PartialEq", "Deserialize"]);
+
+        options.serde = true;
+        options.emit_main = Some(MainErrors::Expect);
+        assert_eq!(
+            options.derives(),
+            [
+                "PartialEq",
+                "Deserialize",
+                "Debug",
+                "Clone",
+                "serde::Serialize"
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_formats() {
+        let cases = [
+            ("$", "$", "."),
+            ("$.users[]", "$.users[*]", ".users[]"),
+            ("$[].tags.*", "$[*].tags.*", "[].tags.*"),
+            (
+                "$.first name.it's",
+                "$['first name']['it\\'s']",
+                ".\"first name\".\"it's\"",
+            ),
+        ];
+        for (path, jsonpath, dotted) in cases {
+            assert_eq!(PathsFormat::JsonPath.format(path), jsonpath);
+            assert_eq!(PathsFormat::Dotted.format(path), dotted);
+        }
+    }
+
+    #[test]
+    fn code_names() {
+        let mut options = Options::default();
+        options
+            .code_names
+            .entry("$.status".to_string())
+            .or_default()
+            .insert(1, "Active".to_string());
+        assert_eq!(options.code_name("$.status", 1), "Active");
+        assert_eq!(options.code_name("$.status", 2), "Code2");
+        assert_eq!(options.code_name("$.kind", -2), "CodeNeg2");
+    }
+}
@@ -0,0 +1,188 @@
+//! Interactive review of the declared types, for choosing names and
+//! overrides without editing flags and re-running over and over.
+
+use std::{
+    collections::BTreeSet,
+    fmt::Write as _,
+    io::{self, BufRead, Write},
+};
+
+use crate::{DataType, Decls, Options, Stats};
+
+/// Help shown at each prompt.
+const PROMPT: &str = "[a]ccept, [r]ename NAME, [i]gnore FIELD, [t]ype FIELD TYPE, [s]kip, [q]uit? ";
+
+/// Walk through the object types that `typ` declares, outermost first,
+/// prompting on `output` and reading answers from `input`. Accepted
+/// answers are recorded in `options`. Skipping a type discards the
+/// answers given for it, and quitting, or reaching the end of the
+/// input, discards those for the current type but keeps the rest.
+pub fn review(
+    typ: &DataType,
+    options: &mut Options,
+    stats: &Stats,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut reviewed = BTreeSet::new();
+    loop {
+        // Answers can change which types are declared and what they're
+        // called, so declare everything afresh each time.
+        let mut decls = Decls::new(options.clone()).with_stats(stats.clone());
+        typ.clone().declare(&mut decls);
+        let declared = match decls
+            .declared()
+            .iter()
+            .find(|declared| !reviewed.contains(&declared.path))
+        {
+            Some(declared) => declared.clone(),
+            None => return Ok(()),
+        };
+        reviewed.insert(declared.path.clone());
+
+        writeln!(output, "\n{} (at {}):", declared.name, declared.path)?;
+        for (field, type_name) in &declared.fields {
+            writeln!(output, "    {}: {}", field, type_name)?;
+        }
+
+        let mut edits = options.clone();
+        loop {
+            write!(output, "{}", PROMPT)?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+
+            let mut words = line.trim().splitn(3, char::is_whitespace);
+            let command = words.next().unwrap_or_default();
+            let field = words.next().map(str::trim);
+            let rest = words.next().map(str::trim);
+            let field_path = |field: &str| format!("{}.{}", declared.path, field);
+            let is_field = |field: &str| declared.fields.iter().any(|(name, _)| name == field);
+            match (command, field, rest) {
+                ("" | "a", None, None) => {
+                    *options = edits;
+                    break;
+                }
+                ("s", None, None) => break,
+                ("q", None, None) => return Ok(()),
+                ("r", Some(name), None) => {
+                    edits.names.insert(declared.path.clone(), name.to_string());
+                }
+                ("i", Some(field), None) if is_field(field) => {
+                    edits.ignore.insert(field_path(field));
+                }
+                ("t", Some(field), Some(type_name)) if is_field(field) => {
+                    edits.types.insert(field_path(field), type_name.to_string());
+                }
+                ("i" | "t", Some(field), _) if !is_field(field) => {
+                    writeln!(output, "{} has no field {:?}", declared.name, field)?;
+                }
+                _ => writeln!(output, "unrecognized answer {:?}", line.trim())?,
+            }
+        }
+    }
+}
+
+/// A snippet of configuration file that reproduces the names, type
+/// overrides and ignored fields in `options`.
+pub fn config_snippet(options: &Options) -> String {
+    let mut s = String::new();
+    if !options.ignore.is_empty() {
+        let ignore = options
+            .ignore
+            .iter()
+            .map(|path| toml_string(path))
+            .collect::<Vec<_>>();
+        writeln!(s, "ignore = [{}]", ignore.join(", ")).expect("writing to a String can't fail");
+    }
+    for (table, entries) in [("names", &options.names), ("types", &options.types)] {
+        if entries.is_empty() {
+            continue;
+        }
+        if !s.is_empty() {
+            s += "\n";
+        }
+        writeln!(s, "[{}]", table).expect("writing to a String can't fail");
+        for (path, value) in entries {
+            writeln!(s, "{} = {}", toml_string(path), toml_string(value))
+                .expect("writing to a String can't fail");
+        }
+    }
+    s
+}
+
+/// Quote a string for TOML.
+fn toml_string(s: &str) -> String {
+    let mut quoted = "\"".to_string();
+    for c in s.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            c if c.is_control() => {
+                write!(quoted, "\\u{:04X}", c as u32).expect("writing to a String can't fail")
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted + "\""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(answers: &str) -> (Options, String) {
+        let typ = DataType::from_json_value(&json::object! {
+            "users": [{"id": 1, "debug": "x", "address": {"city": "a", "zip": "b"}}],
+            "total": 1,
+        })
+        .unwrap();
+        let mut options = Options::default();
+        let mut output = Vec::new();
+        review(
+            &typ,
+            &mut options,
+            &Stats::new(),
+            &mut answers.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        (options, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn answers() {
+        let (options, output) = run("\nr User\ni debug\nt id u64\na\nr Address\ns\n");
+        assert_eq!(
+            config_snippet(&options),
+            "ignore = [\"$.users[].debug\"]\n\n\
+             [names]\n\"$.users[]\" = \"User\"\n\n\
+             [types]\n\"$.users[].id\" = \"u64\"\n"
+        );
+        assert!(output.starts_with("\nData0 (at $):\n    total: i64\n    users: Vec<Data1>\n"));
+        assert!(output.contains("\nData1 (at $.users[].address):\n"));
+
+        let mut decls = Decls::new(options);
+        DataType::from_json_value(&json::object! {"users": [{"id": 1, "debug": "x"}]})
+            .unwrap()
+            .declare(&mut decls);
+        assert_eq!(
+            decls.output(),
+            "struct User {\n    pub id: u64,\n}\n\nstruct Data0 {\n    pub users: Vec<User>,\n}"
+        );
+    }
+
+    #[test]
+    fn quitting_keeps_earlier_answers() {
+        let (options, _) = run("r Root\n\nr User\nq\n");
+        assert_eq!(options.names.len(), 1);
+        assert_eq!(options.names["$"], "Root");
+
+        let (options, output) = run("r Root\ni nope\nbogus\n");
+        assert!(options.names.is_empty());
+        assert!(output.contains("Data0 has no field \"nope\""));
+        assert!(output.contains("unrecognized answer \"bogus\""));
+    }
+}
@@ -0,0 +1,537 @@
+//! Reading of JSON Schemas, for the types of the values they describe,
+//! so that declarations can be written without any samples.
+//!
+//! A schema's `type` is read as the type it names, and `enum` and
+//! `const` as the type of their values. `anyOf` and `oneOf` allow any
+//! of their options, and `allOf` and `$ref`s are merged into the schema
+//! they're in. Properties that aren't `required` can be null, and
+//! objects without `properties` are maps. `$ref`s are only followed
+//! within the document, and those that refer to a definition that's
+//! being read already are unknown, since types can't hold themselves.
+
+use std::collections::BTreeMap;
+
+use crate::{DataType, DepthExceeded, Error, Options, Temporal, Value, MAX_DEPTH};
+
+/// The type of the values a JSON Schema describes, and names for the
+/// types in them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaType {
+    /// The type of the values.
+    pub typ: DataType,
+
+    /// Names for the types at paths in the values, such as `$.owner`:
+    /// the names of the definitions the schemas at them refer to, or
+    /// else their titles.
+    pub names: BTreeMap<String, String>,
+}
+
+impl SchemaType {
+    /// Read the type of the values `schema` describes, as it would be
+    /// inferred from them with `options`.
+    pub fn from_schema(schema: &Value, options: &Options) -> Result<Self, Error> {
+        let mut reader = Reader::new(schema, options);
+        let typ = reader.definition("#", "$", 0)?;
+        Ok(SchemaType {
+            typ,
+            names: reader.names,
+        })
+    }
+}
+
+/// A reader of the schemas in a document, following `$ref`s within it.
+pub(crate) struct Reader<'a> {
+    /// The document `$ref`s are resolved in.
+    root: &'a Value,
+    options: &'a Options,
+    /// The pointers of the definitions being read, innermost last.
+    expanding: Vec<String>,
+    /// Names for the types at paths in the values read so far.
+    pub(crate) names: BTreeMap<String, String>,
+    /// Where the definitions that are declared as types of their own
+    /// are, like `#/components/schemas/`, if there are any.
+    pub(crate) declared_defs: Option<&'static str>,
+    /// The names of the definitions under `declared_defs` that the
+    /// values at paths refer to, by the paths.
+    pub(crate) refs: BTreeMap<String, String>,
+}
+
+impl<'a> Reader<'a> {
+    /// Start reading the schemas in the document `root`.
+    pub(crate) fn new(root: &'a Value, options: &'a Options) -> Self {
+        Reader {
+            root,
+            options,
+            expanding: Vec::new(),
+            names: BTreeMap::new(),
+            declared_defs: None,
+            refs: BTreeMap::new(),
+        }
+    }
+
+    /// The type of the values `schema` describes, which are at `path`
+    /// in the values and nested `depth` levels into them. The schema is
+    /// at `pointer` in the document, for errors.
+    pub(crate) fn typ(
+        &mut self,
+        schema: &'a Value,
+        path: &str,
+        pointer: &str,
+        depth: usize,
+    ) -> Result<DataType, Error> {
+        if depth > MAX_DEPTH {
+            return Err(DepthExceeded.into());
+        }
+        let keywords = match schema {
+            // `true` allows anything, and `false` nothing, which is as
+            // much as is known about values no sample ever had.
+            Value::Bool(_) => return Ok(DataType::unknown()),
+            Value::Object(keywords) => keywords,
+            _ => return Err(invalid(pointer, "a schema must be an object or a boolean")),
+        };
+        let get = |keyword: &str| get(keywords, keyword);
+        let at = |keyword: &str| format!("{}/{}", pointer, keyword);
+
+        if let Some(Value::String(title)) = get("title") {
+            self.names
+                .entry(path.to_string())
+                .or_insert_with(|| title.clone());
+        }
+        let mut typ = DataType::unknown();
+        if let Some(value) = get("const") {
+            typ = DataType::from_value_with(value, self.options)?;
+        } else if let Some(values) = get("enum") {
+            let values = match values {
+                Value::Array(values) => values,
+                _ => return Err(invalid(&at("enum"), "`enum` must be an array")),
+            };
+            for value in values {
+                typ.unify_into(DataType::from_value_with(value, self.options)?);
+            }
+        } else {
+            typ = match get("type") {
+                Some(Value::String(name)) => {
+                    self.named_type(name, keywords, path, pointer, depth)?
+                }
+                Some(Value::Array(names)) => {
+                    let mut typ = DataType::unknown();
+                    for (idx, name) in names.iter().enumerate() {
+                        let name = match name {
+                            Value::String(name) => name,
+                            _ => {
+                                return Err(invalid(
+                                    &format!("{}/type/{}", pointer, idx),
+                                    "a type must be a string",
+                                ))
+                            }
+                        };
+                        typ.unify_into(self.named_type(name, keywords, path, pointer, depth)?);
+                    }
+                    typ
+                }
+                Some(_) => return Err(invalid(&at("type"), "`type` must be a string or an array")),
+                // Schemas without a type are read as the type their
+                // keywords are for.
+                None if ["properties", "additionalProperties", "required"]
+                    .iter()
+                    .any(|keyword| get(keyword).is_some()) =>
+                {
+                    self.object(keywords, path, pointer, depth)?
+                }
+                None if get("items").is_some() || get("prefixItems").is_some() => {
+                    self.array(keywords, path, pointer, depth)?
+                }
+                None => DataType::unknown(),
+            };
+        }
+
+        if let Some(reference) = get("$ref") {
+            let reference = match reference {
+                Value::String(reference) => reference,
+                _ => return Err(invalid(&at("$ref"), "`$ref` must be a string")),
+            };
+            typ = merge(typ, self.reference(reference, path, &at("$ref"), depth)?);
+        }
+        if let Some(parts) = get("allOf") {
+            for (idx, part) in self.subschemas(parts, &at("allOf"))? {
+                typ = merge(typ, self.typ(part, path, &idx, depth + 1)?);
+            }
+        }
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(options) = get(keyword) {
+                let mut any = DataType::unknown();
+                for (idx, option) in self.subschemas(options, &at(keyword))? {
+                    any.unify_into(self.typ(option, path, &idx, depth + 1)?);
+                }
+                typ = merge(typ, any);
+            }
+        }
+        // OpenAPI before 3.1 says values can be null this way.
+        if let Some(Value::Bool(true)) = get("nullable") {
+            typ.unify_into(DataType::Null);
+        }
+        Ok(typ)
+    }
+
+    /// The type of the values of the type `name`, described by the
+    /// schema with `keywords`.
+    fn named_type(
+        &mut self,
+        name: &str,
+        keywords: &'a [(String, Value)],
+        path: &str,
+        pointer: &str,
+        depth: usize,
+    ) -> Result<DataType, Error> {
+        Ok(match name {
+            "null" => DataType::Null,
+            "boolean" => DataType::Bool,
+            "integer" => DataType::Int,
+            "number" => DataType::Float,
+            "string" => {
+                let format = match get(keywords, "format") {
+                    Some(Value::String(format)) => format.as_str(),
+                    _ => "",
+                };
+                match (format, get(keywords, "contentEncoding")) {
+                    (_, Some(Value::String(encoding))) if encoding == "base64" => DataType::Bytes,
+                    ("date", _) if self.options.detect_dates => DataType::Temporal(Temporal::Date),
+                    ("time", _) if self.options.detect_dates => DataType::Temporal(Temporal::Time),
+                    ("date-time", _) if self.options.detect_dates => {
+                        DataType::Temporal(Temporal::DateTime)
+                    }
+                    _ => DataType::String,
+                }
+            }
+            "object" => self.object(keywords, path, pointer, depth)?,
+            "array" => self.array(keywords, path, pointer, depth)?,
+            name => {
+                return Err(invalid(
+                    &format!("{}/type", pointer),
+                    &format!("there's no type {:?}", name),
+                ))
+            }
+        })
+    }
+
+    /// The type of the objects described by the schema with `keywords`:
+    /// an object if it has properties, or else a map.
+    fn object(
+        &mut self,
+        keywords: &'a [(String, Value)],
+        path: &str,
+        pointer: &str,
+        depth: usize,
+    ) -> Result<DataType, Error> {
+        let required = match get(keywords, "required") {
+            Some(Value::Array(required)) => required.iter().collect(),
+            Some(_) => {
+                return Err(invalid(
+                    &format!("{}/required", pointer),
+                    "`required` must be an array",
+                ))
+            }
+            None => Vec::new(),
+        };
+        let properties = match get(keywords, "properties") {
+            Some(Value::Object(properties)) => &properties[..],
+            Some(_) => {
+                return Err(invalid(
+                    &format!("{}/properties", pointer),
+                    "`properties` must be an object",
+                ))
+            }
+            None => &[],
+        };
+
+        let additional = get(keywords, "additionalProperties");
+        if properties.is_empty() && required.is_empty() && additional != Some(&Value::Bool(false)) {
+            let values_path = format!("{}.*", path);
+            let mut values = DataType::unknown();
+            if let Some(additional) = additional {
+                let pointer = format!("{}/additionalProperties", pointer);
+                values = self.typ(additional, &values_path, &pointer, depth + 1)?;
+            }
+            if let Some(Value::Object(patterns)) = get(keywords, "patternProperties") {
+                for (pattern, schema) in patterns {
+                    let pointer = format!("{}/patternProperties/{}", pointer, escape(pattern));
+                    values.unify_into(self.typ(schema, &values_path, &pointer, depth + 1)?);
+                }
+            }
+            return Ok(DataType::Map(Box::new(values)));
+        }
+
+        let mut members = BTreeMap::new();
+        for (name, schema) in properties {
+            let pointer = format!("{}/properties/{}", pointer, escape(name));
+            let mut typ = self.typ(schema, &format!("{}.{}", path, name), &pointer, depth + 1)?;
+            if !required.contains(&&Value::String(name.clone())) {
+                typ.unify_into(DataType::Null);
+            }
+            members.insert(name.clone(), typ);
+        }
+        // Properties that are required but not described can be
+        // anything.
+        for name in required {
+            if let Value::String(name) = name {
+                members
+                    .entry(name.clone())
+                    .or_insert_with(DataType::unknown);
+            }
+        }
+        Ok(DataType::Object(members))
+    }
+
+    /// The type of the arrays described by the schema with `keywords`,
+    /// whose elements can be any of the types of the items it describes.
+    fn array(
+        &mut self,
+        keywords: &'a [(String, Value)],
+        path: &str,
+        pointer: &str,
+        depth: usize,
+    ) -> Result<DataType, Error> {
+        let elem_path = format!("{}[]", path);
+        let mut elem = DataType::unknown();
+        for keyword in ["prefixItems", "items"] {
+            let pointer = format!("{}/{}", pointer, keyword);
+            match get(keywords, keyword) {
+                // Drafts before 2020-12 describe tuples with an array
+                // in `items`.
+                Some(items @ Value::Array(_)) => {
+                    for (idx, item) in self.subschemas(items, &pointer)? {
+                        elem.unify_into(self.typ(item, &elem_path, &idx, depth + 1)?);
+                    }
+                }
+                Some(items) => elem.unify_into(self.typ(items, &elem_path, &pointer, depth + 1)?),
+                None => {}
+            }
+        }
+        Ok(DataType::Array(Box::new(elem)))
+    }
+
+    /// The type of the values described by the schema `reference`
+    /// refers to, from the schema at `pointer`, which describes the
+    /// values at `path`. The values are named after the definition
+    /// it's the last part of the pointer to.
+    fn reference(
+        &mut self,
+        reference: &str,
+        path: &str,
+        pointer: &str,
+        depth: usize,
+    ) -> Result<DataType, Error> {
+        // Schemas in other documents aren't read.
+        let target = match reference.strip_prefix('#') {
+            Some(target) => target,
+            None => return Ok(DataType::unknown()),
+        };
+        if !target.is_empty() && !target.starts_with('/') {
+            return Err(invalid(
+                pointer,
+                &format!("{:?} isn't a JSON pointer", reference),
+            ));
+        }
+        if self.resolve(reference).is_none() {
+            return Err(invalid(
+                pointer,
+                &format!("there's nothing at {:?}", reference),
+            ));
+        }
+        let declared = self
+            .declared_defs
+            .and_then(|defs| reference.strip_prefix(defs))
+            .filter(|def| !def.contains('/'));
+        match (declared, target.rsplit('/').next()) {
+            (Some(def), _) => {
+                self.refs.insert(path.to_string(), unescape(def));
+            }
+            (None, Some(name)) if !name.is_empty() => {
+                self.names
+                    .entry(path.to_string())
+                    .or_insert_with(|| unescape(name));
+            }
+            (None, _) => {}
+        }
+        self.definition(reference, path, depth + 1)
+    }
+
+    /// The type of the values described by the schema at `pointer`, a
+    /// JSON pointer in a URI fragment like `#/$defs/user`, which are at
+    /// `path` in the values and nested `depth` levels into them. If the
+    /// schema is already being read, they're unknown.
+    pub(crate) fn definition(
+        &mut self,
+        pointer: &str,
+        path: &str,
+        depth: usize,
+    ) -> Result<DataType, Error> {
+        let schema = self
+            .resolve(pointer)
+            .ok_or_else(|| invalid(pointer, "there's nothing here"))?;
+        if self.expanding.iter().any(|expanding| expanding == pointer) {
+            return Ok(DataType::unknown());
+        }
+        self.expanding.push(pointer.to_string());
+        let typ = self.typ(schema, path, pointer, depth);
+        self.expanding.pop();
+        typ
+    }
+
+    /// What the JSON pointer in a URI fragment `pointer` points at in
+    /// the document, if anything.
+    pub(crate) fn resolve(&self, pointer: &str) -> Option<&'a Value> {
+        let mut value = self.root;
+        for token in pointer.strip_prefix('#')?.split('/').skip(1) {
+            let token = unescape(token);
+            value = match value {
+                Value::Object(members) => get(members, &token)?,
+                Value::Array(elems) => elems.get(token.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// The schemas in `schemas`, the array at `pointer`, with their own
+    /// pointers.
+    fn subschemas(
+        &self,
+        schemas: &'a Value,
+        pointer: &str,
+    ) -> Result<impl Iterator<Item = (String, &'a Value)>, Error> {
+        let pointer = pointer.to_string();
+        match schemas {
+            Value::Array(schemas) => Ok(schemas
+                .iter()
+                .enumerate()
+                .map(move |(idx, schema)| (format!("{}/{}", pointer, idx), schema))),
+            _ => Err(invalid(&pointer, "this must be an array of schemas")),
+        }
+    }
+}
+
+/// The value of the member `key` of an object with `members`.
+pub(crate) fn get<'a>(members: &'a [(String, Value)], key: &str) -> Option<&'a Value> {
+    members
+        .iter()
+        .find(|(member, _)| member == key)
+        .map(|(_, value)| value)
+}
+
+/// Write `key` as a token of a JSON pointer.
+pub(crate) fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// The key a token of a JSON pointer stands for.
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Merge two types of the same values, each of which only says some of
+/// what's known about them, as `allOf` does: objects get the members of
+/// both.
+fn merge(a: DataType, b: DataType) -> DataType {
+    match (a, b) {
+        (DataType::Object(mut a), DataType::Object(b)) => {
+            for (name, typ) in b {
+                let merged = match a.remove(&name) {
+                    Some(existing) => merge(existing, typ),
+                    None => typ,
+                };
+                a.insert(name, merged);
+            }
+            DataType::Object(a)
+        }
+        (a, b) if a == DataType::unknown() => b,
+        (a, b) if b == DataType::unknown() => a,
+        (a, b) => a.unify(b),
+    }
+}
+
+/// An error for the schema at `pointer`.
+pub(crate) fn invalid(pointer: &str, message: &str) -> Error {
+    Error::Schema {
+        path: None,
+        pointer: pointer.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema_type(schema: &str) -> Result<SchemaType, Error> {
+        let schema = Value::parse_json(schema)?.unwrap();
+        SchemaType::from_schema(&schema, &Options::default())
+    }
+
+    #[test]
+    fn types_of_schemas() {
+        let read = schema_type(
+            r##"{
+                "title": "user",
+                "type": "object",
+                "required": ["id", "tags", "home"],
+                "properties": {
+                    "id": {"type": "integer"},
+                    "email": {"type": ["string", "null"]},
+                    "tags": {"type": "array", "items": {"enum": ["a", "b"]}},
+                    "home": {"$ref": "#/$defs/address"},
+                    "scores": {"additionalProperties": {"type": "number"}},
+                    "parent": {"$ref": "#"},
+                    "kind": {"oneOf": [{"const": 1}, {"type": "string"}]}
+                },
+                "$defs": {
+                    "address": {
+                        "allOf": [
+                            {"properties": {"city": {"type": "string"}}, "required": ["city"]},
+                            {"properties": {"zip": {"type": "string"}}}
+                        ]
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let nullable = |typ: DataType| typ.unify(DataType::Null);
+        let expected = DataType::Object(
+            vec![
+                ("id", DataType::Int),
+                ("email", nullable(DataType::String)),
+                ("tags", DataType::Array(Box::new(DataType::String))),
+                (
+                    "home",
+                    DataType::Object(
+                        vec![
+                            ("city".to_string(), DataType::String),
+                            ("zip".to_string(), nullable(DataType::String)),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    ),
+                ),
+                ("scores", nullable(DataType::Map(Box::new(DataType::Float)))),
+                ("parent", DataType::Null),
+                ("kind", nullable(DataType::Int.unify(DataType::String))),
+            ]
+            .into_iter()
+            .map(|(name, typ)| (name.to_string(), typ))
+            .collect(),
+        );
+        assert_eq!(read.typ, expected);
+        assert_eq!(read.names["$"], "user");
+        assert_eq!(read.names["$.home"], "address");
+
+        assert!(matches!(
+            schema_type(r#"{"properties": {"a/b": {"type": "text"}}}"#),
+            Err(Error::Schema { pointer, .. }) if pointer == "#/properties/a~1b/type"
+        ));
+        assert!(matches!(
+            schema_type(r##"{"$ref": "#/$defs/missing"}"##),
+            Err(Error::Schema { pointer, .. }) if pointer == "#/$ref"
+        ));
+    }
+}
@@ -0,0 +1,297 @@
+//! Field numbers saved between runs, so that backends with numbered
+//! fields keep them stable as the input evolves.
+//!
+//! The state file is JSON, mapping the path of each message to the
+//! numbers of its fields:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "messages": {
+//!     "$.users[]": {
+//!       "fields": {"email": 3, "id": 1, "name": 2},
+//!       "retired": {"nickname": 4}
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Fields keep their numbers for as long as they're seen. A field that
+//! disappears from a message is moved to `retired`, and its number is
+//! never handed out again, even if a field of the same name comes back
+//! later; backends declare retired numbers as reserved. New fields get
+//! numbers above every number the message has ever used.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use json::JsonValue;
+
+use crate::Error;
+
+/// Version of the schema state format.
+const SCHEMA_STATE_VERSION: u32 = 1;
+
+/// Field numbers for every message, by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaState {
+    messages: BTreeMap<String, MessageNumbers>,
+}
+
+/// Field numbers for one message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct MessageNumbers {
+    fields: BTreeMap<String, u32>,
+    retired: BTreeMap<String, u32>,
+}
+
+impl MessageNumbers {
+    /// The number after the highest one the message has ever used.
+    fn next_number(&self) -> u32 {
+        self.fields
+            .values()
+            .chain(self.retired.values())
+            .max()
+            .map_or(1, |max| max + 1)
+    }
+}
+
+/// Error returned when a schema state file can't be loaded or saved.
+#[derive(Debug)]
+pub enum SchemaStateError {
+    /// The file couldn't be read or written.
+    Io(PathBuf, io::Error),
+
+    /// The file isn't a valid schema state file.
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for SchemaStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaStateError::Io(path, e) => {
+                write!(f, "failed to access schema state file {:?}: {}", path, e)
+            }
+            SchemaStateError::Invalid(path, why) => {
+                write!(f, "invalid schema state file {:?}: {}", path, why)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaStateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SchemaStateError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl SchemaState {
+    /// Create a state with no saved numbers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the state saved in `path`, or an empty state if there's no
+    /// such file.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(SchemaStateError::Io(path.to_owned(), e).into()),
+        };
+        let invalid = |why: &str| SchemaStateError::Invalid(path.to_owned(), why.to_string());
+
+        let root = json::parse(&text).map_err(|e| invalid(&e.to_string()))?;
+        match root["version"].as_u32() {
+            Some(SCHEMA_STATE_VERSION) => {}
+            Some(version) => {
+                return Err(invalid(&format!("unsupported version {}", version)).into())
+            }
+            None => return Err(invalid("missing version").into()),
+        }
+
+        let numbers = |table: &JsonValue| -> Result<BTreeMap<String, u32>, SchemaStateError> {
+            table
+                .entries()
+                .map(|(name, number)| match number.as_u32() {
+                    Some(number) if number > 0 => Ok((name.to_string(), number)),
+                    _ => Err(invalid(&format!("bad field number for {:?}", name))),
+                })
+                .collect()
+        };
+        let mut state = Self::new();
+        for (path, message) in root["messages"].entries() {
+            state.messages.insert(
+                path.to_string(),
+                MessageNumbers {
+                    fields: numbers(&message["fields"])?,
+                    retired: numbers(&message["retired"])?,
+                },
+            );
+        }
+        Ok(state)
+    }
+
+    /// Save the state to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut messages = JsonValue::new_object();
+        for (message_path, message) in &self.messages {
+            let table = |numbers: &BTreeMap<String, u32>| {
+                let mut table = JsonValue::new_object();
+                for (name, &number) in numbers {
+                    table[name.as_str()] = number.into();
+                }
+                table
+            };
+            messages[message_path.as_str()] = json::object! {
+                "fields": table(&message.fields),
+                "retired": table(&message.retired),
+            };
+        }
+        let root = json::object! {
+            "version": SCHEMA_STATE_VERSION,
+            "messages": messages,
+        };
+        fs::write(path, json::stringify_pretty(root, 2) + "\n")
+            .map_err(|e| SchemaStateError::Io(path.to_owned(), e).into())
+    }
+
+    /// Start numbering the fields of the message at `path`.
+    pub(crate) fn numbers(&mut self, path: &str) -> FieldNumbers {
+        FieldNumbers {
+            message: Some(self.messages.remove(path).unwrap_or_default()),
+            seen: BTreeSet::new(),
+            next: 1,
+        }
+    }
+
+    /// Finish numbering the fields of the message at `path`, retiring
+    /// any that weren't numbered. Returns the retired numbers.
+    pub(crate) fn finish(&mut self, path: &str, numbers: FieldNumbers) -> Vec<u32> {
+        let FieldNumbers { message, seen, .. } = numbers;
+        let mut message = message.expect("only called on saved numbers");
+        let gone = message
+            .fields
+            .keys()
+            .filter(|name| !seen.contains(*name))
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in gone {
+            let number = message.fields.remove(&name).expect("key was just listed");
+            message.retired.insert(name, number);
+        }
+
+        let mut retired = message.retired.values().copied().collect::<Vec<_>>();
+        retired.sort_unstable();
+        self.messages.insert(path.to_string(), message);
+        retired
+    }
+}
+
+/// Hands out the numbers for the fields of a message, either from a
+/// saved state or sequentially from 1.
+pub(crate) struct FieldNumbers {
+    message: Option<MessageNumbers>,
+    seen: BTreeSet<String>,
+    next: u32,
+}
+
+impl FieldNumbers {
+    /// Number fields sequentially from 1.
+    pub(crate) fn sequential() -> Self {
+        FieldNumbers {
+            message: None,
+            seen: BTreeSet::new(),
+            next: 1,
+        }
+    }
+
+    /// The number for the field `name`.
+    pub(crate) fn number(&mut self, name: &str) -> u32 {
+        let message = match &mut self.message {
+            Some(message) => message,
+            None => {
+                self.next += 1;
+                return self.next - 1;
+            }
+        };
+        self.seen.insert(name.to_string());
+        if let Some(&number) = message.fields.get(name) {
+            return number;
+        }
+        let number = message.next_number();
+        message.fields.insert(name.to_string(), number);
+        number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbering() {
+        let mut state = SchemaState::new();
+        let mut numbers = state.numbers("$");
+        assert_eq!(numbers.number("b"), 1);
+        assert_eq!(numbers.number("c"), 2);
+        assert_eq!(numbers.number("d"), 3);
+        assert_eq!(state.finish("$", numbers), Vec::<u32>::new());
+
+        // "a" is new, and "c" has gone away.
+        let mut numbers = state.numbers("$");
+        assert_eq!(numbers.number("a"), 4);
+        assert_eq!(numbers.number("b"), 1);
+        assert_eq!(numbers.number("d"), 3);
+        assert_eq!(state.finish("$", numbers), vec![2]);
+
+        // "c" comes back, but its old number stays retired.
+        let mut numbers = state.numbers("$");
+        assert_eq!(numbers.number("c"), 5);
+        assert_eq!(state.finish("$", numbers), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schema.json");
+        assert_eq!(SchemaState::load(&path).unwrap(), SchemaState::new());
+
+        let mut state = SchemaState::new();
+        let mut numbers = state.numbers("$.users[]");
+        numbers.number("id");
+        numbers.number("name");
+        state.finish("$.users[]", numbers);
+        let mut numbers = state.numbers("$.users[]");
+        numbers.number("id");
+        state.finish("$.users[]", numbers);
+
+        state.save(&path).unwrap();
+        assert_eq!(SchemaState::load(&path).unwrap(), state);
+        assert_eq!(
+            json::parse(&fs::read_to_string(&path).unwrap()).unwrap(),
+            json::object! {
+                "version": 1,
+                "messages": {
+                    "$.users[]": {"fields": {"id": 1}, "retired": {"name": 2}},
+                },
+            }
+        );
+
+        fs::write(
+            &path,
+            r#"{"version": 1, "messages": {"$": {"fields": {"a": 0}}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            SchemaState::load(&path),
+            Err(Error::SchemaState(SchemaStateError::Invalid(..)))
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+//! Saved inference state, for folding new records into a type inferred
+//! by an earlier run without re-reading the old records.
+
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{DataType, Error, Stats};
+
+/// Bytes at the start of every state file.
+const MAGIC: &[u8; 8] = b"JSANSTAT";
+
+/// Version of the state file format. Bump this whenever `State`
+/// changes shape, so that old files are rejected rather than misread.
+pub const STATE_VERSION: u32 = 2;
+
+/// Everything that's carried between runs of incremental inference.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    /// The type unified from every record seen so far.
+    pub typ: DataType,
+
+    /// The number of records seen so far.
+    pub records: u64,
+
+    /// Presence counts for the fields of every record seen so far.
+    pub stats: Stats,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            typ: DataType::unknown(),
+            records: 0,
+            stats: Stats::new(),
+        }
+    }
+}
+
+/// Error returned when a state file can't be loaded or saved.
+#[derive(Debug)]
+pub enum StateError {
+    /// The file couldn't be read or written.
+    Io(PathBuf, io::Error),
+
+    /// The file isn't a state file, or is damaged.
+    Corrupt(PathBuf),
+
+    /// The file was written by an incompatible version.
+    Version {
+        /// The file in question.
+        path: PathBuf,
+        /// The version the file was written with.
+        found: u32,
+    },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::Io(path, e) => write!(f, "failed to access state file {:?}: {}", path, e),
+            StateError::Corrupt(path) => write!(
+                f,
+                "state file {:?} is corrupt; use --reset to start over",
+                path
+            ),
+            StateError::Version { path, found } => write!(
+                f,
+                "state file {:?} has version {}, but version {} is required; \
+                 use --reset to start over",
+                path, found, STATE_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StateError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl State {
+    /// Load the state saved in `path`, or `None` if there's no such
+    /// file.
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        let mut bytes = Vec::new();
+        match File::open(path) {
+            Ok(mut file) => file
+                .read_to_end(&mut bytes)
+                .map_err(|e| StateError::Io(path.to_owned(), e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(StateError::Io(path.to_owned(), e).into()),
+        };
+
+        let corrupt = || StateError::Corrupt(path.to_owned());
+        let rest = bytes.strip_prefix(MAGIC).ok_or_else(corrupt)?;
+        if rest.len() < 4 {
+            return Err(corrupt().into());
+        }
+        let (version, payload) = rest.split_at(4);
+        let version = u32::from_le_bytes([version[0], version[1], version[2], version[3]]);
+        if version != STATE_VERSION {
+            return Err(StateError::Version {
+                path: path.to_owned(),
+                found: version,
+            }
+            .into());
+        }
+
+        bincode::deserialize(payload)
+            .map(Some)
+            .map_err(|_| corrupt().into())
+    }
+
+    /// Save the state to `path`. The state is written to a temporary
+    /// file that then replaces `path`, so an interrupted save never
+    /// leaves a half-written state behind.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+        tmp_name.push(".tmp");
+        let tmp = path.with_file_name(tmp_name);
+
+        let write = || -> io::Result<()> {
+            let mut file = File::create(&tmp)?;
+            file.write_all(MAGIC)?;
+            file.write_all(&STATE_VERSION.to_le_bytes())?;
+            bincode::serialize_into(&mut file, self).map_err(io::Error::other)?;
+            file.sync_all()?;
+            fs::rename(&tmp, path)
+        };
+        write().map_err(|e| StateError::Io(path.to_owned(), e).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Value;
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.bin");
+        assert!(State::load(&path).unwrap().is_none());
+
+        let record = json::object! {"a": [1, "b"], "c": null};
+        let mut stats = Stats::new();
+        stats.observe(&Value::from_json(&record).unwrap());
+        let state = State {
+            typ: DataType::from_json_value(&record).unwrap(),
+            records: 3,
+            stats,
+        };
+        state.save(&path).unwrap();
+        assert_eq!(State::load(&path).unwrap(), Some(state));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn bad_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.bin");
+
+        fs::write(&path, b"{\"not\": \"a state file\"}").unwrap();
+        assert!(matches!(
+            State::load(&path),
+            Err(Error::State(StateError::Corrupt(_)))
+        ));
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&(STATE_VERSION + 1).to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            State::load(&path),
+            Err(Error::State(StateError::Version { found, .. })) if found == STATE_VERSION + 1
+        ));
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&STATE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&[0xff; 3]);
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            State::load(&path),
+            Err(Error::State(StateError::Corrupt(_)))
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+//! Per-field statistics gathered alongside type inference, for deciding
+//! how fields that are sometimes missing or null should be modeled.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{types::looks_like_map, Value};
+
+/// Counts of how often each object member was seen, keyed by the path
+/// of the object it's a member of.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    objects: BTreeMap<String, ObjectStats>,
+}
+
+/// Counts for the objects found at one path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectStats {
+    /// The number of objects seen at this path.
+    pub count: u64,
+
+    /// Counts for each member seen in any of those objects.
+    pub fields: BTreeMap<String, FieldStats>,
+}
+
+/// Counts for one object member.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldStats {
+    /// The number of objects where the member had a non-null value.
+    pub value: u64,
+
+    /// The number of objects where the member was null.
+    pub null: u64,
+}
+
+impl FieldStats {
+    /// The number of objects where the member was missing, given how
+    /// many objects were seen in total.
+    pub fn absent(&self, count: u64) -> u64 {
+        count - self.value - self.null
+    }
+}
+
+impl Stats {
+    /// Create empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the members of every object in a document.
+    pub fn observe(&mut self, v: &Value) {
+        self.observe_at(v, "$".to_string());
+    }
+
+    /// Count the members of every object in an element of a document
+    /// that's an array, for documents read a piece at a time.
+    pub fn observe_element(&mut self, v: &Value) {
+        self.observe_at(v, "$[]".to_string());
+    }
+
+    /// Count the members of every object in a value found at `path`.
+    fn observe_at(&mut self, v: &Value, path: String) {
+        match v {
+            Value::Object(obj) if looks_like_map(obj) => {
+                for (_, value) in obj {
+                    self.observe_at(value, join(&path, "*"));
+                }
+            }
+            Value::Object(obj) => {
+                let stats = self.objects.entry(path.clone()).or_default();
+                stats.count += 1;
+                for (key, value) in obj {
+                    let field = stats.fields.entry(key.clone()).or_default();
+                    match value {
+                        Value::Null => field.null += 1,
+                        _ => field.value += 1,
+                    }
+                }
+                for (key, value) in obj {
+                    self.observe_at(value, join(&path, key));
+                }
+            }
+            Value::Array(elems) => {
+                for elem in elems {
+                    self.observe_at(elem, format!("{}[]", path));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Add the counts from another set of statistics.
+    pub fn merge(&mut self, other: Stats) {
+        for (path, other) in other.objects {
+            let stats = self.objects.entry(path).or_default();
+            stats.count += other.count;
+            for (key, other) in other.fields {
+                let field = stats.fields.entry(key).or_default();
+                field.value += other.value;
+                field.null += other.null;
+            }
+        }
+    }
+
+    /// The counts for the objects found at `path`, such as
+    /// `$.users[]`.
+    pub fn object(&self, path: &str) -> Option<&ObjectStats> {
+        self.objects.get(path)
+    }
+
+    /// Describe the counts for every field, one per line, as in
+    /// `user.email: 90 value / 5 null / 5 absent`.
+    pub fn report(&self) -> String {
+        let mut s = String::new();
+        for (path, stats) in &self.objects {
+            for (key, field) in &stats.fields {
+                writeln!(
+                    s,
+                    "{}: {} value / {} null / {} absent",
+                    display_path(&join(path, key)),
+                    field.value,
+                    field.null,
+                    field.absent(stats.count)
+                )
+                .expect("writing to a string can't fail");
+            }
+        }
+        s
+    }
+}
+
+/// The path of the member `key` of the object at `path`.
+fn join(path: &str, key: &str) -> String {
+    format!("{}.{}", path, key)
+}
+
+/// A path without the leading `$`, which the report leaves implicit.
+fn display_path(path: &str) -> &str {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    path.strip_prefix('.').unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observe(stats: &mut Stats, v: json::JsonValue) {
+        stats.observe(&Value::from_json(&v).unwrap());
+    }
+
+    #[test]
+    fn presence_counts() {
+        let mut stats = Stats::new();
+        observe(
+            &mut stats,
+            json::object! {"user": {"name": "a", "email": "a@example.com"}},
+        );
+        observe(
+            &mut stats,
+            json::object! {"user": {"name": "b", "email": null}},
+        );
+        observe(&mut stats, json::object! {"user": {"name": "c"}});
+        observe(&mut stats, json::object! {"user": null});
+
+        assert_eq!(
+            stats.report(),
+            "user: 3 value / 1 null / 0 absent\n\
+             user.email: 1 value / 1 null / 1 absent\n\
+             user.name: 3 value / 0 null / 0 absent\n"
+        );
+    }
+
+    #[test]
+    fn arrays_and_merging() {
+        let mut a = Stats::new();
+        observe(
+            &mut a,
+            json::array![{"id": 1, "tags": [{"k": "x"}]}, {"id": 2}],
+        );
+        let mut b = Stats::new();
+        observe(&mut b, json::array![{"id": null, "extra": true}]);
+
+        a.merge(b);
+        assert_eq!(
+            a.report(),
+            "[].extra: 1 value / 0 null / 2 absent\n\
+             [].id: 2 value / 1 null / 0 absent\n\
+             [].tags: 1 value / 0 null / 2 absent\n\
+             [].tags[].k: 1 value / 0 null / 0 absent\n"
+        );
+        assert_eq!(a.object("$[]").map(|stats| stats.count), Some(3));
+    }
+}
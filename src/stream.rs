@@ -0,0 +1,251 @@
+//! Reading of JSON documents a piece at a time, for documents too big
+//! to hold in memory.
+
+use std::{fmt, io::BufRead};
+
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::{value::ValueVisitor, Error, Value};
+
+/// A piece of a JSON document read by [`stream_json`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    /// A whole document that isn't an array.
+    Document(Value),
+
+    /// The next element of a document that's an array.
+    Element(Value),
+
+    /// The end of a document that's an array, after all of its
+    /// elements, with how many of them there were.
+    End(usize),
+}
+
+/// Read the JSON documents one after another from `reader` a piece at
+/// a time, passing each piece to `f`. The elements of a document that's
+/// an array are read one at a time, so only one of them is held at
+/// once; other documents are read whole. There are no pieces if there's
+/// nothing but whitespace. Errors from `f` stop the reading, and are
+/// returned.
+pub fn stream_json<R: BufRead, E: From<Error>>(
+    mut reader: R,
+    mut f: impl FnMut(Piece) -> Result<(), E>,
+) -> Result<(), E> {
+    // Skip the whitespace before the document, to tell if there is
+    // one, keeping track of where it starts for errors.
+    let (mut line, mut column) = (1, 1);
+    loop {
+        let buf = reader
+            .fill_buf()
+            .map_err(|e| Error::from_serde_json(serde_json::Error::io(e), line, column))?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let skipped = buf
+            .iter()
+            .take_while(|byte| byte.is_ascii_whitespace())
+            .count();
+        for &byte in &buf[..skipped] {
+            match byte {
+                b'\n' => (line, column) = (line + 1, 1),
+                _ => column += 1,
+            }
+        }
+        let done = skipped < buf.len();
+        reader.consume(skipped);
+        if done {
+            break;
+        }
+    }
+
+    let mut failed = None;
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    // The visitors stop at `MAX_DEPTH`, as reading documents in memory
+    // does, rather than at `serde_json`'s shallower limit.
+    deserializer.disable_recursion_limit();
+    loop {
+        let read = deserializer.deserialize_any(DocumentVisitor {
+            f: &mut f,
+            failed: &mut failed,
+        });
+        // Checking for the end fails if there's another document.
+        let read = read.map(|()| deserializer.end());
+        match (read, failed.take()) {
+            (_, Some(e)) => return Err(e),
+            (Ok(Ok(())), None) => return Ok(()),
+            (Ok(Err(e)), None) if !e.is_io() => {}
+            (Ok(Err(e)) | Err(e), None) => {
+                return Err(Error::from_serde_json(e, line, column).into())
+            }
+        }
+    }
+}
+
+/// Visitor passing the pieces of a document to `f`, and keeping the
+/// first error it returns in `failed`.
+struct DocumentVisitor<'a, F, E> {
+    f: &'a mut F,
+    failed: &'a mut Option<E>,
+}
+
+impl<F: FnMut(Piece) -> Result<(), E>, E> DocumentVisitor<'_, F, E> {
+    /// Pass `piece` to `f`, keeping its error if it fails.
+    fn emit<D: de::Error>(&mut self, piece: Piece) -> Result<(), D> {
+        (self.f)(piece).map_err(|e| {
+            *self.failed = Some(e);
+            D::custom("stopped")
+        })
+    }
+
+    /// Pass a whole document, `value`, to `f`.
+    fn document<D: de::Error>(mut self, value: Result<Value, D>) -> Result<(), D> {
+        self.emit(Piece::Document(value?))
+    }
+}
+
+impl<'de, F: FnMut(Piece) -> Result<(), E>, E> Visitor<'de> for DocumentVisitor<'_, F, E> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON document")
+    }
+
+    fn visit_bool<D: de::Error>(self, b: bool) -> Result<(), D> {
+        self.document(ValueVisitor::at(0).visit_bool(b))
+    }
+
+    fn visit_i64<D: de::Error>(self, n: i64) -> Result<(), D> {
+        self.document(ValueVisitor::at(0).visit_i64(n))
+    }
+
+    fn visit_u64<D: de::Error>(self, n: u64) -> Result<(), D> {
+        self.document(ValueVisitor::at(0).visit_u64(n))
+    }
+
+    fn visit_f64<D: de::Error>(self, n: f64) -> Result<(), D> {
+        self.document(ValueVisitor::at(0).visit_f64(n))
+    }
+
+    fn visit_str<D: de::Error>(self, s: &str) -> Result<(), D> {
+        self.document(ValueVisitor::at(0).visit_str(s))
+    }
+
+    fn visit_string<D: de::Error>(self, s: String) -> Result<(), D> {
+        self.document(ValueVisitor::at(0).visit_string(s))
+    }
+
+    fn visit_unit<D: de::Error>(self) -> Result<(), D> {
+        self.document(ValueVisitor::at(0).visit_unit())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        self.document(ValueVisitor::at(0).visit_map(map))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        let mut len = 0;
+        while let Some(elem) = seq.next_element_seed(ValueVisitor::at(1))? {
+            self.emit(Piece::Element(elem))?;
+            len += 1;
+        }
+        self.emit(Piece::End(len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pieces of the document in `text`, or the error reading it.
+    fn pieces(text: &str) -> Result<Vec<Piece>, Error> {
+        let mut pieces = Vec::new();
+        stream_json(text.as_bytes(), |piece| {
+            pieces.push(piece);
+            Ok::<_, Error>(())
+        })?;
+        Ok(pieces)
+    }
+
+    #[test]
+    fn pieces_of_documents() {
+        assert_eq!(
+            pieces(" [1, {\"a\": \"x\"}]\n").unwrap(),
+            vec![
+                Piece::Element(Value::Number(1u64.into())),
+                Piece::Element(Value::Object(vec![(
+                    "a".to_string(),
+                    Value::String("x".to_string())
+                )])),
+                Piece::End(2),
+            ]
+        );
+        assert_eq!(
+            pieces("{\"a\": [true]}").unwrap(),
+            vec![Piece::Document(Value::Object(vec![(
+                "a".to_string(),
+                Value::Array(vec![Value::Bool(true)])
+            )]))]
+        );
+        assert_eq!(pieces("[]").unwrap(), vec![Piece::End(0)]);
+        assert_eq!(pieces(" \n ").unwrap(), vec![]);
+
+        assert!(matches!(
+            pieces("\n\n  [1,\n 2,]"),
+            Err(Error::Parse {
+                line: 4,
+                column: 4,
+                ..
+            })
+        ));
+        assert_eq!(
+            pieces("[1]{}\n 2").unwrap(),
+            vec![
+                Piece::Element(Value::Number(1u64.into())),
+                Piece::End(1),
+                Piece::Document(Value::Object(vec![])),
+                Piece::Document(Value::Number(2u64.into())),
+            ]
+        );
+        assert!(matches!(
+            pieces("[1] 2 }"),
+            Err(Error::Parse {
+                line: 1,
+                column: 7,
+                ..
+            })
+        ));
+
+        let mut seen = 0;
+        let stopped = stream_json(&b"[1, 2, 3]"[..], |_| {
+            seen += 1;
+            match seen {
+                2 => Err(Error::TooDeep(crate::DepthExceeded)),
+                _ => Ok(()),
+            }
+        });
+        assert!(matches!(stopped, Err(Error::TooDeep(_))));
+        assert_eq!(seen, 2);
+    }
+
+    #[test]
+    fn same_depths_as_in_memory() {
+        let nested =
+            |depth: usize, open: &str, close: &str| open.repeat(depth) + "1" + &close.repeat(depth);
+        for depth in [128, crate::MAX_DEPTH, crate::MAX_DEPTH + 1] {
+            for text in [nested(depth, "[", "]"), nested(depth, "{\"a\":", "}")] {
+                match Value::parse_json(&text) {
+                    Ok(_) => assert!(pieces(&text).is_ok(), "{}", text),
+                    Err(Error::TooDeep(_)) => {
+                        assert!(matches!(pieces(&text), Err(Error::TooDeep(_))), "{}", text)
+                    }
+                    Err(e) => panic!("{}", e),
+                }
+            }
+        }
+        assert!(pieces(&nested(crate::MAX_DEPTH, "[", "]")).is_ok());
+        assert!(matches!(
+            pieces(&nested(crate::MAX_DEPTH + 1, "[", "]")),
+            Err(Error::TooDeep(_))
+        ));
+    }
+}
@@ -0,0 +1,192 @@
+//! Recognizing strings that hold dates and times, so that they can be
+//! declared with `chrono` types rather than as plain strings.
+
+use serde::{Deserialize, Serialize};
+
+/// A kind of date or time written in ISO 8601 / RFC 3339 form.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Temporal {
+    /// A calendar date, like `2023-11-05`.
+    Date,
+
+    /// A time of day, like `14:30:00`.
+    Time,
+
+    /// A date and time with no time zone, like `2023-11-05T14:30:00`.
+    NaiveDateTime,
+
+    /// A date and time at a known offset from UTC, like
+    /// `2023-11-05T14:30:00Z` or `2023-11-05T14:30:00+01:00`.
+    DateTime,
+}
+
+impl Temporal {
+    /// The kind of date or time `s` holds, if it holds one and nothing
+    /// else.
+    pub fn detect(s: &str) -> Option<Self> {
+        if is_date(s) {
+            return Some(Temporal::Date);
+        }
+        if is_time(s) {
+            return Some(Temporal::Time);
+        }
+
+        // The date is always the first ten characters, which are ASCII
+        // if it's a date at all.
+        let date = s.get(..10).filter(|date| is_date(date))?;
+        let rest = s[date.len()..].strip_prefix(['T', 't'])?;
+        if is_time(rest) {
+            return Some(Temporal::NaiveDateTime);
+        }
+        let offset_start = rest.rfind(['Z', 'z', '+', '-'])?;
+        let (time, offset) = rest.split_at(offset_start);
+        match is_time(time) && is_offset(offset) {
+            true => Some(Temporal::DateTime),
+            false => None,
+        }
+    }
+
+    /// The `chrono` type that holds this kind of date or time.
+    pub fn rust_type(self) -> &'static str {
+        match self {
+            Temporal::Date => "chrono::NaiveDate",
+            Temporal::Time => "chrono::NaiveTime",
+            Temporal::NaiveDateTime => "chrono::NaiveDateTime",
+            Temporal::DateTime => "chrono::DateTime<chrono::Utc>",
+        }
+    }
+
+    /// A short description of this kind of date or time.
+    pub fn kind_name(self) -> &'static str {
+        match self {
+            Temporal::Date => "date",
+            Temporal::Time => "time",
+            Temporal::NaiveDateTime => "datetime",
+            Temporal::DateTime => "timestamp",
+        }
+    }
+
+    /// A representative string of this kind.
+    pub fn example(self) -> &'static str {
+        match self {
+            Temporal::Date => "2023-11-05",
+            Temporal::Time => "14:30:00",
+            Temporal::NaiveDateTime => "2023-11-05T14:30:00",
+            Temporal::DateTime => "2023-11-05T14:30:00Z",
+        }
+    }
+}
+
+/// Whether `s` is a date in `YYYY-MM-DD` form.
+fn is_date(s: &str) -> bool {
+    match fields(s, '-', &[4, 2, 2]).as_deref() {
+        Some([_, month, day]) => (1..=12).contains(month) && (1..=31).contains(day),
+        _ => false,
+    }
+}
+
+/// Whether `s` is a time in `HH:MM:SS` form, with an optional fraction
+/// of a second.
+fn is_time(s: &str) -> bool {
+    let (s, fraction) = match s.split_once('.') {
+        Some((s, fraction)) => (s, Some(fraction)),
+        None => (s, None),
+    };
+    if !fraction.is_none_or(|fraction| !fraction.is_empty() && is_digits(fraction)) {
+        return false;
+    }
+    match fields(s, ':', &[2, 2, 2]).as_deref() {
+        // Leap seconds are written as second 60.
+        Some([hour, minute, second]) => *hour < 24 && *minute < 60 && *second <= 60,
+        _ => false,
+    }
+}
+
+/// Whether `s` is an offset from UTC: `Z`, or `+HH:MM` or `-HH:MM`.
+fn is_offset(s: &str) -> bool {
+    if s.eq_ignore_ascii_case("z") {
+        return true;
+    }
+    let rest = match s.strip_prefix(['+', '-']) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    match fields(rest, ':', &[2, 2]).as_deref() {
+        Some([hour, minute]) => *hour < 24 && *minute < 60,
+        _ => false,
+    }
+}
+
+/// The numbers in `s` separated by `separator`, if it's nothing else
+/// and they have the given numbers of digits.
+fn fields(s: &str, separator: char, widths: &[usize]) -> Option<Vec<u32>> {
+    let fields = s.split(separator).collect::<Vec<_>>();
+    if fields.len() != widths.len() {
+        return None;
+    }
+    fields
+        .iter()
+        .zip(widths)
+        .map(
+            |(field, &width)| match field.len() == width && is_digits(field) {
+                true => field.parse().ok(),
+                false => None,
+            },
+        )
+        .collect()
+}
+
+/// Whether `s` is one or more ASCII digits.
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        for (s, kind) in [
+            ("2023-11-05", Some(Temporal::Date)),
+            ("14:30:00", Some(Temporal::Time)),
+            ("14:30:00.123", Some(Temporal::Time)),
+            ("23:59:60", Some(Temporal::Time)),
+            ("2023-11-05T14:30:00", Some(Temporal::NaiveDateTime)),
+            ("2023-11-05T14:30:00.5", Some(Temporal::NaiveDateTime)),
+            ("2023-11-05T14:30:00Z", Some(Temporal::DateTime)),
+            ("2023-11-05t14:30:00z", Some(Temporal::DateTime)),
+            ("2023-11-05T14:30:00.123+01:00", Some(Temporal::DateTime)),
+            ("2023-11-05T14:30:00-08:00", Some(Temporal::DateTime)),
+            ("2023-13-05", None),
+            ("2023-11-5", None),
+            ("2023-1-005", None),
+            ("1:30:000", None),
+            ("+2023-11-05", None),
+            ("2023-11-05 14:30:00", None),
+            ("2023-11-05T14:30", None),
+            ("2023-11-05T14:30:00+0100", None),
+            ("2023-11-05T14:30:00.Z", None),
+            ("24:00:00", None),
+            ("14:30", None),
+            ("١٢:٣٠:٠٠", None),
+            ("2023-11-05é", None),
+            ("hello", None),
+            ("", None),
+        ] {
+            assert_eq!(Temporal::detect(s), kind, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn examples_detect() {
+        for kind in [
+            Temporal::Date,
+            Temporal::Time,
+            Temporal::NaiveDateTime,
+            Temporal::DateTime,
+        ] {
+            assert_eq!(Temporal::detect(kind.example()), Some(kind));
+        }
+    }
+}
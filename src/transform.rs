@@ -0,0 +1,135 @@
+//! Changes made to an inferred type before it's declared, so that users
+//! of the library can adjust what's emitted without patching it.
+//!
+//! Transforms are given to [`Decls::with_transform`](crate::Decls::with_transform)
+//! and applied in the order they were given when a type is declared.
+
+use std::{collections::BTreeSet, mem};
+
+use crate::DataType;
+
+/// A change made to an inferred type before it's declared.
+pub trait Transform {
+    /// Transform `typ`, the type of a whole document.
+    fn apply(&self, typ: DataType) -> DataType;
+}
+
+/// Any function from types to types is a transform.
+impl<F: Fn(DataType) -> DataType> Transform for F {
+    fn apply(&self, typ: DataType) -> DataType {
+        self(typ)
+    }
+}
+
+/// Turns integer enums with more than a number of codes back into
+/// plain integers. This is what inference does itself when
+/// `Options::int_enum_threshold` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitIntEnums(pub usize);
+
+impl Transform for LimitIntEnums {
+    fn apply(&self, mut typ: DataType) -> DataType {
+        typ.limit_int_enums(self.0);
+        typ
+    }
+}
+
+/// Removes the object members at some paths, such as `$.users[].id`,
+/// along with everything inside them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Prune {
+    paths: BTreeSet<String>,
+}
+
+impl Prune {
+    /// Remove the members at `paths`.
+    pub fn new<S: Into<String>>(paths: impl IntoIterator<Item = S>) -> Self {
+        Prune {
+            paths: paths.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Remove the members at the paths from `typ`, the type found at
+    /// `path`.
+    fn prune(&self, typ: &mut DataType, path: &str) {
+        match typ {
+            DataType::Object(members) => {
+                members.retain(|key, _| !self.paths.contains(&format!("{}.{}", path, key)));
+                for (key, member_type) in members {
+                    self.prune(member_type, &format!("{}.{}", path, key));
+                }
+            }
+            DataType::Array(elem) | DataType::FixedArray(elem, _) => {
+                self.prune(elem, &format!("{}[]", path))
+            }
+            DataType::Map(values) => self.prune(values, &format!("{}.*", path)),
+            DataType::Variant(options) => {
+                // Options can't be changed while they're in the set.
+                *options = mem::take(options)
+                    .into_iter()
+                    .map(|mut option| {
+                        self.prune(&mut option, path);
+                        option
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Transform for Prune {
+    fn apply(&self, mut typ: DataType) -> DataType {
+        self.prune(&mut typ, "$");
+        typ
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Decls, Options, Value};
+
+    #[test]
+    fn pipeline() {
+        let v = Value::from_json(&json::object! {
+            "id": 1,
+            "status": 2,
+            "users": [{"id": 3, "name": "a"}, null],
+            "meta": {"id": 4},
+        })
+        .unwrap();
+        let typ = DataType::from_value_with(
+            &v,
+            &Options {
+                int_enum_threshold: Some(4),
+                ..Options::default()
+            },
+        )
+        .unwrap();
+
+        let mut decls = Decls::new(Options::default())
+            .with_transform(Prune::new(["$.users[].id", "$.meta"]))
+            .with_transform(LimitIntEnums(0))
+            .with_transform(|typ: DataType| match typ {
+                DataType::Object(mut members) => {
+                    members.remove("status");
+                    DataType::Object(members)
+                }
+                typ => typ,
+            });
+        typ.declare(&mut decls);
+        assert_eq!(
+            decls.output(),
+            "struct Data1 {
+    pub name: String,
+}
+
+struct Data0 {
+    pub id: i64,
+    pub users: Vec<Option<Data1>>,
+}"
+        );
+    }
+}
@@ -0,0 +1,3322 @@
+//! The inferred types of JSON data, and the operations for building
+//! and emitting them.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, io,
+    iter::once,
+    mem,
+};
+
+use json::JsonValue;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    intern::Interner,
+    lang::{
+        arrow, avro, bigquery, c, capnp, clojure, cpp, csharp, dart, elm, flatbuffers, fsharp,
+        graphql, io_ts, java, json_schema, julia, kotlin, mongoose, nim, ocaml, parquet, proto,
+        pyspark, python, scala, sql, swift, thrift, typescript, zig, zod, Lang,
+    },
+    namer::{Case, Namer},
+    options::{MainErrors, NullFields, Options, StringType, RUST_UNKNOWN_TYPE},
+    schema_state::{FieldNumbers, SchemaState},
+    stats::Stats,
+    temporal::Temporal,
+    transform::Transform,
+    value::Value,
+    warnings::{WarningKind, Warnings},
+};
+
+/// The deepest level of nesting that will be analyzed. Inference and
+/// emission both recurse into nested values, so without a limit a
+/// maliciously deep document would overflow the stack.
+pub const MAX_DEPTH: usize = 256;
+
+/// The most distinct values an integer enum is allowed to collect.
+/// Unifying enums with more codes than this between them gives a plain
+/// integer, so that fields like IDs don't pile up every value seen.
+pub const MAX_INT_ENUM_CODES: usize = 64;
+
+/// The longest array that can be declared with a fixed length, which is
+/// the longest that serde can deserialize.
+pub const MAX_MATRIX_LEN: usize = 32;
+
+/// The lifetime parameter of declared types that borrow from the input,
+/// such as those holding `Cow` strings.
+const LIFETIME: &str = "'a";
+
+/// The most members an object can have for its type to be written
+/// inline with `Options::inline_small_types`.
+const INLINE_MEMBERS: usize = 3;
+
+/// Error returned when a value is nested more than `MAX_DEPTH` levels
+/// deep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthExceeded;
+
+impl fmt::Display for DepthExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value is nested more than {} levels deep", MAX_DEPTH)
+    }
+}
+
+impl std::error::Error for DepthExceeded {}
+
+/// Types of data in a JSON structure.
+#[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DataType {
+    /// Data that is always Null. In practice, this is usually
+    /// combined with `Variant` to create an optional value.
+    Null,
+
+    /// A string of characters.
+    String,
+
+    /// A number that must always be an integer that fits in 64 bits.
+    Int,
+
+    /// A number that can be either a float or an integer.
+    Float,
+
+    /// A boolean.
+    Bool,
+
+    /// A heterogeneous data structure with named elements, like a
+    /// struct.
+    Object(BTreeMap<String, DataType>),
+
+    /// An array of elements with the same type.
+    Array(Box<DataType>),
+
+    /// An object whose keys are data rather than member names, like a
+    /// dictionary from IDs to records, with values of the same type.
+    Map(Box<DataType>),
+
+    /// One of several possible types. An empty Variant is also used
+    /// to represent an unknown type.
+    Variant(BTreeSet<DataType>),
+
+    /// An integer that only ever took one of a few values, which are
+    /// probably codes for something. Only inferred when
+    /// `Options::int_enum_threshold` is set. This comes after the
+    /// types above so that saved states from before it existed can
+    /// still be read.
+    IntEnum(BTreeSet<i64>),
+
+    /// A string that always held a date or time of the same kind. Only
+    /// inferred when `Options::detect_dates` is set, and last for the
+    /// same reason as `IntEnum`.
+    Temporal(Temporal),
+
+    /// An array of numbers, or of such arrays, that always had the same
+    /// length. Only inferred when `Options::matrix` is set, and last
+    /// for the same reason as `IntEnum`.
+    FixedArray(Box<DataType>, usize),
+
+    /// A string of bytes, from a binary format like CBOR that has them.
+    /// Last for the same reason as `IntEnum`.
+    Bytes,
+}
+
+impl DataType {
+    /// The type of values nothing is known about.
+    pub fn unknown() -> Self {
+        DataType::Variant(BTreeSet::new())
+    }
+
+    /// Generate a data type that could represent something of this
+    /// type, or of the `other` type.
+    pub fn unify(mut self, other: DataType) -> Self {
+        self.unify_into(other);
+        self
+    }
+
+    /// Widen this data type in place so that it can also represent
+    /// something of the `other` type. This is what `unify` does, but
+    /// folding many types into one this way reuses the maps and sets
+    /// that are already there rather than rebuilding them every time.
+    pub fn unify_into(&mut self, other: DataType) {
+        match (&mut *self, other) {
+            (DataType::Variant(types), t2) => {
+                // Flatten `t2` into its options and fold each of them
+                // into `types`, so that variants never nest and the
+                // result doesn't depend on which side the variant
+                // was on.
+                match t2 {
+                    DataType::Variant(options) => {
+                        for option in options {
+                            Self::add_option(types, option);
+                        }
+                    }
+                    t2 => Self::add_option(types, t2),
+                }
+                if types.len() == 1 {
+                    *self = types.pop_first().expect("set has one element");
+                }
+            }
+            (_, DataType::Variant(mut types)) => {
+                Self::add_option(&mut types, mem::replace(self, DataType::Null));
+                *self = Self::from_options(types);
+            }
+            (DataType::Float, DataType::Int | DataType::IntEnum(_))
+            | (DataType::Int, DataType::IntEnum(_)) => {}
+            (DataType::Int | DataType::IntEnum(_), DataType::Float) => *self = DataType::Float,
+            (DataType::IntEnum(_), DataType::Int) => *self = DataType::Int,
+            (DataType::String, DataType::Temporal(_)) => {}
+            (DataType::Temporal(a), DataType::Temporal(b)) if *a == b => {}
+            (DataType::Temporal(_), DataType::String | DataType::Temporal(_)) => {
+                *self = DataType::String
+            }
+            (DataType::IntEnum(a), DataType::IntEnum(b)) => {
+                a.extend(b);
+                if a.len() > MAX_INT_ENUM_CODES {
+                    *self = DataType::Int;
+                }
+            }
+            (DataType::Object(a), DataType::Object(mut b)) => {
+                // Unify each element of `a` with its counterpart in
+                // `b`, using `null` for missing elements, and then add
+                // the elements that only occur in `b`.
+                for (key, value) in a.iter_mut() {
+                    value.unify_into(b.remove(key).unwrap_or(DataType::Null));
+                }
+                for (key, mut value) in b {
+                    value.unify_into(DataType::Null);
+                    a.insert(key, value);
+                }
+            }
+            (DataType::FixedArray(a, n), DataType::FixedArray(b, m)) if *n == m => {
+                a.unify_into(*b);
+                if !a.fits_matrix() {
+                    self.unfix();
+                }
+            }
+            (
+                DataType::Array(_) | DataType::FixedArray(..),
+                DataType::Array(b) | DataType::FixedArray(b, _),
+            ) => {
+                // Arrays that don't all have the same length can only
+                // be declared as vectors.
+                self.unfix();
+                if let DataType::Array(a) = self {
+                    a.unify_into(*b);
+                }
+            }
+            (DataType::Map(a), DataType::Map(b)) => a.unify_into(*b),
+            (t1, t2) if *t1 == t2 => {}
+            (_, t2) => {
+                let t1 = mem::replace(self, DataType::Null);
+                *self = DataType::Variant(vec![t1, t2].into_iter().collect());
+            }
+        }
+    }
+
+    /// Whether two types are of the same kind, and so should be
+    /// unified into a single type rather than kept as separate
+    /// options of a variant.
+    pub(crate) fn same_kind(&self, other: &DataType) -> bool {
+        let is_number =
+            |typ: &DataType| matches!(typ, DataType::Int | DataType::Float | DataType::IntEnum(_));
+        let is_string = |typ: &DataType| matches!(typ, DataType::String | DataType::Temporal(_));
+        (is_number(self) && is_number(other))
+            || (is_string(self) && is_string(other))
+            || matches!(
+                (self, other),
+                (DataType::Object(_), DataType::Object(_))
+                    | (
+                        DataType::Array(_) | DataType::FixedArray(..),
+                        DataType::Array(_) | DataType::FixedArray(..)
+                    )
+                    | (DataType::Map(_), DataType::Map(_))
+            )
+            || self == other
+    }
+
+    /// Add `typ` as an option to a set of variant options, merging it
+    /// with an existing option of the same kind if there is one.
+    fn add_option(options: &mut BTreeSet<DataType>, typ: DataType) {
+        // Options can't be changed while they're in the set, so move
+        // them all out and back. There's at most one of each kind, so
+        // there are never more than a handful of them.
+        let mut typ = Some(typ);
+        *options = mem::take(options)
+            .into_iter()
+            .map(|mut option| {
+                if let Some(typ) = typ.take_if(|typ| option.same_kind(typ)) {
+                    option.unify_into(typ);
+                }
+                option
+            })
+            .collect();
+        options.extend(typ);
+    }
+
+    /// Whether arrays of this type can have a fixed length: whether it's
+    /// a number or an array of them that has one.
+    pub(crate) fn fits_matrix(&self) -> bool {
+        matches!(
+            self,
+            DataType::Int | DataType::Float | DataType::IntEnum(_) | DataType::FixedArray(..)
+        )
+    }
+
+    /// Turn a fixed-length array into one of any length.
+    fn unfix(&mut self) {
+        if let DataType::FixedArray(elem, _) = self {
+            let elem = mem::replace(elem, Box::new(DataType::Null));
+            *self = DataType::Array(elem);
+        }
+    }
+
+    /// The options of this type if it's a variant, or else the type
+    /// itself.
+    pub(crate) fn options(&self) -> Vec<&DataType> {
+        match self {
+            DataType::Variant(options) => options.iter().collect(),
+            typ => vec![typ],
+        }
+    }
+
+    /// Build the type that can be any of `options`, avoiding
+    /// single-option variants.
+    fn from_options(mut options: BTreeSet<DataType>) -> Self {
+        if options.len() == 1 {
+            options.pop_first().expect("set has one element")
+        } else {
+            DataType::Variant(options)
+        }
+    }
+
+    /// Create a data type that can reprent the given value. Fails if
+    /// the value is nested more than `MAX_DEPTH` levels deep.
+    pub fn from_value(v: &Value) -> Result<Self, Error> {
+        Self::from_value_with(v, &Options::default())
+    }
+
+    /// Create a data type that can represent the given value, inferring
+    /// according to `options`.
+    pub fn from_value_with(v: &Value, options: &Options) -> Result<Self, Error> {
+        let mut interner = Interner::with_options(options);
+        let id = interner.infer(v)?;
+        Ok(interner.to_data_type(id))
+    }
+
+    /// Turn the integer enums in this type that have more than
+    /// `threshold` codes back into plain integers.
+    pub fn limit_int_enums(&mut self, threshold: usize) {
+        match self {
+            DataType::IntEnum(codes) if codes.len() > threshold => *self = DataType::Int,
+            DataType::Object(members) => {
+                for member_type in members.values_mut() {
+                    member_type.limit_int_enums(threshold);
+                }
+            }
+            DataType::Array(inner) | DataType::Map(inner) | DataType::FixedArray(inner, _) => {
+                inner.limit_int_enums(threshold)
+            }
+            DataType::Variant(options) => {
+                *options = mem::take(options)
+                    .into_iter()
+                    .map(|mut option| {
+                        option.limit_int_enums(threshold);
+                        option
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+
+    /// Create a data type that can represent a value parsed by the
+    /// `json` crate.
+    pub fn from_json_value(v: &JsonValue) -> Result<Self, Error> {
+        Self::from_value(&Value::from_json(v)?)
+    }
+
+    /// The type of a value that isn't an object or an array.
+    pub(crate) fn from_scalar(v: &Value, options: &Options) -> Self {
+        match v {
+            Value::String(s) => match Temporal::detect(s) {
+                Some(kind) if options.detect_dates => Self::Temporal(kind),
+                _ => Self::String,
+            },
+            Value::Number(n) => match n.as_i64() {
+                Some(n) if options.int_enum_threshold.is_some() => Self::IntEnum(once(n).collect()),
+                Some(_) => Self::Int,
+                None => Self::Float,
+            },
+            Value::Bool(_) => Self::Bool,
+            Value::Bytes(_) => Self::Bytes,
+            Value::Null | Value::Object(_) | Value::Array(_) => Self::Null,
+        }
+    }
+
+    /// Emit a representation of the data type in the language chosen
+    /// by the options. Return the (automatically-generated) name of the
+    /// newly-declared type. The transforms given to `decls` are
+    /// applied first, in order.
+    pub fn declare(self, decls: &mut Decls) -> String {
+        let transforms = std::mem::take(&mut decls.transforms);
+        let typ = transforms
+            .iter()
+            .fold(self, |typ, transform| transform.apply(typ));
+        decls.transforms = transforms;
+        typ.declare_transformed(decls)
+    }
+
+    /// Emit the data type as `declare` does, naming it `name` rather
+    /// than as the options say, for declaring several top-level types
+    /// together.
+    pub fn declare_as(self, decls: &mut Decls, name: &str) -> String {
+        let given = decls
+            .options
+            .names
+            .insert("$".to_string(), name.to_string());
+        let declared = self.declare(decls);
+        match given {
+            Some(given) => decls.options.names.insert("$".to_string(), given),
+            None => decls.options.names.remove("$"),
+        };
+        declared
+    }
+
+    /// Emit the data type as `declare` does, after the transforms.
+    fn declare_transformed(self, decls: &mut Decls) -> String {
+        if self == DataType::Null {
+            decls.warnings.push("$", WarningKind::NullDocument, "");
+        }
+        match decls.options.lang {
+            Lang::Rust => {
+                let name = self.declare_root(decls);
+                if let Some(errors) = decls.options.emit_main {
+                    // `main` has no lifetime of its own to borrow with.
+                    let root = name.replace(LIFETIME, "'_");
+                    write_main(decls.begin_decl("main"), &root, errors);
+                }
+                name
+            }
+            Lang::Proto => proto::declare(self, decls),
+            Lang::TypeScript => typescript::declare(self, decls),
+            Lang::Python | Lang::Pydantic => python::declare(self, decls),
+            Lang::Java => java::declare(self, decls),
+            Lang::Kotlin => kotlin::declare(self, decls),
+            Lang::CSharp => csharp::declare(self, decls),
+            Lang::C => c::declare(self, decls),
+            Lang::Cpp => cpp::declare(self, decls),
+            Lang::Swift => swift::declare(self, decls),
+            Lang::Elm => elm::declare(self, decls),
+            Lang::OCaml => ocaml::declare(self, decls),
+            Lang::Scala => scala::declare(self, decls),
+            Lang::Dart => dart::declare(self, decls),
+            Lang::Julia => julia::declare(self, decls),
+            Lang::Zig => zig::declare(self, decls),
+            Lang::Avro => avro::declare(self, decls),
+            Lang::FlatBuffers => flatbuffers::declare(self, decls),
+            Lang::Capnp => capnp::declare(self, decls),
+            Lang::Thrift => thrift::declare(self, decls),
+            Lang::JsonSchema => json_schema::declare(self, decls),
+            Lang::GraphQL => graphql::declare(self, decls),
+            Lang::Sql => sql::declare(self, decls),
+            Lang::Zod => zod::declare(self, decls),
+            Lang::IoTs => io_ts::declare(self, decls),
+            Lang::Clojure => clojure::declare(self, decls),
+            Lang::Nim => nim::declare(self, decls),
+            Lang::FSharp => fsharp::declare(self, decls),
+            Lang::BigQuery => bigquery::declare(self, decls),
+            Lang::PySpark => pyspark::declare(self, decls),
+            Lang::Arrow => arrow::declare(self, decls),
+            Lang::Parquet => parquet::declare(self, decls),
+            Lang::Mongoose => mongoose::declare(self, decls),
+        }
+    }
+
+    /// Emit a Rust representation of the data type of a whole document.
+    /// Types that aren't declared items themselves, like scalars and
+    /// arrays, are given a name with a type alias, and a null document
+    /// is taken to be an optional unknown value.
+    fn declare_root(self, decls: &mut Decls) -> String {
+        use std::fmt::Write;
+
+        let declared = decls.items.len();
+        let type_name = match self {
+            DataType::Null if !decls.options.types.contains_key("$") => {
+                format!("Option<{}>", decls.options.unknown_type(RUST_UNKNOWN_TYPE))
+            }
+            typ => typ.declare_at(decls, "$"),
+        };
+        // The document's type is a struct or enum of its own, which
+        // already has a name.
+        let item = without_lifetime(&type_name);
+        let named = item.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if decls.items.len() > declared && named {
+            return item.to_string();
+        }
+
+        let given = decls.options.names.get("$").map_or("Root", String::as_str);
+        let name = decls.namer.type_name(Some(given));
+        let mut s = decls.take_scratch();
+        decls.occurs_at(&mut s, "///", &name, "$");
+        write!(s, "{}type {}", decls.visibility(), name).expect("writing to a String can't fail");
+        if borrows(&type_name) {
+            write!(s, "<{}>", LIFETIME).expect("writing to a String can't fail");
+        }
+        write!(s, " = {};", type_name).expect("writing to a String can't fail");
+        decls.begin_decl(&name).push_str(&s);
+        decls.put_scratch(s);
+        name
+    }
+
+    /// Emit a Rust representation of the data type found at `path`.
+    fn declare_at(self, decls: &mut Decls, path: &str) -> String {
+        if let Some(type_name) = decls.options.types.get(path) {
+            return type_name.clone();
+        }
+
+        match self {
+            DataType::Null => "()".to_string(),
+            DataType::String => decls.options.string_type.path().to_string(),
+            DataType::Temporal(kind) => kind.rust_type().to_string(),
+            DataType::Bytes => "serde_bytes::ByteBuf".to_string(),
+            DataType::Int => "i64".to_string(),
+            DataType::Float => "f64".to_string(),
+            DataType::Bool => "bool".to_string(),
+            DataType::IntEnum(codes) => {
+                use std::fmt::Write;
+
+                let name = decls.name_at(path);
+                let code_names = codes
+                    .iter()
+                    .map(|&code| decls.options.code_name(path, code))
+                    .collect::<Vec<_>>();
+                let code_names = code_names.iter().map(String::as_str).collect::<Vec<_>>();
+                let variants = decls
+                    .namer
+                    .variants(&code_names)
+                    .into_iter()
+                    .zip(codes.iter().copied())
+                    .collect::<Vec<_>>();
+                let mut s = decls.take_scratch();
+                decls.occurs_at(&mut s, "///", &name, path);
+                // serde_repr's derives take the place of serde's.
+                let derives = decls
+                    .options
+                    .derives()
+                    .into_iter()
+                    .filter(|derive| !is_serde_derive(derive))
+                    .chain(["serde_repr::Serialize_repr", "serde_repr::Deserialize_repr"])
+                    .collect::<Vec<_>>();
+                write_derives(&mut s, &derives);
+                writeln!(s, "#[repr(i64)]\n{}enum {} {{", decls.visibility(), name)
+                    .expect("writing to a String can't fail");
+                for (variant, code) in &variants {
+                    writeln!(s, "    {} = {},", variant, code)
+                        .expect("writing to a String can't fail");
+                }
+                s += "}";
+                decls.begin_decl(&name).push_str(&s);
+                decls.put_scratch(s);
+                if decls.options.explicit_default {
+                    write!(
+                        decls.begin_decl(&name),
+                        "impl Default for {} {{\n    fn default() -> Self {{\n        {}::{}\n    }}\n}}",
+                        name,
+                        name,
+                        variants[0].0
+                    )
+                    .expect("writing to a String can't fail");
+                }
+                name
+            }
+            DataType::Object(members) => {
+                use std::fmt::Write;
+
+                let name = decls.name_at(path);
+                let declared = decls.declare_object(path, &name);
+                let explicit_default = decls.options.explicit_default;
+
+                // Members declare their own types as they go, so this
+                // one is assembled on the side and appended after them.
+                let mut s = decls.take_scratch();
+                let mut defaults = decls.take_scratch();
+                decls.occurs_at(&mut s, "///", &name, path);
+                let members = members
+                    .into_iter()
+                    .filter(|(member, _)| !decls.omits(path, member))
+                    .collect::<Vec<_>>();
+                let keys = members
+                    .iter()
+                    .map(|(member, _)| member.as_str())
+                    .collect::<Vec<_>>();
+                let field_names = decls.namer.cased_fields(&keys);
+                let derives = decls.options.derives();
+                let serde = derives.iter().any(|derive| is_serde_derive(derive));
+                let derived = |name| {
+                    derives
+                        .iter()
+                        .any(|derive| derive.trim_start_matches("serde::") == name)
+                };
+                let traits = (derived("Serialize"), derived("Deserialize"));
+                write_derives(&mut s, &derives);
+                let rename_all = match serde {
+                    true => rename_all(&keys, &field_names),
+                    false => None,
+                };
+                if let Some(rule) = rename_all {
+                    writeln!(s, "#[serde(rename_all = {:?})]", rule)
+                        .expect("writing to a String can't fail");
+                }
+                write!(s, "{}struct {}", decls.visibility(), name)
+                    .expect("writing to a String can't fail");
+                // The lifetime parameter goes here if a field turns out
+                // to borrow.
+                let generics_at = s.len();
+                s += " {\n";
+                let mut fields = Vec::new();
+                for ((member, member_type), field) in members.into_iter().zip(field_names) {
+                    let member_path = format!("{}.{}", path, member);
+                    let uncertainty = match decls.options.types.contains_key(&member_path) {
+                        false if decls.options.annotate_uncertain => Uncertainty::of(&member_type),
+                        _ => None,
+                    };
+                    let (member_type, inlined) = match member_type {
+                        DataType::Object(inner) if decls.options.inline_small_types => {
+                            match single_scalar_member(inner) {
+                                // Strings borrowed from the input can't
+                                // be read through the helpers below.
+                                Ok((inner_name, DataType::String))
+                                    if serde && borrows(decls.options.string_type.path()) =>
+                                {
+                                    let inner = BTreeMap::from([(inner_name, DataType::String)]);
+                                    (DataType::Object(inner), None)
+                                }
+                                Ok((inner_name, inner_type)) => {
+                                    writeln!(s, "    // inlined from {{ {:?}: ... }}", inner_name)
+                                        .expect("writing to a String can't fail");
+                                    (inner_type, Some(inner_name))
+                                }
+                                Err(inner) => (DataType::Object(inner), None),
+                            }
+                        }
+                        member_type => (member_type, None),
+                    };
+                    let (default, type_name) = match (member_type, decls.options.null_fields) {
+                        (DataType::Null, NullFields::Drop) => {
+                            decls
+                                .warnings
+                                .push(member_path, WarningKind::NullFieldDropped, "");
+                            continue;
+                        }
+                        (DataType::Null, NullFields::Option) => (
+                            Some("None".to_string()),
+                            format!("Option<{}>", decls.options.unknown_type(RUST_UNKNOWN_TYPE)),
+                        ),
+                        (member_type, _)
+                            if decls.options.newtype_leaves
+                                && member_type.is_leaf()
+                                && !decls.options.types.contains_key(&member_path) =>
+                        {
+                            let default = member_type
+                                .default_expr(&decls.options)
+                                .expect("leaves have default expressions");
+                            let inner = member_type.declare_at(decls, &member_path);
+                            (None, decls.newtype(&member_path, &inner, &default))
+                        }
+                        (member_type, _) => (
+                            member_type.default_expr(&decls.options),
+                            member_type.declare_at(decls, &member_path),
+                        ),
+                    };
+                    if let Some((uncertainty, depth)) = uncertainty {
+                        writeln!(
+                            s,
+                            "    // uncertain: {}",
+                            decls.uncertain_comment(uncertainty, depth, &type_name)
+                        )
+                        .expect("writing to a String can't fail");
+                    }
+                    if serde && renamed(rename_all, &field) != member {
+                        writeln!(s, "    #[serde(rename = {:?})]", member)
+                            .expect("writing to a String can't fail");
+                    }
+                    if let (true, Some(inner_name)) = (serde, inlined) {
+                        // The field still has to be read from and
+                        // written as the object it was inlined from.
+                        let helper = decls.namer.helper_name(&format!(
+                            "{} {}",
+                            name,
+                            field.trim_start_matches("r#")
+                        ));
+                        let attributes = inlined_helpers(
+                            decls.begin_decl(&name),
+                            &helper,
+                            &format!("{}.{}", name, field.trim_start_matches("r#")),
+                            &inner_name,
+                            &type_name,
+                            traits,
+                        );
+                        writeln!(s, "    #[serde({})]", attributes)
+                            .expect("writing to a String can't fail");
+                    }
+                    if serde && borrows(&type_name) {
+                        s += "    #[serde(borrow)]\n";
+                    }
+                    writeln!(s, "    pub {}: {},", field, type_name)
+                        .expect("writing to a String can't fail");
+                    if explicit_default {
+                        match default {
+                            Some(default) => {
+                                writeln!(defaults, "            {}: {},", field, default)
+                            }
+                            None => writeln!(
+                                defaults,
+                                "            {}: {}::default(),",
+                                field,
+                                without_lifetime(&type_name)
+                            ),
+                        }
+                        .expect("writing to a String can't fail");
+                    }
+                    fields.push((member, type_name));
+                }
+                s += "}";
+                let borrowing = fields.iter().any(|(_, type_name)| borrows(type_name));
+                if borrowing {
+                    s.insert_str(generics_at, &format!("<{}>", LIFETIME));
+                }
+
+                decls.declared[declared].fields = fields;
+                decls.begin_decl(&name).push_str(&s);
+                if explicit_default {
+                    write!(
+                        decls.begin_decl(&name),
+                        "{} {{\n    fn default() -> Self {{\n        {} {{\n{}        }}\n    }}\n}}",
+                        impl_header("Default for ", &name, borrowing),
+                        name,
+                        defaults
+                    )
+                    .expect("writing to a String can't fail");
+                }
+                decls.put_scratch(s);
+                decls.put_scratch(defaults);
+                with_lifetime(name, borrowing)
+            }
+            DataType::Variant(options) if options.is_empty() => {
+                decls.options.unknown_type(RUST_UNKNOWN_TYPE).to_string()
+            }
+            DataType::Array(elems) => {
+                let elem_name = elems.declare_at(decls, &format!("{}[]", path));
+                format!("Vec<{}>", elem_name)
+            }
+            DataType::FixedArray(elems, len) => {
+                let elem_name = elems.declare_at(decls, &format!("{}[]", path));
+                format!("[{}; {}]", elem_name, len)
+            }
+            DataType::Map(values) => {
+                let value_name = values.declare_at(decls, &format!("{}.*", path));
+                format!("{}<String, {}>", decls.options.map_type.path(), value_name)
+            }
+            DataType::Variant(mut options) if options.contains(&DataType::Null) => {
+                // Whatever else the values were, the enum for it only
+                // needs arms for the values that aren't null.
+                options.remove(&DataType::Null);
+                let present = match options.len() {
+                    1 => options.into_iter().next().expect("there's one option"),
+                    _ => DataType::Variant(options),
+                };
+                format!("Option<{}>", present.declare_at(decls, path))
+            }
+            DataType::Variant(options) => {
+                use std::fmt::Write;
+
+                let name = decls.next_name();
+
+                decls.warnings.push(
+                    path,
+                    WarningKind::EnumFallback,
+                    options
+                        .iter()
+                        .map(DataType::kind_name)
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                );
+
+                let mut s = decls.take_scratch();
+                decls.occurs_at(&mut s, "///", &name, path);
+                let derives = decls.options.derives();
+                let serde = derives.iter().any(|derive| is_serde_derive(derive));
+                write_derives(&mut s, &derives);
+                if serde {
+                    // The arms hold the values themselves, with no tag.
+                    s += "#[serde(untagged)]\n";
+                }
+                write!(s, "{}enum {}", decls.visibility(), name)
+                    .expect("writing to a String can't fail");
+                let generics_at = s.len();
+                s += " {\n";
+                let arm_names = (0..options.len())
+                    .map(|idx| format!("Option{}", idx))
+                    .collect::<Vec<_>>();
+                let arm_names = decls
+                    .namer
+                    .variants(&arm_names.iter().map(String::as_str).collect::<Vec<_>>());
+                let mut default = None;
+                let mut arms = Vec::new();
+                for (option_type, arm) in options.into_iter().zip(arm_names) {
+                    let option_default = option_type.default_expr(&decls.options);
+                    let kind = option_type.kind_name();
+                    let type_name = option_type.declare_at(decls, path);
+                    let borrow = match serde && borrows(&type_name) {
+                        true => "#[serde(borrow)] ",
+                        false => "",
+                    };
+                    writeln!(s, "    {}({}{}),", arm, borrow, type_name)
+                        .expect("writing to a String can't fail");
+                    default.get_or_insert_with(|| {
+                        option_default.unwrap_or_else(|| {
+                            format!("{}::default()", without_lifetime(&type_name))
+                        })
+                    });
+                    arms.push((arm, kind, type_name));
+                }
+                s += "}";
+                let borrowing = arms.iter().any(|(_, _, type_name)| borrows(type_name));
+                if borrowing {
+                    s.insert_str(generics_at, &format!("<{}>", LIFETIME));
+                }
+
+                decls.begin_decl(&name).push_str(&s);
+                decls.put_scratch(s);
+                if decls.options.annotate_uncertain {
+                    decls.arms.insert(
+                        name.clone(),
+                        arms.iter()
+                            .map(|(_, kind, type_name)| (*kind, type_name.clone()))
+                            .collect(),
+                    );
+                }
+                if decls.options.emit_helpers {
+                    let as_str = decls.options.string_type.as_str();
+                    variant_helpers(decls.begin_decl(&name), &name, borrowing, &arms, as_str);
+                }
+                if decls.options.explicit_default {
+                    write!(
+                        decls.begin_decl(&name),
+                        "{} {{\n    fn default() -> Self {{\n        {}::{}({})\n    }}\n}}",
+                        impl_header("Default for ", &name, borrowing),
+                        name,
+                        arms[0].0,
+                        default.expect("variants with no options aren't declared")
+                    )
+                    .expect("writing to a String can't fail");
+                }
+                with_lifetime(name, borrowing)
+            }
+        }
+    }
+
+    /// A short description of what kind of type this is.
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            DataType::Null => "null",
+            DataType::String => "string",
+            DataType::Int => "int",
+            DataType::Float => "float",
+            DataType::Bool => "bool",
+            DataType::Object(_) => "object",
+            DataType::Array(_) | DataType::FixedArray(..) => "array",
+            DataType::Map(_) => "map",
+            DataType::Variant(_) => "variant",
+            DataType::IntEnum(_) => "code",
+            DataType::Temporal(kind) => kind.kind_name(),
+            DataType::Bytes => "bytes",
+        }
+    }
+
+    /// Whether this is a scalar that's declared as a primitive type
+    /// rather than one of its own, and so can be wrapped in a newtype.
+    fn is_leaf(&self) -> bool {
+        matches!(
+            self,
+            DataType::String
+                | DataType::Int
+                | DataType::Float
+                | DataType::Bool
+                | DataType::Temporal(_)
+                | DataType::Bytes
+        )
+    }
+
+    /// Whether this is a type with no structure of its own.
+    pub fn is_scalar(&self) -> bool {
+        matches!(
+            self,
+            DataType::Null
+                | DataType::String
+                | DataType::Int
+                | DataType::Float
+                | DataType::Bool
+                | DataType::IntEnum(_)
+                | DataType::Temporal(_)
+                | DataType::Bytes
+        )
+    }
+
+    /// The natural default value of this type as a Rust expression, or
+    /// `None` if it's a declared type whose expression is
+    /// `Name::default()`.
+    fn default_expr(&self, options: &Options) -> Option<String> {
+        match self {
+            DataType::Null => Some("()".to_string()),
+            DataType::String if options.string_type == StringType::String => {
+                Some("String::new()".to_string())
+            }
+            // Neither `Cow` nor arbitrary string types have a `new`.
+            DataType::String => Some("Default::default()".to_string()),
+            DataType::Int => Some("0".to_string()),
+            DataType::Float => Some("0.0".to_string()),
+            DataType::Bool => Some("false".to_string()),
+            DataType::Bytes => Some("serde_bytes::ByteBuf::new()".to_string()),
+            DataType::Array(_) => Some("Vec::new()".to_string()),
+            DataType::Map(_) => Some(format!("{}::new()", options.map_type.path())),
+            DataType::Variant(options) if options.is_empty() => {
+                Some("Default::default()".to_string())
+            }
+            DataType::Variant(options) if options.contains(&DataType::Null) => {
+                Some("None".to_string())
+            }
+            // Generic paths like `chrono::DateTime<chrono::Utc>` can't
+            // be followed by `::default()`.
+            DataType::Temporal(_) => Some("Default::default()".to_string()),
+            DataType::FixedArray(..) => Some("Default::default()".to_string()),
+            DataType::Object(_) | DataType::Variant(_) | DataType::IntEnum(_) => None,
+        }
+    }
+
+    /// A value of this data type that has every member of every object
+    /// in it, for use as a test fixture. Arrays have one element, or
+    /// their fixed length of them, and maps one entry, variants take
+    /// their first arm that isn't null,
+    /// and scalars take a representative value that infers back to the
+    /// same type, except bytes, which JSON can't write, and so are an
+    /// array with a zero byte in it. Only arrays and maps of the unknown
+    /// type are empty, and only the unknown type itself is null.
+    pub fn example(&self) -> JsonValue {
+        match self {
+            DataType::Null => JsonValue::Null,
+            DataType::String => "string".into(),
+            DataType::Int => 1.into(),
+            DataType::Float => 0.5.into(),
+            DataType::Bool => true.into(),
+            DataType::IntEnum(codes) => codes.iter().next().copied().unwrap_or_default().into(),
+            DataType::Temporal(kind) => kind.example().into(),
+            DataType::Bytes => JsonValue::Array(vec![0.into()]),
+            DataType::Object(members) => {
+                let mut obj = JsonValue::new_object();
+                for (member, member_type) in members {
+                    obj[member.as_str()] = member_type.example();
+                }
+                obj
+            }
+            DataType::Array(elems) if **elems == DataType::unknown() => JsonValue::new_array(),
+            DataType::Array(elems) => JsonValue::Array(vec![elems.example()]),
+            DataType::FixedArray(elems, len) => JsonValue::Array(vec![elems.example(); *len]),
+            DataType::Map(values) if **values == DataType::unknown() => JsonValue::new_object(),
+            DataType::Map(values) => {
+                // Keys that start with a digit are what mark objects
+                // as maps.
+                let mut obj = JsonValue::new_object();
+                obj["0"] = values.example();
+                obj
+            }
+            DataType::Variant(options) => options
+                .iter()
+                .find(|option_type| **option_type != DataType::Null)
+                .map_or(JsonValue::Null, DataType::example),
+        }
+    }
+
+    /// Render the data type as an indented tree, one node per line,
+    /// for inspecting what was inferred.
+    pub fn dump(&self) -> String {
+        let mut s = String::new();
+        self.dump_into(&mut s, 0);
+        s
+    }
+
+    /// Write the tree for this data type into `s`, with children
+    /// indented one level deeper than `depth`.
+    fn dump_into(&self, s: &mut String, depth: usize) {
+        match self {
+            DataType::Null => *s += "null\n",
+            DataType::String => *s += "string\n",
+            DataType::Int => *s += "int\n",
+            DataType::Float => *s += "float\n",
+            DataType::Bool => *s += "bool\n",
+            DataType::Bytes => *s += "bytes\n",
+            DataType::Temporal(kind) => {
+                *s += kind.kind_name();
+                *s += "\n";
+            }
+            DataType::IntEnum(codes) => {
+                *s += "int, one of";
+                for code in codes {
+                    *s += " ";
+                    *s += &code.to_string();
+                }
+                *s += "\n";
+            }
+            DataType::Object(members) => {
+                *s += "object\n";
+                for (member, member_type) in members {
+                    indent(s, depth + 1);
+                    *s += member;
+                    *s += ": ";
+                    member_type.dump_into(s, depth + 1);
+                }
+            }
+            DataType::Array(elems) => {
+                *s += "array of ";
+                elems.dump_into(s, depth);
+            }
+            DataType::FixedArray(elems, len) => {
+                *s += &format!("array of {} ", len);
+                elems.dump_into(s, depth);
+            }
+            DataType::Map(values) => {
+                *s += "map of ";
+                values.dump_into(s, depth);
+            }
+            DataType::Variant(options) if options.is_empty() => *s += "unknown\n",
+            DataType::Variant(options) => {
+                *s += "one of\n";
+                for option_type in options {
+                    indent(s, depth + 1);
+                    *s += "| ";
+                    option_type.dump_into(s, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+/// Write a `#[derive]` attribute for `derives` to `out`, if there are
+/// any.
+fn write_derives(out: &mut String, derives: &[&str]) {
+    use std::fmt::Write;
+
+    if !derives.is_empty() {
+        writeln!(out, "#[derive({})]", derives.join(", ")).expect("writing to a String can't fail");
+    }
+}
+
+/// Whether a derive is serde's `Serialize` or `Deserialize`.
+fn is_serde_derive(derive: &str) -> bool {
+    matches!(
+        derive.trim_start_matches("serde::"),
+        "Serialize" | "Deserialize"
+    )
+}
+
+/// The rules serde's `rename_all` can rename fields by, with the ones
+/// keys are most often written in first.
+const RENAME_RULES: &[&str] = &[
+    "camelCase",
+    "kebab-case",
+    "PascalCase",
+    "SCREAMING_SNAKE_CASE",
+    "SCREAMING-KEBAB-CASE",
+];
+
+/// The rule for a `#[serde(rename_all)]` on a struct whose fields named
+/// `fields` hold the members with `keys`, if one would leave fewer
+/// fields needing a `#[serde(rename)]` of their own than none would.
+/// serde_derive can't apply the rules to fields with names that aren't
+/// ASCII, so structs with any such fields get none.
+fn rename_all(keys: &[&str], fields: &[String]) -> Option<&'static str> {
+    if !fields.iter().all(|field| field.is_ascii()) {
+        return None;
+    }
+    let renames = |rule| {
+        keys.iter()
+            .zip(fields)
+            .filter(|(key, field)| renamed(rule, field) != **key)
+            .count()
+    };
+    let unruled = renames(None);
+    RENAME_RULES
+        .iter()
+        .map(|&rule| (renames(Some(rule)), rule))
+        .filter(|&(count, _)| count < unruled)
+        .min_by_key(|&(count, _)| count)
+        .map(|(_, rule)| rule)
+}
+
+/// The key serde reads and writes the field named `field` as, going by
+/// the struct's `rename_all` rule if it has one.
+fn renamed(rule: Option<&str>, field: &str) -> String {
+    let field = field.trim_start_matches("r#");
+    let pascal = || {
+        let mut s = String::new();
+        let mut capitalize = true;
+        for c in field.chars() {
+            match c {
+                '_' => capitalize = true,
+                c if capitalize => {
+                    s.push(c.to_ascii_uppercase());
+                    capitalize = false;
+                }
+                c => s.push(c),
+            }
+        }
+        s
+    };
+    match rule {
+        Some("camelCase") => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                None => pascal,
+            }
+        }
+        Some("kebab-case") => field.replace('_', "-"),
+        Some("PascalCase") => pascal(),
+        Some("SCREAMING_SNAKE_CASE") => field.to_ascii_uppercase(),
+        Some("SCREAMING-KEBAB-CASE") => field.to_ascii_uppercase().replace('_', "-"),
+        _ => field.to_string(),
+    }
+}
+
+/// Write a `main` to `out` that reads the file named by its first
+/// argument, deserializes it as `root` with serde_json and prints the
+/// result, as a quick check that the declarations fit the input.
+fn write_main(out: &mut String, root: &str, errors: MainErrors) {
+    use std::fmt::Write;
+
+    match errors {
+        MainErrors::Expect => write!(
+            out,
+            "fn main() {{\n    \
+             let path = std::env::args().nth(1).expect(\"usage: main FILE\");\n    \
+             let text = std::fs::read_to_string(&path).expect(\"unable to read input\");\n    \
+             let value: {} = serde_json::from_str(&text).expect(\"unable to deserialize input\");\n    \
+             println!(\"{{:#?}}\", value);\n\
+             }}",
+            root
+        ),
+        MainErrors::Anyhow => write!(
+            out,
+            "fn main() -> anyhow::Result<()> {{\n    \
+             use anyhow::Context;\n\n    \
+             let path = std::env::args().nth(1).context(\"usage: main FILE\")?;\n    \
+             let text = std::fs::read_to_string(&path)\n        \
+             .with_context(|| format!(\"unable to read {{}}\", path))?;\n    \
+             let value: {} = serde_json::from_str(&text)\n        \
+             .with_context(|| format!(\"unable to deserialize {{}}\", path))?;\n    \
+             println!(\"{{:#?}}\", value);\n    \
+             Ok(())\n\
+             }}",
+            root
+        ),
+    }
+    .expect("writing to a String can't fail");
+}
+
+/// Write an `impl` block of accessors for a variant enum named `name`
+/// to `out`, in the style of `serde_json::Value`: an `is_*` predicate
+/// for each arm, and an `as_*` accessor for each arm that holds data.
+/// `arms` gives the name, kind and type of each arm.
+fn variant_helpers(
+    out: &mut String,
+    name: &str,
+    borrowing: bool,
+    arms: &[(String, &str, String)],
+    as_str: &'static str,
+) {
+    use std::fmt::Write;
+
+    writeln!(out, "{} {{", impl_header("", name, borrowing))
+        .expect("writing to a String can't fail");
+    for (idx, (arm, kind, type_name)) in arms.iter().enumerate() {
+        let (suffix, accessor) = match *kind {
+            "null" => ("null".to_string(), None),
+            "string" => (
+                "string".to_string(),
+                Some(("str".to_string(), "&str".to_string(), as_str)),
+            ),
+            "int" | "float" | "bool" => (
+                kind.to_string(),
+                Some((kind.to_string(), type_name.clone(), "*value")),
+            ),
+            "object" => {
+                let snake = Case::Snake.apply(without_lifetime(type_name), false);
+                (
+                    snake.clone(),
+                    Some((snake, format!("&{}", type_name), "value")),
+                )
+            }
+            _ => (
+                kind.to_string(),
+                Some((kind.to_string(), format!("&{}", type_name), "value")),
+            ),
+        };
+
+        if idx > 0 {
+            *out += "\n";
+        }
+        writeln!(
+            out,
+            "    pub fn is_{}(&self) -> bool {{\n        matches!(self, {}::{}(_))\n    }}",
+            suffix, name, arm
+        )
+        .expect("writing to a String can't fail");
+        if let Some((accessor, return_type, value)) = accessor {
+            writeln!(
+                out,
+                "\n    pub fn as_{}(&self) -> Option<{}> {{\n        match self {{\n            \
+                 {}::{}(value) => Some({}),\n            _ => None,\n        }}\n    }}",
+                accessor, return_type, name, arm, value
+            )
+            .expect("writing to a String can't fail");
+        }
+    }
+    *out += "}";
+}
+
+/// Whether the Rust type `type_name` borrows from the input, and so
+/// needs the lifetime parameter on whatever holds it.
+fn borrows(type_name: &str) -> bool {
+    type_name.contains(LIFETIME)
+}
+
+/// `name`, with the lifetime parameter if the type borrows.
+fn with_lifetime(name: String, borrowing: bool) -> String {
+    match borrowing {
+        true => format!("{}<{}>", name, LIFETIME),
+        false => name,
+    }
+}
+
+/// The name of a declared type without its lifetime parameter, as it's
+/// written before `::` in an expression.
+fn without_lifetime(type_name: &str) -> &str {
+    type_name
+        .strip_suffix(&format!("<{}>", LIFETIME))
+        .unwrap_or(type_name)
+}
+
+/// The start of an `impl` block for the type called `name`, implementing
+/// `trait_for` (such as `Default for `) if it isn't empty.
+fn impl_header(trait_for: &str, name: &str, borrowing: bool) -> String {
+    match borrowing {
+        true => format!("impl<{0}> {1}{2}<{0}>", LIFETIME, trait_for, name),
+        false => format!("impl {}{}", trait_for, name),
+    }
+}
+
+/// Write functions to `out` that read and write the field `field`,
+/// declared as `type_name`, as the member `member` of the object it was
+/// inlined from, for whichever of serde's `Serialize` and
+/// `Deserialize` `traits` says are derived, named after `helper`. Returns what goes in the field's `#[serde]`
+/// attribute to use them.
+fn inlined_helpers(
+    out: &mut String,
+    helper: &str,
+    field: &str,
+    member: &str,
+    type_name: &str,
+    (serialize, deserialize): (bool, bool),
+) -> String {
+    use std::fmt::Write;
+
+    let mut attributes = Vec::new();
+    if deserialize {
+        writeln!(
+            out,
+            "/// Reads `{field}` from the {member:?} member of the object it's inlined from.\n\
+             fn deserialize_{helper}<'de, D>(deserializer: D) -> Result<{type_name}, D::Error>\n\
+             where\n    \
+             D: serde::Deserializer<'de>,\n\
+             {{\n    \
+             #[derive(serde::Deserialize)]\n    \
+             struct Inlined {{\n        \
+             #[serde(rename = {member:?})]\n        \
+             value: {type_name},\n    \
+             }}\n\n    \
+             serde::Deserialize::deserialize(deserializer).map(|inlined: Inlined| inlined.value)\n\
+             }}",
+            field = field,
+            member = member,
+            helper = helper,
+            type_name = type_name,
+        )
+        .expect("writing to a String can't fail");
+        attributes.push(format!("deserialize_with = \"deserialize_{}\"", helper));
+    }
+    if serialize {
+        if !attributes.is_empty() {
+            *out += "\n";
+        }
+        write!(
+            out,
+            "/// Writes `{field}` as the {member:?} member of an object, as it was read.\n\
+             fn serialize_{helper}<S>(value: &{type_name}, serializer: S) -> Result<S::Ok, S::Error>\n\
+             where\n    \
+             S: serde::Serializer,\n\
+             {{\n    \
+             #[derive(serde::Serialize)]\n    \
+             struct Inlined<'a> {{\n        \
+             #[serde(rename = {member:?})]\n        \
+             value: &'a {type_name},\n    \
+             }}\n\n    \
+             serde::Serialize::serialize(&Inlined {{ value }}, serializer)\n\
+             }}",
+            field = field,
+            member = member,
+            helper = helper,
+            type_name = type_name,
+        )
+        .expect("writing to a String can't fail");
+        attributes.push(format!("serialize_with = \"serialize_{}\"", helper));
+    }
+    let trimmed = out.trim_end_matches('\n').len();
+    out.truncate(trimmed);
+    attributes.join(", ")
+}
+
+/// Whether objects with `members` are small enough for their type to be
+/// written inline with `Options::inline_small_types`, in languages that
+/// can: with a few members, all scalars.
+pub(crate) fn is_small_object(members: &BTreeMap<String, DataType>) -> bool {
+    (1..=INLINE_MEMBERS).contains(&members.len()) && members.values().all(DataType::is_scalar)
+}
+
+/// Split the members of an object into its only member, if it has
+/// exactly one and it's a scalar, or give the members back otherwise.
+fn single_scalar_member(
+    mut members: BTreeMap<String, DataType>,
+) -> Result<(String, DataType), BTreeMap<String, DataType>> {
+    match members.iter().next() {
+        Some((_, typ)) if members.len() == 1 && typ.is_scalar() => {
+            Ok(members.pop_first().expect("object has one member"))
+        }
+        _ => Err(members),
+    }
+}
+
+/// Whether an object's keys look like data rather than member names,
+/// i.e. none of them could start an identifier. This catches objects
+/// keyed by IDs, dates, version numbers and the like, without
+/// mistaking ordinary records for maps.
+pub(crate) fn looks_like_map(obj: &[(String, Value)]) -> bool {
+    !obj.is_empty()
+        && obj.iter().all(|(key, _)| {
+            key.chars()
+                .next()
+                .is_some_and(|c| !c.is_alphabetic() && c != '_' && c != '$')
+        })
+}
+
+/// Seed of the generator that chooses elements when sampling randomly,
+/// so that the choice is the same from run to run.
+const SAMPLE_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The elements of an array to infer the element type from: all of
+/// them, or at most `Options::sample_limit` of them, either the first
+/// ones or, with `Options::sample_random`, a random selection.
+pub(crate) fn sample<'a>(elems: &'a [Value], options: &Options) -> Vec<&'a Value> {
+    let mut sampler = Sampler::new(options);
+    let mut sampled = elems
+        .iter()
+        .filter_map(|elem| sampler.push(elem))
+        .collect::<Vec<_>>();
+    sampled.extend(sampler.finish().1);
+    sampled
+}
+
+/// Chooses the elements of an array to infer the element type from, as
+/// `sample` does, for arrays that are seen an element at a time.
+#[derive(Debug, Clone)]
+pub struct Sampler<T> {
+    limit: Option<usize>,
+    random: bool,
+    seen: usize,
+    state: u64,
+    held: Vec<(usize, T)>,
+}
+
+impl<T> Sampler<T> {
+    /// Create a sampler that hasn't seen any elements, choosing them as
+    /// `options` say to.
+    pub fn new(options: &Options) -> Self {
+        Sampler {
+            limit: options.sample_limit,
+            random: options.sample_random,
+            seen: 0,
+            state: SAMPLE_SEED,
+            held: Vec::new(),
+        }
+    }
+
+    /// Offer the next element of the array. It's returned if it's
+    /// chosen and can be used right away. When sampling randomly,
+    /// chosen elements are held until `finish` instead, since later
+    /// ones can take their places.
+    pub fn push(&mut self, elem: T) -> Option<T> {
+        let idx = self.seen;
+        self.seen += 1;
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return Some(elem),
+        };
+        if !self.random {
+            return (idx < limit).then_some(elem);
+        }
+        if idx < limit {
+            self.held.push((idx, elem));
+            return None;
+        }
+
+        // Reservoir sampling, with a xorshift generator.
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        let slot = (self.state % (idx as u64 + 1)) as usize;
+        if slot < limit {
+            self.held[slot] = (idx, elem);
+        }
+        None
+    }
+
+    /// Stop sampling, and return how many elements there were and the
+    /// chosen elements that were held, in the order they came in.
+    pub fn finish(mut self) -> (usize, Vec<T>) {
+        self.held.sort_unstable_by_key(|(idx, _)| *idx);
+        (
+            self.seen,
+            self.held.into_iter().map(|(_, elem)| elem).collect(),
+        )
+    }
+}
+
+/// Append `depth` levels of indentation to `s`.
+fn indent(s: &mut String, depth: usize) {
+    for _ in 0..depth {
+        *s += "  ";
+    }
+}
+
+/// The fewest objects at a path that `Options::min_presence` will drop
+/// members based on, since below that a member's frequency says little.
+pub const MIN_PRESENCE_SAMPLES: u64 = 20;
+
+/// Why the type inferred for an object member might not be the one
+/// that's wanted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Uncertainty {
+    /// The member was always an empty array, so its elements could be
+    /// anything.
+    EmptyArray,
+
+    /// The member was always null, so it could be anything when it's
+    /// present.
+    AlwaysNull,
+
+    /// The member had values of several types.
+    Mixed,
+}
+
+impl Uncertainty {
+    /// How uncertain a member of type `typ` is, if at all, along with
+    /// how many levels of arrays the uncertain type is inside.
+    fn of(typ: &DataType) -> Option<(Self, usize)> {
+        match typ {
+            DataType::Array(elem) if **elem == DataType::unknown() => {
+                Some((Uncertainty::EmptyArray, 0))
+            }
+            DataType::Array(elem) => {
+                Self::of(elem).map(|(uncertainty, depth)| (uncertainty, depth + 1))
+            }
+            DataType::Null => Some((Uncertainty::AlwaysNull, 0)),
+            // Null and one other type is just an `Option` of it.
+            DataType::Variant(options)
+                if options.len() == 2 && options.contains(&DataType::Null) =>
+            {
+                let present = options.iter().find(|typ| **typ != DataType::Null);
+                present.and_then(Self::of)
+            }
+            DataType::Variant(options) if !options.is_empty() => Some((Uncertainty::Mixed, 0)),
+            _ => None,
+        }
+    }
+}
+
+/// An object type that was declared, such as a struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declared {
+    /// The path of the objects the type was inferred from.
+    pub path: String,
+
+    /// The name of the type.
+    pub name: String,
+
+    /// The names of the type's members, each with the text that
+    /// declares its type.
+    pub fields: Vec<(String, String)>,
+}
+
+/// Declarations generated so far while emitting a data type.
+pub struct Decls {
+    pub(crate) namer: Namer,
+    out: String,
+    items: Vec<(usize, String)>,
+    scratch: Vec<String>,
+    pub(crate) declared: Vec<Declared>,
+    arms: BTreeMap<String, Vec<(&'static str, String)>>,
+    newtypes: BTreeMap<String, String>,
+    paths: BTreeMap<String, BTreeSet<String>>,
+    pub(crate) imports: BTreeSet<String>,
+    pub(crate) options: Options,
+    pub(crate) warnings: Warnings,
+    stats: Option<Stats>,
+    schema_state: Option<SchemaState>,
+    transforms: Vec<Box<dyn Transform>>,
+}
+
+impl Decls {
+    /// Create an empty set of declarations, to be generated according
+    /// to `options`.
+    pub fn new(options: Options) -> Self {
+        Decls {
+            namer: Namer::new(&options),
+            out: String::new(),
+            items: Vec::new(),
+            scratch: Vec::new(),
+            declared: Vec::new(),
+            arms: BTreeMap::new(),
+            newtypes: BTreeMap::new(),
+            paths: BTreeMap::new(),
+            imports: BTreeSet::new(),
+            options,
+            warnings: Warnings::new(),
+            stats: None,
+            schema_state: None,
+            transforms: Vec::new(),
+        }
+    }
+
+    /// Use presence counts gathered from the input, for the options
+    /// that need them.
+    pub fn with_stats(mut self, stats: Stats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Number fields as recorded in a saved schema state, for the
+    /// backends that number fields. The state is updated with the
+    /// numbers given to new fields.
+    pub fn with_schema_state(mut self, state: SchemaState) -> Self {
+        self.schema_state = Some(state);
+        self
+    }
+
+    /// Apply `transform` to types before they're declared, after the
+    /// transforms given before it.
+    pub fn with_transform(mut self, transform: impl Transform + 'static) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// The schema state, updated with the fields declared so far.
+    pub fn schema_state(&self) -> Option<&SchemaState> {
+        self.schema_state.as_ref()
+    }
+
+    /// The warnings raised while emitting declarations.
+    pub fn warnings(&self) -> &Warnings {
+        &self.warnings
+    }
+
+    /// Take the warnings raised while emitting declarations so far,
+    /// leaving none.
+    pub fn take_warnings(&mut self) -> Warnings {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// The object types declared so far, in the order they were named,
+    /// so that outer types come before the types of their members.
+    pub fn declared(&self) -> &[Declared] {
+        &self.declared
+    }
+
+    /// The paths of the values each type was declared for, by the
+    /// type's name.
+    pub fn paths(&self) -> &BTreeMap<String, BTreeSet<String>> {
+        &self.paths
+    }
+
+    /// Render all the declarations, separated by blank lines.
+    pub fn output(&self) -> String {
+        self.options.lang.header(&self.imports) + &self.out
+    }
+
+    /// Write all the declarations to `w`, as rendered by `output`.
+    pub fn write_output(&self, w: &mut impl io::Write) -> io::Result<()> {
+        w.write_all(self.options.lang.header(&self.imports).as_bytes())?;
+        w.write_all(self.out.as_bytes())
+    }
+
+    /// The declarations, each with the name of the type it belongs to.
+    /// Impl blocks belong to the type they're for, and an emitted
+    /// `main` belongs to `main`.
+    pub fn items(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(move |(idx, (start, owner))| {
+                let end = match self.items.get(idx + 1) {
+                    Some((next, _)) => next - "\n\n".len(),
+                    None => self.out.len(),
+                };
+                (owner.as_str(), &self.out[*start..end])
+            })
+    }
+
+    /// Start a new declaration belonging to the type `owner`, returning
+    /// the buffer to write it to.
+    pub(crate) fn begin_decl(&mut self, owner: &str) -> &mut String {
+        if !self.out.is_empty() {
+            self.out += "\n\n";
+        }
+        self.items.push((self.out.len(), owner.to_string()));
+        &mut self.out
+    }
+
+    /// An empty buffer for assembling a declaration whose members have
+    /// to be declared first. Give it back with `put_scratch` when done.
+    pub(crate) fn take_scratch(&mut self) -> String {
+        self.scratch.pop().unwrap_or_default()
+    }
+
+    /// Return a buffer taken with `take_scratch`, to be reused.
+    pub(crate) fn put_scratch(&mut self, mut buf: String) {
+        buf.clear();
+        self.scratch.push(buf);
+    }
+
+    /// A description of why a member declared as `type_name` is
+    /// uncertain, with a best guess at the type it should have. The
+    /// uncertain type is `depth` levels of arrays into the member,
+    /// which can be `Option`s too where there were nulls.
+    fn uncertain_comment(&self, uncertainty: Uncertainty, depth: usize, type_name: &str) -> String {
+        // Take the `Vec`s and `Option`s around the uncertain type off,
+        // to put them back around the guess.
+        let mut inner = type_name;
+        let mut wrappers = Vec::new();
+        let mut vecs = 0;
+        loop {
+            let wrapper = if inner.starts_with("Option<") {
+                "Option"
+            } else if vecs < depth && inner.starts_with("Vec<") {
+                vecs += 1;
+                "Vec"
+            } else {
+                break;
+            };
+            inner = &inner[wrapper.len() + 1..inner.len() - 1];
+            wrappers.push(wrapper);
+        }
+
+        let (mut what, mut guess) = match uncertainty {
+            Uncertainty::EmptyArray => (
+                "always an empty array".to_string(),
+                "Vec<String>".to_string(),
+            ),
+            Uncertainty::AlwaysNull => ("always null".to_string(), "Option<String>".to_string()),
+            Uncertainty::Mixed => self.mixed_guess(&self.arms[inner]),
+        };
+        if wrappers.last() == Some(&"Option") {
+            what = format!("null or {}", what);
+        }
+        for wrapper in wrappers.iter().rev() {
+            guess = format!("{}<{}>", wrapper, guess);
+        }
+        match depth {
+            0 => format!("was {}, guessing {}", what, guess),
+            _ => format!("elements were {}, guessing {}", what, guess),
+        }
+    }
+
+    /// What a member whose values had several types was, given the
+    /// kind and type of each arm of its enum, and a guess at the type
+    /// it should have.
+    fn mixed_guess(&self, arms: &[(&str, String)]) -> (String, String) {
+        let kinds = arms.iter().map(|(kind, _)| *kind).collect::<Vec<_>>();
+        let guess = match kinds.contains(&"string")
+            && kinds
+                .iter()
+                .all(|kind| ["string", "int", "float", "bool", "code"].contains(kind))
+        {
+            true => "String",
+            false => self.options.unknown_type(RUST_UNKNOWN_TYPE),
+        };
+        (format!("one of {}", kinds.join(" | ")), guess.to_string())
+    }
+
+    /// Record that the type `name` is declared for the values at
+    /// `path`, and write a comment saying where it occurs to `out` if
+    /// asked to, starting with `marker`.
+    pub(crate) fn occurs_at(&mut self, out: &mut String, marker: &str, name: &str, path: &str) {
+        use std::fmt::Write;
+
+        let paths = self.paths.entry(name.to_string()).or_default();
+        paths.insert(path.to_string());
+        if let Some(format) = self.options.annotate_paths {
+            let paths = paths
+                .iter()
+                .map(|path| format.format(path))
+                .collect::<Vec<_>>();
+            writeln!(out, "{} Occurs at: {}", marker, paths.join(", "))
+                .expect("writing to a String can't fail");
+        }
+    }
+
+    /// The name of a newtype wrapping `inner`, the type of the
+    /// primitive values at `path`, whose default is `default`. It's
+    /// named after the path, and declared unless a newtype of that
+    /// name already wraps the same type, in which case that one is
+    /// used. Names taken by other types are numbered past.
+    pub(crate) fn newtype(&mut self, path: &str, inner: &str, default: &str) -> String {
+        use std::fmt::Write;
+
+        let style = self.namer.style();
+        let base = style.sanitize(path, style.type_case, "Leaf");
+        let borrowing = borrows(inner);
+        let mut name = base.clone();
+        let mut n = 1;
+        loop {
+            match self.newtypes.get(&name) {
+                Some(wrapped) if wrapped == inner => {
+                    self.paths
+                        .entry(name.clone())
+                        .or_default()
+                        .insert(path.to_string());
+                    return with_lifetime(name, borrowing);
+                }
+                None if !self.namer.is_taken(&name) => break,
+                _ => {
+                    n += 1;
+                    name = format!("{}{}", base, n);
+                }
+            }
+        }
+        self.namer.type_name(Some(&name));
+        self.newtypes.insert(name.clone(), inner.to_string());
+
+        let mut s = self.take_scratch();
+        self.occurs_at(&mut s, "///", &name, path);
+        let derives = self.options.derives();
+        let serde = derives.iter().any(|derive| is_serde_derive(derive));
+        write_derives(&mut s, &derives);
+        if serde {
+            // Serialized as the value it wraps.
+            s += "#[serde(transparent)]\n";
+        }
+        write!(s, "{}struct {}", self.visibility(), name).expect("writing to a String can't fail");
+        if borrowing {
+            write!(s, "<{}>", LIFETIME).expect("writing to a String can't fail");
+        }
+        let borrow = match serde && borrowing {
+            true => "#[serde(borrow)] ",
+            false => "",
+        };
+        write!(s, "({}pub {});", borrow, inner).expect("writing to a String can't fail");
+        self.begin_decl(&name).push_str(&s);
+        self.put_scratch(s);
+        if self.options.explicit_default {
+            write!(
+                self.begin_decl(&name),
+                "{} {{\n    fn default() -> Self {{\n        {}({})\n    }}\n}}",
+                impl_header("Default for ", &name, borrowing),
+                name,
+                default
+            )
+            .expect("writing to a String can't fail");
+        }
+        with_lifetime(name, borrowing)
+    }
+
+    /// The visibility to declare types with.
+    fn visibility(&self) -> &'static str {
+        match self.options.pub_types {
+            true => "pub ",
+            false => "",
+        }
+    }
+
+    /// Whether the member `key` of the objects at `path` should be left
+    /// out, either because it's ignored or because it's rare.
+    pub(crate) fn omits(&mut self, path: &str, key: &str) -> bool {
+        (!self.options.ignore.is_empty()
+            && self.options.ignore.contains(&format!("{}.{}", path, key)))
+            || self.is_rare(path, key)
+    }
+
+    /// Whether the member `key` of the objects at `path` is present too
+    /// rarely to be worth declaring. Members are only ever dropped when
+    /// there are presence counts for enough objects to go on, and a
+    /// warning is raised for each one.
+    fn is_rare(&mut self, path: &str, key: &str) -> bool {
+        let (threshold, stats) = match (self.options.min_presence, &self.stats) {
+            (Some(threshold), Some(stats)) => (threshold, stats),
+            _ => return false,
+        };
+        let object = match stats.object(path) {
+            Some(object) if object.count >= MIN_PRESENCE_SAMPLES => object,
+            _ => return false,
+        };
+        let present = object
+            .fields
+            .get(key)
+            .map_or(0, |field| field.value + field.null);
+        if present as f64 >= threshold * object.count as f64 {
+            return false;
+        }
+
+        self.warnings.push(
+            format!("{}.{}", path, key),
+            WarningKind::RareFieldDropped,
+            format!(
+                "present in {} of {} objects, {:.2}%",
+                present,
+                object.count,
+                100.0 * present as f64 / object.count as f64
+            ),
+        );
+        true
+    }
+
+    /// Name the type declared for the values at `path`, using the name
+    /// given in the options if there is one.
+    pub(crate) fn name_at(&mut self, path: &str) -> String {
+        let given = self.options.names.get(path).map(String::as_str);
+        self.namer.type_name(given)
+    }
+
+    /// Record that an object type is being declared, returning its
+    /// index in `declared` so its fields can be filled in afterwards.
+    pub(crate) fn declare_object(&mut self, path: &str, name: &str) -> usize {
+        self.declared.push(Declared {
+            path: path.to_string(),
+            name: name.to_string(),
+            fields: Vec::new(),
+        });
+        self.declared.len() - 1
+    }
+
+    /// Start numbering the fields of the message at `path`.
+    pub(crate) fn field_numbers(&mut self, path: &str) -> FieldNumbers {
+        match &mut self.schema_state {
+            Some(state) => state.numbers(path),
+            None => FieldNumbers::sequential(),
+        }
+    }
+
+    /// Finish numbering the fields of the message at `path`, returning
+    /// the numbers it must not use.
+    pub(crate) fn finish_field_numbers(&mut self, path: &str, numbers: FieldNumbers) -> Vec<u32> {
+        match &mut self.schema_state {
+            Some(state) => state.finish(path, numbers),
+            None => Vec::new(),
+        }
+    }
+
+    /// Generate a name for a new declaration.
+    pub(crate) fn next_name(&mut self) -> String {
+        self.namer.type_name(None)
+    }
+}
+
+impl Default for Decls {
+    fn default() -> Self {
+        Self::new(Options::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use crate::options::{MapType, PathsFormat};
+
+    use json::short::Short;
+
+    /// Build `declarations` into a program with serde and serde_json
+    /// that reads `input` as `root` and writes it back out, and return
+    /// what it wrote, to check that the declarations compile and read
+    /// and write what they were inferred from. Each caller needs its
+    /// own `name` for the program, since they're built side by side.
+    fn round_trip(name: &str, declarations: &str, root: &str, input: &str) -> String {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            format!(
+                "[package]\nname = {:?}\nversion = \"0.0.0\"\nedition = \"2018\"\n\n\
+                 [dependencies]\nserde = {{ version = \"1\", features = [\"derive\"] }}\n\
+                 serde_json = \"1\"\n\n[workspace]\n",
+                name
+            ),
+        )
+        .unwrap();
+        // The same versions as this crate's, so nothing has to be
+        // fetched.
+        std::fs::copy(
+            manifest_dir.join("Cargo.lock"),
+            dir.path().join("Cargo.lock"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("src").join("main.rs"),
+            format!(
+                "#![allow(dead_code)]\n\n{}\n\n\
+                 fn main() {{\n    \
+                 let text = std::fs::read_to_string(std::env::args().nth(1).unwrap()).unwrap();\n    \
+                 let value: {} = serde_json::from_str(&text).unwrap();\n    \
+                 print!(\"{{}}\", serde_json::to_string(&value).unwrap());\n\
+                 }}\n",
+                declarations, root
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("input.json"), input).unwrap();
+
+        let output =
+            std::process::Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+                .args(["run", "--quiet", "--offline", "--"])
+                .arg(dir.path().join("input.json"))
+                .current_dir(dir.path())
+                .env(
+                    "CARGO_TARGET_DIR",
+                    manifest_dir.join("target").join("round-trip"),
+                )
+                .output()
+                .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn basic_types() {
+        assert_eq!(
+            DataType::from_json_value(&JsonValue::Null).unwrap(),
+            DataType::Null
+        );
+        assert_eq!(
+            DataType::from_json_value(&JsonValue::String("hello".to_string())).unwrap(),
+            DataType::String
+        );
+
+        let s = "foo";
+        if json::short::MAX_LEN >= s.len() {
+            // SAFETY: A Short is defined to be able to store at least
+            // MAX_LEN bytes, and MAX_LEN >= the length of s,
+            // therefore a Short is able to store a string of s's
+            // length.
+            let s = JsonValue::Short(unsafe { Short::from_slice(s) });
+
+            assert_eq!(DataType::from_json_value(&s).unwrap(), DataType::String);
+        } else {
+            panic!("Failed to test Short");
+        }
+
+        assert_eq!(
+            DataType::from_json_value(&JsonValue::Boolean(true)).unwrap(),
+            DataType::Bool
+        );
+    }
+
+    #[test]
+    fn short_strings() {
+        // The parser stores strings of up to `MAX_LEN` bytes inline, and
+        // those have to be refined just like longer ones.
+        let options = Options {
+            detect_dates: true,
+            ..Options::default()
+        };
+        for kind in [
+            Temporal::Date,
+            Temporal::Time,
+            Temporal::NaiveDateTime,
+            Temporal::DateTime,
+        ] {
+            let s = kind.example();
+            assert!(s.len() <= json::short::MAX_LEN);
+            // SAFETY: the string fits, as checked above.
+            let short = JsonValue::Short(unsafe { Short::from_slice(s) });
+            let long = JsonValue::String(s.to_string());
+            let infer = |v: &JsonValue| {
+                DataType::from_value_with(&Value::from_json(v).unwrap(), &options).unwrap()
+            };
+            assert_eq!(infer(&short), DataType::Temporal(kind));
+            assert_eq!(infer(&long), DataType::Temporal(kind));
+            assert_eq!(
+                Value::from_json(&short).unwrap(),
+                Value::from_json(&long).unwrap()
+            );
+        }
+
+        let mut parsed = json::parse(r#"["2023-11-05", "x"]"#).unwrap();
+        assert!(matches!(parsed[0], JsonValue::Short(_)));
+        parsed[1] = JsonValue::String("2023-11-06".to_string());
+        assert_eq!(
+            DataType::from_value_with(&Value::from_json(&parsed).unwrap(), &options).unwrap(),
+            DataType::Array(Box::new(DataType::Temporal(Temporal::Date)))
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            DataType::from_json_value(&JsonValue::Number(10.into())).unwrap(),
+            DataType::Int
+        );
+        assert_eq!(
+            DataType::from_json_value(&JsonValue::Number((10.5).into())).unwrap(),
+            DataType::Float
+        );
+    }
+
+    #[test]
+    fn big_numbers() {
+        let big = json::parse("9007199254740993").unwrap();
+        let typ = DataType::from_json_value(&big).unwrap();
+        assert_eq!(typ, DataType::Int);
+        let mut decls = Decls::default();
+        typ.declare(&mut decls);
+        assert_eq!(decls.output(), "type Root = i64;");
+
+        // Integral, but too big for any integer type.
+        let huge = json::parse("1e300").unwrap();
+        assert_eq!(DataType::from_json_value(&huge).unwrap(), DataType::Float);
+    }
+
+    #[test]
+    fn unification() {
+        assert_eq!(
+            DataType::unify(DataType::String, DataType::Bool),
+            DataType::Variant(vec![DataType::String, DataType::Bool].into_iter().collect())
+        );
+        assert_eq!(
+            DataType::unify(
+                DataType::Variant(vec![DataType::String, DataType::Bool].into_iter().collect()),
+                DataType::Null
+            ),
+            DataType::Variant(
+                vec![DataType::String, DataType::Bool, DataType::Null]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn variants_dont_nest() {
+        let string_or_bool =
+            DataType::Variant(vec![DataType::String, DataType::Bool].into_iter().collect());
+        assert_eq!(
+            DataType::unify(DataType::Null, string_or_bool.clone()),
+            DataType::unify(string_or_bool.clone(), DataType::Null)
+        );
+        assert_eq!(
+            DataType::unify(DataType::Int, DataType::Variant(BTreeSet::new())),
+            DataType::Int
+        );
+
+        let int_or_string =
+            DataType::Variant(vec![DataType::Int, DataType::String].into_iter().collect());
+        assert_eq!(
+            DataType::unify(int_or_string, DataType::Float),
+            DataType::Variant(
+                vec![DataType::Float, DataType::String]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn floats_override_ints() {
+        assert_eq!(
+            DataType::unify(DataType::Int, DataType::Float),
+            DataType::Float
+        );
+    }
+
+    #[test]
+    fn structs() {
+        let a = DataType::from_json_value(&json::object! {
+            "null": null,
+            "string": "hello",
+            "number": 123,
+            "bool": true,
+            "object": {
+                "hello": "world"
+            },
+            "arr": [1, 2, 3]
+        })
+        .unwrap();
+        let b = DataType::Object(
+            [
+                ("null", DataType::Null),
+                ("string", DataType::String),
+                ("number", DataType::Int),
+                ("bool", DataType::Bool),
+                (
+                    "object",
+                    DataType::Object(
+                        [("hello", DataType::String)]
+                            .iter()
+                            .map(|(name, typ)| (name.to_string(), (*typ).clone()))
+                            .collect::<BTreeMap<String, DataType>>(),
+                    ),
+                ),
+                ("arr", DataType::Array(Box::new(DataType::Int))),
+            ]
+            .iter()
+            .map(|(name, typ)| (name.to_string(), (*typ).clone()))
+            .collect::<BTreeMap<String, DataType>>(),
+        );
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn object_unification() {
+        let arr = JsonValue::Array(vec![
+            JsonValue::Number(1.into()),
+            JsonValue::String("hello".to_string()),
+        ]);
+        let arr_typ = DataType::Array(Box::new(DataType::Variant(
+            vec![DataType::Int, DataType::String].into_iter().collect(),
+        )));
+        assert_eq!(DataType::from_json_value(&arr).unwrap(), arr_typ);
+
+        let objs = JsonValue::Array(vec![
+            json::object! {
+                "foo": "bar"
+            },
+            json::object! {
+                "foo": 123,
+                "baz": true
+            },
+        ]);
+        let objs_type = DataType::Array(Box::new(DataType::Object(
+            [
+                (
+                    "foo",
+                    DataType::Variant(vec![DataType::String, DataType::Int].into_iter().collect()),
+                ),
+                (
+                    "baz",
+                    DataType::Variant(vec![DataType::Bool, DataType::Null].into_iter().collect()),
+                ),
+            ]
+            .iter()
+            .map(|(name, typ)| (name.to_string(), (*typ).clone()))
+            .collect::<BTreeMap<String, DataType>>(),
+        )));
+
+        assert_eq!(DataType::from_json_value(&objs).unwrap(), objs_type);
+    }
+
+    #[test]
+    fn dump() {
+        let typ = DataType::from_json_value(&json::object! {
+            "name": "x",
+            "tags": [],
+            "values": [1, "two", {"three": 3.5}]
+        })
+        .unwrap();
+
+        assert_eq!(
+            typ.dump(),
+            "object
+  name: string
+  tags: array of unknown
+  values: array of one of
+    | string
+    | int
+    | object
+      three: float
+"
+        );
+    }
+
+    #[test]
+    fn example() {
+        let typ = DataType::from_json_value(&json::array![
+            {"name": "x", "tags": [], "scores": {"1": 2.5}, "parent": null, "id": 1},
+            {"name": null, "tags": [], "scores": {"2": 1}, "parent": null, "id": "a"},
+        ])
+        .unwrap();
+        let example = typ.example();
+        assert_eq!(
+            example.dump(),
+            r#"[{"id":"string","name":"string","parent":null,"scores":{"0":0.5},"tags":[]}]"#
+        );
+
+        // Variants only have one of their arms in the example.
+        let reinferred = DataType::from_json_value(&example).unwrap();
+        assert_ne!(reinferred, typ);
+        assert_eq!(typ.clone().unify(reinferred), typ);
+    }
+
+    #[test]
+    fn top_level_scalars() {
+        let declare = |v: JsonValue, options: Options| {
+            let mut decls = Decls::new(options);
+            let name = DataType::from_json_value(&v).unwrap().declare(&mut decls);
+            let warnings = decls
+                .warnings()
+                .summary()
+                .keys()
+                .map(|warning| warning.kind)
+                .collect::<Vec<_>>();
+            (name, decls.output(), warnings)
+        };
+
+        for (v, type_name) in [
+            (JsonValue::from("hello"), "String"),
+            (42.into(), "i64"),
+            (1.5.into(), "f64"),
+            (true.into(), "bool"),
+            (json::array![1, 2], "Vec<i64>"),
+            (JsonValue::Null, "Option<serde_json::Value>"),
+        ] {
+            let (name, output, _) = declare(v, Options::default());
+            assert_eq!(name, "Root");
+            assert_eq!(output, format!("type Root = {};", type_name));
+        }
+
+        let (_, _, warnings) = declare(JsonValue::Null, Options::default());
+        assert_eq!(warnings, [WarningKind::NullDocument]);
+
+        let mut options = Options {
+            pub_types: true,
+            ..Options::default()
+        };
+        options
+            .names
+            .insert("$".to_string(), "Greeting".to_string());
+        let (name, output, warnings) = declare("hello".into(), options);
+        assert_eq!(name, "Greeting");
+        assert_eq!(output, "pub type Greeting = String;");
+        assert!(warnings.is_empty());
+
+        // Documents that are declared types of their own don't get an
+        // alias, but arrays of them do.
+        let (name, output, _) = declare(json::object! {"id": 1}, Options::default());
+        assert_eq!(name, "Data0");
+        assert_eq!(output, "struct Data0 {\n    pub id: i64,\n}");
+        let (name, output, _) = declare(json::array![{"id": 1}], Options::default());
+        assert_eq!(name, "Root");
+        assert_eq!(
+            output,
+            "struct Data0 {\n    pub id: i64,\n}\n\ntype Root = Vec<Data0>;"
+        );
+    }
+
+    #[test]
+    fn unknown_types() {
+        let typ = DataType::from_json_value(&json::array![]).unwrap();
+
+        let mut decls = Decls::default();
+        typ.clone().declare(&mut decls);
+        assert_eq!(decls.output(), "type Root = Vec<serde_json::Value>;");
+
+        let mut decls = Decls::new(Options {
+            unknown_type: Some("Box<dyn std::any::Any>".to_string()),
+            ..Options::default()
+        });
+        typ.declare(&mut decls);
+        assert_eq!(decls.output(), "type Root = Vec<Box<dyn std::any::Any>>;");
+    }
+
+    #[test]
+    fn maps() {
+        let typ = DataType::from_json_value(&json::object! {
+            "users": {
+                "1001": {"name": "a"},
+                "1002": {"name": "b", "admin": true}
+            },
+            "2021-01-01": 3
+        })
+        .unwrap();
+
+        let user = DataType::Object(
+            vec![
+                ("name".to_string(), DataType::String),
+                (
+                    "admin".to_string(),
+                    DataType::Variant(vec![DataType::Bool, DataType::Null].into_iter().collect()),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(
+            typ,
+            DataType::Object(
+                vec![
+                    ("users".to_string(), DataType::Map(Box::new(user))),
+                    ("2021-01-01".to_string(), DataType::Int),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn map_types() {
+        let typ = DataType::Map(Box::new(DataType::Bool));
+        for (map_type, expected) in [
+            (MapType::BTree, "std::collections::BTreeMap<String, bool>"),
+            (MapType::Hash, "std::collections::HashMap<String, bool>"),
+            (MapType::Index, "indexmap::IndexMap<String, bool>"),
+        ] {
+            let mut decls = Decls::new(Options {
+                map_type,
+                ..Options::default()
+            });
+            typ.clone().declare(&mut decls);
+            assert_eq!(decls.output(), format!("type Root = {};", expected));
+        }
+    }
+
+    #[test]
+    fn explicit_defaults() {
+        let typ = DataType::from_json_value(&json::object! {
+            "name": "x",
+            "count": 1,
+            "tags": [],
+            "inner": {"ratio": 0.5, "ok": true},
+            "either": [1, "one"]
+        })
+        .unwrap();
+
+        let mut decls = Decls::new(Options {
+            explicit_default: true,
+            ..Options::default()
+        });
+        typ.declare(&mut decls);
+        let output = decls.output();
+        let output = output.split("\n\n").collect::<Vec<_>>();
+        assert_eq!(
+            output[1],
+            "impl Default for Data1 {
+    fn default() -> Self {
+        Data1::Option0(String::new())
+    }
+}"
+        );
+        assert_eq!(
+            output[5],
+            "impl Default for Data0 {
+    fn default() -> Self {
+        Data0 {
+            count: 0,
+            either: Vec::new(),
+            inner: Data2::default(),
+            name: String::new(),
+            tags: Vec::new(),
+        }
+    }
+}"
+        );
+    }
+
+    #[test]
+    fn inline_small_types() {
+        let typ = DataType::from_json_value(&json::object! {
+            "id": {"value": 7},
+            "location": {"lat": 1.5, "lon": 2.5},
+            "tags": {"list": ["a"]}
+        })
+        .unwrap();
+
+        let mut decls = Decls::new(Options {
+            inline_small_types: true,
+            ..Options::default()
+        });
+        typ.declare(&mut decls);
+        assert_eq!(
+            decls.output().split("\n\n").last().unwrap(),
+            "struct Data0 {
+    // inlined from { \"value\": ... }
+    pub id: i64,
+    pub location: Data1,
+    pub tags: Data2,
+}"
+        );
+
+        // With serde, the inlined fields are still read from and written
+        // as the objects they were in.
+        let input = r#"{"id":{"value":7},"name":{"first":"a"},"location":{"lat":1.5,"lon":2.5}}"#;
+        let typ = DataType::from_json_value(&json::parse(input).unwrap()).unwrap();
+        let mut decls = Decls::new(Options {
+            inline_small_types: true,
+            serde: true,
+            ..Options::default()
+        });
+        let root = typ.declare(&mut decls);
+        let output = decls.output();
+        assert!(output.contains(
+            "    #[serde(deserialize_with = \"deserialize_data0_id\", \
+             serialize_with = \"serialize_data0_id\")]\n    pub id: i64,\n"
+        ));
+        let written = round_trip("inline_small_types", &output, &root, input);
+        assert_eq!(json::parse(&written).unwrap(), json::parse(input).unwrap());
+    }
+
+    #[test]
+    fn null_fields() {
+        let typ = DataType::from_json_value(&json::array![
+            {"id": 1, "note": null},
+            {"id": 2, "note": null}
+        ])
+        .unwrap();
+
+        for (null_fields, expected) in [
+            (
+                NullFields::KeepUnit,
+                "struct Data0 {\n    pub id: i64,\n    pub note: (),\n}",
+            ),
+            (
+                NullFields::Option,
+                "struct Data0 {\n    pub id: i64,\n    pub note: Option<serde_json::Value>,\n}",
+            ),
+            (NullFields::Drop, "struct Data0 {\n    pub id: i64,\n}"),
+        ] {
+            let mut decls = Decls::new(Options {
+                null_fields,
+                ..Options::default()
+            });
+            typ.clone().declare(&mut decls);
+            assert_eq!(
+                decls.output(),
+                format!("{}\n\ntype Root = Vec<Data0>;", expected)
+            );
+        }
+    }
+
+    #[test]
+    fn emission_warnings() {
+        let typ = DataType::from_json_value(&json::array![
+            {"id": 1, "gone": null, "value": "a"},
+            {"id": 2, "gone": null, "value": [3]},
+        ])
+        .unwrap();
+        let mut decls = Decls::new(Options {
+            null_fields: NullFields::Drop,
+            ..Options::default()
+        });
+        typ.declare(&mut decls);
+        assert_eq!(
+            decls
+                .warnings()
+                .summary()
+                .into_keys()
+                .map(|warning| (warning.path.as_str(), warning.kind, warning.detail.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("$[].gone", WarningKind::NullFieldDropped, ""),
+                ("$[].value", WarningKind::EnumFallback, "string | array"),
+            ]
+        );
+    }
+
+    #[test]
+    fn min_presence() {
+        let mut records = (0..100)
+            .map(|n| json::object! {"user_id": n, "event": {"kind": "click"}})
+            .collect::<Vec<_>>();
+        records[7]["usre_id"] = 7.into();
+        records[8]["event"]["knd"] = "view".into();
+        let small = json::array![{"a": 1}, {"a": 2, "b": 3}];
+
+        let declare = |records: &JsonValue| {
+            let data = Value::from_json(records).unwrap();
+            let mut stats = Stats::new();
+            stats.observe(&data);
+            let mut decls = Decls::new(Options {
+                min_presence: Some(0.05),
+                ..Options::default()
+            })
+            .with_stats(stats);
+            DataType::from_value(&data).unwrap().declare(&mut decls);
+            let dropped = decls
+                .warnings()
+                .summary()
+                .into_keys()
+                .filter(|warning| warning.kind == WarningKind::RareFieldDropped)
+                .map(|warning| format!("{} {}", warning.path, warning.detail))
+                .collect::<Vec<_>>();
+            (decls.output(), dropped)
+        };
+
+        assert_eq!(
+            declare(&JsonValue::Array(records)),
+            (
+                "struct Data1 {\n    pub kind: String,\n}\n\n\
+                 struct Data0 {\n    pub event: Data1,\n    pub user_id: i64,\n}\n\n\
+                 type Root = Vec<Data0>;"
+                    .to_string(),
+                vec![
+                    "$[].event.knd present in 1 of 100 objects, 1.00%".to_string(),
+                    "$[].usre_id present in 1 of 100 objects, 1.00%".to_string(),
+                ]
+            )
+        );
+
+        // Too few samples to judge.
+        let (output, dropped) = declare(&small);
+        assert!(output.contains("pub b: Option<i64>,"));
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn sample_limit() {
+        let data = Value::from_json(&json::array![1, 2, 3, "four", 5, 6.5]).unwrap();
+        let infer = |sample_limit, sample_random| {
+            DataType::from_value_with(
+                &data,
+                &Options {
+                    sample_limit,
+                    sample_random,
+                    ..Options::default()
+                },
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            infer(Some(3), false),
+            DataType::Array(Box::new(DataType::Int))
+        );
+        assert_eq!(infer(Some(6), false), DataType::from_value(&data).unwrap());
+        assert_eq!(infer(None, false), DataType::from_value(&data).unwrap());
+
+        let elems = match &data {
+            Value::Array(elems) => elems,
+            _ => unreachable!(),
+        };
+        let options = Options {
+            sample_limit: Some(3),
+            sample_random: true,
+            ..Options::default()
+        };
+        let sampled = sample(elems, &options);
+        assert_eq!(sampled.len(), 3);
+        assert_eq!(sampled, sample(elems, &options));
+        assert!(sampled.windows(2).all(|pair| {
+            let idx = |v| elems.iter().position(|elem| std::ptr::eq(elem, v));
+            idx(pair[0]) < idx(pair[1])
+        }));
+    }
+
+    #[test]
+    fn int_enums() {
+        let mut options = Options {
+            int_enum_threshold: Some(3),
+            explicit_default: true,
+            ..Options::default()
+        };
+        options
+            .code_names
+            .entry("$[].status".to_string())
+            .or_default()
+            .insert(0, "Pending".to_string());
+        let data = Value::from_json(&json::array![
+            {"status": 0, "kind": -1, "id": 1},
+            {"status": 2, "kind": 1, "id": 2},
+            {"status": 0, "kind": 1.5, "id": 3},
+            {"status": 2, "kind": 1, "id": 4},
+        ])
+        .unwrap();
+        let typ = DataType::from_value_with(&data, &options).unwrap();
+        assert_eq!(
+            typ,
+            DataType::Array(Box::new(DataType::Object(
+                vec![
+                    ("id".to_string(), DataType::Int),
+                    ("kind".to_string(), DataType::Float),
+                    (
+                        "status".to_string(),
+                        DataType::IntEnum(vec![0, 2].into_iter().collect())
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            )))
+        );
+
+        let mut decls = Decls::new(options);
+        typ.declare(&mut decls);
+        assert_eq!(
+            decls.output(),
+            "#[derive(serde_repr::Serialize_repr, serde_repr::Deserialize_repr)]
+#[repr(i64)]
+enum Data1 {
+    Pending = 0,
+    Code2 = 2,
+}
+
+impl Default for Data1 {
+    fn default() -> Self {
+        Data1::Pending
+    }
+}
+
+struct Data0 {
+    pub id: i64,
+    pub kind: f64,
+    pub status: Data1,
+}
+
+impl Default for Data0 {
+    fn default() -> Self {
+        Data0 {
+            id: 0,
+            kind: 0.0,
+            status: Data1::default(),
+        }
+    }
+}
+
+type Root = Vec<Data0>;"
+        );
+
+        let mut codes = DataType::IntEnum((0..MAX_INT_ENUM_CODES as i64).collect());
+        codes.unify_into(DataType::IntEnum(once(-1).collect()));
+        assert_eq!(codes, DataType::Int);
+    }
+
+    #[test]
+    fn nested_arrays() {
+        let int_arrays =
+            |depth| (0..depth).fold(DataType::Int, |typ, _| DataType::Array(Box::new(typ)));
+        assert_eq!(
+            DataType::from_json_value(&json::array![[1, 2], [3, 4, 5], []]).unwrap(),
+            int_arrays(2)
+        );
+        assert_eq!(
+            DataType::from_json_value(&json::array![[1], [[2]]]).unwrap(),
+            DataType::Array(Box::new(DataType::Array(Box::new(DataType::Variant(
+                vec![DataType::Int, int_arrays(1)].into_iter().collect()
+            )))))
+        );
+        assert_eq!(int_arrays(3).unify(int_arrays(3)), int_arrays(3));
+    }
+
+    #[test]
+    fn matrices() {
+        let options = Options {
+            matrix: true,
+            ..Options::default()
+        };
+        let declare = |v: JsonValue| {
+            let typ = DataType::from_value_with(&Value::from_json(&v).unwrap(), &options).unwrap();
+            let mut decls = Decls::new(options.clone());
+            typ.clone().declare(&mut decls);
+            (typ, decls.output())
+        };
+
+        let (typ, output) = declare(json::object! {
+            "rotation": [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+            "origin": [0.5, 1, 2],
+        });
+        assert_eq!(
+            typ,
+            DataType::Object(
+                vec![
+                    (
+                        "origin".to_string(),
+                        DataType::FixedArray(Box::new(DataType::Float), 3)
+                    ),
+                    (
+                        "rotation".to_string(),
+                        DataType::FixedArray(
+                            Box::new(DataType::FixedArray(Box::new(DataType::Int), 3)),
+                            3
+                        )
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+        assert_eq!(
+            output,
+            "struct Data0 {
+    pub origin: [f64; 3],
+    pub rotation: [[i64; 3]; 3],
+}"
+        );
+
+        // Ragged rows fall back to vectors, as does everything around
+        // them.
+        let (typ, output) = declare(json::array![[[1, 2], [3]], [[4, 5], [6, 7]]]);
+        assert_eq!(
+            typ,
+            DataType::Array(Box::new(DataType::Array(Box::new(DataType::Array(
+                Box::new(DataType::Int)
+            )))))
+        );
+        assert_eq!(output, "type Root = Vec<Vec<Vec<i64>>>;");
+
+        // Rows of the same length stay fixed even if there are
+        // different numbers of them.
+        let (_, output) = declare(json::array![[[1, 2]], [[3, 4], [5, 6]]]);
+        assert_eq!(output, "type Root = Vec<Vec<[i64; 2]>>;");
+
+        let (_, output) = declare(json::array![["a", "b"], ["c", "d"]]);
+        assert_eq!(output, "type Root = Vec<Vec<String>>;");
+        let long = JsonValue::Array(vec![1.into(); MAX_MATRIX_LEN + 1]);
+        assert_eq!(declare(long).1, "type Root = Vec<i64>;");
+    }
+
+    #[test]
+    fn temporal_types() {
+        let options = Options {
+            detect_dates: true,
+            explicit_default: true,
+            ..Options::default()
+        };
+        let data = Value::from_json(&json::array![
+            {"day": "2023-11-05", "at": "2023-11-05T14:30:00Z", "local": "2023-11-05T14:30:00"},
+            {"day": "2023-11-06", "at": "2023-11-05T14:30:00.5+01:00", "local": "2023-11-05T09:00:00"},
+        ])
+        .unwrap();
+        let typ = DataType::from_value_with(&data, &options).unwrap();
+        assert_eq!(
+            typ,
+            DataType::Array(Box::new(DataType::Object(
+                vec![
+                    ("at".to_string(), DataType::Temporal(Temporal::DateTime)),
+                    ("day".to_string(), DataType::Temporal(Temporal::Date)),
+                    (
+                        "local".to_string(),
+                        DataType::Temporal(Temporal::NaiveDateTime)
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            )))
+        );
+        assert_eq!(
+            DataType::from_value(&data).unwrap(),
+            DataType::from_json_value(&json::array![{"at": "", "day": "", "local": ""}]).unwrap()
+        );
+
+        let mut decls = Decls::new(options);
+        typ.declare(&mut decls);
+        assert_eq!(
+            decls.output(),
+            "struct Data0 {
+    pub at: chrono::DateTime<chrono::Utc>,
+    pub day: chrono::NaiveDate,
+    pub local: chrono::NaiveDateTime,
+}
+
+impl Default for Data0 {
+    fn default() -> Self {
+        Data0 {
+            at: Default::default(),
+            day: Default::default(),
+            local: Default::default(),
+        }
+    }
+}
+
+type Root = Vec<Data0>;"
+        );
+
+        // Different kinds of dates and times are only alike as strings.
+        let date = DataType::Temporal(Temporal::Date);
+        let time = DataType::Temporal(Temporal::Time);
+        assert_eq!(date.clone().unify(date.clone()), date);
+        assert_eq!(date.clone().unify(time), DataType::String);
+        assert_eq!(DataType::String.unify(date.clone()), DataType::String);
+        assert_eq!(
+            date.unify(DataType::Null),
+            DataType::Variant(
+                vec![DataType::Temporal(Temporal::Date), DataType::Null]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn bytes_type() {
+        let data = Value::Object(vec![
+            ("blob".to_string(), Value::Bytes(vec![7, 8])),
+            ("name".to_string(), Value::String("a".to_string())),
+        ]);
+        let typ = DataType::from_value(&data).unwrap();
+        let mut decls = Decls::new(Options::default());
+        typ.declare(&mut decls);
+        assert_eq!(
+            decls.output(),
+            "struct Data0 {
+    pub blob: serde_bytes::ByteBuf,
+    pub name: String,
+}"
+        );
+        assert_eq!(
+            DataType::Bytes.unify(DataType::String),
+            DataType::Variant(
+                vec![DataType::Bytes, DataType::String]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn annotate_uncertain() {
+        let typ = DataType::from_json_value(&json::array![
+            {"tags": [], "parent": null, "id": 1, "codes": [1, "a"], "owner": {"id": 1}},
+            {"tags": [], "parent": null, "id": "a", "codes": [], "owner": null},
+        ])
+        .unwrap();
+        let mut options = Options {
+            annotate_uncertain: true,
+            ..Options::default()
+        };
+        options.types.insert("$[].id".to_string(), "Id".to_string());
+        let mut decls = Decls::new(options);
+        typ.declare(&mut decls);
+        assert!(decls.output().ends_with(
+            "struct Data0 {
+    // uncertain: elements were one of string | int, guessing Vec<String>
+    pub codes: Vec<Data1>,
+    pub id: Id,
+    pub owner: Option<Data2>,
+    // uncertain: was always null, guessing Option<String>
+    pub parent: (),
+    // uncertain: was always an empty array, guessing Vec<String>
+    pub tags: Vec<serde_json::Value>,
+}
+
+type Root = Vec<Data0>;"
+        ));
+    }
+
+    #[test]
+    fn annotate_paths() {
+        let declare = |format| {
+            let typ = DataType::from_json_value(&json::object! {
+                "users": [{"id": 1, "name": "a"}],
+                "owner": [{"id": 1}, "a"],
+            })
+            .unwrap();
+            let mut decls = Decls::new(Options {
+                annotate_paths: Some(format),
+                ..Options::default()
+            });
+            typ.declare(&mut decls);
+            decls
+        };
+
+        let decls = declare(PathsFormat::JsonPath);
+        assert_eq!(
+            decls.output(),
+            "/// Occurs at: $.owner[*]
+struct Data2 {
+    pub id: i64,
+}
+
+/// Occurs at: $.owner[*]
+enum Data1 {
+    Option0(String),
+    Option1(Data2),
+}
+
+/// Occurs at: $.users[*]
+struct Data3 {
+    pub id: i64,
+    pub name: String,
+}
+
+/// Occurs at: $
+struct Data0 {
+    pub owner: Vec<Data1>,
+    pub users: Vec<Data3>,
+}"
+        );
+        assert_eq!(
+            decls.paths()["Data2"],
+            BTreeSet::from(["$.owner[]".to_string()])
+        );
+
+        let output = declare(PathsFormat::Dotted).output();
+        assert!(output.contains("/// Occurs at: .users[]\nstruct Data3 {"));
+        assert!(output.contains("/// Occurs at: .\nstruct Data0 {"));
+    }
+
+    #[test]
+    fn emit_main() {
+        let declare = |v: JsonValue, emit_main| {
+            let mut options = Options {
+                emit_main: Some(emit_main),
+                ..Options::default()
+            };
+            options
+                .names
+                .insert("$[]".to_string(), "Record".to_string());
+            let mut decls = Decls::new(options);
+            DataType::from_json_value(&v).unwrap().declare(&mut decls);
+            decls.output()
+        };
+
+        assert_eq!(
+            declare(json::array![{"id": 1}], MainErrors::Expect),
+            "#[derive(Debug, serde::Deserialize)]
+struct Record {
+    pub id: i64,
+}
+
+type Root = Vec<Record>;
+
+fn main() {
+    let path = std::env::args().nth(1).expect(\"usage: main FILE\");
+    let text = std::fs::read_to_string(&path).expect(\"unable to read input\");
+    let value: Root = serde_json::from_str(&text).expect(\"unable to deserialize input\");
+    println!(\"{:#?}\", value);
+}"
+        );
+        assert_eq!(
+            declare(json::object! {"id": [1, "a"]}, MainErrors::Anyhow),
+            "#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum Data1 {
+    Option0(String),
+    Option1(i64),
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Data0 {
+    pub id: Vec<Data1>,
+}
+
+fn main() -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    let path = std::env::args().nth(1).context(\"usage: main FILE\")?;
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!(\"unable to read {}\", path))?;
+    let value: Data0 = serde_json::from_str(&text)
+        .with_context(|| format!(\"unable to deserialize {}\", path))?;
+    println!(\"{:#?}\", value);
+    Ok(())
+}"
+        );
+    }
+
+    #[test]
+    fn emit_helpers() {
+        let typ = DataType::from_json_value(&json::array![
+            {"value": "a", "tag": null},
+            {"value": 1, "tag": true},
+            {"value": {"x": 1.5}},
+            {"value": [1]},
+            {"value": {"1": 2}},
+        ])
+        .unwrap();
+        let mut decls = Decls::new(Options {
+            emit_helpers: true,
+            ..Options::default()
+        });
+        typ.declare(&mut decls);
+        let output = decls.output();
+        assert!(output.contains(
+            "impl Data1 {\n    \
+             pub fn is_string(&self) -> bool {\n        matches!(self, Data1::Option0(_))\n    }\n\n    \
+             pub fn as_str(&self) -> Option<&str> {\n        match self {\n            \
+             Data1::Option0(value) => Some(value.as_str()),\n            _ => None,\n        }\n    }\n\n    \
+             pub fn is_int(&self) -> bool {\n        matches!(self, Data1::Option1(_))\n    }\n\n    \
+             pub fn as_int(&self) -> Option<i64> {\n        match self {\n            \
+             Data1::Option1(value) => Some(*value),\n            _ => None,\n        }\n    }"
+        ));
+        assert!(output.contains("pub tag: Option<bool>,"));
+        for method in [
+            "fn as_data2(&self) -> Option<&Data2>",
+            "fn is_data2(&self) -> bool",
+            "fn as_array(&self) -> Option<&Vec<i64>>",
+            "fn as_map(&self) -> Option<&std::collections::HashMap<String, i64>>",
+        ] {
+            assert!(output.contains(method), "{}", method);
+        }
+
+        // The helpers must actually compile.
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("generated.rs");
+        std::fs::write(&source, format!("#![allow(dead_code)]\n\n{}\n", output)).unwrap();
+        let status =
+            std::process::Command::new(std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+                .args([
+                    "--edition",
+                    "2018",
+                    "--crate-type",
+                    "lib",
+                    "--emit",
+                    "metadata",
+                    "-o",
+                ])
+                .arg(dir.path().join("libgenerated.rmeta"))
+                .arg(&source)
+                .status()
+                .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let mut v = JsonValue::Null;
+        for _ in 0..100_000 {
+            v = JsonValue::Array(vec![v]);
+        }
+        assert!(matches!(
+            DataType::from_json_value(&v),
+            Err(Error::TooDeep(DepthExceeded))
+        ));
+
+        // Dropping the value recursively would itself overflow the
+        // stack, so take it apart by hand.
+        while let JsonValue::Array(mut elems) = v {
+            v = elems.pop().unwrap_or(JsonValue::Null);
+        }
+
+        let mut v = JsonValue::Null;
+        for _ in 0..MAX_DEPTH {
+            v = JsonValue::Array(vec![v]);
+        }
+        assert!(DataType::from_json_value(&v).is_ok());
+    }
+
+    #[test]
+    fn sanitized_names() {
+        let declare = |derives: &[&str]| {
+            let typ = DataType::from_json_value(&json::object! {
+                "first name": "a",
+                "first_name": "b",
+                "type": 1,
+                "2fa": true,
+                "?": null,
+            })
+            .unwrap();
+            let mut decls = Decls::new(Options {
+                derives: derives.iter().map(|derive| derive.to_string()).collect(),
+                null_fields: NullFields::Option,
+                ..Options::default()
+            });
+            typ.declare(&mut decls);
+            decls.output()
+        };
+
+        assert_eq!(
+            declare(&[]),
+            "struct Data0 {
+    pub _2fa: bool,
+    pub field: Option<serde_json::Value>,
+    pub first_name_2: String,
+    pub first_name: String,
+    pub r#type: i64,
+}"
+        );
+        assert_eq!(
+            declare(&["serde::Deserialize"]),
+            "#[derive(serde::Deserialize)]
+struct Data0 {
+    #[serde(rename = \"2fa\")]
+    pub _2fa: bool,
+    #[serde(rename = \"?\")]
+    pub field: Option<serde_json::Value>,
+    #[serde(rename = \"first name\")]
+    pub first_name_2: String,
+    pub first_name: String,
+    pub r#type: i64,
+}"
+        );
+    }
+
+    #[test]
+    fn nullable_options() {
+        let typ = DataType::from_json_value(&json::array![
+            {"id": 1, "owner": {"login": "a"}, "score": [1, "a"]},
+            {"id": null, "owner": null, "score": null},
+        ])
+        .unwrap();
+        let mut decls = Decls::new(Options {
+            explicit_default: true,
+            ..Options::default()
+        });
+        typ.declare(&mut decls);
+        assert!(decls.output().ends_with(
+            "struct Data0 {
+    pub id: Option<i64>,
+    pub owner: Option<Data1>,
+    pub score: Option<Vec<Data2>>,
+}
+
+impl Default for Data0 {
+    fn default() -> Self {
+        Data0 {
+            id: None,
+            owner: None,
+            score: None,
+        }
+    }
+}
+
+type Root = Vec<Data0>;"
+        ));
+    }
+
+    #[test]
+    fn renamed_fields() {
+        let declare = |value: json::JsonValue| {
+            let typ = DataType::from_json_value(&value).unwrap();
+            let mut decls = Decls::new(Options {
+                serde: true,
+                ..Options::default()
+            });
+            typ.declare(&mut decls);
+            decls.output()
+        };
+
+        assert_eq!(
+            declare(json::object! {"userId": 1, "displayName": "a", "id": 2, "avatar_url": "b"}),
+            "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = \"camelCase\")]
+struct Data0 {
+    #[serde(rename = \"avatar_url\")]
+    pub avatar_url: String,
+    pub display_name: String,
+    pub id: i64,
+    pub user_id: i64,
+}"
+        );
+        assert_eq!(
+            declare(json::object! {"content-type": "a", "x-request-id": "b", "type": "c"}),
+            "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = \"kebab-case\")]
+struct Data0 {
+    pub content_type: String,
+    pub r#type: String,
+    pub x_request_id: String,
+}"
+        );
+        assert_eq!(
+            declare(json::object! {"userId": 1, "user_id": 2}),
+            "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Data0 {
+    #[serde(rename = \"userId\")]
+    pub user_id_2: i64,
+    pub user_id: i64,
+}"
+        );
+    }
+
+    #[test]
+    fn renamed_fields_round_trip() {
+        let input = r#"{"userId":1,"displayName":"a","é":true,"content-type":"b"}"#;
+        let typ = DataType::from_json_value(&json::parse(input).unwrap()).unwrap();
+        let mut decls = Decls::new(Options {
+            serde: true,
+            ..Options::default()
+        });
+        let root = typ.declare(&mut decls);
+        let output = decls.output();
+        assert!(!output.contains("rename_all"));
+        assert!(output.contains("    pub é: bool,\n"));
+
+        let written = round_trip("renamed_fields", &output, &root, input);
+        assert_eq!(json::parse(&written).unwrap(), json::parse(input).unwrap());
+    }
+
+    #[test]
+    fn string_types() {
+        let declare = |string_type: &str, derives: &[&str]| {
+            let typ = DataType::from_json_value(&json::object! {
+                "name": "a",
+                "tags": ["b"],
+                "owner": {"id": 1, "login": "c"},
+                "mixed": [1, "d"],
+            })
+            .unwrap();
+            let mut decls = Decls::new(Options {
+                string_type: string_type.parse().unwrap(),
+                derives: derives.iter().map(ToString::to_string).collect(),
+                explicit_default: true,
+                ..Options::default()
+            });
+            typ.declare(&mut decls);
+            decls.output()
+        };
+
+        assert!(declare("String", &[]).contains("    pub name: String,\n"));
+        assert!(declare("String", &[]).contains("            name: String::new(),\n"));
+        let custom = declare("compact_str::CompactString", &[]);
+        assert!(custom.contains("    pub name: compact_str::CompactString,\n"));
+        assert!(custom.contains("    pub tags: Vec<compact_str::CompactString>,\n"));
+        assert!(custom.contains("            name: Default::default(),\n"));
+        assert!("".parse::<StringType>().is_err());
+
+        assert_eq!(
+            declare("Cow", &["serde::Deserialize"]),
+            "#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Data1<'a> {
+    Option0(#[serde(borrow)] std::borrow::Cow<'a, str>),
+    Option1(i64),
+}
+
+impl<'a> Default for Data1<'a> {
+    fn default() -> Self {
+        Data1::Option0(Default::default())
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Data2<'a> {
+    pub id: i64,
+    #[serde(borrow)]
+    pub login: std::borrow::Cow<'a, str>,
+}
+
+impl<'a> Default for Data2<'a> {
+    fn default() -> Self {
+        Data2 {
+            id: 0,
+            login: Default::default(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Data0<'a> {
+    #[serde(borrow)]
+    pub mixed: Vec<Data1<'a>>,
+    #[serde(borrow)]
+    pub name: std::borrow::Cow<'a, str>,
+    #[serde(borrow)]
+    pub owner: Data2<'a>,
+    #[serde(borrow)]
+    pub tags: Vec<std::borrow::Cow<'a, str>>,
+}
+
+impl<'a> Default for Data0<'a> {
+    fn default() -> Self {
+        Data0 {
+            mixed: Vec::new(),
+            name: Default::default(),
+            owner: Data2::default(),
+            tags: Vec::new(),
+        }
+    }
+}"
+        );
+
+        let mut decls = Decls::new(Options {
+            string_type: StringType::Cow,
+            ..Options::default()
+        });
+        let root = DataType::from_json_value(&json::array!["a"])
+            .unwrap()
+            .declare(&mut decls);
+        assert_eq!(root, "Root");
+        assert_eq!(
+            decls.output(),
+            "type Root<'a> = Vec<std::borrow::Cow<'a, str>>;"
+        );
+
+        let mut decls = Decls::new(Options {
+            string_type: StringType::Cow,
+            emit_main: Some(MainErrors::Expect),
+            ..Options::default()
+        });
+        DataType::from_json_value(&json::array![{"name": "a"}])
+            .unwrap()
+            .declare(&mut decls);
+        assert!(decls
+            .output()
+            .contains("type Root<'a> = Vec<Data0<'a>>;\n\nfn main() {"));
+        assert!(decls
+            .output()
+            .contains("    let value: Root = serde_json::from_str(&text)"));
+    }
+
+    #[test]
+    fn newtype_leaves() {
+        let typ = DataType::from_json_value(&json::object! {
+            "age_years": 30,
+            "height": 180,
+            "age": {"years": 31},
+            "tags": ["a"],
+        })
+        .unwrap();
+        let mut decls = Decls::new(Options {
+            newtype_leaves: true,
+            derives: vec!["serde::Deserialize".to_string()],
+            ..Options::default()
+        });
+        typ.declare(&mut decls);
+        assert_eq!(
+            decls.output(),
+            "#[derive(serde::Deserialize)]
+#[serde(transparent)]
+struct AgeYears(pub i64);
+
+#[derive(serde::Deserialize)]
+struct Data1 {
+    pub years: AgeYears,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(transparent)]
+struct Height(pub i64);
+
+#[derive(serde::Deserialize)]
+struct Data0 {
+    pub age: Data1,
+    pub age_years: AgeYears,
+    pub height: Height,
+    pub tags: Vec<String>,
+}"
+        );
+        assert_eq!(decls.paths()["AgeYears"].len(), 2);
+
+        // A newtype of the same name for a different type gets a number.
+        let typ = DataType::from_json_value(&json::object! {"a_b": 1, "a": {"b": "x"}}).unwrap();
+        let mut decls = Decls::new(Options {
+            newtype_leaves: true,
+            ..Options::default()
+        });
+        typ.declare(&mut decls);
+        assert!(decls.output().contains("struct AB2(pub i64);"));
+        assert!(decls.output().contains("pub a_b: AB2,"));
+    }
+}
@@ -0,0 +1,635 @@
+//! A parsed document, independent of the format and parser it came
+//! from.
+
+use std::{convert::TryFrom, fmt, fs, path::Path, str::FromStr};
+
+use json::JsonValue;
+use serde::{
+    de::{
+        self, DeserializeSeed, EnumAccess, IgnoredAny, MapAccess, SeqAccess, VariantAccess, Visitor,
+    },
+    Deserialize, Deserializer,
+};
+
+use crate::{DepthExceeded, Error, MAX_DEPTH};
+
+/// A value in a parsed document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// The null value.
+    Null,
+
+    /// A boolean.
+    Bool(bool),
+
+    /// A number, exactly as written.
+    Number(Number),
+
+    /// A string of characters.
+    String(String),
+
+    /// An ordered list of values.
+    Array(Vec<Value>),
+
+    /// A set of named values, in the order they were written.
+    Object(Vec<(String, Value)>),
+
+    /// A string of bytes, from a binary format that has them.
+    Bytes(Vec<u8>),
+}
+
+impl Value {
+    /// Parse the text of a JSON document, or return `None` if there's
+    /// nothing but whitespace in it.
+    pub fn parse_json(text: &str) -> Result<Option<Self>, Error> {
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        let v = json::parse(text).map_err(|e| Error::from_json(e, text))?;
+        Self::from_json(&v).map(Some)
+    }
+
+    /// Parse the JSON documents one after another in `text`, as some
+    /// loggers and `jq -c` write them, with or without whitespace
+    /// between them.
+    pub fn parse_json_documents(text: &str) -> Result<Vec<Self>, Error> {
+        let error = match Self::parse_json(text) {
+            Ok(document) => return Ok(document.into_iter().collect()),
+            Err(e @ Error::TooDeep(_)) => return Err(e),
+            Err(e) => e,
+        };
+
+        // The `json` crate only parses whole documents, so find where
+        // each one ends with `serde_json`, then parse them one by one.
+        let mut ends = serde_json::Deserializer::from_str(text).into_iter::<IgnoredAny>();
+        let mut documents = Vec::new();
+        let mut start = 0;
+        while let Some(skipped) = ends.next() {
+            match skipped {
+                Ok(_) => {}
+                // The first document was what failed to parse.
+                Err(_) if documents.is_empty() => return Err(error),
+                Err(e) => return Err(Error::from_serde_json(e, 1, 1)),
+            }
+            let end = ends.byte_offset();
+            let document = Self::parse_json(&text[start..end]).map_err(|e| e.after(text, start))?;
+            documents.extend(document);
+            start = end;
+        }
+        Ok(documents)
+    }
+
+    /// Read and parse the JSON document in the file at `path`, or
+    /// return `None` if there's nothing but whitespace in it.
+    pub fn read_json(path: &Path) -> Result<Option<Self>, Error> {
+        let text = fs::read_to_string(path).map_err(Error::io(path))?;
+        Self::parse_json(&text).map_err(|e| e.in_file(path, 1))
+    }
+
+    /// Parse newline-delimited JSON, with a document on each line that
+    /// isn't blank. Errors give the line they were found on.
+    pub fn parse_json_lines(text: &str) -> impl Iterator<Item = Result<Self, Error>> + '_ {
+        text.lines().enumerate().filter_map(|(idx, line)| {
+            Self::parse_json(line)
+                .map_err(|e| match e {
+                    Error::Parse {
+                        format,
+                        path,
+                        column,
+                        message,
+                        ..
+                    } => Error::Parse {
+                        format,
+                        path,
+                        line: idx + 1,
+                        column,
+                        message,
+                    },
+                    e => e,
+                })
+                .transpose()
+        })
+    }
+
+    /// Convert a value parsed by the `json` crate. Fails if the value
+    /// is nested more than `MAX_DEPTH` levels deep.
+    pub fn from_json(v: &JsonValue) -> Result<Self, Error> {
+        Ok(Self::from_json_at(v, 0)?)
+    }
+
+    /// Convert a value parsed by the `json` crate, which is nested
+    /// `depth` levels into the document.
+    fn from_json_at(v: &JsonValue, depth: usize) -> Result<Self, DepthExceeded> {
+        if depth > MAX_DEPTH {
+            return Err(DepthExceeded);
+        }
+
+        Ok(match v {
+            JsonValue::Null => Value::Null,
+            // Strings short enough are stored inline by the parser.
+            // Reading both kinds through `as_str` means that whatever
+            // looks at their text later, like date detection, sees
+            // them the same way.
+            JsonValue::Short(_) | JsonValue::String(_) => {
+                Value::String(v.as_str().expect("strings have text").to_string())
+            }
+            JsonValue::Number(n) => Value::Number((*n).into()),
+            JsonValue::Boolean(b) => Value::Bool(*b),
+            JsonValue::Object(obj) => Value::Object(
+                obj.iter()
+                    .map(|(key, value)| {
+                        Ok((key.to_string(), Self::from_json_at(value, depth + 1)?))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            JsonValue::Array(elems) => Value::Array(
+                elems
+                    .iter()
+                    .map(|elem| Self::from_json_at(elem, depth + 1))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
+/// Reading values from formats other than JSON, through their `serde`
+/// deserializers.
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ValueVisitor::at(0).deserialize(deserializer)
+    }
+}
+
+/// The key of the member a TOML date or time is passed to visitors in.
+const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+/// Visitor building a `Value` out of whatever the document has, which
+/// is nested `depth` levels into it. Nothing deeper than `MAX_DEPTH` is
+/// read, so that parsers that don't limit how deep they recurse can't
+/// overflow the stack.
+pub(crate) struct ValueVisitor {
+    depth: usize,
+}
+
+impl ValueVisitor {
+    /// A visitor for a value nested `depth` levels into the document.
+    pub(crate) fn at(depth: usize) -> Self {
+        Self { depth }
+    }
+
+    /// A visitor for the values in the one being visited.
+    fn nested(&self) -> Self {
+        Self::at(self.depth + 1)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        if self.depth > MAX_DEPTH {
+            return Err(de::Error::custom(DepthExceeded));
+        }
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value JSON can represent")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Value, E> {
+        Ok(Value::Number(n.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Value, E> {
+        Ok(Value::Number(n.into()))
+    }
+
+    fn visit_i128<E: de::Error>(self, n: i128) -> Result<Value, E> {
+        i64::try_from(n)
+            .map(|n| Value::Number(n.into()))
+            .map_err(|_| E::custom(format_args!("{} is too big to read", n)))
+    }
+
+    fn visit_u128<E: de::Error>(self, n: u128) -> Result<Value, E> {
+        u64::try_from(n)
+            .map(|n| Value::Number(n.into()))
+            .map_err(|_| E::custom(format_args!("{} is too big to read", n)))
+    }
+
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Value, E> {
+        Number::from_f64(n)
+            .map(Value::Number)
+            .ok_or_else(|| E::custom(format_args!("{} isn't a number JSON can represent", n)))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(bytes.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(bytes))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut elems = Vec::new();
+        while let Some(elem) = seq.next_element_seed(self.nested())? {
+            elems.push(elem);
+        }
+        Ok(Value::Array(elems))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut members = Vec::new();
+        while let Some(Key(key)) = map.next_key()? {
+            members.push((key, map.next_value_seed(self.nested())?));
+        }
+        match members.as_slice() {
+            // TOML's dates and times come as a member of an object
+            // with a special key, and are read as the strings they're
+            // written as, like dates in JSON.
+            [(key, Value::String(_))] if key == TOML_DATETIME_KEY => {
+                Ok(members.pop().expect("checked above").1)
+            }
+            _ => Ok(Value::Object(members)),
+        }
+    }
+
+    /// Tagged values, like YAML's `!Ref name`, are read as the value
+    /// the tag is on.
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Value, A::Error> {
+        let (Key(_), value) = data.variant::<Key>()?;
+        value.newtype_variant_seed(self)
+    }
+}
+
+/// The key of an object member. Formats where keys can be other
+/// scalars, like YAML, have them written as they'd be in JSON.
+struct Key(String);
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
+
+/// Visitor reading the key of an object member.
+struct KeyVisitor;
+
+impl<'de> Visitor<'de> for KeyVisitor {
+    type Value = Key;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, number, boolean or null key")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Key, E> {
+        Ok(Key(b.to_string()))
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Key, E> {
+        Ok(Key(n.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Key, E> {
+        Ok(Key(n.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Key, E> {
+        Ok(Key(n.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Key, E> {
+        Ok(Key(s.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Key, E> {
+        Ok(Key(s))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Key, E> {
+        Ok(Key("null".to_string()))
+    }
+}
+
+/// A number, stored as an exact decimal: `mantissa * 10^exponent`,
+/// negated if `negative` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Number {
+    negative: bool,
+    mantissa: u64,
+    exponent: i32,
+}
+
+impl Number {
+    /// Build a number from its decimal parts.
+    pub fn from_parts(negative: bool, mantissa: u64, exponent: i32) -> Self {
+        Number {
+            negative,
+            mantissa,
+            exponent,
+        }
+    }
+
+    /// Convert a floating-point number, returning `None` for NaN and
+    /// infinities.
+    pub fn from_f64(n: f64) -> Option<Self> {
+        if n.is_finite() {
+            // `{:e}` prints the shortest representation that reads
+            // back as the same float.
+            format!("{:e}", n).parse().ok()
+        } else {
+            None
+        }
+    }
+
+    /// The number as an `i64`, if it's an integer in range.
+    pub fn as_i64(&self) -> Option<i64> {
+        let magnitude = self.magnitude()?;
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    /// The number as a `u64`, if it's a non-negative integer in range.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.magnitude()? {
+            0 => Some(0),
+            _ if self.negative => None,
+            magnitude => Some(magnitude),
+        }
+    }
+
+    /// The nearest floating-point number.
+    pub fn as_f64(&self) -> f64 {
+        self.to_string()
+            .parse()
+            .expect("numbers always print as valid floats")
+    }
+
+    /// Whether the number has no fractional part.
+    pub fn is_integer(&self) -> bool {
+        self.exponent >= 0
+            || self.mantissa == 0
+            || 10u64
+                .checked_pow(self.exponent.unsigned_abs())
+                .is_some_and(|scale| self.mantissa.is_multiple_of(scale))
+    }
+
+    /// The absolute value of the number, if it's an integer that fits
+    /// in a `u64`.
+    fn magnitude(&self) -> Option<u64> {
+        let scale = 10u64.checked_pow(self.exponent.unsigned_abs());
+        if self.exponent >= 0 {
+            self.mantissa.checked_mul(scale?)
+        } else {
+            match scale {
+                Some(scale) if self.mantissa.is_multiple_of(scale) => Some(self.mantissa / scale),
+                // Dividing by more than the largest power of ten a
+                // `u64` holds only leaves an integer for zero.
+                None if self.mantissa == 0 => Some(0),
+                _ => None,
+            }
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(n: i64) -> Self {
+        Number::from_parts(n < 0, n.unsigned_abs(), 0)
+    }
+}
+
+impl From<u64> for Number {
+    fn from(n: u64) -> Self {
+        Number::from_parts(false, n, 0)
+    }
+}
+
+impl From<json::number::Number> for Number {
+    fn from(n: json::number::Number) -> Self {
+        let (positive, mantissa, exponent) = n.as_parts();
+        Number::from_parts(!positive, mantissa, exponent.into())
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.mantissa)?;
+        if self.exponent != 0 {
+            write!(f, "e{}", self.exponent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when parsing a string that isn't a number, or whose
+/// digits don't fit in a `u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseNumberError;
+
+impl fmt::Display for ParseNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid or out-of-range number")
+    }
+}
+
+impl std::error::Error for ParseNumberError {}
+
+impl FromStr for Number {
+    type Err = ParseNumberError;
+
+    /// Parse a number in JSON syntax, additionally allowing a leading
+    /// `+` on the exponent.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let (significand, exponent) = match s.find(['e', 'E']) {
+            Some(idx) => (
+                &s[..idx],
+                s[idx + 1..]
+                    .strip_prefix('+')
+                    .unwrap_or(&s[idx + 1..])
+                    .parse::<i32>()
+                    .map_err(|_| ParseNumberError)?,
+            ),
+            None => (s, 0),
+        };
+        let (int, frac) = match significand.split_once('.') {
+            Some((_, "")) => return Err(ParseNumberError),
+            Some((int, frac)) => (int, frac),
+            None => (significand, ""),
+        };
+
+        let mut digits = int.chars().chain(frac.chars());
+        if int.is_empty() || !digits.clone().all(|c| c.is_ascii_digit()) {
+            return Err(ParseNumberError);
+        }
+        let mantissa = digits.try_fold(0u64, |acc, c| {
+            acc.checked_mul(10)?
+                .checked_add(c.to_digit(10).expect("checked above").into())
+        });
+
+        Ok(Number::from_parts(
+            negative,
+            mantissa.ok_or(ParseNumberError)?,
+            exponent
+                .checked_sub(frac.len() as i32)
+                .ok_or(ParseNumberError)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_documents() {
+        assert_eq!(
+            Value::parse_json(" \"hello\"\n").unwrap(),
+            Some(Value::String("hello".to_string()))
+        );
+        assert_eq!(Value::parse_json("null").unwrap(), Some(Value::Null));
+        assert_eq!(Value::parse_json("").unwrap(), None);
+        assert_eq!(Value::parse_json(" \n\t").unwrap(), None);
+        assert!(matches!(
+            Value::parse_json("{\n  \"a\": }"),
+            Err(Error::Parse {
+                path: None,
+                line: 2,
+                column: 8,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Value::parse_json("[1,\n\n]"),
+            Err(Error::Parse {
+                line: 3,
+                column: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Value::parse_json("[1,\n"),
+            Err(Error::Parse {
+                line: 2,
+                column: 1,
+                ..
+            })
+        ));
+        let deep = "[".repeat(MAX_DEPTH + 2) + &"]".repeat(MAX_DEPTH + 2);
+        assert!(matches!(Value::parse_json(&deep), Err(Error::TooDeep(_))));
+
+        let records = Value::parse_json_lines("1\n\n[2]\n{\n").collect::<Vec<_>>();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[1].as_ref().unwrap(),
+            &Value::Array(vec![Value::Number("2".parse().unwrap())])
+        );
+        assert!(matches!(records[2], Err(Error::Parse { line: 4, .. })));
+    }
+
+    #[test]
+    fn read_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.json");
+        assert!(matches!(
+            Value::read_json(&path),
+            Err(Error::Io { path: ref p, .. }) if *p == path
+        ));
+        std::fs::write(&path, "[true, ?]").unwrap();
+        match Value::read_json(&path) {
+            Err(Error::Parse {
+                path: Some(p),
+                line: 1,
+                column: 8,
+                ..
+            }) => assert_eq!(p, path),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn exact_integers() {
+        let big = json::parse("9007199254740993").unwrap();
+        let n = match Value::from_json(&big).unwrap() {
+            Value::Number(n) => n,
+            v => panic!("expected a number, got {:?}", v),
+        };
+        assert_eq!(n.as_i64(), Some(9007199254740993));
+        assert_eq!(n.as_u64(), Some(9007199254740993));
+
+        assert_eq!(Number::from(-5i64).as_u64(), None);
+        assert_eq!(Number::from(u64::MAX).as_i64(), None);
+        assert_eq!(Number::from(i64::MIN).as_i64(), Some(i64::MIN));
+    }
+
+    #[test]
+    fn parse_numbers() {
+        let n = "10.50".parse::<Number>().unwrap();
+        assert_eq!(n, Number::from_parts(false, 1050, -2));
+        assert!(!n.is_integer());
+        assert_eq!(n.as_f64(), 10.5);
+
+        let n = "-2.0e+3".parse::<Number>().unwrap();
+        assert!(n.is_integer());
+        assert_eq!(n.as_i64(), Some(-2000));
+
+        assert_eq!("1e300".parse::<Number>().unwrap().as_i64(), None);
+        assert_eq!(
+            Number::from_f64(0.1),
+            Some(Number::from_parts(false, 1, -1))
+        );
+        for invalid in ["", "-", "1.", ".5", "1e", "abc", "99999999999999999999"] {
+            assert_eq!(
+                invalid.parse::<Number>(),
+                Err(ParseNumberError),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
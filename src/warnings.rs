@@ -0,0 +1,204 @@
+//! Warnings about inference and emission decisions that lose
+//! information or might surprise the user.
+
+use std::{collections::BTreeMap, fmt};
+
+/// A single lossy or surprising decision.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Warning {
+    /// The path of the value the decision was made for, such as
+    /// `$.users[].id`.
+    pub path: String,
+
+    /// The kind of decision.
+    pub kind: WarningKind,
+
+    /// Specifics of this decision, which may be empty.
+    pub detail: String,
+}
+
+/// Kinds of lossy or surprising decisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WarningKind {
+    /// Integers and non-integers were seen at the same path, so all of
+    /// them are typed as floats.
+    IntFloatMerged,
+
+    /// Every array at a path was empty, so the element type is
+    /// unknown.
+    EmptyArray,
+
+    /// An object's keys looked like data rather than field names, so it
+    /// was typed as a map.
+    MapDetected,
+
+    /// Values of several kinds were seen at the same path, so a union
+    /// type was emitted for them.
+    EnumFallback,
+
+    /// A member was null in every sample and was left out.
+    NullFieldDropped,
+
+    /// A member was present in too few samples and was left out.
+    RareFieldDropped,
+
+    /// Only some of the elements of an array were inspected.
+    ArraySampled,
+
+    /// The document was null or empty, so nothing is known about its
+    /// type.
+    NullDocument,
+
+    /// A response in a HAR capture said it was JSON but couldn't be
+    /// read as JSON, so it was skipped.
+    InvalidResponse,
+}
+
+impl WarningKind {
+    /// A short, stable name for the kind, for machine-readable output.
+    pub fn name(self) -> &'static str {
+        match self {
+            WarningKind::IntFloatMerged => "int-float-merged",
+            WarningKind::EmptyArray => "empty-array",
+            WarningKind::MapDetected => "map-detected",
+            WarningKind::EnumFallback => "enum-fallback",
+            WarningKind::NullFieldDropped => "null-field-dropped",
+            WarningKind::RareFieldDropped => "rare-field-dropped",
+            WarningKind::ArraySampled => "array-sampled",
+            WarningKind::NullDocument => "null-document",
+            WarningKind::InvalidResponse => "invalid-response",
+        }
+    }
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            WarningKind::IntFloatMerged => "integers and non-integers were merged into a float",
+            WarningKind::EmptyArray => "arrays were always empty, so their element type is unknown",
+            WarningKind::MapDetected => "keys look like data, so the object was typed as a map",
+            WarningKind::EnumFallback => "values had several types, so a union was emitted",
+            WarningKind::NullFieldDropped => "member was always null, so it was left out",
+            WarningKind::RareFieldDropped => "member was rarely present, so it was left out",
+            WarningKind::ArraySampled => {
+                "only some elements were inspected, so members and types may be missing"
+            }
+            WarningKind::NullDocument => "the document was null or empty, so its type is unknown",
+            WarningKind::InvalidResponse => "a response wasn't valid JSON, so it was skipped",
+        })
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)?;
+        if !self.detail.is_empty() {
+            write!(f, " ({})", self.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// A sink that warnings are pushed into as they're found.
+#[derive(Debug, Clone, Default)]
+pub struct Warnings {
+    warnings: Vec<Warning>,
+}
+
+impl Warnings {
+    /// Create an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a warning.
+    pub fn push(&mut self, path: impl Into<String>, kind: WarningKind, detail: impl Into<String>) {
+        self.warnings.push(Warning {
+            path: path.into(),
+            kind,
+            detail: detail.into(),
+        });
+    }
+
+    /// The same warnings, with `prefix` and a space before each of
+    /// their paths, to tell apart the warnings about different types.
+    pub fn prefixed(mut self, prefix: &str) -> Self {
+        for warning in &mut self.warnings {
+            warning.path = format!("{} {}", prefix, warning.path);
+        }
+        self
+    }
+
+    /// Add all the warnings recorded in another sink.
+    pub fn extend(&mut self, other: Warnings) {
+        self.warnings.extend(other.warnings);
+    }
+
+    /// Whether no warnings were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// The distinct warnings recorded, with the number of times each
+    /// was recorded.
+    pub fn summary(&self) -> BTreeMap<&Warning, usize> {
+        let mut summary = BTreeMap::new();
+        for warning in &self.warnings {
+            *summary.entry(warning).or_insert(0) += 1;
+        }
+        summary
+    }
+
+    /// Describe the distinct warnings, one per line.
+    pub fn to_text(&self) -> String {
+        self.summary()
+            .into_iter()
+            .map(|(warning, count)| match count {
+                1 => format!("warning: {}\n", warning),
+                _ => format!("warning: {} [{} times]\n", warning, count),
+            })
+            .collect()
+    }
+
+    /// Describe the distinct warnings as a JSON array.
+    pub fn to_json(&self) -> String {
+        json::stringify(
+            self.summary()
+                .into_iter()
+                .map(|(warning, count)| {
+                    json::object! {
+                        "path": warning.path.as_str(),
+                        "kind": warning.kind.name(),
+                        "detail": warning.detail.as_str(),
+                        "count": count,
+                    }
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering() {
+        let mut warnings = Warnings::new();
+        warnings.push("$.a", WarningKind::EnumFallback, "string | int");
+        warnings.push("$.b", WarningKind::EmptyArray, "");
+        warnings.push("$.b", WarningKind::EmptyArray, "");
+        assert_eq!(
+            warnings.to_text(),
+            "warning: $.a: values had several types, so a union was emitted (string | int)\n\
+             warning: $.b: arrays were always empty, so their element type is unknown [2 times]\n"
+        );
+        assert_eq!(
+            json::parse(&warnings.to_json()).unwrap(),
+            json::array![
+                {"path": "$.a", "kind": "enum-fallback", "detail": "string | int", "count": 1},
+                {"path": "$.b", "kind": "empty-array", "detail": "", "count": 2},
+            ]
+        );
+    }
+}